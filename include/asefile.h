@@ -0,0 +1,59 @@
+/* C bindings for asefile. Build the library with
+ *
+ *     cargo rustc --release --features capi --crate-type cdylib
+ *
+ * See the documentation of the `capi` module for details.
+ *
+ * Functions that take a handle accept NULL. On NULL or an out of range index
+ * they return 0 or NULL, except for the functions below that document a
+ * different error value. */
+#ifndef ASEFILE_H
+#define ASEFILE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct AsefileHandle AsefileHandle;
+
+AsefileHandle *asefile_open(const char *path);
+AsefileHandle *asefile_open_memory(const uint8_t *data, size_t len);
+void asefile_free(AsefileHandle *handle);
+const char *asefile_last_error(void);
+
+uint32_t asefile_width(const AsefileHandle *handle);
+uint32_t asefile_height(const AsefileHandle *handle);
+
+uint32_t asefile_num_frames(const AsefileHandle *handle);
+uint32_t asefile_frame_duration_ms(const AsefileHandle *handle, uint32_t frame);
+/* These return 0 on success and -1 on error. */
+int32_t asefile_frame_rgba(const AsefileHandle *handle, uint32_t frame,
+                           uint8_t *out, size_t out_len);
+int32_t asefile_cel_rgba(const AsefileHandle *handle, uint32_t frame,
+                         uint32_t layer, uint8_t *out, size_t out_len);
+
+uint32_t asefile_num_layers(const AsefileHandle *handle);
+const char *asefile_layer_name(const AsefileHandle *handle, uint32_t layer);
+int32_t asefile_layer_is_visible(const AsefileHandle *handle, uint32_t layer);
+uint32_t asefile_layer_opacity(const AsefileHandle *handle, uint32_t layer);
+
+#define ASEFILE_DIRECTION_FORWARD 0
+#define ASEFILE_DIRECTION_REVERSE 1
+#define ASEFILE_DIRECTION_PING_PONG 2
+/* Returned by asefile_tag_direction on error. */
+#define ASEFILE_DIRECTION_INVALID UINT32_MAX
+
+uint32_t asefile_num_tags(const AsefileHandle *handle);
+const char *asefile_tag_name(const AsefileHandle *handle, uint32_t tag);
+uint32_t asefile_tag_from_frame(const AsefileHandle *handle, uint32_t tag);
+uint32_t asefile_tag_to_frame(const AsefileHandle *handle, uint32_t tag);
+uint32_t asefile_tag_direction(const AsefileHandle *handle, uint32_t tag);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ASEFILE_H */
@@ -0,0 +1,276 @@
+//! Helpers for playing back animations.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::animation::AnimationPlayer;
+//! use std::time::Duration;
+//!
+//! let tag = ase.tag_by_name("T3").unwrap();
+//! let mut player = AnimationPlayer::new(&ase, Some(tag));
+//! assert_eq!(player.current_frame(), 1);
+//! // Called once per game tick.
+//! player.advance(Duration::from_millis(120));
+//! assert_eq!(player.current_frame(), 2);
+//! let image = player.image(&ase);
+//! ```
+
+use std::time::Duration;
+
+use image::RgbaImage;
+
+use crate::{tags::Playback, AnimationDirection, AsepriteFile, Tag};
+
+/// Tracks the current frame of an animation as time passes.
+///
+/// If the tag has a finite [repeat count](Tag::repeat), the player stops on
+/// the last frame once all repetitions have been played. For ping-pong tags,
+/// each direction counts as one repetition (same as in Aseprite).
+///
+/// The player copies all the information it needs from the file, so it does
+/// not borrow the [AsepriteFile]. This makes it easy to store in game
+/// entities.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    playback: Playback,
+    // Durations of `playback.from..=playback.to`.
+    durations: Vec<Duration>,
+    // Time spent in the current frame.
+    elapsed: Duration,
+}
+
+impl AnimationPlayer {
+    /// Play the frames of the given tag using the tag's animation direction.
+    ///
+    /// If `tag` is `None`, plays all frames of the file in forward direction
+    /// and loops forever.
+    pub fn new(file: &AsepriteFile, tag: Option<&Tag>) -> Self {
+        let last_frame = file.num_frames().saturating_sub(1);
+        let (from_frame, to_frame, direction, repeat) = match tag {
+            Some(tag) => (
+                tag.from_frame().min(last_frame),
+                tag.to_frame().min(last_frame),
+                tag.animation_direction(),
+                tag.repeat().map(|r| r.get()),
+            ),
+            None => (0, last_frame, AnimationDirection::Forward, None),
+        };
+        let to_frame = to_frame.max(from_frame);
+        let durations = (from_frame..=to_frame)
+            .map(|frame| file.frame(frame).duration())
+            .collect();
+        AnimationPlayer {
+            playback: Playback::new(from_frame, to_frame, direction, repeat),
+            durations,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Restart the animation from the beginning.
+    pub fn reset(&mut self) {
+        self.playback.reset();
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Advance the animation by the given amount of time.
+    ///
+    /// May skip several frames if `dt` is longer than the current frame's
+    /// duration. Does nothing once the animation [is
+    /// finished](Self::is_finished).
+    pub fn advance(&mut self, dt: Duration) {
+        if self.playback.finished || self.durations.iter().all(|d| d.is_zero()) {
+            return;
+        }
+        self.elapsed += dt;
+        loop {
+            let duration = self.current_duration();
+            if self.elapsed < duration {
+                break;
+            }
+            self.playback.step();
+            if self.playback.finished {
+                self.elapsed = duration;
+                break;
+            }
+            self.elapsed -= duration;
+        }
+    }
+
+    /// Returns `true` if the tag has a finite repeat count and all
+    /// repetitions have been played. Looping animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.playback.finished
+    }
+
+    /// The frame index (in the file) of the frame that should be displayed.
+    pub fn current_frame(&self) -> u32 {
+        self.playback.current
+    }
+
+    /// Time spent in the current frame so far.
+    pub fn elapsed_in_frame(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The image of the current frame.
+    ///
+    /// `file` should be the same file that was used to create the player.
+    pub fn image(&self, file: &AsepriteFile) -> RgbaImage {
+        file.frame(self.playback.current).image()
+    }
+
+    fn current_duration(&self) -> Duration {
+        self.durations[(self.playback.current - self.playback.from) as usize]
+    }
+}
+
+/// An [AnimationPlayer] together with one value per frame of the animation,
+/// e.g., a texture of a game engine.
+///
+/// The `ggez` and `macroquad` modules use this for their `AnimatedSprite`
+/// types. Other engines can use [from_fn](Self::from_fn) to upload the
+/// frames.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::animation::AnimatedFrames;
+/// use std::time::Duration;
+///
+/// let tag = ase.tag_by_name("T3").unwrap();
+/// let mut frames = AnimatedFrames::from_fn(&ase, Some(tag), |frame| frame * 10);
+/// assert_eq!(*frames.current(), 10);
+/// frames.update(Duration::from_millis(120));
+/// assert_eq!(*frames.current(), 20);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnimatedFrames<T> {
+    player: AnimationPlayer,
+    frames: Vec<T>,
+}
+
+impl<T> AnimatedFrames<T> {
+    /// Play the frames of the given tag. If `tag` is `None`, plays all frames
+    /// of the file in a loop. See [AnimationPlayer::new].
+    ///
+    /// `load` is called once for each frame of the animation, in frame order,
+    /// with the frame index in the file.
+    pub fn from_fn<F>(file: &AsepriteFile, tag: Option<&Tag>, load: F) -> Self
+    where
+        F: FnMut(u32) -> T,
+    {
+        let player = AnimationPlayer::new(file, tag);
+        let frames = (player.playback.from..=player.playback.to)
+            .map(load)
+            .collect();
+        AnimatedFrames { player, frames }
+    }
+
+    /// Advance the animation by the given amount of time.
+    pub fn update(&mut self, dt: Duration) {
+        self.player.advance(dt);
+    }
+
+    /// Restart the animation from the beginning.
+    pub fn reset(&mut self) {
+        self.player.reset();
+    }
+
+    /// See [AnimationPlayer::is_finished].
+    pub fn is_finished(&self) -> bool {
+        self.player.is_finished()
+    }
+
+    /// The frame index (in the file) of the frame that is displayed.
+    pub fn current_frame(&self) -> u32 {
+        self.player.current_frame()
+    }
+
+    /// The value of the frame that is displayed.
+    pub fn current(&self) -> &T {
+        &self.frames[(self.player.current_frame() - self.player.playback.from) as usize]
+    }
+
+    /// The underlying animation player.
+    pub fn player(&self) -> &AnimationPlayer {
+        &self.player
+    }
+}
+
+/// Convert frames with varying durations into a sequence with one entry per
+/// tick of a fixed-rate clock.
+///
+/// `frames` are `(frame_index, duration)` pairs in playback order. Each tick
+/// shows the frame that would be visible at the start of that tick. The
+/// number of ticks is the total duration times `fps`, rounded to the nearest
+/// integer, so looping the result does not drift.
+///
+/// # Panics
+///
+/// Panics if `fps` is zero.
+pub fn retime<I>(frames: I, fps: u32) -> Vec<u32>
+where
+    I: IntoIterator<Item = (u32, Duration)>,
+{
+    assert!(fps > 0, "fps must be greater than zero");
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    let frames: Vec<(u32, Duration)> = frames.into_iter().collect();
+    let total: u128 = frames.iter().map(|(_, d)| d.as_nanos()).sum();
+    if frames.is_empty() || total == 0 {
+        return frames.first().map(|f| vec![f.0]).unwrap_or_default();
+    }
+    let fps = fps as u128;
+    let ticks = ((total * fps + NANOS_PER_SEC / 2) / NANOS_PER_SEC).max(1);
+
+    let mut result = Vec::with_capacity(ticks as usize);
+    let mut current = 0;
+    let mut frame_end = frames[0].1.as_nanos();
+    for tick in 0..ticks {
+        let time = tick * NANOS_PER_SEC / fps;
+        while time >= frame_end && current + 1 < frames.len() {
+            current += 1;
+            frame_end += frames[current].1.as_nanos();
+        }
+        result.push(frames[current].0);
+    }
+    result
+}
+
+/// Sample the given tag (or all frames if `tag` is `None`) at a fixed frame
+/// rate. See [retime] for details.
+///
+/// The tag is expanded using [Tag::frame_sequence], so the result honors the
+/// animation direction and finite repeat counts.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::animation::retime_tag;
+///
+/// // Each frame lasts 100ms, so two ticks at 20 Hz.
+/// let ticks = retime_tag(&ase, ase.tag_by_name("T1"), 20);
+/// assert_eq!(ticks, vec![0, 0, 1, 1]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `fps` is zero.
+pub fn retime_tag(file: &AsepriteFile, tag: Option<&Tag>, fps: u32) -> Vec<u32> {
+    let sequence = match tag {
+        Some(tag) => tag.frame_sequence(),
+        None => (0..file.num_frames()).collect(),
+    };
+    let durations = file.frame_durations();
+    retime(
+        sequence
+            .into_iter()
+            .filter_map(|frame| durations.get(frame as usize).map(|d| (frame, *d))),
+        fps,
+    )
+}
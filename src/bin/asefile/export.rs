@@ -0,0 +1,340 @@
+use std::{fs, ops::RangeInclusive, path::PathBuf};
+
+use asefile::{
+    json::AsepriteSlices,
+    sheet::{SheetLayout, SheetOptions, SpriteSheet},
+    AnimationDirection, AsepriteFile, Layer, LayerType,
+};
+use image::RgbaImage;
+use serde_json::json;
+
+use crate::{option_number, option_value};
+
+const USAGE: &str = "\
+Usage: asefile export [OPTIONS] <FILE>
+
+Writes one PNG per frame unless one of --sheet, --tilesets or --tilemap is
+given. Use --frames to get frame PNGs in addition to those. The path of each
+written file is printed to stdout.
+
+Options:
+  -o, --output <DIR>     Output directory [default: .]
+  -n, --name <NAME>      Base name of the output files [default: input file name]
+      --frames           Export every frame as <NAME>_<FRAME>.png
+      --tag <TAG>        Only export the frames of the given tag
+      --layer <LAYER>    Export a single layer instead of the composed frame
+      --split-layers     Export every layer separately as <NAME>_<LAYER>_<FRAME>.png
+      --sheet            Build a sprite sheet <NAME>.png with metadata in <NAME>.json
+      --layout <LAYOUT>  Sheet layout: packed, horizontal, vertical or grid [default: packed]
+      --columns <N>      Number of columns of a grid layout
+      --spacing <N>      Empty pixels between frames in the sheet [default: 0]
+      --border <N>       Empty pixels around the sheet [default: 0]
+      --tilesets         Export every tileset as <NAME>_tileset<ID>.tsx and .png
+      --tilemap          Export the tilemap layers as <NAME>.tmx for Tiled
+  -h, --help             Print this help";
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    name: Option<String>,
+    frames: bool,
+    tag: Option<String>,
+    layer: Option<String>,
+    split_layers: bool,
+    sheet: bool,
+    sheet_options: SheetOptions,
+    tilesets: bool,
+    tilemap: bool,
+}
+
+fn parse_options<I>(mut args: I) -> Result<Option<Options>, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut input = None;
+    let mut options = Options {
+        input: PathBuf::new(),
+        output: PathBuf::from("."),
+        name: None,
+        frames: false,
+        tag: None,
+        layer: None,
+        split_layers: false,
+        sheet: false,
+        sheet_options: SheetOptions::default(),
+        tilesets: false,
+        tilemap: false,
+    };
+    let mut layout = None;
+    let mut columns = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            "-o" | "--output" => options.output = option_value(&mut args, &arg)?.into(),
+            "-n" | "--name" => options.name = Some(option_value(&mut args, &arg)?),
+            "--frames" => options.frames = true,
+            "--tag" => options.tag = Some(option_value(&mut args, &arg)?),
+            "--layer" => options.layer = Some(option_value(&mut args, &arg)?),
+            "--split-layers" => options.split_layers = true,
+            "--sheet" => options.sheet = true,
+            "--layout" => layout = Some(option_value(&mut args, &arg)?),
+            "--columns" => columns = Some(option_number(&mut args, &arg)?),
+            "--spacing" => options.sheet_options.spacing = option_number(&mut args, &arg)?,
+            "--border" => options.sheet_options.border = option_number(&mut args, &arg)?,
+            "--tilesets" => options.tilesets = true,
+            "--tilemap" => options.tilemap = true,
+            _ if arg.starts_with('-') => {
+                return Err(format!("Unknown option: {}\n\n{}", arg, USAGE));
+            }
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    options.input = input.ok_or_else(|| format!("Missing input file\n\n{}", USAGE))?;
+    options.sheet_options.layout = match (layout.as_deref(), columns) {
+        (None | Some("grid"), Some(columns)) if columns > 0 => SheetLayout::Grid { columns },
+        (_, Some(_)) => return Err("--columns must be greater than zero".to_owned()),
+        (None | Some("packed"), None) => SheetLayout::Packed,
+        (Some("horizontal"), None) => SheetLayout::HorizontalStrip,
+        (Some("vertical"), None) => SheetLayout::VerticalStrip,
+        (Some("grid"), None) => return Err("--layout grid requires --columns".to_owned()),
+        (Some(layout), None) => return Err(format!("Unknown layout: {}", layout)),
+    };
+    if options.layer.is_some() && options.split_layers {
+        return Err("--layer and --split-layers cannot be combined".to_owned());
+    }
+    if !(options.sheet || options.tilesets || options.tilemap) {
+        options.frames = true;
+    }
+    Ok(Some(options))
+}
+
+/// Run `asefile export` with the arguments following the command name.
+pub fn run<I>(args: I) -> Result<(), String>
+where
+    I: Iterator<Item = String>,
+{
+    let Some(options) = parse_options(args)? else {
+        return Ok(());
+    };
+    let ase = AsepriteFile::read_file(&options.input)
+        .map_err(|err| format!("{}: {}", options.input.display(), err))?;
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None => options
+            .input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let frames = match &options.tag {
+        Some(tag_name) => {
+            let tag = ase
+                .tag_by_name(tag_name)
+                .ok_or_else(|| format!("No tag named {:?}", tag_name))?;
+            tag.from_frame()..=tag.to_frame().min(ase.num_frames() - 1)
+        }
+        None => 0..=ase.num_frames() - 1,
+    };
+    fs::create_dir_all(&options.output)
+        .map_err(|err| format!("{}: {}", options.output.display(), err))?;
+
+    let exporter = Exporter {
+        ase: &ase,
+        options: &options,
+        name,
+        frames,
+    };
+    if options.frames {
+        exporter.export_frames()?;
+    }
+    if options.sheet {
+        exporter.export_sheet()?;
+    }
+    if options.tilesets {
+        exporter.export_tilesets()?;
+    }
+    if options.tilemap {
+        exporter.export_tilemaps()?;
+    }
+    Ok(())
+}
+
+struct Exporter<'a> {
+    ase: &'a AsepriteFile,
+    options: &'a Options,
+    name: String,
+    frames: RangeInclusive<u32>,
+}
+
+impl Exporter<'_> {
+    fn path(&self, file_name: String) -> PathBuf {
+        self.options.output.join(file_name)
+    }
+
+    fn save_image(&self, image: &RgbaImage, path: &PathBuf) -> Result<(), String> {
+        image
+            .save(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        println!("{}", path.display());
+        Ok(())
+    }
+
+    fn export_frames(&self) -> Result<(), String> {
+        let layers: Vec<Option<Layer>> = if let Some(layer_name) = &self.options.layer {
+            let layer = self
+                .ase
+                .layer_by_name(layer_name)
+                .ok_or_else(|| format!("No layer named {:?}", layer_name))?;
+            if layer.layer_type() == LayerType::Group {
+                return Err(format!("Layer {:?} is a group", layer_name));
+            }
+            vec![Some(layer)]
+        } else if self.options.split_layers {
+            self.ase
+                .layers()
+                .filter(|layer| layer.layer_type() != LayerType::Group)
+                .map(Some)
+                .collect()
+        } else {
+            vec![None]
+        };
+        // Pad frame numbers so that the files sort in frame order.
+        let digits = (self.ase.num_frames() - 1).to_string().len();
+        for frame in self.frames.clone() {
+            for layer in &layers {
+                let (image, file_name) = match layer {
+                    Some(layer) => (
+                        self.ase.cel(frame, layer.id()).image(),
+                        format!(
+                            "{}_{}_{:0digits$}.png",
+                            self.name,
+                            file_name_part(layer.name()),
+                            frame
+                        ),
+                    ),
+                    None => (
+                        self.ase.frame(frame).image(),
+                        format!("{}_{:0digits$}.png", self.name, frame),
+                    ),
+                };
+                self.save_image(&image, &self.path(file_name))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the sheet image plus metadata in the JSON array format of
+    // Aseprite's own sprite sheet export.
+    fn export_sheet(&self) -> Result<(), String> {
+        let sheet =
+            SpriteSheet::from_frames(self.ase, self.frames.clone(), &self.options.sheet_options);
+        let image_name = format!("{}.png", self.name);
+        self.save_image(sheet.image(), &self.path(image_name.clone()))?;
+
+        let (width, height) = self.ase.size();
+        let first = *self.frames.start();
+        let frames: Vec<_> = sheet
+            .frames()
+            .iter()
+            .map(|frame| {
+                json!({
+                    "filename": format!("{} {}.aseprite", self.name, frame.frame),
+                    "frame": { "x": frame.x, "y": frame.y, "w": frame.width, "h": frame.height },
+                    "rotated": false,
+                    "trimmed": false,
+                    "spriteSourceSize": { "x": 0, "y": 0, "w": frame.width, "h": frame.height },
+                    "sourceSize": { "w": width, "h": height },
+                    "duration": frame.duration,
+                })
+            })
+            .collect();
+        // Tag frame indices refer to the exported frames, so tags are clipped
+        // to the exported range.
+        let tags: Vec<_> = self
+            .ase
+            .tags()
+            .filter(|tag| tag.from_frame() <= *self.frames.end() && tag.to_frame() >= first)
+            .map(|tag| {
+                json!({
+                    "name": tag.name(),
+                    "from": tag.from_frame().max(first) - first,
+                    "to": tag.to_frame().min(*self.frames.end()) - first,
+                    "direction": match tag.animation_direction() {
+                        AnimationDirection::Forward => "forward",
+                        AnimationDirection::Reverse => "reverse",
+                        AnimationDirection::PingPong => "pingpong",
+                    },
+                })
+            })
+            .collect();
+        let metadata = json!({
+            "frames": frames,
+            "meta": {
+                "app": env!("CARGO_PKG_HOMEPAGE"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "image": image_name,
+                "format": "RGBA8888",
+                "size": { "w": sheet.image().width(), "h": sheet.image().height() },
+                "scale": "1",
+                "frameTags": tags,
+                "slices": AsepriteSlices::new(self.ase).slices,
+            },
+        });
+        let json = serde_json::to_string_pretty(&metadata).map_err(|err| err.to_string())?;
+        let path = self.path(format!("{}.json", self.name));
+        fs::write(&path, json).map_err(|err| format!("{}: {}", path.display(), err))?;
+        println!("{}", path.display());
+        Ok(())
+    }
+
+    fn export_tilesets(&self) -> Result<(), String> {
+        for tileset in self.ase.tilesets().iter() {
+            let path = self.path(format!("{}_tileset{}.tsx", self.name, tileset.id().value()));
+            let columns = (tileset.tile_count() as f64).sqrt().ceil() as u32;
+            asefile::tiled::save_tsx(tileset, &path, columns)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+
+    fn export_tilemaps(&self) -> Result<(), String> {
+        if !self.ase.layers().any(|layer| layer.is_tilemap()) {
+            return Err(format!(
+                "{} has no tilemap layers",
+                self.options.input.display()
+            ));
+        }
+        let single = self.frames.start() == self.frames.end();
+        let digits = (self.ase.num_frames() - 1).to_string().len();
+        for frame in self.frames.clone() {
+            let file_name = if single {
+                format!("{}.tmx", self.name)
+            } else {
+                format!("{}_{:0digits$}.tmx", self.name, frame)
+            };
+            let path = self.path(file_name);
+            asefile::tiled::save_tmx(self.ase, frame, &path)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+}
+
+// Replaces characters that are not safe to use in file names.
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use asefile::{
+    raw, AnimationDirection, AsepriteFile, LayerNode, LayerType, PixelFormat, PropertyValue,
+    UserData,
+};
+
+const USAGE: &str = "\
+Usage: asefile info [OPTIONS] <FILE>
+
+Prints a summary of the file: size, color mode, layers, tags, slices,
+tilesets, user data and the number and size of the chunks in the file.
+
+Options:
+  -h, --help  Print this help";
+
+/// Run `asefile info` with the arguments following the command name.
+pub fn run<I>(args: I) -> Result<(), String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut input = None;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("Unknown option: {}\n\n{}", arg, USAGE));
+            }
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    let input = input.ok_or_else(|| format!("Missing input file\n\n{}", USAGE))?;
+    let data = fs::read(&input).map_err(|err| format!("{}: {}", input.display(), err))?;
+    let ase =
+        AsepriteFile::read(&data[..]).map_err(|err| format!("{}: {}", input.display(), err))?;
+    print!("{}", summary(&input, &ase, &data));
+    Ok(())
+}
+
+// Writing to a String cannot fail, so the results of `writeln!` are ignored
+// throughout.
+fn summary(input: &Path, ase: &AsepriteFile, data: &[u8]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "File:       {} ({} bytes)",
+        input.display(),
+        data.len()
+    );
+    let _ = writeln!(out, "Size:       {}x{}", ase.width(), ase.height());
+    let color_mode = match ase.pixel_format() {
+        PixelFormat::Rgba => "RGBA".to_owned(),
+        PixelFormat::Grayscale => "Grayscale".to_owned(),
+        PixelFormat::Indexed {
+            transparent_color_index,
+        } => format!("Indexed (transparent index {})", transparent_color_index),
+    };
+    let _ = writeln!(out, "Color mode: {}", color_mode);
+    if let Some(palette) = ase.palette() {
+        let _ = writeln!(out, "Palette:    {} colors", palette.num_colors());
+    }
+    let _ = writeln!(
+        out,
+        "Frames:     {} ({} ms)",
+        ase.num_frames(),
+        ase.total_duration().as_millis()
+    );
+    write_user_data(&mut out, 0, ase.sprite_user_data());
+
+    let _ = writeln!(out, "\nLayers ({}):", ase.num_layers());
+    for node in ase.layer_tree().roots() {
+        write_layer(&mut out, 1, node);
+    }
+
+    let _ = writeln!(out, "\nTags ({}):", ase.num_tags());
+    for tag in ase.tags() {
+        let direction = match tag.animation_direction() {
+            AnimationDirection::Forward => "forward",
+            AnimationDirection::Reverse => "reverse",
+            AnimationDirection::PingPong => "pingpong",
+        };
+        let repeat = match tag.repeat() {
+            Some(repeat) => format!(", repeat {}", repeat),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            out,
+            "  {:?}: frames {}-{}, {}{}",
+            tag.name(),
+            tag.from_frame(),
+            tag.to_frame(),
+            direction,
+            repeat
+        );
+        write_user_data(&mut out, 2, tag.user_data());
+    }
+
+    let _ = writeln!(out, "\nSlices ({}):", ase.slices().len());
+    for slice in ase.slices() {
+        let _ = writeln!(out, "  {:?}:", slice.name);
+        for key in &slice.keys {
+            let bounds = key.bounds();
+            let _ = write!(
+                out,
+                "    from frame {}: {}x{} at ({}, {})",
+                key.from_frame, bounds.w, bounds.h, bounds.x, bounds.y
+            );
+            if let Some(slice9) = &key.slice9 {
+                let center = slice9.center();
+                let _ = write!(
+                    out,
+                    ", 9-slice center {}x{} at ({}, {})",
+                    center.w, center.h, center.x, center.y
+                );
+            }
+            if let Some(pivot) = key.pivot {
+                let _ = write!(out, ", pivot ({}, {})", pivot.x, pivot.y);
+            }
+            let _ = writeln!(out);
+        }
+        write_user_data(&mut out, 2, slice.user_data.as_ref());
+    }
+
+    let tilesets = ase.tilesets();
+    let _ = writeln!(out, "\nTilesets ({}):", tilesets.len());
+    for tileset in tilesets.iter() {
+        let size = tileset.tile_size();
+        let _ = writeln!(
+            out,
+            "  {} {:?}: {} tiles of {}x{}, base index {}",
+            tileset.id().value(),
+            tileset.name(),
+            tileset.tile_count(),
+            size.width(),
+            size.height(),
+            tileset.base_index()
+        );
+        write_user_data(&mut out, 2, tileset.user_data());
+    }
+
+    let chunks = chunk_statistics(data);
+    let _ = writeln!(out, "\nChunks:");
+    let _ = writeln!(out, "  {:<24} {:>7} {:>10}", "Type", "Count", "Bytes");
+    for (chunk_type, (count, bytes)) in &chunks {
+        let name = format!(
+            "{} (0x{:04x})",
+            raw::chunk_type_name(*chunk_type).unwrap_or("Unknown"),
+            chunk_type
+        );
+        let _ = writeln!(out, "  {:<24} {:>7} {:>10}", name, count, bytes);
+    }
+    out
+}
+
+fn write_layer(out: &mut String, depth: usize, node: &LayerNode) {
+    let layer = node.layer();
+    let kind = match layer.layer_type() {
+        LayerType::Image => "image".to_owned(),
+        LayerType::Group => "group".to_owned(),
+        LayerType::Tilemap(tileset) => format!("tilemap, tileset {}", tileset.value()),
+    };
+    let mut attributes = vec![kind];
+    if !layer.is_visible() {
+        attributes.push("hidden".to_owned());
+    }
+    if layer.opacity() != 255 {
+        attributes.push(format!("opacity {}", layer.opacity()));
+    }
+    if node.is_group() {
+        attributes.push(format!("{} children", node.children().len()));
+    } else {
+        attributes.push(format!("{:?}", layer.blend_mode()));
+    }
+    let _ = writeln!(
+        out,
+        "{:indent$}{} {:?} [{}]",
+        "",
+        layer.id(),
+        layer.name(),
+        attributes.join(", "),
+        indent = 2 * depth
+    );
+    write_user_data(out, depth + 1, layer.user_data());
+    for child in node.children() {
+        write_layer(out, depth + 1, child);
+    }
+}
+
+fn write_user_data(out: &mut String, depth: usize, user_data: Option<&UserData>) {
+    let Some(user_data) = user_data else {
+        return;
+    };
+    let indent = 2 * depth;
+    if let Some(text) = &user_data.text {
+        let _ = writeln!(out, "{:indent$}User data text: {:?}", "", text);
+    }
+    if let Some(color) = user_data.color {
+        let [r, g, b, a] = color.0;
+        let _ = writeln!(
+            out,
+            "{:indent$}User data color: #{:02x}{:02x}{:02x}{:02x}",
+            "", r, g, b, a
+        );
+    }
+    let extension_properties = user_data
+        .extension_properties
+        .iter()
+        .flat_map(|(_, properties)| properties.iter());
+    for (name, value) in user_data.properties.iter().chain(extension_properties) {
+        let _ = writeln!(
+            out,
+            "{:indent$}Property {:?}: {}",
+            "",
+            name,
+            property_value(value)
+        );
+    }
+}
+
+fn property_value(value: &PropertyValue) -> String {
+    if let Some(text) = value.as_str() {
+        format!("{:?}", text)
+    } else if let Some(flag) = value.as_bool() {
+        flag.to_string()
+    } else if let Some(number) = value.as_f64() {
+        number.to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+// Counts the chunks of each type and their total size in bytes, including
+// chunk headers.
+fn chunk_statistics(data: &[u8]) -> BTreeMap<u16, (u32, u64)> {
+    let mut stats = BTreeMap::new();
+    // The file has already been parsed successfully, so errors are not
+    // expected here.
+    let Ok(chunks) = raw::chunks(data) else {
+        return stats;
+    };
+    for chunk in chunks.flatten() {
+        let entry = stats.entry(chunk.chunk_type).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += chunk.data.len() as u64 + 6;
+    }
+    stats
+}
@@ -0,0 +1,61 @@
+//! Command line interface to asefile. (Requires feature `cli`.)
+//!
+//! ```text
+//! cargo install asefile --features cli
+//! asefile info player.aseprite
+//! asefile export player.aseprite --sheet --output assets
+//! ```
+
+use std::{env, process::ExitCode};
+
+mod export;
+mod info;
+
+const USAGE: &str = "\
+Usage: asefile <COMMAND> [OPTIONS] <FILE>
+
+Commands:
+  export    Export frames, layers, sprite sheets, tilesets and tilemaps
+  info      Print a summary of the contents of a file
+
+Run `asefile <COMMAND> --help` for the options of a command.";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("export") => export::run(args),
+        Some("info") => info::run(args),
+        Some("-h" | "--help") | None => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The value following an option, e.g., `out` for `--output out`.
+fn option_value<I>(args: &mut I, option: &str) -> Result<String, String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", option))
+}
+
+/// Parse the value following an option as a number.
+fn option_number<I>(args: &mut I, option: &str) -> Result<u32, String>
+where
+    I: Iterator<Item = String>,
+{
+    let value = option_value(args, option)?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
@@ -124,7 +124,7 @@ pub(crate) fn multiply(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, multiply_baseline)
 }
 
-fn multiply_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn multiply_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_multiply)
 }
 
@@ -138,7 +138,7 @@ pub(crate) fn screen(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, screen_baseline)
 }
 
-fn screen_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn screen_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_screen)
 }
 
@@ -153,7 +153,7 @@ pub(crate) fn overlay(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, overlay_baseline)
 }
 
-fn overlay_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn overlay_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_overlay)
 }
 
@@ -172,7 +172,7 @@ pub(crate) fn darken(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, darken_baseline)
 }
 
-fn darken_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn darken_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_darken)
 }
 
@@ -186,7 +186,7 @@ pub(crate) fn lighten(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, lighten_baseline)
 }
 
-fn lighten_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn lighten_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_lighten)
 }
 
@@ -200,7 +200,7 @@ pub(crate) fn color_dodge(backdrop: Color8, src: Color8, opacity: u8) -> Color8
     blender(backdrop, src, opacity, color_dodge_baseline)
 }
 
-fn color_dodge_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn color_dodge_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_color_dodge)
 }
 
@@ -223,7 +223,7 @@ pub(crate) fn color_burn(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, color_burn_baseline)
 }
 
-fn color_burn_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn color_burn_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_color_burn)
 }
 
@@ -246,7 +246,7 @@ pub(crate) fn hard_light(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, hard_light_baseline)
 }
 
-fn hard_light_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn hard_light_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_hard_light)
 }
 
@@ -264,7 +264,7 @@ pub(crate) fn soft_light(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, soft_light_baseline)
 }
 
-fn soft_light_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn soft_light_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     let (back_r, back_g, back_b, _) = as_rgba_i32(backdrop);
     let (src_r, src_g, src_b, src_a) = as_rgba_i32(src);
     let r = blend_soft_light(back_r, src_r);
@@ -303,7 +303,7 @@ pub(crate) fn divide(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, divide_baseline)
 }
 
-fn divide_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn divide_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_divide)
 }
 
@@ -323,7 +323,7 @@ pub(crate) fn difference(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, difference_baseline)
 }
 
-fn difference_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn difference_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_difference)
 }
 
@@ -337,7 +337,7 @@ pub(crate) fn exclusion(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, exclusion_baseline)
 }
 
-fn exclusion_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn exclusion_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blend_channel(backdrop, src, opacity, blend_exclusion)
 }
 
@@ -353,7 +353,7 @@ pub(crate) fn addition(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, addition_baseline)
 }
 
-fn addition_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn addition_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     let (back_r, back_g, back_b, _) = as_rgba_i32(backdrop);
     let (src_r, src_g, src_b, src_a) = as_rgba_i32(src);
     let r = back_r + src_r;
@@ -371,7 +371,7 @@ pub(crate) fn subtract(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, subtract_baseline)
 }
 
-fn subtract_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn subtract_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     let (back_r, back_g, back_b, _) = as_rgba_i32(backdrop);
     let (src_r, src_g, src_b, src_a) = as_rgba_i32(src);
     let r = back_r - src_r;
@@ -385,18 +385,25 @@ fn subtract_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
 
 // --- hsl_hue -----------------------------------------------------------------
 
-pub(crate) fn hsl_hue(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
-    blender(backdrop, src, opacity, hsl_hue_baseline)
+pub(crate) fn hsl_hue(backdrop: Color8, src: Color8, opacity: u8, bug_compatible: bool) -> Color8 {
+    blender(backdrop, src, opacity, |backdrop, src, opacity| {
+        hsl_hue_baseline(backdrop, src, opacity, bug_compatible)
+    })
 }
 
-fn hsl_hue_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn hsl_hue_baseline(
+    backdrop: Color8,
+    src: Color8,
+    opacity: u8,
+    bug_compatible: bool,
+) -> Color8 {
     let (r, g, b) = as_rgb_f64(backdrop);
     let sat = saturation(r, g, b);
     let lum = luminosity(r, g, b);
 
     let (r, g, b) = as_rgb_f64(src);
 
-    let (r, g, b) = set_saturation(r, g, b, sat);
+    let (r, g, b) = set_saturation(r, g, b, sat, bug_compatible);
     let (r, g, b) = set_luminocity(r, g, b, lum);
 
     let src = from_rgb_f64(r, g, b, src[3]);
@@ -406,11 +413,23 @@ fn hsl_hue_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
 
 // --- hsl_saturation ----------------------------------------------------------
 
-pub(crate) fn hsl_saturation(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
-    blender(backdrop, src, opacity, hsl_saturation_baseline)
-}
-
-fn hsl_saturation_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn hsl_saturation(
+    backdrop: Color8,
+    src: Color8,
+    opacity: u8,
+    bug_compatible: bool,
+) -> Color8 {
+    blender(backdrop, src, opacity, |backdrop, src, opacity| {
+        hsl_saturation_baseline(backdrop, src, opacity, bug_compatible)
+    })
+}
+
+pub(crate) fn hsl_saturation_baseline(
+    backdrop: Color8,
+    src: Color8,
+    opacity: u8,
+    bug_compatible: bool,
+) -> Color8 {
     //dbg!(backdrop, src);
     let (r, g, b) = as_rgb_f64(src);
     //dbg!("src", (r, g, b));
@@ -422,7 +441,7 @@ fn hsl_saturation_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8
     let lum = luminosity(r, g, b);
     //dbg!(lum);
 
-    let (r, g, b) = set_saturation(r, g, b, sat);
+    let (r, g, b) = set_saturation(r, g, b, sat, bug_compatible);
     //dbg!("sat", (r, g, b));
     let (r, g, b) = set_luminocity(r, g, b, lum);
 
@@ -439,7 +458,7 @@ pub(crate) fn hsl_color(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     blender(backdrop, src, opacity, hsl_color_baseline)
 }
 
-fn hsl_color_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn hsl_color_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     let (r, g, b) = as_rgb_f64(backdrop);
     let lum = luminosity(r, g, b);
 
@@ -457,7 +476,7 @@ pub(crate) fn hsl_luminosity(backdrop: Color8, src: Color8, opacity: u8) -> Colo
     blender(backdrop, src, opacity, hsl_luminosity_baseline)
 }
 
-fn hsl_luminosity_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
+pub(crate) fn hsl_luminosity_baseline(backdrop: Color8, src: Color8, opacity: u8) -> Color8 {
     let (r, g, b) = as_rgb_f64(src);
     let lum = luminosity(r, g, b);
 
@@ -626,13 +645,12 @@ fn static_sort3_orig(r: f64, g: f64, b: f64) -> (usize, usize, usize) {
     (min, mid, max)
 }
 
-// Ensure that we produce the same output as Aseprite, even though it's wrong.
-const ASEPRITE_SATURATION_BUG_COMPATIBLE: bool = true;
-
-fn set_saturation(r: f64, g: f64, b: f64, sat: f64) -> (f64, f64, f64) {
+// With `bug_compatible`, we produce the same output as Aseprite, even though
+// it's wrong.
+fn set_saturation(r: f64, g: f64, b: f64, sat: f64, bug_compatible: bool) -> (f64, f64, f64) {
     let mut col = [r, g, b];
 
-    let (min, mid, max) = if ASEPRITE_SATURATION_BUG_COMPATIBLE {
+    let (min, mid, max) = if bug_compatible {
         static_sort3_orig(r, g, b)
     } else {
         static_sort3(r, g, b)
@@ -649,13 +667,9 @@ fn set_saturation(r: f64, g: f64, b: f64, sat: f64) -> (f64, f64, f64) {
     (col[0], col[1], col[2])
 }
 
-// This test actually fails because Aseprite's version fails this test.
+// Only the spec version passes this test. Aseprite's version fails it.
 #[test]
 fn test_set_saturation() {
-    if ASEPRITE_SATURATION_BUG_COMPATIBLE {
-        // This fails for the Aseprite implementation
-        return;
-    }
     // Test that:
     //
     //     saturation(set_saturation(r, g, b, s) == s)
@@ -671,7 +685,7 @@ fn test_set_saturation() {
                         "* x = ({:.3}, {:.3}, {:.3}); x.sat() = {:.5}",
                         r, g, b, sat0
                     );
-                    let (r1, g1, b1) = set_saturation(r, g, b, sat);
+                    let (r1, g1, b1) = set_saturation(r, g, b, sat, false);
                     let sat1 = saturation(r1, g1, b1);
                     println!(
                         "  y = x.set_sat({:.5}); y = ({:.3}, {:.3}, {:.3}), y.sat() = {:.5}",
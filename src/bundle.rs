@@ -0,0 +1,406 @@
+//! Pre-decoded sprites in a single binary file.
+//!
+//! Parsing an Aseprite file means decompressing and blending every cel of
+//! every frame. A [Bundle] does this work ahead of time, e.g., in a build
+//! script: it stores the final frame images of many files together with their
+//! durations, tags and slices. Loading a bundle only has to decompress the
+//! pixels.
+//!
+//! [BundledSprite::from_file] creates the same type directly from a parsed
+//! file, so development builds can keep loading (and hot-reloading) the
+//! original `.aseprite` files:
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! use asefile::bundle::{Bundle, BundledSprite};
+//!
+//! // At build time.
+//! let mut bundle = Bundle::new();
+//! bundle.add_file("player", Path::new("./tests/data/layers_and_tags.aseprite")).unwrap();
+//! let mut bytes = Vec::new();
+//! bundle.write(&mut bytes).unwrap();
+//!
+//! // At runtime.
+//! fn load_player(bundle_bytes: &[u8]) -> BundledSprite {
+//!     if cfg!(debug_assertions) {
+//!         let path = Path::new("./tests/data/layers_and_tags.aseprite");
+//!         BundledSprite::from_file("player", &AsepriteFile::read_file(path).unwrap())
+//!     } else {
+//!         let bundle = Bundle::read(bundle_bytes).unwrap();
+//!         bundle.sprite("player").unwrap().clone()
+//!     }
+//! }
+//! let player = load_player(&bytes);
+//! assert_eq!(player.frames.len(), 4);
+//! assert!(player.tag_by_name("T3").is_some());
+//! ```
+//!
+//! User data, layers and cels are not included.
+
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use flate2::{write::ZlibEncoder, Compression};
+use image::RgbaImage;
+
+use crate::{
+    reader::AseReader, slice, tags, AsepriteFile, AsepriteParseError, Point, Result, Slice, Tag,
+};
+
+const MAGIC: &[u8; 4] = b"ASEB";
+const VERSION: u16 = 1;
+
+/// A single sprite in a [Bundle].
+#[derive(Debug, Clone)]
+pub struct BundledSprite {
+    /// The name the sprite was added with.
+    pub name: String,
+    /// Canvas width in pixels.
+    pub width: u32,
+    /// Canvas height in pixels.
+    pub height: u32,
+    /// All frames, in frame order.
+    pub frames: Vec<BundledFrame>,
+    /// All tags in the order they appear in the file.
+    pub tags: Vec<Tag>,
+    /// All slices in the order they appear in the file.
+    pub slices: Vec<Slice>,
+}
+
+/// The image and duration of a single frame of a [BundledSprite].
+#[derive(Debug, Clone)]
+pub struct BundledFrame {
+    /// The composited frame image. See [Frame::image](crate::Frame::image).
+    pub image: RgbaImage,
+    /// Frame duration in milliseconds.
+    pub duration_ms: u32,
+}
+
+impl BundledFrame {
+    /// How long this frame is displayed.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms as u64)
+    }
+}
+
+impl BundledSprite {
+    /// Compose all frames of a file.
+    pub fn from_file(name: &str, file: &AsepriteFile) -> Self {
+        let frames = (0..file.num_frames())
+            .map(|index| {
+                let frame = file.frame(index);
+                BundledFrame {
+                    image: frame.image(),
+                    duration_ms: frame.duration_ms(),
+                }
+            })
+            .collect();
+        let tags = file
+            .tags()
+            .map(|tag| {
+                let mut tag = tag.clone();
+                tag.user_data = None;
+                tag
+            })
+            .collect();
+        let slices = file
+            .slices()
+            .iter()
+            .map(|slice| Slice {
+                user_data: None,
+                ..slice.clone()
+            })
+            .collect();
+        BundledSprite {
+            name: name.to_owned(),
+            width: file.width() as u32,
+            height: file.height() as u32,
+            frames,
+            tags,
+            slices,
+        }
+    }
+
+    /// Lookup tag by name. If multiple tags have the same name, returns the
+    /// first one.
+    pub fn tag_by_name(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|tag| tag.name() == name)
+    }
+
+    /// Lookup slice by name. If multiple slices have the same name, returns
+    /// the first one.
+    pub fn slice_by_name(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+
+    fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        write_string(output, &self.name)?;
+        output.write_u32::<LittleEndian>(self.width)?;
+        output.write_u32::<LittleEndian>(self.height)?;
+        output.write_u32::<LittleEndian>(self.frames.len() as u32)?;
+        for frame in &self.frames {
+            output.write_u32::<LittleEndian>(frame.duration_ms)?;
+        }
+        write_block(output, &tags_chunk(&self.tags)?)?;
+        output.write_u32::<LittleEndian>(self.slices.len() as u32)?;
+        for slice in &self.slices {
+            write_block(output, &slice_chunk(slice)?)?;
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for frame in &self.frames {
+            encoder.write_all(frame.image.as_raw())?;
+        }
+        write_block(output, &encoder.finish()?)
+    }
+
+    fn read<R: Read>(reader: &mut AseReader<R>) -> Result<Self> {
+        let name = reader.string()?;
+        let width = reader.dword()?;
+        let height = reader.dword()?;
+        let num_frames = reader.dword()?;
+        let durations = (0..num_frames)
+            .map(|_| reader.dword())
+            .collect::<Result<Vec<_>>>()?;
+        let tags = tags::parse_chunk(&read_block(reader)?)?;
+        let num_slices = reader.dword()?;
+        let slices = (0..num_slices)
+            .map(|_| read_slice(reader))
+            .collect::<Result<Vec<_>>>()?;
+
+        let too_large =
+            || AsepriteParseError::InvalidInput(format!("Bundled sprite {} is too large", name));
+        let frame_size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|size| size.checked_mul(4))
+            .ok_or_else(too_large)?;
+        let expected_size = frame_size
+            .checked_mul(num_frames as usize)
+            .ok_or_else(too_large)?;
+        let compressed = read_block(reader)?;
+        // zlib expands data by at most a factor of about 1032, so a corrupt
+        // size cannot preallocate more than that.
+        let capacity = expected_size.min(compressed.len().saturating_mul(1032));
+        let pixels = AseReader::new(&compressed).unzip(capacity)?;
+        if pixels.len() != expected_size {
+            return Err(AsepriteParseError::InvalidInput(format!(
+                "Bundled sprite {} has {} bytes of pixel data, expected {}",
+                name,
+                pixels.len(),
+                expected_size
+            )));
+        }
+        // Frames of an empty canvas have no pixel data, but still have a
+        // duration.
+        let frames = durations
+            .into_iter()
+            .enumerate()
+            .map(|(index, duration_ms)| {
+                let data = &pixels[index * frame_size..(index + 1) * frame_size];
+                BundledFrame {
+                    image: RgbaImage::from_raw(width, height, data.to_vec())
+                        .expect("Frame size checked above"),
+                    duration_ms,
+                }
+            })
+            .collect();
+        Ok(BundledSprite {
+            name,
+            width,
+            height,
+            frames,
+            tags,
+            slices,
+        })
+    }
+}
+
+/// A collection of [BundledSprite]s that can be written to and loaded from a
+/// single binary file. See the [module docs](self) for an example.
+///
+/// The format is specific to this crate and may change between versions. Use
+/// the same version of this crate to write and read a bundle.
+#[derive(Debug, Clone, Default)]
+pub struct Bundle {
+    sprites: Vec<BundledSprite>,
+}
+
+impl Bundle {
+    /// Create an empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sprite. Replaces any sprite with the same name.
+    pub fn add(&mut self, sprite: BundledSprite) {
+        match self.sprites.iter_mut().find(|s| s.name == sprite.name) {
+            Some(existing) => *existing = sprite,
+            None => self.sprites.push(sprite),
+        }
+    }
+
+    /// Compose all frames of a parsed file and add them under the given name.
+    pub fn add_aseprite(&mut self, name: &str, file: &AsepriteFile) {
+        self.add(BundledSprite::from_file(name, file));
+    }
+
+    /// Load an Aseprite file and add it under the given name.
+    pub fn add_file(&mut self, name: &str, path: &Path) -> Result<()> {
+        let file = AsepriteFile::read_file(path)?;
+        self.add_aseprite(name, &file);
+        Ok(())
+    }
+
+    /// The sprite with the given name.
+    pub fn sprite(&self, name: &str) -> Option<&BundledSprite> {
+        self.sprites.iter().find(|sprite| sprite.name == name)
+    }
+
+    /// All sprites, in the order they were added.
+    pub fn sprites(&self) -> &[BundledSprite] {
+        &self.sprites
+    }
+
+    /// Write the bundle in its binary format.
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_u16::<LittleEndian>(VERSION)?;
+        output.write_u32::<LittleEndian>(self.sprites.len() as u32)?;
+        for sprite in &self.sprites {
+            sprite.write(&mut output)?;
+        }
+        Ok(())
+    }
+
+    /// Load a bundle written by [write](Self::write).
+    pub fn read<R: Read>(input: R) -> Result<Self> {
+        let mut reader = AseReader::with(input);
+        let mut magic = [0_u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(AsepriteParseError::InvalidInput(
+                "Not a sprite bundle".to_owned(),
+            ));
+        }
+        let version = reader.word()?;
+        if version != VERSION {
+            return Err(AsepriteParseError::UnsupportedFeature(format!(
+                "Sprite bundle version {}",
+                version
+            )));
+        }
+        let num_sprites = reader.dword()?;
+        let sprites = (0..num_sprites)
+            .map(|_| BundledSprite::read(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Bundle { sprites })
+    }
+}
+
+// Converts a value to the integer type of its field, or fails if it does not
+// fit.
+fn fit<T, U>(value: U, what: &str) -> io::Result<T>
+where
+    T: TryFrom<U>,
+    U: Copy + std::fmt::Display,
+{
+    T::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too large for a sprite bundle: {}", what, value),
+        )
+    })
+}
+
+fn write_string<W: Write>(output: &mut W, s: &str) -> io::Result<()> {
+    output.write_u16::<LittleEndian>(fit(s.len(), "String length")?)?;
+    output.write_all(s.as_bytes())
+}
+
+fn write_block<W: Write>(output: &mut W, data: &[u8]) -> io::Result<()> {
+    output.write_u32::<LittleEndian>(fit(data.len(), "Block size")?)?;
+    output.write_all(data)
+}
+
+// Like Aseprite, keys of a slice that has 9-slice or pivot information on
+// some of its keys store an empty center or `NO_PIVOT` if they have none.
+const NO_PIVOT: i32 = i32::MIN;
+
+fn read_slice<R: Read>(reader: &mut AseReader<R>) -> Result<Slice> {
+    let mut slice = slice::parse_chunk(&read_block(reader)?)?;
+    for key in &mut slice.keys {
+        if key
+            .slice9
+            .as_ref()
+            .is_some_and(|s| s.center_width == 0 || s.center_height == 0)
+        {
+            key.slice9 = None;
+        }
+        if key.pivot == Some(Point::new(NO_PIVOT, NO_PIVOT)) {
+            key.pivot = None;
+        }
+    }
+    Ok(slice)
+}
+
+fn read_block<R: Read>(reader: &mut AseReader<R>) -> Result<Vec<u8>> {
+    let len = reader.dword()?;
+    reader.bytes(len as usize)
+}
+
+// Tags and slices are stored in the layout of their Aseprite chunks, so they
+// can be read back with the regular parsers.
+
+fn tags_chunk(tags: &[Tag]) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    data.write_u16::<LittleEndian>(fit(tags.len(), "Number of tags")?)?;
+    data.write_all(&[0; 8])?;
+    for tag in tags {
+        data.write_u16::<LittleEndian>(fit(tag.from_frame(), "Tag frame")?)?;
+        data.write_u16::<LittleEndian>(fit(tag.to_frame(), "Tag frame")?)?;
+        data.write_u8(tag.animation_direction() as u8)?;
+        let repeat = tag.repeat().map_or(0, |r| r.get());
+        data.write_u16::<LittleEndian>(fit(repeat, "Tag repeat count")?)?;
+        data.write_all(&[0; 6])?;
+        data.write_u32::<LittleEndian>(0)?;
+        write_string(&mut data, tag.name())?;
+    }
+    Ok(data)
+}
+
+fn slice_chunk(slice: &Slice) -> io::Result<Vec<u8>> {
+    let has_slice9 = slice.keys.iter().any(|key| key.slice9.is_some());
+    let has_pivot = slice.keys.iter().any(|key| key.pivot.is_some());
+    let flags = has_slice9 as u32 | (has_pivot as u32) << 1;
+    let mut data = Vec::new();
+    data.write_u32::<LittleEndian>(fit(slice.keys.len(), "Number of slice keys")?)?;
+    data.write_u32::<LittleEndian>(flags)?;
+    data.write_u32::<LittleEndian>(0)?;
+    write_string(&mut data, &slice.name)?;
+    for key in &slice.keys {
+        data.write_u32::<LittleEndian>(key.from_frame)?;
+        data.write_i32::<LittleEndian>(key.origin.x)?;
+        data.write_i32::<LittleEndian>(key.origin.y)?;
+        data.write_u32::<LittleEndian>(key.size.w)?;
+        data.write_u32::<LittleEndian>(key.size.h)?;
+        if has_slice9 {
+            let center = key.slice9.as_ref().map_or((0, 0, 0, 0), |s| {
+                (s.center_x, s.center_y, s.center_width, s.center_height)
+            });
+            data.write_i32::<LittleEndian>(center.0)?;
+            data.write_i32::<LittleEndian>(center.1)?;
+            data.write_u32::<LittleEndian>(center.2)?;
+            data.write_u32::<LittleEndian>(center.3)?;
+        }
+        if has_pivot {
+            let pivot = key.pivot.map_or((NO_PIVOT, NO_PIVOT), |p| (p.x, p.y));
+            data.write_i32::<LittleEndian>(pivot.0)?;
+            data.write_i32::<LittleEndian>(pivot.1)?;
+        }
+    }
+    Ok(data)
+}
@@ -0,0 +1,157 @@
+//! A bounded cache for cel images.
+//!
+//! Composing a cel image with [Cel::image](crate::Cel::image) decodes and
+//! converts its pixels every time. Tools that show the same cels over and over
+//! again, e.g., while scrubbing through the timeline, can keep the results in a
+//! [CelImageCache]. The cache evicts the least recently used images once their
+//! total size exceeds a byte budget, so memory use stays predictable.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::cache::CelImageCache;
+//!
+//! // At most 16 MiB of images.
+//! let mut cache = CelImageCache::new(16 << 20);
+//! let image = cache.get(&ase, 0, 1);
+//! assert_eq!(image.dimensions(), (16, 16));
+//! assert!(cache.contains(0, 1));
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use image::RgbaImage;
+
+use crate::AsepriteFile;
+
+/// An LRU cache for [cel images](crate::Cel::image), keyed by frame and layer.
+///
+/// A cache only stores images of one file. Call [clear](Self::clear) before
+/// using it with a different file (or a new version of the same file).
+#[derive(Debug, Clone)]
+pub struct CelImageCache {
+    budget: usize,
+    bytes: usize,
+    // Incremented on every access. Used to order entries by last use.
+    tick: u64,
+    entries: HashMap<(u32, u32), Entry>,
+    // Maps the last use of each entry to its key.
+    lru: BTreeMap<u64, (u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    image: Arc<RgbaImage>,
+    last_used: u64,
+}
+
+impl CelImageCache {
+    /// Create an empty cache that holds at most `budget` bytes of pixel data.
+    pub fn new(budget: usize) -> Self {
+        CelImageCache {
+            budget,
+            bytes: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+        }
+    }
+
+    /// The image of the cel at the given frame and layer. Composes the image
+    /// if it is not in the cache yet.
+    ///
+    /// Images larger than the budget are returned, but not stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames` or if `layer` is not
+    /// less than `num_layers`.
+    pub fn get(&mut self, file: &AsepriteFile, frame: u32, layer: u32) -> Arc<RgbaImage> {
+        self.tick += 1;
+        let key = (frame, layer);
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.lru.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.lru.insert(self.tick, key);
+            return entry.image.clone();
+        }
+        let image = Arc::new(file.cel(frame, layer).image());
+        let size = image_bytes(&image);
+        if size > self.budget {
+            return image;
+        }
+        while self.bytes + size > self.budget {
+            self.evict_oldest();
+        }
+        self.bytes += size;
+        self.lru.insert(self.tick, key);
+        self.entries.insert(
+            key,
+            Entry {
+                image: image.clone(),
+                last_used: self.tick,
+            },
+        );
+        image
+    }
+
+    /// Returns `true` if the image of the cel at the given frame and layer is
+    /// in the cache. Does not count as a use.
+    pub fn contains(&self, frame: u32, layer: u32) -> bool {
+        self.entries.contains_key(&(frame, layer))
+    }
+
+    /// Number of cached images.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no images are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of the pixel data of all cached images in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// The maximum size of the cached pixel data in bytes.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Change the budget. Evicts the least recently used images until the
+    /// cache fits.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        while self.bytes > self.budget {
+            self.evict_oldest();
+        }
+    }
+
+    /// Remove all images.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+        self.bytes = 0;
+    }
+
+    fn evict_oldest(&mut self) {
+        let Some((_, key)) = self.lru.pop_first() else {
+            return;
+        };
+        if let Some(entry) = self.entries.remove(&key) {
+            self.bytes -= image_bytes(&entry.image);
+        }
+    }
+}
+
+fn image_bytes(image: &RgbaImage) -> usize {
+    image.as_raw().len()
+}
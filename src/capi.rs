@@ -0,0 +1,372 @@
+//! C bindings. (Requires feature `capi`.)
+//!
+//! Exposes a small C ABI for loading files, querying frames, layers and tags,
+//! and copying composited pixels. The matching header is
+//! `include/asefile.h`. To build a shared or static library, run
+//!
+//! ```text
+//! cargo rustc --release --features capi --crate-type cdylib
+//! cargo rustc --release --features capi --crate-type staticlib
+//! ```
+//!
+//! All functions that take an `AsefileHandle` pointer accept `NULL` and
+//! return `0` or `NULL` in that case. Out of range indices are handled the
+//! same way. The exceptions are [asefile_frame_rgba] and [asefile_cel_rgba],
+//! which return `-1`, and [asefile_tag_direction], which returns
+//! `UINT32_MAX`, because `0` is a valid result for them. Strings returned by the library are owned by the handle and stay
+//! valid until it is freed with [asefile_free].
+//!
+//! ```c
+//! AsefileHandle *ase = asefile_open("player.aseprite");
+//! if (!ase) {
+//!     fprintf(stderr, "%s\n", asefile_last_error());
+//!     return 1;
+//! }
+//! size_t len = 4 * asefile_width(ase) * asefile_height(ase);
+//! uint8_t *pixels = malloc(len);
+//! asefile_frame_rgba(ase, 0, pixels, len);
+//! asefile_free(ase);
+//! ```
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr, slice,
+};
+
+use crate::{AnimationDirection, AsepriteFile, AsepriteParseError};
+
+/// An opened file plus the C strings handed out for it.
+pub struct AsefileHandle {
+    file: AsepriteFile,
+    layer_names: Vec<CString>,
+    tag_names: Vec<CString>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: &str) {
+    // Interior NUL bytes cannot be represented, so drop them.
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = message);
+}
+
+// Converts a parse result into a handle, recording errors and panics.
+fn into_handle<F>(load: F) -> *mut AsefileHandle
+where
+    F: FnOnce() -> crate::Result<AsepriteFile>,
+{
+    let result = panic::catch_unwind(AssertUnwindSafe(load)).unwrap_or_else(|_| {
+        Err(AsepriteParseError::InternalError(
+            "Panic while parsing".to_owned(),
+        ))
+    });
+    match result {
+        Ok(file) => {
+            let c_string = |name: &str| CString::new(name.replace('\0', "")).unwrap_or_default();
+            let layer_names = file.layers().map(|layer| c_string(layer.name())).collect();
+            let tag_names = file.tags().map(|tag| c_string(tag.name())).collect();
+            Box::into_raw(Box::new(AsefileHandle {
+                file,
+                layer_names,
+                tag_names,
+            }))
+        }
+        Err(error) => {
+            set_last_error(&error.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Open the file at the given path. Returns `NULL` on error, see
+/// [asefile_last_error].
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a valid NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_open(path: *const c_char) -> *mut AsefileHandle {
+    if path.is_null() {
+        set_last_error("Path is NULL");
+        return ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => {
+            set_last_error("Path is not valid UTF-8");
+            return ptr::null_mut();
+        }
+    };
+    into_handle(|| AsepriteFile::read_file(Path::new(path)))
+}
+
+/// Parse a file from memory. The data is copied, so it can be freed after
+/// this call. Returns `NULL` on error, see [asefile_last_error].
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_open_memory(data: *const u8, len: usize) -> *mut AsefileHandle {
+    if data.is_null() {
+        set_last_error("Data is NULL");
+        return ptr::null_mut();
+    }
+    let data = slice::from_raw_parts(data, len);
+    into_handle(|| AsepriteFile::read(data))
+}
+
+/// Free a handle returned by [asefile_open] or [asefile_open_memory].
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a handle that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_free(handle: *mut AsefileHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// The message of the last error on the calling thread, or an empty string.
+/// The pointer stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn asefile_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+// Helper for turning a handle pointer into a file reference.
+unsafe fn file<'a>(handle: *const AsefileHandle) -> Option<&'a AsefileHandle> {
+    handle.as_ref()
+}
+
+/// Canvas width in pixels.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_width(handle: *const AsefileHandle) -> u32 {
+    file(handle).map_or(0, |h| h.file.width() as u32)
+}
+
+/// Canvas height in pixels.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_height(handle: *const AsefileHandle) -> u32 {
+    file(handle).map_or(0, |h| h.file.height() as u32)
+}
+
+/// Number of frames.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_num_frames(handle: *const AsefileHandle) -> u32 {
+    file(handle).map_or(0, |h| h.file.num_frames())
+}
+
+/// Duration of the given frame in milliseconds.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_frame_duration_ms(
+    handle: *const AsefileHandle,
+    frame: u32,
+) -> u32 {
+    file(handle)
+        .filter(|h| frame < h.file.num_frames())
+        .map_or(0, |h| h.file.frame(frame).duration_ms())
+}
+
+/// Copy the composited image of a frame into `out` as RGBA with 8 bits per
+/// channel, row by row. `out_len` must be at least `4 * width * height`.
+///
+/// Returns `0` on success and `-1` if an argument is invalid.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle. `out` must point to `out_len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_frame_rgba(
+    handle: *const AsefileHandle,
+    frame: u32,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    let Some(handle) = file(handle).filter(|h| frame < h.file.num_frames()) else {
+        return -1;
+    };
+    copy_pixels(|| handle.file.frame(frame).image().into_raw(), out, out_len)
+}
+
+/// Like [asefile_frame_rgba], but only copies the pixels of a single layer.
+///
+/// # Safety
+///
+/// Same as [asefile_frame_rgba].
+#[no_mangle]
+pub unsafe extern "C" fn asefile_cel_rgba(
+    handle: *const AsefileHandle,
+    frame: u32,
+    layer: u32,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    let Some(handle) =
+        file(handle).filter(|h| frame < h.file.num_frames() && layer < h.file.num_layers())
+    else {
+        return -1;
+    };
+    copy_pixels(
+        || handle.file.cel(frame, layer).image().into_raw(),
+        out,
+        out_len,
+    )
+}
+
+unsafe fn copy_pixels<F>(render: F, out: *mut u8, out_len: usize) -> i32
+where
+    F: FnOnce() -> Vec<u8>,
+{
+    if out.is_null() {
+        return -1;
+    }
+    let pixels = match panic::catch_unwind(AssertUnwindSafe(render)) {
+        Ok(pixels) => pixels,
+        Err(_) => {
+            set_last_error("Panic while rendering");
+            return -1;
+        }
+    };
+    if out_len < pixels.len() {
+        set_last_error("Output buffer too small");
+        return -1;
+    }
+    ptr::copy_nonoverlapping(pixels.as_ptr(), out, pixels.len());
+    0
+}
+
+/// Number of layers.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_num_layers(handle: *const AsefileHandle) -> u32 {
+    file(handle).map_or(0, |h| h.file.num_layers())
+}
+
+/// Name of the given layer.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_layer_name(
+    handle: *const AsefileHandle,
+    layer: u32,
+) -> *const c_char {
+    file(handle)
+        .and_then(|h| h.layer_names.get(layer as usize))
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Returns `1` if the layer and all its parents are visible, `0` otherwise.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_layer_is_visible(handle: *const AsefileHandle, layer: u32) -> i32 {
+    file(handle)
+        .filter(|h| layer < h.file.num_layers())
+        .map_or(0, |h| h.file.layer(layer).is_visible() as i32)
+}
+
+/// Layer opacity from 0 to 255.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_layer_opacity(handle: *const AsefileHandle, layer: u32) -> u32 {
+    file(handle)
+        .filter(|h| layer < h.file.num_layers())
+        .map_or(0, |h| h.file.layer(layer).opacity() as u32)
+}
+
+/// Number of tags.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_num_tags(handle: *const AsefileHandle) -> u32 {
+    file(handle).map_or(0, |h| h.file.num_tags())
+}
+
+/// Name of the given tag.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_tag_name(handle: *const AsefileHandle, tag: u32) -> *const c_char {
+    file(handle)
+        .and_then(|h| h.tag_names.get(tag as usize))
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// First frame of the given tag.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_tag_from_frame(handle: *const AsefileHandle, tag: u32) -> u32 {
+    file(handle)
+        .and_then(|h| h.file.get_tag(tag))
+        .map_or(0, |tag| tag.from_frame())
+}
+
+/// Last frame of the given tag.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_tag_to_frame(handle: *const AsefileHandle, tag: u32) -> u32 {
+    file(handle)
+        .and_then(|h| h.file.get_tag(tag))
+        .map_or(0, |tag| tag.to_frame())
+}
+
+/// Animation direction of the given tag: `0` forward, `1` reverse, `2`
+/// ping-pong. Returns `UINT32_MAX` if an argument is invalid.
+///
+/// # Safety
+///
+/// `handle` must be `NULL` or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn asefile_tag_direction(handle: *const AsefileHandle, tag: u32) -> u32 {
+    file(handle)
+        .and_then(|h| h.file.get_tag(tag))
+        .map_or(u32::MAX, |tag| match tag.animation_direction() {
+            AnimationDirection::Forward => 0,
+            AnimationDirection::Reverse => 1,
+            AnimationDirection::PingPong => 2,
+        })
+}
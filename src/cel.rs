@@ -1,10 +1,11 @@
 use crate::layer::LayerType;
-use crate::pixel::{Pixels, RawPixels};
+use crate::pixel::{PixelInterner, Pixels, RawPixels};
 use crate::reader::AseReader;
 use crate::tilemap::TilemapData;
 use crate::user_data::UserData;
 use crate::{
-    layer::LayersData, AsepriteFile, AsepriteParseError, ColorPalette, PixelFormat, Result,
+    layer::LayersData, AsepriteFile, AsepriteParseError, ColorPalette, PixelFormat, Point, Rect,
+    Result, Size,
 };
 
 use image::RgbaImage;
@@ -61,9 +62,47 @@ impl<'a> Cel<'a> {
     ///
     /// These may be negative or outside of the visible area. This can happen if
     /// you drag a layer around.
-    pub fn top_left(&self) -> (i32, i32) {
-        self.raw_cel()
-            .map_or_else(|| (0, 0), |raw| (raw.data.x as i32, raw.data.y as i32))
+    pub fn top_left(&self) -> Point {
+        self.raw_cel().map_or_else(Point::default, |raw| {
+            Point::new(raw.data.x as i32, raw.data.y as i32)
+        })
+    }
+
+    /// The non-empty rectangular area of the cel in canvas coordinates, or
+    /// `None` if the cel is empty.
+    ///
+    /// Like [top_left](Self::top_left), this may extend beyond the canvas.
+    pub fn bounds(&self) -> Option<Rect> {
+        let raw = self.raw_cel()?;
+        let content = match raw.content {
+            CelContent::Linked(frame) => {
+                &self
+                    .file
+                    .framedata
+                    .cel(CelId {
+                        frame,
+                        layer: self.cel_id.layer,
+                    })?
+                    .content
+            }
+            ref content => content,
+        };
+        let size = match content {
+            CelContent::Raw(image) => Size::new(image.size.width as u32, image.size.height as u32),
+            CelContent::Tilemap(tilemap) => {
+                let LayerType::Tilemap(tileset_id) = self.file.layer(self.layer()).layer_type()
+                else {
+                    return None;
+                };
+                let tile_size = self.file.tilesets().get(tileset_id)?.tile_size();
+                Size::new(
+                    tilemap.width() as u32 * tile_size.width() as u32,
+                    tilemap.height() as u32 * tile_size.height() as u32,
+                )
+            }
+            CelContent::Linked(_) => return None,
+        };
+        Some(Rect::from_origin_size(self.top_left(), size))
     }
 
     /// Does this cel include a tilemap.
@@ -81,7 +120,268 @@ impl<'a> Cel<'a> {
     }
 }
 
+/// A mutable reference to a single cel. Created via [AsepriteFile::cel_mut].
+///
+/// Changes only affect the file in memory. Linked cels share their pixels,
+/// position and opacity, as in Aseprite, so changing them via one of the
+/// linked cels changes all of them. Use [unlink](Self::unlink) first to only
+/// change a single cel.
+///
+/// The cels of all frames are shared between clones of a file as a whole. The
+/// first change to a cel of a clone therefore copies the position, opacity,
+/// user data and tilemap data of every cel in the file. Image pixels are
+/// shared separately and are not copied.
+///
+/// ```
+/// # use asefile::{AsepriteFile, Point};
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// use image::{Rgba, RgbaImage};
+///
+/// let mut ase = AsepriteFile::read_file(&path).unwrap();
+/// let stamp = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+/// ase.cel_mut(0, 1).set_image(Point::new(3, 4), &stamp).unwrap();
+/// assert_eq!(*ase.frame(0).image().get_pixel(4, 5), Rgba([255, 0, 0, 255]));
+/// ```
+#[derive(Debug)]
+pub struct CelMut<'a> {
+    pub(crate) file: &'a mut AsepriteFile,
+    pub(crate) cel_id: CelId,
+}
+
+impl<'a> CelMut<'a> {
+    /// A read-only reference to the same cel.
+    pub fn as_cel(&self) -> Cel<'_> {
+        Cel {
+            file: self.file,
+            cel_id: self.cel_id,
+        }
+    }
+
+    /// The frame coordinate of this cel.
+    pub fn frame(&self) -> u32 {
+        self.cel_id.frame as u32
+    }
+
+    /// The layer coordinate of this cel.
+    pub fn layer(&self) -> u32 {
+        self.cel_id.layer as u32
+    }
+
+    /// Replace the pixels of the cel with `image` and move its top left
+    /// corner to `position`.
+    ///
+    /// The image is converted to the pixel format of the file. In indexed
+    /// files, transparent pixels become the transparent color and all other
+    /// pixels the palette color with the same value, or else the nearest
+    /// palette color (see [ColorDistance::Rgb](crate::ColorDistance::Rgb)).
+    ///
+    /// Creates the cel if it is empty. Fails if the cel is not on an image
+    /// layer or if the image or position exceed the limits of the file format.
+    pub fn set_image(&mut self, position: Point, image: &RgbaImage) -> Result<()> {
+        let layer_is_background = self.check_image_layer()?;
+        let size = image_size(image.width(), image.height())?;
+        let pixels = Pixels::from_rgba(
+            image.pixels().copied().collect(),
+            self.file.pixel_format,
+            self.file.palette.as_ref(),
+            layer_is_background,
+        )?;
+        self.set_content(position, ImageContent { size, pixels })
+    }
+
+    /// Like [set_image](Self::set_image), but takes palette indices (row by
+    /// row) instead of colors. Only works in indexed files.
+    pub fn set_indexed_image(
+        &mut self,
+        position: Point,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let layer_is_background = self.check_image_layer()?;
+        let size = image_size(width, height)?;
+        let PixelFormat::Indexed {
+            transparent_color_index,
+        } = self.file.pixel_format
+        else {
+            return Err(AsepriteParseError::InvalidInput(format!(
+                "Cannot set indexed pixels in a file with pixel format {:?}",
+                self.file.pixel_format
+            )));
+        };
+        if data.len() != size.pixel_count() {
+            return Err(AsepriteParseError::InvalidInput(format!(
+                "Expected {} indexed pixels, got {}",
+                size.pixel_count(),
+                data.len()
+            )));
+        }
+        let palette = self
+            .file
+            .palette
+            .clone()
+            .ok_or(AsepriteParseError::MissingPalette)?;
+        palette.validate_indexed_pixels(&data)?;
+        let pixels = Pixels::Indexed {
+            palette,
+            transparent_color_index,
+            layer_is_background,
+            data: Arc::new(data),
+        };
+        self.set_content(position, ImageContent { size, pixels })
+    }
+
+    /// Move the top left corner of the cel to `position`. Does nothing if the
+    /// cel is empty.
+    pub fn set_position(&mut self, position: Point) -> Result<()> {
+        let (x, y) = cel_position(position)?;
+        self.update_linked(|data| {
+            data.x = x;
+            data.y = y;
+        });
+        Ok(())
+    }
+
+    /// Change the opacity of the cel. Does nothing if the cel is empty.
+    pub fn set_opacity(&mut self, opacity: u8) {
+        self.update_linked(|data| data.opacity = opacity);
+    }
+
+    /// Replace the cel's [UserData]. `None` removes it. Does nothing if the
+    /// cel is empty.
+    ///
+    /// Unlike the pixels, user data is not shared by linked cels.
+    pub fn set_user_data(&mut self, user_data: Option<UserData>) {
+        let cel_id = self.cel_id;
+        if let Some(cel) = self.cels().cel_mut(&cel_id) {
+            cel.user_data = user_data;
+        }
+    }
+
+    /// Turn a linked cel into a copy of the cel it links to, so that it can
+    /// be changed on its own. Does nothing if the cel is not linked.
+    pub fn unlink(&mut self) {
+        let cel_id = self.cel_id;
+        let target = self.target_id();
+        if target.frame == cel_id.frame {
+            return;
+        }
+        let cels = self.cels();
+        let Some(source) = cels.cel(target).cloned() else {
+            return;
+        };
+        let cel = cels.cel_mut(&cel_id).expect("Linked cel exists");
+        cel.data = source.data;
+        cel.content = source.content;
+    }
+
+    /// Remove the cel. Cels that are linked to it keep its content.
+    pub fn clear(&mut self) {
+        let cel_id = self.cel_id;
+        let cels = self.cels();
+        cels.remove_cel(cel_id);
+    }
+
+    // The cel that holds the content, i.e., the link target of linked cels.
+    fn target_id(&self) -> CelId {
+        match self.file.framedata.cel(self.cel_id).map(|c| &c.content) {
+            Some(CelContent::Linked(frame)) => CelId {
+                frame: *frame,
+                layer: self.cel_id.layer,
+            },
+            _ => self.cel_id,
+        }
+    }
+
+    // Applies `update` to the cel and all cels it is linked with.
+    fn update_linked<F>(&mut self, mut update: F)
+    where
+        F: FnMut(&mut CelCommon),
+    {
+        let target = self.target_id();
+        let num_frames = self.file.num_frames;
+        let cels = self.cels();
+        let mut frames = cels.linked_frames(target, num_frames);
+        frames.push(target.frame);
+        for frame in frames {
+            let id = CelId {
+                frame,
+                layer: target.layer,
+            };
+            if let Some(cel) = cels.cel_mut(&id) {
+                update(&mut cel.data);
+            }
+        }
+    }
+
+    fn cels(&mut self) -> &mut CelsData<Pixels> {
+        Arc::make_mut(&mut self.file.framedata)
+    }
+
+    // Returns whether the layer is a background layer.
+    fn check_image_layer(&self) -> Result<bool> {
+        let layer = &self.file.layers[self.cel_id.layer as u32];
+        match layer.layer_type {
+            LayerType::Image => Ok(layer.is_background()),
+            _ => Err(AsepriteParseError::InvalidInput(format!(
+                "Cannot set pixels of layer {}, which is not an image layer",
+                self.cel_id.layer
+            ))),
+        }
+    }
+
+    fn set_content(&mut self, position: Point, content: ImageContent<Pixels>) -> Result<()> {
+        let (x, y) = cel_position(position)?;
+        let target = self.target_id();
+        self.update_linked(|data| {
+            data.x = x;
+            data.y = y;
+        });
+        let cels = self.cels();
+        match cels.cel_mut(&target) {
+            Some(cel) => cel.content = CelContent::Raw(content),
+            None => {
+                let data = CelCommon {
+                    layer_index: target.layer,
+                    x,
+                    y,
+                    opacity: 255,
+                };
+                let cel = RawCel {
+                    data,
+                    content: CelContent::Raw(content),
+                    user_data: None,
+                };
+                cels.set_cel(target, Some(cel));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn cel_position(position: Point) -> Result<(i16, i16)> {
+    match (i16::try_from(position.x), i16::try_from(position.y)) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(AsepriteParseError::InvalidInput(format!(
+            "Cel position out of range: {:?}",
+            position
+        ))),
+    }
+}
+
+fn image_size(width: u32, height: u32) -> Result<ImageSize> {
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok(ImageSize { width, height }),
+        _ => Err(AsepriteParseError::InvalidInput(format!(
+            "Cel size out of range: {}x{}",
+            width, height
+        ))),
+    }
+}
+
 /// Organizes all Cels into a 2d array.
+#[derive(Clone)]
 pub(crate) struct CelsData<P> {
     // Mapping: frame_id -> layer_id -> Option<RawCel>
     data: Vec<Vec<Option<RawCel<P>>>>,
@@ -150,10 +450,10 @@ impl<P> CelsData<P> {
             layers.resize_with(min_layers as usize, || None);
         }
         if layers[layer_id as usize].is_some() {
-            return Err(AsepriteParseError::InvalidInput(format!(
-                "Multiple Cels for frame {}, layer {}",
-                frame_id, layer_id
-            )));
+            return Err(AsepriteParseError::DuplicateCel {
+                frame: frame_id as u32,
+                layer: layer_id as u32,
+            });
         }
         layers[layer_id as usize] = Some(cel);
 
@@ -179,6 +479,122 @@ impl<P> CelsData<P> {
         }
     }
 
+    pub(crate) fn cels_mut(&mut self) -> impl Iterator<Item = &mut RawCel<P>> {
+        self.data.iter_mut().flatten().flatten()
+    }
+
+    // Frame ID must be valid. Grows the layers of the frame if necessary.
+    pub(crate) fn set_cel(&mut self, cel_id: CelId, cel: Option<RawCel<P>>) {
+        let layers = &mut self.data[cel_id.frame as usize];
+        if layers.len() <= cel_id.layer as usize {
+            layers.resize_with(cel_id.layer as usize + 1, || None);
+        }
+        layers[cel_id.layer as usize] = cel;
+    }
+
+    // Moves cels to new layers after the layer structure changed. `mapping`
+    // maps old to new layer IDs. Cels of layers mapped to `None` are dropped.
+    pub(crate) fn remap_layers(&mut self, mapping: &[Option<u32>]) {
+        for layers in &mut self.data {
+            let mut remapped = Vec::new();
+            for (old, cel) in layers.drain(..).enumerate() {
+                let (Some(mut cel), Some(Some(new))) = (cel, mapping.get(old)) else {
+                    continue;
+                };
+                let new = *new as usize;
+                if remapped.len() <= new {
+                    remapped.resize_with(new + 1, || None);
+                }
+                cel.data.layer_index = new as u16;
+                remapped[new] = Some(cel);
+            }
+            *layers = remapped;
+        }
+    }
+
+    // Inserts a frame with the given cels (indexed by layer). Links to frames
+    // at or after `frame_id` are updated, the new cels must already use the
+    // new frame numbers.
+    pub(crate) fn insert_frame(&mut self, frame_id: u16, cels: Vec<Option<RawCel<P>>>) {
+        for cel in self.cels_mut() {
+            if let CelContent::Linked(target) = &mut cel.content {
+                if *target >= frame_id {
+                    *target += 1;
+                }
+            }
+        }
+        self.data.insert(frame_id as usize, cels);
+        self.num_frames += 1;
+    }
+
+    // Removes a frame and its cels. Cels linked to a removed cel take over
+    // its content.
+    pub(crate) fn remove_frame(&mut self, frame_id: u16) {
+        for layer in 0..self.data[frame_id as usize].len() {
+            self.remove_cel(CelId {
+                frame: frame_id,
+                layer: layer as u16,
+            });
+        }
+        self.data.remove(frame_id as usize);
+        self.num_frames -= 1;
+        for cel in self.cels_mut() {
+            if let CelContent::Linked(target) = &mut cel.content {
+                if *target > frame_id {
+                    *target -= 1;
+                }
+            }
+        }
+    }
+
+    // Removes a cel. The first cel linked to it takes over its content, the
+    // others link to that cel instead.
+    fn remove_cel(&mut self, cel_id: CelId) {
+        let layers = &mut self.data[cel_id.frame as usize];
+        let Some(cel) = layers.get_mut(cel_id.layer as usize).and_then(Option::take) else {
+            return;
+        };
+        if matches!(cel.content, CelContent::Linked(_)) {
+            return;
+        }
+        let linked_frames = self.linked_frames(cel_id, self.data.len() as u16);
+        let Some((&owner, others)) = linked_frames.split_first() else {
+            return;
+        };
+        let RawCel { data, content, .. } = cel;
+        let owner_cel = self
+            .cel_mut(&CelId {
+                frame: owner,
+                layer: cel_id.layer,
+            })
+            .expect("Linked cel exists");
+        owner_cel.data = data;
+        owner_cel.content = content;
+        for &frame in others {
+            let id = CelId {
+                frame,
+                layer: cel_id.layer,
+            };
+            self.cel_mut(&id).expect("Linked cel exists").content = CelContent::Linked(owner);
+        }
+    }
+
+    // Frames of the cels in the same layer that link to the given cel.
+    fn linked_frames(&self, cel_id: CelId, num_frames: u16) -> Vec<u16> {
+        (0..num_frames)
+            .filter(|&frame| {
+                let id = CelId {
+                    frame,
+                    layer: cel_id.layer,
+                };
+                matches!(
+                    self.cel(id).map(|c| &c.content),
+                    Some(CelContent::Linked(target)) if *target == cel_id.frame
+                )
+            })
+            .collect()
+    }
+
     pub(crate) fn cel_mut(&mut self, cel_id: &CelId) -> Option<&mut RawCel<P>> {
         let frame = cel_id.frame;
         let layer = cel_id.layer;
@@ -262,7 +678,7 @@ impl CelsData<RawPixels> {
                     frame: frame as u16,
                     layer: layer as u16,
                 };
-                is_linkable_cel.push(self.cel(cel_id).map_or(false, |c| c.content.is_raw()));
+                is_linkable_cel.push(self.cel(cel_id).is_some_and(|c| c.content.is_raw()));
             }
         }
         let validate_ref = |id: CelId| {
@@ -278,6 +694,7 @@ impl CelsData<RawPixels> {
         };
 
         // Validate and transform each cel. Consumes input arrays.
+        let mut interner = PixelInterner::default();
         for (frame, cels_by_layer) in self.data.into_iter().enumerate() {
             result.data.push(Vec::with_capacity(cels_by_layer.len()));
             for (layer, opt_cel) in cels_by_layer.into_iter().enumerate() {
@@ -286,13 +703,12 @@ impl CelsData<RawPixels> {
                         frame: frame as u16,
                         layer: layer as u16,
                     };
-                    Some(cel.validate(
-                        cel_id,
-                        layers,
-                        pixel_format,
-                        palette.clone(),
-                        &validate_ref,
-                    )?)
+                    let mut cel =
+                        cel.validate(cel_id, layers, pixel_format, palette.clone(), &validate_ref)?;
+                    if let CelContent::Raw(image) = &mut cel.content {
+                        interner.intern(&mut image.pixels);
+                    }
+                    Some(cel)
                 } else {
                     None
                 };
@@ -323,7 +739,7 @@ impl ImageSize {
 }
 
 // CelData holds fields which are common to all cel types.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct CelCommon {
     pub layer_index: u16,
     pub x: i16,
@@ -346,7 +762,7 @@ impl CelCommon {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct ImageContent<P> {
     pub size: ImageSize,
     pub pixels: P,
@@ -368,7 +784,7 @@ impl ImageContent<RawPixels> {
 }
 
 // CelContent holds data specific to each type of cel.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum CelContent<P> {
     Raw(ImageContent<P>),
     Linked(u16),
@@ -406,7 +822,7 @@ impl CelContent<RawPixels> {
 //     }
 // }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct RawCel<P = Pixels> {
     pub data: CelCommon,
     pub content: CelContent<P>,
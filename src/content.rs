@@ -0,0 +1,545 @@
+// Content hashes and structural equality.
+//
+// Both are based on a canonical byte representation of the parsed content,
+// written by `ContentWriter`. All integers are written as little endian with a
+// fixed width, so hashes are the same on every platform and in every run. They
+// only change if the parsed content changes (or with a new version of this
+// crate). Hashes use 64 bit FNV-1a.
+
+use crate::{
+    cel::{CelContent, CelId, RawCel},
+    layer::LayerType,
+    pixel::Pixels,
+    AsepriteFile, Layer, PixelFormat, Properties, PropertyValue, Rect, Slice, Tag, Tileset,
+    UserData,
+};
+use image::RgbaImage;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+trait Sink {
+    fn write(&mut self, bytes: &[u8]);
+}
+
+struct Fnv(u64);
+
+impl Sink for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+impl Sink for Vec<u8> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+struct ContentWriter<S> {
+    out: S,
+}
+
+impl<S: Sink> ContentWriter<S> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.out.write(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    // Writes a tag for `None` and a different one followed by the value for
+    // `Some`, so that `None` and `Some(<empty>)` differ.
+    fn option<T, F>(&mut self, value: Option<T>, hash: F)
+    where
+        F: FnOnce(&mut Self, T),
+    {
+        match value {
+            None => self.u8(0),
+            Some(value) => {
+                self.u8(1);
+                hash(self, value);
+            }
+        }
+    }
+
+    fn rect(&mut self, rect: Rect) {
+        self.i32(rect.x);
+        self.i32(rect.y);
+        self.u32(rect.w);
+        self.u32(rect.h);
+    }
+
+    fn pixel_format(&mut self, pixel_format: PixelFormat) {
+        match pixel_format {
+            PixelFormat::Rgba => self.u8(0),
+            PixelFormat::Grayscale => self.u8(1),
+            PixelFormat::Indexed {
+                transparent_color_index,
+            } => {
+                self.u8(2);
+                self.u8(transparent_color_index);
+            }
+        }
+    }
+
+    fn palette(&mut self, file: &AsepriteFile, with_names: bool) {
+        self.option(file.palette(), |w, palette| {
+            w.len(palette.num_colors() as usize);
+            for entry in palette.iter() {
+                w.u32(entry.id());
+                w.bytes(&entry.raw_rgba8());
+                if with_names {
+                    w.option(entry.name(), Self::str);
+                }
+            }
+        });
+    }
+
+    fn pixels(&mut self, pixels: &Pixels) {
+        match pixels {
+            Pixels::Rgba(pixels) => {
+                self.u8(0);
+                self.len(pixels.len());
+                for pixel in pixels.iter() {
+                    self.bytes(&pixel.0);
+                }
+            }
+            Pixels::Grayscale(pixels) => {
+                self.u8(1);
+                self.len(pixels.len());
+                for pixel in pixels.iter() {
+                    let [value, _, _, alpha] = pixel.into_rgba().0;
+                    self.bytes(&[value, alpha]);
+                }
+            }
+            Pixels::Indexed {
+                transparent_color_index,
+                layer_is_background,
+                data,
+                // The palette is written once per frame.
+                palette: _,
+            } => {
+                self.u8(2);
+                self.u8(*transparent_color_index);
+                self.bool(*layer_is_background);
+                self.len(data.len());
+                self.bytes(data);
+            }
+        }
+    }
+
+    fn user_data(&mut self, user_data: Option<&UserData>) {
+        self.option(user_data, |w, user_data| {
+            w.option(user_data.text.as_deref(), Self::str);
+            w.option(user_data.color, |w, color| w.bytes(&color.0));
+            w.properties(&user_data.properties);
+            w.len(user_data.extension_properties.len());
+            for (id, properties) in &user_data.extension_properties {
+                w.u32(id.value());
+                w.properties(properties);
+            }
+        });
+    }
+
+    fn properties(&mut self, properties: &Properties) {
+        self.len(properties.len());
+        for (name, value) in properties.iter() {
+            self.str(name);
+            self.property_value(value);
+        }
+    }
+
+    fn property_value(&mut self, value: &PropertyValue) {
+        match value {
+            PropertyValue::Bool(v) => {
+                self.u8(0);
+                self.bool(*v);
+            }
+            PropertyValue::I8(v) => {
+                self.u8(1);
+                self.u8(*v as u8);
+            }
+            PropertyValue::U8(v) => {
+                self.u8(2);
+                self.u8(*v);
+            }
+            PropertyValue::I16(v) => {
+                self.u8(3);
+                self.u16(*v as u16);
+            }
+            PropertyValue::U16(v) => {
+                self.u8(4);
+                self.u16(*v);
+            }
+            PropertyValue::I32(v) => {
+                self.u8(5);
+                self.i32(*v);
+            }
+            PropertyValue::U32(v) => {
+                self.u8(6);
+                self.u32(*v);
+            }
+            PropertyValue::I64(v) => {
+                self.u8(7);
+                self.u64(*v as u64);
+            }
+            PropertyValue::U64(v) => {
+                self.u8(8);
+                self.u64(*v);
+            }
+            PropertyValue::Fixed(v) => {
+                self.u8(9);
+                self.u64(v.to_bits());
+            }
+            PropertyValue::F32(v) => {
+                self.u8(10);
+                self.u32(v.to_bits());
+            }
+            PropertyValue::F64(v) => {
+                self.u8(11);
+                self.u64(v.to_bits());
+            }
+            PropertyValue::String(v) => {
+                self.u8(12);
+                self.str(v);
+            }
+            PropertyValue::Point(v) => {
+                self.u8(13);
+                self.i32(v.x);
+                self.i32(v.y);
+            }
+            PropertyValue::Size(v) => {
+                self.u8(14);
+                self.u32(v.w);
+                self.u32(v.h);
+            }
+            PropertyValue::Rect(v) => {
+                self.u8(15);
+                self.rect(*v);
+            }
+            PropertyValue::Vector(values) => {
+                self.u8(16);
+                self.len(values.len());
+                for value in values {
+                    self.property_value(value);
+                }
+            }
+            PropertyValue::Properties(v) => {
+                self.u8(17);
+                self.properties(v);
+            }
+            PropertyValue::Uuid(v) => {
+                self.u8(18);
+                self.bytes(v);
+            }
+        }
+    }
+
+    fn canvas(&mut self, file: &AsepriteFile, with_palette_names: bool) {
+        self.u16(file.width);
+        self.u16(file.height);
+        self.pixel_format(file.pixel_format);
+        self.palette(file, with_palette_names);
+    }
+
+    fn layer_properties(&mut self, layer: Layer<'_>) {
+        self.str(layer.name());
+        self.u32(layer.flags().bits());
+        self.u8(layer.blend_mode() as u8);
+        self.u8(layer.opacity());
+        match layer.layer_type() {
+            LayerType::Image => self.u8(0),
+            LayerType::Group => self.u8(1),
+            LayerType::Tilemap(tileset_id) => {
+                self.u8(2);
+                self.u32(tileset_id.value());
+            }
+        }
+        self.option(layer.parent(), |w, parent| w.u32(parent.id()));
+    }
+
+    fn tag(&mut self, tag: &Tag) {
+        self.str(tag.name());
+        self.u32(tag.from_frame());
+        self.u32(tag.to_frame());
+        self.u8(tag.animation_direction() as u8);
+        self.option(tag.repeat(), |w, repeat| w.u32(repeat.get()));
+        self.user_data(tag.user_data());
+    }
+
+    fn slice(&mut self, slice: &Slice) {
+        self.str(&slice.name);
+        self.len(slice.keys.len());
+        for key in &slice.keys {
+            self.u32(key.from_frame);
+            self.rect(key.bounds());
+            self.option(key.slice9.as_ref(), |w, slice9| w.rect(slice9.center()));
+            self.option(key.pivot, |w, pivot| {
+                w.i32(pivot.x);
+                w.i32(pivot.y);
+            });
+        }
+        self.user_data(slice.user_data.as_ref());
+    }
+
+    // Everything that affects the tile images.
+    fn tileset_pixels(&mut self, tileset: &Tileset) {
+        self.u32(tileset.id().value());
+        self.bool(tileset.empty_tile_is_id_zero());
+        self.u32(tileset.tile_count());
+        self.u16(tileset.tile_size().width());
+        self.u16(tileset.tile_size().height());
+        self.option(tileset.pixels.as_ref(), Self::pixels);
+    }
+
+    fn tileset(&mut self, tileset: &Tileset) {
+        self.tileset_pixels(tileset);
+        self.u16(tileset.base_index() as u16);
+        self.str(tileset.name());
+        self.option(tileset.external_file(), |w, reference| {
+            w.u32(reference.external_file_id().value());
+            w.u32(reference.tileset_id().value());
+        });
+        self.user_data(tileset.user_data());
+        self.len(tileset.tile_user_data.len());
+        for user_data in &tileset.tile_user_data {
+            self.user_data(user_data.as_ref());
+        }
+    }
+
+    // The image content of a cel. Linked cels are resolved, so a linked cel
+    // and a copy of the same cel have the same content.
+    fn cel_content(&mut self, file: &AsepriteFile, cel_id: CelId) {
+        let cel = file.framedata.cel(cel_id);
+        self.option(cel, |w, cel: &RawCel| {
+            w.i32(cel.data.x as i32);
+            w.i32(cel.data.y as i32);
+            w.u8(cel.data.opacity);
+            let content = match cel.content {
+                CelContent::Linked(frame) => file
+                    .framedata
+                    .cel(CelId {
+                        frame,
+                        layer: cel_id.layer,
+                    })
+                    .map(|linked| &linked.content),
+                ref content => Some(content),
+            };
+            w.option(content, |w, content| match content {
+                CelContent::Raw(image) => {
+                    w.u8(0);
+                    w.u16(image.size.width);
+                    w.u16(image.size.height);
+                    w.pixels(&image.pixels);
+                }
+                CelContent::Tilemap(tilemap) => {
+                    w.u8(1);
+                    w.u16(tilemap.width());
+                    w.u16(tilemap.height());
+                    for y in 0..tilemap.height() {
+                        for x in 0..tilemap.width() {
+                            let tile = tilemap.tile(x, y).expect("Tile is in bounds");
+                            w.u32(tile.id());
+                            w.bool(tile.flip_x());
+                            w.bool(tile.flip_y());
+                            w.bool(tile.rotate_90cw());
+                        }
+                    }
+                    let layer = file.layer(cel_id.layer as u32);
+                    if let LayerType::Tilemap(tileset_id) = layer.layer_type() {
+                        w.option(file.tilesets().get(tileset_id), Self::tileset_pixels);
+                    }
+                }
+                // A link to another link is invalid.
+                CelContent::Linked(_) => w.u8(2),
+            });
+        });
+    }
+}
+
+// Everything that affects the composited image of the frame and the images
+// of its cels, plus the frame duration and the layer names.
+fn write_frame<S: Sink>(w: &mut ContentWriter<S>, file: &AsepriteFile, frame: u32) {
+    w.canvas(file, false);
+    w.u32(file.frame(frame).duration_ms());
+    w.len(file.num_layers() as usize);
+    for layer in file.layers() {
+        w.layer_properties(layer);
+        w.cel_content(
+            file,
+            CelId {
+                frame: frame as u16,
+                layer: layer.id() as u16,
+            },
+        );
+    }
+}
+
+// Everything not covered by `write_frame`.
+fn write_metadata<S: Sink>(w: &mut ContentWriter<S>, file: &AsepriteFile) {
+    w.u32(file.num_frames());
+    // Palette color names do not affect any frame.
+    w.palette(file, true);
+    for frame in 0..file.num_frames() {
+        for layer in 0..file.num_layers() {
+            w.user_data(file.cel(frame, layer).user_data());
+        }
+    }
+    for layer in file.layers() {
+        w.user_data(layer.user_data());
+        w.option(layer.uuid(), |w, uuid| w.bytes(uuid.as_bytes()));
+    }
+    w.len(file.tags.len());
+    for tag in file.tags() {
+        w.tag(tag);
+    }
+    w.len(file.slices.len());
+    for slice in file.slices() {
+        w.slice(slice);
+    }
+    let mut tilesets: Vec<&Tileset> = file.tilesets().iter().collect();
+    tilesets.sort_by_key(|tileset| tileset.id());
+    w.len(tilesets.len());
+    for tileset in tilesets {
+        w.tileset(tileset);
+    }
+    let mut external_files: Vec<_> = file.external_files().map().values().collect();
+    external_files.sort_by_key(|external_file| external_file.id().value());
+    w.len(external_files.len());
+    for external_file in external_files {
+        w.u32(external_file.id().value());
+        w.str(external_file.name());
+    }
+    w.user_data(file.sprite_user_data());
+}
+
+pub(crate) fn frame_hash(file: &AsepriteFile, frame: u32) -> u64 {
+    let mut w = ContentWriter {
+        out: Fnv(FNV_OFFSET_BASIS),
+    };
+    write_frame(&mut w, file, frame);
+    w.out.0
+}
+
+// Only the pixels. Used to find frames that look the same.
+pub(crate) fn image_hash(image: &RgbaImage) -> u64 {
+    let mut w = ContentWriter {
+        out: Fnv(FNV_OFFSET_BASIS),
+    };
+    w.u32(image.width());
+    w.u32(image.height());
+    w.bytes(image.as_raw());
+    w.out.0
+}
+
+pub(crate) fn file_hash(file: &AsepriteFile) -> u64 {
+    let mut w = ContentWriter {
+        out: Fnv(FNV_OFFSET_BASIS),
+    };
+    for frame in 0..file.num_frames() {
+        w.u64(frame_hash(file, frame));
+    }
+    write_metadata(&mut w, file);
+    w.out.0
+}
+
+fn to_bytes<F>(write: F) -> Vec<u8>
+where
+    F: FnOnce(&mut ContentWriter<Vec<u8>>),
+{
+    let mut w = ContentWriter { out: Vec::new() };
+    write(&mut w);
+    w.out
+}
+
+fn frame_bytes(file: &AsepriteFile, frame: u32) -> Vec<u8> {
+    to_bytes(|w| write_frame(w, file, frame))
+}
+
+fn metadata_bytes(file: &AsepriteFile) -> Vec<u8> {
+    to_bytes(|w| write_metadata(w, file))
+}
+
+// Canvas size, pixel format and palette.
+pub(crate) fn canvas_bytes(file: &AsepriteFile) -> Vec<u8> {
+    to_bytes(|w| w.canvas(file, true))
+}
+
+// The properties of the layer and its cels in the first `num_frames` frames.
+pub(crate) fn layer_bytes(file: &AsepriteFile, layer: u32, num_frames: u32) -> Vec<u8> {
+    to_bytes(|w| {
+        let layer = file.layer(layer);
+        w.layer_properties(layer);
+        w.user_data(layer.user_data());
+        w.option(layer.uuid(), |w, uuid| w.bytes(uuid.as_bytes()));
+        for frame in 0..num_frames {
+            let cel_id = CelId {
+                frame: frame as u16,
+                layer: layer.id() as u16,
+            };
+            w.cel_content(file, cel_id);
+            w.user_data(file.cel(frame, layer.id()).user_data());
+        }
+    })
+}
+
+pub(crate) fn tag_bytes(tag: &Tag) -> Vec<u8> {
+    to_bytes(|w| w.tag(tag))
+}
+
+pub(crate) fn slice_bytes(slice: &Slice) -> Vec<u8> {
+    to_bytes(|w| w.slice(slice))
+}
+
+pub(crate) fn user_data_bytes(user_data: Option<&UserData>) -> Vec<u8> {
+    to_bytes(|w| w.user_data(user_data))
+}
+
+pub(crate) fn tileset_bytes(tileset: &Tileset) -> Vec<u8> {
+    to_bytes(|w| w.tileset(tileset))
+}
+
+// Compares one frame at a time, so at most the content of one frame per file
+// is held in memory.
+pub(crate) fn frames_equal(a: &AsepriteFile, a_frame: u32, b: &AsepriteFile, b_frame: u32) -> bool {
+    frame_bytes(a, a_frame) == frame_bytes(b, b_frame)
+}
+
+pub(crate) fn files_equal(a: &AsepriteFile, b: &AsepriteFile) -> bool {
+    metadata_bytes(a) == metadata_bytes(b)
+        && (0..a.num_frames()).all(|frame| frames_equal(a, frame, b, frame))
+}
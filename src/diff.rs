@@ -0,0 +1,149 @@
+use std::{collections::HashSet, hash::Hash};
+
+use crate::{content, AsepriteFile, TilesetId};
+
+/// The differences between two versions of a file. See [AsepriteFile::diff].
+///
+/// Frames and layers are matched by index, tags and slices by name and
+/// tilesets by ID. If several tags or slices have the same name, only the
+/// first one is compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// The canvas size, pixel format or palette changed. This usually affects
+    /// all frames.
+    pub canvas_changed: bool,
+    /// Frame indices. A frame is changed if its
+    /// [content hash](crate::Frame::content_hash) differs, i.e., if it looks
+    /// different or its duration changed.
+    pub frames: Changes<u32>,
+    /// Layer IDs. A layer is changed if its properties, user data or any of
+    /// its cels in frames that exist in both files changed.
+    pub layers: Changes<u32>,
+    /// Tag names.
+    pub tags: Changes<String>,
+    /// Slice names.
+    pub slices: Changes<String>,
+    /// Tileset IDs.
+    pub tilesets: Changes<TilesetId>,
+    /// The [sprite user data](AsepriteFile::sprite_user_data) changed.
+    pub sprite_user_data_changed: bool,
+}
+
+impl FileDiff {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        !self.canvas_changed
+            && self.frames.is_empty()
+            && self.layers.is_empty()
+            && self.tags.is_empty()
+            && self.slices.is_empty()
+            && self.tilesets.is_empty()
+            && !self.sprite_user_data_changed
+    }
+}
+
+/// Added, removed and changed items of one kind. See [FileDiff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changes<K> {
+    /// Items that only exist in the new file.
+    pub added: Vec<K>,
+    /// Items that only exist in the old file.
+    pub removed: Vec<K>,
+    /// Items that exist in both files, but differ.
+    pub changed: Vec<K>,
+}
+
+impl<K> Changes<K> {
+    fn new() -> Self {
+        Changes {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if no items were added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Items identified by their index.
+fn by_index<F>(old_len: u32, new_len: u32, mut is_equal: F) -> Changes<u32>
+where
+    F: FnMut(u32) -> bool,
+{
+    let mut changes = Changes::new();
+    changes.added.extend(old_len..new_len);
+    changes.removed.extend(new_len..old_len);
+    changes
+        .changed
+        .extend((0..old_len.min(new_len)).filter(|&index| !is_equal(index)));
+    changes
+}
+
+// Items identified by a key. Only the first item with each key is used.
+fn by_key<K, T, F>(old: Vec<(K, T)>, new: Vec<(K, T)>, mut is_equal: F) -> Changes<K>
+where
+    K: Eq + Hash + Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut changes = Changes::new();
+    let first = |items: Vec<(K, T)>| {
+        let mut seen = HashSet::new();
+        items
+            .into_iter()
+            .filter(|(key, _)| seen.insert(key.clone()))
+            .collect::<Vec<_>>()
+    };
+    let old = first(old);
+    let new = first(new);
+    for (key, old_item) in &old {
+        match new.iter().find(|(new_key, _)| new_key == key) {
+            Some((_, new_item)) if !is_equal(old_item, new_item) => {
+                changes.changed.push(key.clone())
+            }
+            Some(_) => {}
+            None => changes.removed.push(key.clone()),
+        }
+    }
+    for (key, _) in &new {
+        if !old.iter().any(|(old_key, _)| old_key == key) {
+            changes.added.push(key.clone());
+        }
+    }
+    changes
+}
+
+pub(crate) fn diff(old: &AsepriteFile, new: &AsepriteFile) -> FileDiff {
+    let common_frames = old.num_frames().min(new.num_frames());
+    let mut old_tilesets: Vec<_> = old.tilesets().iter().map(|t| (t.id(), t)).collect();
+    let mut new_tilesets: Vec<_> = new.tilesets().iter().map(|t| (t.id(), t)).collect();
+    old_tilesets.sort_by_key(|(id, _)| *id);
+    new_tilesets.sort_by_key(|(id, _)| *id);
+    FileDiff {
+        canvas_changed: content::canvas_bytes(old) != content::canvas_bytes(new),
+        frames: by_index(old.num_frames(), new.num_frames(), |frame| {
+            content::frames_equal(old, frame, new, frame)
+        }),
+        layers: by_index(old.num_layers(), new.num_layers(), |layer| {
+            content::layer_bytes(old, layer, common_frames)
+                == content::layer_bytes(new, layer, common_frames)
+        }),
+        tags: by_key(
+            old.tags().map(|t| (t.name().to_owned(), t)).collect(),
+            new.tags().map(|t| (t.name().to_owned(), t)).collect(),
+            |a, b| content::tag_bytes(a) == content::tag_bytes(b),
+        ),
+        slices: by_key(
+            old.slices().iter().map(|s| (s.name.clone(), s)).collect(),
+            new.slices().iter().map(|s| (s.name.clone(), s)).collect(),
+            |a, b| content::slice_bytes(a) == content::slice_bytes(b),
+        ),
+        tilesets: by_key(old_tilesets, new_tilesets, |a, b| {
+            content::tileset_bytes(a) == content::tileset_bytes(b)
+        }),
+        sprite_user_data_changed: content::user_data_bytes(old.sprite_user_data())
+            != content::user_data_bytes(new.sprite_user_data()),
+    }
+}
@@ -1,13 +1,69 @@
 use std::{error::Error, fmt, io, string::FromUtf8Error};
 
+use crate::TilesetId;
+
 /// An error occured while reading the Aseprite file.
 #[derive(Debug)]
 pub enum AsepriteParseError {
     /// The input data was malformed. String contains detailed message.
+    ///
+    /// Common problems have their own variants, see
+    /// [is_invalid_input](Self::is_invalid_input).
     InvalidInput(String),
+    /// The magic number of the file header is wrong. Usually means that the
+    /// input is not an Aseprite file.
+    BadMagicNumber {
+        /// The magic number of Aseprite files.
+        expected: u16,
+        /// The magic number in the input.
+        found: u16,
+    },
+    /// The magic number of a frame header is wrong. Usually means that the
+    /// file is truncated or a frame size is wrong.
+    BadFrameMagicNumber {
+        /// The magic number of Aseprite frames.
+        expected: u16,
+        /// The magic number in the input.
+        found: u16,
+    },
+    /// A chunk is smaller than its own header or larger than the rest of its
+    /// frame.
+    BadChunkSize {
+        /// Size of the chunk in bytes according to its header.
+        size: u32,
+        /// Bytes left in the frame.
+        bytes_available: i64,
+    },
+    /// The file uses indexed colors, but contains no palette.
+    MissingPalette,
+    /// An indexed pixel refers to a color that is not in the palette.
+    InvalidPaletteIndex {
+        /// The palette index of the pixel.
+        index: u8,
+    },
+    /// A user data chunk does not follow any chunk it could belong to.
+    DanglingUserData,
+    /// A string is not valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+    /// There is more than one cel for the same frame and layer.
+    DuplicateCel {
+        /// Index of the frame.
+        frame: u32,
+        /// Index of the layer.
+        layer: u32,
+    },
+    /// A tilemap layer references a tileset that does not exist.
+    MissingTileset {
+        /// The ID the layer refers to.
+        tileset: TilesetId,
+    },
     /// The input data was correct, but uses a feature that is not supported by
     /// this version of `asefile`. String contains detailed message.
     UnsupportedFeature(String),
+    /// A method that edits the file was called with arguments that are not
+    /// valid for this file, e.g., when removing its only frame. String
+    /// contains detailed message.
+    InvalidEdit(String),
     /// An internal error occurred.
     InternalError(String),
     /// An IO error occured. Also includes errors where the input was shorter
@@ -15,6 +71,21 @@ pub enum AsepriteParseError {
     IoError(io::Error),
 }
 
+impl AsepriteParseError {
+    /// Returns `true` if the input data was malformed, i.e., for
+    /// [InvalidInput](Self::InvalidInput) and all variants that describe a
+    /// specific problem with the input.
+    pub fn is_invalid_input(&self) -> bool {
+        !matches!(
+            self,
+            AsepriteParseError::UnsupportedFeature(_)
+                | AsepriteParseError::InvalidEdit(_)
+                | AsepriteParseError::InternalError(_)
+                | AsepriteParseError::IoError(_)
+        )
+    }
+}
+
 impl From<io::Error> for AsepriteParseError {
     fn from(err: io::Error) -> Self {
         AsepriteParseError::IoError(err)
@@ -23,17 +94,71 @@ impl From<io::Error> for AsepriteParseError {
 
 impl From<FromUtf8Error> for AsepriteParseError {
     fn from(err: FromUtf8Error) -> Self {
-        AsepriteParseError::InvalidInput(format!("Could not decode utf8: {}", err))
+        AsepriteParseError::InvalidUtf8(err)
     }
 }
 
 impl fmt::Display for AsepriteParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_invalid_input() {
+            write!(f, "Invalid Aseprite input: ")?;
+        }
         match self {
-            AsepriteParseError::InvalidInput(msg) => write!(f, "Invalid Aseprite input: {}", msg),
+            AsepriteParseError::InvalidInput(msg) => write!(f, "{}", msg),
+            AsepriteParseError::BadMagicNumber { expected, found } => {
+                write!(
+                    f,
+                    "Invalid magic number for header: {:x} != {:x}",
+                    found, expected
+                )
+            }
+            AsepriteParseError::BadFrameMagicNumber { expected, found } => {
+                write!(
+                    f,
+                    "Invalid magic number for frame: {:x} != {:x}",
+                    found, expected
+                )
+            }
+            AsepriteParseError::BadChunkSize {
+                size,
+                bytes_available,
+            } => {
+                if (*size as usize) < crate::parse::CHUNK_HEADER_SIZE {
+                    write!(
+                        f,
+                        "Chunk size is too small {}, minimum_size: {}",
+                        size,
+                        crate::parse::CHUNK_HEADER_SIZE
+                    )
+                } else {
+                    write!(
+                        f,
+                        "Trying to read chunk of size {}, but there are only {} bytes available in the frame",
+                        size, bytes_available
+                    )
+                }
+            }
+            AsepriteParseError::MissingPalette => write!(f, "Indexed colors without a palette"),
+            AsepriteParseError::InvalidPaletteIndex { index } => {
+                write!(f, "Palette index invalid: {}", index)
+            }
+            AsepriteParseError::DanglingUserData => write!(
+                f,
+                "Found dangling user data chunk. Expected a previous chunk to attach user data"
+            ),
+            AsepriteParseError::InvalidUtf8(err) => write!(f, "Could not decode utf8: {}", err),
+            AsepriteParseError::DuplicateCel { frame, layer } => {
+                write!(f, "Multiple Cels for frame {}, layer {}", frame, layer)
+            }
+            AsepriteParseError::MissingTileset { tileset } => write!(
+                f,
+                "Tilemap layer references a missing tileset ({})",
+                tileset
+            ),
             AsepriteParseError::UnsupportedFeature(msg) => {
                 write!(f, "Unsupported Aseprite feature: {}", msg)
             }
+            AsepriteParseError::InvalidEdit(msg) => write!(f, "Invalid edit: {}", msg),
             AsepriteParseError::InternalError(msg) => {
                 write!(f, "Internal error: {}", msg)
             }
@@ -45,6 +170,7 @@ impl fmt::Display for AsepriteParseError {
 impl Error for AsepriteParseError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
+            AsepriteParseError::InvalidUtf8(err) => Some(err),
             AsepriteParseError::IoError(err) => Some(err),
             _ => None,
         }
@@ -0,0 +1,140 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+/// A map that stores at most one value of each type.
+///
+/// Values must implement [Clone], so that a clone of an
+/// [AsepriteFile](crate::AsepriteFile) can modify its extensions without
+/// affecting the original. Values are only cloned on the first modification.
+///
+/// Used to attach the results of custom chunk handlers to an
+/// [AsepriteFile](crate::AsepriteFile). See
+/// [ParseOptions::chunk_handler](crate::ParseOptions::chunk_handler).
+///
+/// ```
+/// # use asefile::Extensions;
+/// #[derive(Clone)]
+/// struct Checksum(u32);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(Checksum(42));
+/// assert_eq!(extensions.get::<Checksum>().unwrap().0, 42);
+/// assert!(extensions.get::<String>().is_none());
+/// ```
+#[derive(Default, Clone)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Value>>,
+}
+
+// Object safe version of `Any + Clone`.
+trait Value: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn Value>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + Clone + Send + Sync> Value for T {
+    fn clone_box(&self) -> Box<dyn Value> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Clone for Box<dyn Value> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+impl Extensions {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value, returning the previous value of the same type.
+    pub fn insert<T: Any + Clone + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.into_any().downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    /// The value of the given type, if there is one.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Mutable reference to the value of the given type, if there is one.
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Mutable reference to the value of the given type. Inserts the result
+    /// of `default` if there is no such value yet. Useful for collecting the
+    /// contents of several chunks into one value.
+    pub fn get_or_insert_with<T, F>(&mut self, default: F) -> &mut T
+    where
+        T: Any + Clone + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        let value: &mut dyn Value = &mut **self
+            .map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(default()));
+        value
+            .as_any_mut()
+            .downcast_mut()
+            .expect("Extensions are keyed by their type")
+    }
+
+    /// Remove and return the value of the given type.
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.into_any().downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// Returns `true` if there is a value of the given type.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Number of stored values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
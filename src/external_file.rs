@@ -20,7 +20,7 @@ impl ExternalFileId {
 }
 
 /// An external file. Used to reference external palettes or tilesets.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExternalFile {
     id: ExternalFileId,
     name: String,
@@ -59,7 +59,7 @@ impl ExternalFile {
 }
 
 /// A map of [ExternalFileId] values to [ExternalFile] instances.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExternalFilesById(HashMap<ExternalFileId, ExternalFile>);
 
 impl ExternalFilesById {
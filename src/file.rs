@@ -1,15 +1,18 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::{BufReader, Read},
+    iter::FusedIterator,
     path::Path,
     sync::Arc,
+    time::Duration,
 };
 
 use crate::{
     blend::{self, mul_un8, Color8},
     cel::{CelCommon, CelId, CelsData, ImageContent, ImageSize},
     external_file::{ExternalFile, ExternalFileId, ExternalFilesById},
-    layer::{Layer, LayerType, LayersData},
+    layer::{Layer, LayerData, LayerType, LayersData},
     pixel::Pixels,
     slice::Slice,
     tile::TileId,
@@ -22,7 +25,12 @@ use cel::{CelContent, RawCel};
 use image::{Rgba, RgbaImage};
 
 /// A parsed Aseprite file.
-#[derive(Debug)]
+///
+/// Cloning is cheap: pixel data and tilesets are shared between clones via
+/// [Arc], so several systems or threads can hold the same file without
+/// copying its images. Editing a clone copies the shared data it changes
+/// first; see [CelMut] for the cost of editing cels.
+#[derive(Debug, Clone)]
 pub struct AsepriteFile {
     pub(crate) width: u16,
     pub(crate) height: u16,
@@ -32,13 +40,18 @@ pub struct AsepriteFile {
     pub(crate) palette: Option<Arc<ColorPalette>>,
     pub(crate) layers: LayersData,
     // pub(crate) color_profile: Option<ColorProfile>,
-    pub(crate) frame_times: Vec<u16>,
+    pub(crate) frame_times: Vec<Duration>,
     pub(crate) tags: Vec<Tag>,
-    pub(crate) framedata: CelsData<Pixels>, // Vec<Vec<cel::RawCel>>,
+    // framedata and tilesets are Arcs to make cloning cheap. Editing a clone
+    // copies them first. framedata holds the cels of all frames, so editing a
+    // single cel copies the metadata of every cel (but not their pixels).
+    pub(crate) framedata: Arc<CelsData<Pixels>>, // Vec<Vec<cel::RawCel>>,
     pub(crate) external_files: ExternalFilesById,
-    pub(crate) tilesets: TilesetsById,
+    pub(crate) tilesets: Arc<TilesetsById>,
     pub(crate) sprite_user_data: Option<UserData>,
     pub(crate) slices: Vec<Slice>,
+    // Copied on the first modification of a clone.
+    pub(crate) extensions: Arc<Extensions>,
 }
 
 /// A reference to a single frame.
@@ -48,6 +61,31 @@ pub struct Frame<'a> {
     index: u32,
 }
 
+/// Options for rendering frames. See [Frame::image_with_options].
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Combine layers with the layer blending method that Aseprite used before
+    /// version 1.2.25. Blend modes other than normal then ignore the alpha of
+    /// the pixels below, so the result matches files authored with Aseprite's
+    /// "new layer blending method" setting turned off. Default: `false`.
+    pub legacy_blending: bool,
+    /// Reproduce a bug in Aseprite's hue and saturation blend modes, which
+    /// sorts the color channels incorrectly if some of them are equal. Turn
+    /// it off to blend as specified in the PDF blend modes, which Aseprite's
+    /// blend modes are based on. Default: `true`, i.e., the output matches
+    /// Aseprite byte for byte.
+    pub saturation_bug_compatible: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            legacy_blending: false,
+            saturation_bug_compatible: true,
+        }
+    }
+}
+
 /// Pixel format of the source Aseprite file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelFormat {
@@ -97,6 +135,37 @@ impl AsepriteFile {
         parse::read_aseprite(input)
     }
 
+    /// Load Aseprite file and any tilesets it references in external files.
+    ///
+    /// `resolver` is called once per referenced tileset with the
+    /// [ExternalFile] that contains it. It should return the contents of that
+    /// file, or `None` if it cannot be found. If the file cannot be resolved
+    /// reading fails the same way as [read](Self::read) does.
+    ///
+    /// ```no_run
+    /// # use asefile::AsepriteFile;
+    /// # use std::{fs, path::Path};
+    /// let dir = Path::new("./assets");
+    /// let input = fs::read(dir.join("level.aseprite")).unwrap();
+    /// let ase = AsepriteFile::read_with_external_resolver(&input[..], |external| {
+    ///     fs::read(dir.join(external.name())).ok()
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn read_with_external_resolver<R, F>(input: R, resolver: F) -> Result<AsepriteFile>
+    where
+        R: Read,
+        F: FnMut(&ExternalFile) -> Option<Vec<u8>>,
+    {
+        Self::read_with_options(input, ParseOptions::new().external_resolver(resolver))
+    }
+
+    /// Load Aseprite file with custom parser behavior, e.g., handlers for
+    /// additional chunk types. See [ParseOptions].
+    pub fn read_with_options<R: Read>(input: R, options: ParseOptions<'_>) -> Result<AsepriteFile> {
+        parse::read_aseprite_with_options(input, options)
+    }
+
     /// Width in pixels.
     pub fn width(&self) -> usize {
         self.width as usize
@@ -117,6 +186,283 @@ impl AsepriteFile {
         self.num_frames as u32
     }
 
+    /// The duration of each frame, indexed by frame number.
+    pub fn frame_durations(&self) -> &[Duration] {
+        &self.frame_times
+    }
+
+    /// The sum of all frame durations, i.e., the time it takes to play every
+    /// frame once.
+    pub fn total_duration(&self) -> Duration {
+        self.frame_times.iter().sum()
+    }
+
+    /// Change how long a frame is displayed.
+    ///
+    /// Like in Aseprite, durations are whole milliseconds from 1 to 65535.
+    /// Other values are rounded down and clamped to that range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn set_frame_duration(&mut self, frame: u32, duration: Duration) {
+        assert!(frame < self.num_frames());
+        self.frame_times[frame as usize] = clamp_frame_duration(duration.as_millis());
+    }
+
+    /// Multiply all frame durations by `factor`, e.g., `0.5` to play the
+    /// animation twice as fast.
+    ///
+    /// Results are rounded to the nearest millisecond and clamped like in
+    /// [set_frame_duration](Self::set_frame_duration).
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let before = ase.total_duration();
+    /// ase.scale_durations(2.0);
+    /// assert_eq!(ase.total_duration(), before * 2);
+    /// ```
+    pub fn scale_durations(&mut self, factor: f64) {
+        for duration in &mut self.frame_times {
+            let ms = (duration.as_millis() as f64 * factor).round().max(0.0);
+            *duration = clamp_frame_duration(ms as u128);
+        }
+    }
+
+    /// Insert an empty frame at `index`. Frames at or after `index` move up
+    /// by one.
+    ///
+    /// Tags that include the frames on both sides of `index` grow to include
+    /// the new frame, tags and slice keys after it are moved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file already has the maximum of 65535 frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than `num_frames`.
+    pub fn insert_frame(&mut self, index: u32, duration: Duration) -> Result<()> {
+        assert!(index <= self.num_frames());
+        let cels = vec![None; self.num_layers() as usize];
+        self.insert_frame_with(index, duration, cels, |tag| {
+            tag.from_frame() < index && index <= tag.to_frame()
+        })
+    }
+
+    /// Insert a copy of `frame` right after it and return the index of the
+    /// copy.
+    ///
+    /// If `linked` is set, the cels of the copy are linked to the original
+    /// cels like in Aseprite's "New Frame" with "Continuous" layers, so they
+    /// share their pixels. Otherwise the copy has its own cels. Tags that
+    /// include `frame` also include the copy.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let copy = ase.duplicate_frame(1, true).unwrap();
+    /// assert_eq!(copy, 2);
+    /// assert_eq!(ase.frame(copy).image(), ase.frame(1).image());
+    /// assert_eq!(ase.tag_by_name("T3").unwrap().to_frame(), 4);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file already has the maximum of 65535 frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn duplicate_frame(&mut self, frame: u32, linked: bool) -> Result<u32> {
+        assert!(frame < self.num_frames());
+        let mut cels = vec![None; self.num_layers() as usize];
+        for (layer, cel) in self.framedata.frame_cels(frame as u16) {
+            let target = match cel.content {
+                CelContent::Linked(target) => target,
+                _ => frame as u16,
+            };
+            let content = if linked {
+                CelContent::Linked(target)
+            } else {
+                let target_id = CelId {
+                    frame: target,
+                    layer: layer as u16,
+                };
+                self.framedata
+                    .cel(target_id)
+                    .expect("Linked cel exists")
+                    .content
+                    .clone()
+            };
+            cels[layer as usize] = Some(RawCel {
+                data: cel.data.clone(),
+                content,
+                user_data: cel.user_data.clone(),
+            });
+        }
+        let duration = self.frame_times[frame as usize];
+        self.insert_frame_with(frame + 1, duration, cels, |tag| tag.contains_frame(frame))?;
+        Ok(frame + 1)
+    }
+
+    /// Remove a frame and its cels. Frames after it move down by one.
+    ///
+    /// Cels in other frames that were linked to the removed cels keep their
+    /// images. Tag ranges shrink or move accordingly. Tags that only
+    /// contained the removed frame are removed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `frame` is the only frame of the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn remove_frame(&mut self, frame: u32) -> Result<()> {
+        assert!(frame < self.num_frames());
+        if self.num_frames == 1 {
+            return Err(AsepriteParseError::InvalidEdit(
+                "Cannot remove the only frame".to_owned(),
+            ));
+        }
+        Arc::make_mut(&mut self.framedata).remove_frame(frame as u16);
+        self.frame_times.remove(frame as usize);
+        self.num_frames -= 1;
+
+        self.tags
+            .retain(|tag| tag.from_frame() != frame || tag.to_frame() != frame);
+        for tag in &mut self.tags {
+            let (from, to) = (tag.from_frame(), tag.to_frame());
+            if from > frame {
+                tag.set_range(from - 1, to - 1);
+            } else if to >= frame {
+                tag.set_range(from, to - 1);
+            }
+        }
+        for slice in &mut self.slices {
+            // A key on the removed frame now applies to the next frame,
+            // unless that frame has its own key.
+            let replaced = slice.keys.iter().any(|key| key.from_frame == frame + 1);
+            slice
+                .keys
+                .retain(|key| !replaced || key.from_frame != frame);
+            for key in &mut slice.keys {
+                if key.from_frame > frame {
+                    key.from_frame -= 1;
+                }
+            }
+            // A key on the removed last frame is dropped, unless the slice
+            // has no other key. Then it moves to the new last frame.
+            let last = self.num_frames as u32 - 1;
+            if slice.keys.iter().any(|key| key.from_frame <= last) {
+                slice.keys.retain(|key| key.from_frame <= last);
+            } else if let Some(key) = slice.keys.first_mut() {
+                key.from_frame = last;
+                slice.keys.truncate(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// A copy of the file with all layers merged into a single RGBA image
+    /// layer, like Aseprite's "Layer > Flatten".
+    ///
+    /// Every frame of the copy looks like the [composited frame](Frame::image)
+    /// of this file. Frame durations, tags, slices, the palette and the sprite
+    /// user data are kept. Hidden layers are dropped and tilemaps are
+    /// converted to pixels, so the copy has no tilesets.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let ase = AsepriteFile::read_file(&path).unwrap();
+    /// let flat = ase.flattened();
+    /// assert_eq!(flat.num_layers(), 1);
+    /// assert_eq!(flat.frame(1).image(), ase.frame(1).image());
+    /// ```
+    pub fn flattened(&self) -> AsepriteFile {
+        let mut framedata = CelsData::new(self.num_frames());
+        for frame in 0..self.num_frames() {
+            let image = self.frame(frame).image();
+            if image.pixels().all(|pixel| pixel.0[3] == 0) {
+                continue;
+            }
+            let cel = RawCel {
+                data: CelCommon {
+                    layer_index: 0,
+                    x: 0,
+                    y: 0,
+                    opacity: 255,
+                },
+                content: CelContent::Raw(ImageContent {
+                    size: ImageSize {
+                        width: self.width,
+                        height: self.height,
+                    },
+                    pixels: Pixels::Rgba(Arc::new(image.pixels().copied().collect())),
+                }),
+                user_data: None,
+            };
+            framedata
+                .add_cel(frame as u16, cel)
+                .expect("Each frame has one cel");
+        }
+        let layers = LayersData::from_vec(vec![LayerData::new_image("Flattened")])
+            .expect("A single layer is valid");
+        AsepriteFile {
+            pixel_format: PixelFormat::Rgba,
+            layers,
+            framedata: Arc::new(framedata),
+            external_files: ExternalFilesById::new(),
+            tilesets: Arc::new(TilesetsById::new()),
+            ..self.clone()
+        }
+    }
+
+    // `grows` selects the tags that include the new frame.
+    fn insert_frame_with<F>(
+        &mut self,
+        index: u32,
+        duration: Duration,
+        cels: Vec<Option<RawCel>>,
+        grows: F,
+    ) -> Result<()>
+    where
+        F: Fn(&Tag) -> bool,
+    {
+        if self.num_frames == u16::MAX {
+            return Err(AsepriteParseError::InvalidEdit(
+                "Too many frames".to_owned(),
+            ));
+        }
+        Arc::make_mut(&mut self.framedata).insert_frame(index as u16, cels);
+        self.frame_times
+            .insert(index as usize, clamp_frame_duration(duration.as_millis()));
+        self.num_frames += 1;
+
+        for tag in &mut self.tags {
+            let (from, to) = (tag.from_frame(), tag.to_frame());
+            if grows(tag) {
+                tag.set_range(from, to + 1);
+            } else if from >= index {
+                tag.set_range(from + 1, to + 1);
+            }
+        }
+        for key in self.slices.iter_mut().flat_map(|s| &mut s.keys) {
+            if key.from_frame >= index {
+                key.from_frame += 1;
+            }
+        }
+        Ok(())
+    }
+
     /// Number of layers.
     pub fn num_layers(&self) -> u32 {
         self.layers.layers.len() as u32
@@ -137,6 +483,308 @@ impl AsepriteFile {
         self.palette.as_deref()
     }
 
+    /// Replace the palette without changing any pixel indices.
+    ///
+    /// Fails if indexed pixels use an index that is missing from `palette`.
+    pub fn set_palette(&mut self, palette: ColorPalette) -> Result<()> {
+        let mut result = Ok(());
+        self.for_each_indexed_pixels(|pixels| {
+            if let Pixels::Indexed { data, .. } = pixels {
+                if result.is_ok() {
+                    result = palette.validate_indexed_pixels(data);
+                }
+            }
+        });
+        result?;
+        self.replace_palette(palette, None);
+        Ok(())
+    }
+
+    /// Change a single palette color. Adds a new entry if there is none at
+    /// that index yet. In indexed files, this changes all pixels that use
+    /// the color.
+    pub fn set_palette_color(&mut self, index: u32, rgba8: [u8; 4]) {
+        let mut palette = self.palette_or_empty();
+        palette.set_color(index, rgba8);
+        self.replace_palette(palette, None);
+    }
+
+    /// Add a color after the palette entry with the largest index. Returns
+    /// the index of the new entry.
+    pub fn add_palette_color(&mut self, rgba8: [u8; 4]) -> u32 {
+        let mut palette = self.palette_or_empty();
+        let index = palette.add_color(rgba8);
+        self.replace_palette(palette, None);
+        index
+    }
+
+    /// Remove a palette entry. Entries with higher indices move down by one.
+    ///
+    /// Indexed pixels and the
+    /// [transparent color index](Self::transparent_color_index) are updated
+    /// to keep their colors. Pixels that used the removed color get the
+    /// nearest remaining color (see [ColorDistance::Rgb]), other than the
+    /// transparent color.
+    ///
+    /// Fails if there is no entry at `index`, if it is the transparent color
+    /// of an indexed file, or if pixels use it and there is no other color to
+    /// replace it with.
+    pub fn remove_palette_color(&mut self, index: u32) -> Result<()> {
+        let palette = self.palette.as_deref().ok_or_else(no_palette)?;
+        let removed = palette.color(index).ok_or_else(|| {
+            AsepriteParseError::InvalidEdit(format!("No palette entry at index {}", index))
+        })?;
+        let transparent_color_index = self.transparent_color_index().map(u32::from);
+        if transparent_color_index == Some(index) {
+            return Err(AsepriteParseError::InvalidEdit(format!(
+                "Cannot remove palette entry {}, which is the transparent color",
+                index
+            )));
+        }
+        let new_palette = palette.without(index);
+        let removed = Rgba(removed.raw_rgba8());
+        let replacement = match transparent_color_index {
+            Some(transparent) => {
+                let transparent = if transparent > index {
+                    transparent - 1
+                } else {
+                    transparent
+                };
+                new_palette.nearest_except(removed, ColorDistance::Rgb, transparent)
+            }
+            None => new_palette.nearest(removed, ColorDistance::Rgb),
+        };
+        let mut remap = [0_u8; 256];
+        for (old, new) in remap.iter_mut().enumerate() {
+            *new = match (old as u32).cmp(&index) {
+                std::cmp::Ordering::Less => old as u8,
+                std::cmp::Ordering::Equal => replacement.unwrap_or(index) as u8,
+                std::cmp::Ordering::Greater => old as u8 - 1,
+            };
+        }
+        if replacement.is_none() {
+            let mut used = false;
+            self.for_each_indexed_pixels(|pixels| {
+                if let Pixels::Indexed { data, .. } = pixels {
+                    used |= data.contains(&(index as u8));
+                }
+            });
+            if used {
+                return Err(AsepriteParseError::InvalidEdit(format!(
+                    "Cannot remove palette entry {}, which is used by pixels",
+                    index
+                )));
+            }
+        }
+        self.replace_palette(new_palette, Some(&remap));
+        Ok(())
+    }
+
+    /// Reorder the palette, so that the entry at index `order[i]` moves to
+    /// index `i`. Indexed pixels and the
+    /// [transparent color index](Self::transparent_color_index) are updated
+    /// to keep their colors.
+    ///
+    /// Fails if `order` does not contain every palette index exactly once.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/indexed.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let before = ase.frame(0).image();
+    /// let reversed: Vec<u32> = (0..ase.palette().unwrap().num_colors()).rev().collect();
+    /// ase.reorder_palette(&reversed).unwrap();
+    /// assert_eq!(ase.frame(0).image(), before);
+    /// ```
+    pub fn reorder_palette(&mut self, order: &[u32]) -> Result<()> {
+        let palette = self.palette.as_deref().ok_or_else(no_palette)?;
+        let new_palette = palette.reordered(order)?;
+        let mut remap: [u8; 256] = std::array::from_fn(|index| index as u8);
+        for (new_index, &old_index) in order.iter().enumerate() {
+            if old_index < 256 {
+                remap[old_index as usize] = new_index as u8;
+            }
+        }
+        self.replace_palette(new_palette, Some(&remap));
+        Ok(())
+    }
+
+    fn palette_or_empty(&self) -> ColorPalette {
+        self.palette
+            .as_deref()
+            .cloned()
+            .unwrap_or_else(|| ColorPalette::from_colors([]))
+    }
+
+    // Installs `palette` in the file and all indexed pixels. `remap` maps old
+    // to new indices.
+    fn replace_palette(&mut self, palette: ColorPalette, remap: Option<&[u8; 256]>) {
+        let palette = Arc::new(palette);
+        if let (
+            Some(remap),
+            PixelFormat::Indexed {
+                transparent_color_index,
+            },
+        ) = (remap, &mut self.pixel_format)
+        {
+            *transparent_color_index = remap[*transparent_color_index as usize];
+        }
+        let file_transparent_color_index = self.transparent_color_index();
+        // Cels may share pixel buffers. Remap each buffer only once. The old
+        // buffers are kept alive, so that their addresses stay unique.
+        let mut remapped: HashMap<usize, Arc<Vec<u8>>> = HashMap::new();
+        let mut old_buffers = Vec::new();
+        self.for_each_indexed_pixels(|pixels| {
+            if let Pixels::Indexed {
+                palette: pixels_palette,
+                transparent_color_index,
+                data,
+                ..
+            } = pixels
+            {
+                *pixels_palette = palette.clone();
+                if let Some(remap) = remap {
+                    if let Some(index) = file_transparent_color_index {
+                        *transparent_color_index = index;
+                    }
+                    let new_data =
+                        remapped
+                            .entry(Arc::as_ptr(data) as usize)
+                            .or_insert_with(|| {
+                                old_buffers.push(data.clone());
+                                Arc::new(data.iter().map(|&i| remap[i as usize]).collect())
+                            });
+                    *data = new_data.clone();
+                }
+            }
+        });
+        self.palette = Some(palette);
+    }
+
+    /// Convert all pixels to a different pixel format, like Aseprite's
+    /// "Sprite > Color Mode" menu.
+    ///
+    /// Converting to grayscale uses the luma of each color. Converting to
+    /// indexed colors uses the file's palette like
+    /// [CelMut::set_image](crate::CelMut::set_image). Use
+    /// [generate_palette](Self::generate_palette) to create a palette that
+    /// fits the image.
+    ///
+    /// ```
+    /// # use asefile::{AsepriteFile, PixelFormat};
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let before = ase.frame(1).image();
+    /// ase.set_palette(ase.generate_palette(256)).unwrap();
+    /// ase.convert_pixel_format(PixelFormat::Indexed { transparent_color_index: 0 }).unwrap();
+    /// assert!(ase.is_indexed_color());
+    /// assert_eq!(ase.frame(1).image(), before);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Converting to indexed colors fails if the file has no palette or the
+    /// palette has no entry at the transparent color index.
+    pub fn convert_pixel_format(&mut self, pixel_format: PixelFormat) -> Result<()> {
+        if let PixelFormat::Indexed {
+            transparent_color_index,
+        } = pixel_format
+        {
+            let palette = self.palette.as_deref().ok_or_else(no_palette)?;
+            if palette.color(transparent_color_index as u32).is_none() {
+                return Err(AsepriteParseError::InvalidEdit(format!(
+                    "No palette entry at the transparent color index {}",
+                    transparent_color_index
+                )));
+            }
+        }
+        let palette = self.palette.clone();
+        // Convert shared pixel buffers only once, see `replace_palette`.
+        let mut converted: HashMap<(usize, bool), Pixels> = HashMap::new();
+        let mut old_pixels = Vec::new();
+        self.for_each_pixels(|pixels, layer_is_background| {
+            let key = (pixels_address(pixels), layer_is_background);
+            let new_pixels = converted.entry(key).or_insert_with(|| {
+                old_pixels.push(pixels.clone());
+                Pixels::from_rgba(
+                    pixels.clone_as_image_rgba().into_owned(),
+                    pixel_format,
+                    palette.as_ref(),
+                    layer_is_background,
+                )
+                .expect("Palette was checked")
+            });
+            *pixels = new_pixels.clone();
+        });
+        self.pixel_format = pixel_format;
+        Ok(())
+    }
+
+    /// A palette with at most `max_colors` entries for converting the file
+    /// to indexed colors, like Aseprite's "Palette > New Palette from
+    /// Sprite".
+    ///
+    /// Entry 0 is transparent black, for use as the
+    /// [transparent color index](Self::transparent_color_index). The other
+    /// entries are the colors of all cels and tiles, reduced with
+    /// [ColorPalette::quantize] if there are too many. `max_colors` is
+    /// limited to 256, the maximum for indexed files.
+    pub fn generate_palette(&self, max_colors: u32) -> ColorPalette {
+        let mut colors = Vec::new();
+        let cels = (0..self.num_frames()).flat_map(|frame| {
+            self.framedata
+                .frame_cels(frame as u16)
+                .map(|(_, cel)| &cel.content)
+        });
+        for content in cels {
+            if let CelContent::Raw(image) = content {
+                colors.extend(image.pixels.clone_as_image_rgba().iter().copied());
+            }
+        }
+        for tileset in self.tilesets.iter() {
+            if let Some(pixels) = &tileset.pixels {
+                colors.extend(pixels.clone_as_image_rgba().iter().copied());
+            }
+        }
+        colors.retain(|color| color.0[3] > 0);
+        let quantized = ColorPalette::quantize(colors, max_colors.clamp(1, 256) - 1);
+        ColorPalette::from_colors(
+            std::iter::once([0, 0, 0, 0]).chain(quantized.iter().map(|c| c.raw_rgba8())),
+        )
+    }
+
+    fn for_each_indexed_pixels<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Pixels),
+    {
+        self.for_each_pixels(|pixels, _| {
+            if matches!(pixels, Pixels::Indexed { .. }) {
+                f(pixels);
+            }
+        });
+    }
+
+    // Calls `f` with the pixels of all cels and tiles and whether they are on
+    // a background layer.
+    fn for_each_pixels<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Pixels, bool),
+    {
+        for cel in Arc::make_mut(&mut self.framedata).cels_mut() {
+            if let CelContent::Raw(image) = &mut cel.content {
+                let layer_is_background = self.layers[cel.data.layer_index as u32].is_background();
+                f(&mut image.pixels, layer_is_background);
+            }
+        }
+        for tileset in Arc::make_mut(&mut self.tilesets).iter_mut() {
+            if let Some(pixels) = &mut tileset.pixels {
+                f(pixels, false);
+            }
+        }
+    }
+
     /// Does this file use indexed color format.
     pub fn is_indexed_color(&self) -> bool {
         match self.pixel_format() {
@@ -160,7 +808,7 @@ impl AsepriteFile {
     /// # Panics
     ///
     /// Panics if the ID is not valid. ID must be less than number of layers.
-    pub fn layer(&self, id: u32) -> Layer {
+    pub fn layer(&self, id: u32) -> Layer<'_> {
         assert!(id < self.num_layers());
         Layer {
             file: self,
@@ -171,8 +819,9 @@ impl AsepriteFile {
     /// Access a layer by name.
     ///
     /// If multiple layers with the same name exist returns the layer with
-    /// the lower ID.
-    pub fn layer_by_name(&self, name: &str) -> Option<Layer> {
+    /// the lower ID. Use [layers_by_name](Self::layers_by_name) to get all of
+    /// them.
+    pub fn layer_by_name(&self, name: &str) -> Option<Layer<'_>> {
         for layer_id in 0..self.num_layers() {
             let l = self.layer(layer_id);
             if l.name() == name {
@@ -182,11 +831,156 @@ impl AsepriteFile {
         None
     }
 
+    /// Modify a layer by ID. See [LayerMut].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID is not valid. ID must be less than number of layers.
+    pub fn layer_mut(&mut self, id: u32) -> LayerMut<'_> {
+        assert!(id < self.num_layers());
+        LayerMut {
+            file: self,
+            layer_id: id,
+        }
+    }
+
+    /// Modify a layer by name. If multiple layers with the same name exist
+    /// returns the layer with the lower ID.
+    pub fn layer_by_name_mut(&mut self, name: &str) -> Option<LayerMut<'_>> {
+        let id = self.layer_by_name(name)?.id();
+        Some(self.layer_mut(id))
+    }
+
+    /// Add an empty image layer on top of the children of `parent`, or on
+    /// top of all layers if `parent` is `None`. Returns the ID of the new
+    /// layer.
+    ///
+    /// Layers with the same or a higher ID move up by one.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let id = ase.add_layer("Overlay", None).unwrap();
+    /// assert_eq!(id, ase.num_layers() - 1);
+    /// assert!(ase.cel(0, id).is_empty());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if `parent` is not a [group layer](LayerType::Group).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is not less than `num_layers`.
+    pub fn add_layer(&mut self, name: &str, parent: Option<u32>) -> Result<u32> {
+        if let Some(parent) = parent {
+            if self.layer(parent).layer_type() != LayerType::Group {
+                return Err(AsepriteParseError::InvalidEdit(format!(
+                    "Layer {} is not a group",
+                    parent
+                )));
+            }
+        }
+        let (id, mapping) = self.layers.insert(parent, LayerData::new_image(name));
+        Arc::make_mut(&mut self.framedata).remap_layers(&mapping);
+        Ok(id)
+    }
+
+    /// Remove a layer and all of its cels. Removing a group also removes all
+    /// of its descendants.
+    ///
+    /// Layers with higher IDs move down to fill the gap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not less than `num_layers`.
+    pub fn remove_layer(&mut self, id: u32) {
+        assert!(id < self.num_layers());
+        let mapping = self.layers.remove(id);
+        Arc::make_mut(&mut self.framedata).remap_layers(&mapping);
+    }
+
+    /// Move a layer (and its descendants) to a different position within its
+    /// parent group. `position` counts the layer's siblings from the bottom,
+    /// i.e., `0` moves the layer below all other layers in the group.
+    ///
+    /// Layer IDs change to reflect the new order.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `position` is larger than the number of siblings, or if the
+    /// layer would move to or from the position of the background layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not less than `num_layers`.
+    pub fn move_layer(&mut self, id: u32, position: u32) -> Result<()> {
+        assert!(id < self.num_layers());
+        let mapping = self.layers.move_within_parent(id, position)?;
+        Arc::make_mut(&mut self.framedata).remap_layers(&mapping);
+        Ok(())
+    }
+
+    /// Access a layer by its full path, e.g., `"Body/Arms/Left"`. See
+    /// [Layer::path] for the path format.
+    ///
+    /// If multiple layers have the same path returns the layer with the lower
+    /// ID.
+    pub fn layer_by_path(&self, path: &str) -> Option<Layer<'_>> {
+        // Cheap check on the last component first to avoid building the path
+        // of every layer.
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.layers()
+            .filter(|l| l.name().ends_with(name))
+            .find(|l| l.path() == path)
+    }
+
+    /// All layers with the given name, ordered by ID.
+    pub fn layers_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Layer<'a>> + 'a {
+        self.layers().filter(move |l| l.name() == name)
+    }
+
+    /// All layers whose name matches `name`, ignoring ASCII case, ordered by
+    /// ID.
+    pub fn layers_by_name_ignore_case<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = Layer<'a>> + 'a {
+        self.layers()
+            .filter(move |l| l.name().eq_ignore_ascii_case(name))
+    }
+
+    /// All layers whose name matches a glob pattern, ordered by ID.
+    ///
+    /// In the pattern, `*` matches any sequence of characters (including none)
+    /// and `?` matches exactly one character. All other characters must match
+    /// exactly.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// # let ase = AsepriteFile::read_file(&path).unwrap();
+    /// let ids: Vec<u32> = ase.layers_matching("Layer ?").map(|l| l.id()).collect();
+    /// assert_eq!(ids, vec![0, 1, 4, 5]);
+    /// ```
+    pub fn layers_matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = Layer<'a>> + 'a {
+        self.layers().filter(move |l| glob_match(pattern, l.name()))
+    }
+
+    /// The layers of this file arranged by group nesting.
+    pub fn layer_tree(&self) -> LayerTree<'_> {
+        LayerTree::new(self)
+    }
+
     /// An iterator over all layers.
-    pub fn layers(&self) -> LayersIter {
+    pub fn layers(&self) -> LayersIter<'_> {
         LayersIter {
             file: self,
             next: 0,
+            end: self.num_layers(),
         }
     }
 
@@ -195,11 +989,93 @@ impl AsepriteFile {
     /// # Panics
     ///
     /// Panics if `index` is not less than `num_frames`.
-    pub fn frame(&self, index: u32) -> Frame {
+    pub fn frame(&self, index: u32) -> Frame<'_> {
         assert!(index < self.num_frames as u32);
         Frame { file: self, index }
     }
 
+    /// The areas of the canvas that look different in the frames `from` and
+    /// `to`, e.g., in a frame and the next one. Streaming renderers can use
+    /// them to only update the changed parts of a texture.
+    ///
+    /// Layers whose cels are the same in both frames, e.g., linked cels, are
+    /// skipped. The areas of all other cels are compared pixel by pixel. The
+    /// result contains the bounds of the changed pixels in each area, where
+    /// overlapping areas are merged. It is empty if both frames look the
+    /// same.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let ase = AsepriteFile::read_file(&path).unwrap();
+    /// assert!(ase.dirty_rects(1, 1).is_empty());
+    /// for rect in ase.dirty_rects(0, 1) {
+    ///     // Upload the pixels in `rect` of `ase.frame(1).image()`.
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` or `to` is not less than `num_frames`.
+    pub fn dirty_rects(&self, from: u32, to: u32) -> Vec<Rect> {
+        assert!(from < self.num_frames() && to < self.num_frames());
+        let canvas = Rect::new(0, 0, self.width as u32, self.height as u32);
+        let mut areas = Vec::new();
+        for layer in self.layers() {
+            if !layer.is_visible() {
+                continue;
+            }
+            let (a, b) = (self.cel(from, layer.id()), self.cel(to, layer.id()));
+            if self.same_cel(a.cel_id, b.cel_id) {
+                continue;
+            }
+            let bounds = [a.bounds(), b.bounds()];
+            areas.extend(
+                bounds
+                    .iter()
+                    .flatten()
+                    .filter_map(|rect| rect.intersection(&canvas)),
+            );
+        }
+        if areas.is_empty() {
+            return Vec::new();
+        }
+        // Merge overlapping areas, so that no pixel is compared twice.
+        while let Some((i, j)) = (0..areas.len())
+            .flat_map(|i| (i + 1..areas.len()).map(move |j| (i, j)))
+            .find(|&(i, j)| areas[i].intersection(&areas[j]).is_some())
+        {
+            let other = areas.swap_remove(j);
+            areas[i] = areas[i].union(&other);
+        }
+        let (image_a, image_b) = (self.frame(from).image(), self.frame(to).image());
+        areas
+            .into_iter()
+            .filter_map(|area| changed_bounds(&image_a, &image_b, area))
+            .collect()
+    }
+
+    // Do both cels draw the same pixels at the same position?
+    fn same_cel(&self, a: CelId, b: CelId) -> bool {
+        match (self.resolved_cel(a), self.resolved_cel(b)) {
+            (None, None) => true,
+            (Some(a), Some(b)) if std::ptr::eq(a, b) => true,
+            (Some(a), Some(b)) => {
+                let same_data =
+                    (a.data.x, a.data.y, a.data.opacity) == (b.data.x, b.data.y, b.data.opacity);
+                same_data
+                    && match (&a.content, &b.content) {
+                        (CelContent::Raw(a), CelContent::Raw(b)) => {
+                            pixels_address(&a.pixels) == pixels_address(&b.pixels)
+                        }
+                        _ => false,
+                    }
+            }
+            _ => false,
+        }
+    }
+
     /// Get a direct reference to a [Cel].
     ///
     /// Argument order is `x, y` if you think of the timeline panel in the GUI.
@@ -208,7 +1084,7 @@ impl AsepriteFile {
     ///
     /// Panics if `frame` is not less than `num_frames` or if `layer` is not
     /// less than `num_layers`.
-    pub fn cel(&self, frame: u32, layer: u32) -> Cel {
+    pub fn cel(&self, frame: u32, layer: u32) -> Cel<'_> {
         assert!(frame < self.num_frames as u32 && layer < self.num_layers());
         Cel {
             file: self,
@@ -219,6 +1095,23 @@ impl AsepriteFile {
         }
     }
 
+    /// Modify the cel at the given frame and layer. See [CelMut].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames` or if `layer` is not
+    /// less than `num_layers`.
+    pub fn cel_mut(&mut self, frame: u32, layer: u32) -> CelMut<'_> {
+        assert!(frame < self.num_frames as u32 && layer < self.num_layers());
+        CelMut {
+            file: self,
+            cel_id: CelId {
+                frame: frame as u16,
+                layer: layer as u16,
+            },
+        }
+    }
+
     /// A mapping from external file ids to external files.
     pub fn external_files(&self) -> &ExternalFilesById {
         &self.external_files
@@ -248,14 +1141,77 @@ impl AsepriteFile {
         &self.tags[tag_id as usize]
     }
 
+    /// An iterator over all tags, ordered by ID.
+    pub fn tags(&self) -> std::slice::Iter<'_, Tag> {
+        self.tags.iter()
+    }
+
     /// Lookup tag by name.
     ///
     /// If multiple tags with the same name exist, returns the one with the
-    /// lower ID.
+    /// lower ID. Use [tags_by_name](Self::tags_by_name) to get all of them.
     pub fn tag_by_name(&self, name: &str) -> Option<&Tag> {
         self.tags.iter().find(|&tag| tag.name() == name)
     }
 
+    /// Modify a tag by ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag_id` is not less than `num_tags`.
+    pub fn tag_mut(&mut self, tag_id: u32) -> &mut Tag {
+        &mut self.tags[tag_id as usize]
+    }
+
+    /// Modify a tag by name. If multiple tags with the same name exist,
+    /// returns the one with the lowest ID.
+    pub fn tag_by_name_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.tags.iter_mut().find(|tag| tag.name() == name)
+    }
+
+    /// Add a tag after all existing tags. Returns the ID of the new tag.
+    ///
+    /// ```
+    /// # use asefile::{AsepriteFile, AnimationDirection, Tag};
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let mut ase = AsepriteFile::read_file(&path).unwrap();
+    /// let mut tag = Tag::new("Idle", 0, 1);
+    /// tag.set_animation_direction(AnimationDirection::PingPong);
+    /// let id = ase.add_tag(tag);
+    /// assert_eq!(ase.tag_by_name("Idle").unwrap().len(), 2);
+    /// ase.remove_tag(id);
+    /// ```
+    pub fn add_tag(&mut self, tag: Tag) -> u32 {
+        self.tags.push(tag);
+        self.tags.len() as u32 - 1
+    }
+
+    /// Remove a tag. Tags with higher IDs move down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag_id` is not less than `num_tags`.
+    pub fn remove_tag(&mut self, tag_id: u32) -> Tag {
+        self.tags.remove(tag_id as usize)
+    }
+
+    /// All tags with the given name, ordered by ID.
+    ///
+    /// Aseprite does not require tag names to be unique.
+    pub fn tags_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Tag> + 'a {
+        self.tags.iter().filter(move |tag| tag.name() == name)
+    }
+
+    /// All tags whose range includes the given frame, ordered by ID.
+    ///
+    /// Tag ranges may overlap, so a frame may belong to several tags.
+    pub fn tags_at_frame(&self, frame: u32) -> impl Iterator<Item = &Tag> {
+        self.tags
+            .iter()
+            .filter(move |tag| tag.contains_frame(frame))
+    }
+
     /// Access the file's [Tileset]s.
     pub fn tilesets(&self) -> &TilesetsById {
         &self.tilesets
@@ -264,7 +1220,7 @@ impl AsepriteFile {
     /// Get the [Tilemap] at the given cel.
     ///
     /// Returns `None` if the cel is empty or if it is not a tilemap.
-    pub fn tilemap(&self, layer_id: u32, frame: u32) -> Option<Tilemap> {
+    pub fn tilemap(&self, layer_id: u32, frame: u32) -> Option<Tilemap<'_>> {
         if layer_id >= self.num_layers() || frame >= self.num_frames() {
             return None;
         }
@@ -278,8 +1234,8 @@ impl AsepriteFile {
                 let pixel_width = self.width() as u32;
                 let pixel_height = self.height() as u32;
                 let (tile_width, tile_height) = tileset.tile_size().into();
-                let w = (pixel_width + tile_width - 1) / tile_width;
-                let h = (pixel_height + tile_height - 1) / tile_height;
+                let w = pixel_width.div_ceil(tile_width);
+                let h = pixel_height.div_ceil(tile_height);
                 assert!(w < (1u32 << 16) && h < (1u32 << 16));
                 Some(Tilemap {
                     cel,
@@ -296,11 +1252,136 @@ impl AsepriteFile {
         self.sprite_user_data.as_ref()
     }
 
-    /// All [Slice]s in the file.
+    /// Check the file for content that is valid but likely a mistake, e.g.,
+    /// duplicate layer names or layers without any pixels. Useful for
+    /// checking art assets in CI.
+    ///
+    /// Returns an empty list if nothing suspicious was found.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// # let ase = AsepriteFile::read_file(&path).unwrap();
+    /// for warning in ase.lint() {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// ```
+    pub fn lint(&self) -> Vec<LintWarning> {
+        lint::lint(self)
+    }
+
+    /// A hash of the parsed content of the file, e.g., for use as a cache key
+    /// in an asset pipeline.
+    ///
+    /// Covers everything this crate parses: pixels, palette, layers, tags,
+    /// slices, tilesets and user data. Files that are saved again without
+    /// changes have the same hash. The hash is the same on every platform, but
+    /// may change with new versions of this crate.
+    ///
+    /// To find out which frames changed, use [Frame::content_hash].
+    pub fn content_hash(&self) -> u64 {
+        content::file_hash(self)
+    }
+
+    /// Groups of frames that look the same, e.g., to store each distinct image
+    /// only once in a sprite sheet and map the other frames to it.
+    ///
+    /// Frames are compared by their composited [image](Frame::image), so
+    /// frames count as duplicates even if they do not use linked cels, and
+    /// frame durations are ignored. Each group contains at least two frames in
+    /// ascending order. Groups are ordered by their first frame. Frames
+    /// without duplicates are not part of any group.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// let ase = AsepriteFile::read_file(&path).unwrap();
+    /// for group in ase.duplicate_frames() {
+    ///     // Use the image of `group[0]` for all frames in `group`.
+    /// }
+    /// ```
+    pub fn duplicate_frames(&self) -> Vec<Vec<u32>> {
+        // Frames with the same hash are compared pixel by pixel, so hash
+        // collisions never group different images.
+        let mut buckets: HashMap<u64, Vec<(RgbaImage, usize)>> = HashMap::new();
+        let mut groups: Vec<Vec<u32>> = Vec::new();
+        for frame in 0..self.num_frames() {
+            let image = self.frame(frame).image();
+            let bucket = buckets.entry(content::image_hash(&image)).or_default();
+            match bucket.iter().find(|(other, _)| *other == image) {
+                Some(&(_, group)) => groups[group].push(frame),
+                None => {
+                    bucket.push((image, groups.len()));
+                    groups.push(vec![frame]);
+                }
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// The differences between this file and a newer version of it, e.g.,
+    /// for hot reloading only the affected textures and animations.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// let old = AsepriteFile::read_file(Path::new("./tests/data/layers_and_tags.aseprite")).unwrap();
+    /// let new = AsepriteFile::read_file(Path::new("./tests/data/layers_and_tags.aseprite")).unwrap();
+    /// let diff = old.diff(&new);
+    /// for frame in diff.frames.changed.iter().chain(&diff.frames.added) {
+    ///     // Update the texture of `frame`.
+    /// }
+    /// assert!(diff.is_empty());
+    /// ```
+    pub fn diff(&self, newer: &AsepriteFile) -> FileDiff {
+        diff::diff(self, newer)
+    }
+
+    /// Values attached to the file by custom chunk handlers. See
+    /// [ParseOptions::chunk_handler].
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Mutable access to the [extensions](Self::extensions), e.g., for
+    /// attaching your own data to the file after loading it.
+    ///
+    /// If this file is a clone, the extensions are copied first, so the
+    /// changes do not affect other clones.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        Arc::make_mut(&mut self.extensions)
+    }
+
+    /// All [Slice]s in the file, in the order they appear in the file.
     pub fn slices(&self) -> &[Slice] {
         &self.slices
     }
 
+    /// Lookup slice by name.
+    ///
+    /// Slice names are not guaranteed to be unique. If several slices have the
+    /// same name, returns the first one. Use
+    /// [slices_by_name](Self::slices_by_name) to get all of them.
+    pub fn slice_by_name(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+
+    /// All slices with the given name, in file order.
+    pub fn slices_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Slice> + 'a {
+        self.slices.iter().filter(move |slice| slice.name == name)
+    }
+
+    /// All slices that exist at the given frame together with their active
+    /// [SliceKey], in file order.
+    pub fn slices_at_frame(&self, frame: u32) -> impl Iterator<Item = (&Slice, &SliceKey)> {
+        self.slices
+            .iter()
+            .filter_map(move |slice| Some((slice, slice.key_for_frame(frame)?)))
+    }
+
     // pub fn color_profile(&self) -> Option<&ColorProfile> {
     //     self.color_profile.as_ref()
     // }
@@ -311,7 +1392,22 @@ impl AsepriteFile {
     ///
     /// Can fail if the `frame` does not exist, an unsupported feature is
     /// used, or the file is malformed.
-    fn frame_image(&self, frame: u16) -> RgbaImage {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    fn frame_image(&self, frame: u16, options: &RenderOptions) -> RgbaImage {
+        self.frame_image_with_positions(frame, options, |_| None)
+    }
+
+    // Like `frame_image`, but draws the cels of the layers for which
+    // `position` returns a point at that position instead.
+    pub(crate) fn frame_image_with_positions<F>(
+        &self,
+        frame: u16,
+        options: &RenderOptions,
+        position: F,
+    ) -> RgbaImage
+    where
+        F: Fn(u32) -> Option<Point>,
+    {
         let mut image = RgbaImage::new(self.width as u32, self.height as u32);
 
         for (layer_id, cel) in self.framedata.frame_cels(frame) {
@@ -319,16 +1415,100 @@ impl AsepriteFile {
             if !self.layer(layer_id).is_visible() {
                 continue;
             }
-            self.write_cel(&mut image, cel);
+            match position(layer_id) {
+                Some(point) => {
+                    let cel_id = CelId {
+                        frame,
+                        layer: layer_id as u16,
+                    };
+                    let Some(mut moved) = self.resolved_cel(cel_id).cloned() else {
+                        continue;
+                    };
+                    moved.data.x = point.x.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                    moved.data.y = point.y.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                    self.write_cel(&mut image, &moved, options);
+                }
+                None => self.write_cel(&mut image, cel, options),
+            }
         }
 
         image
     }
 
-    fn write_cel(&self, image: &mut RgbaImage, cel: &RawCel<Pixels>) {
+    // The cel with the content of the given cel, i.e., the link target of
+    // linked cels.
+    pub(crate) fn resolved_cel(&self, cel_id: CelId) -> Option<&RawCel<Pixels>> {
+        let cel = self.framedata.cel(cel_id)?;
+        match cel.content {
+            CelContent::Linked(frame) => self.framedata.cel(CelId {
+                frame,
+                layer: cel_id.layer,
+            }),
+            _ => Some(cel),
+        }
+    }
+
+    fn frame_content_bounds(&self, frame: u16) -> Option<Rect> {
+        let canvas = Rect::new(0, 0, self.width as u32, self.height as u32);
+        let mut bounds: Option<Rect> = None;
+        for (layer_id, cel) in self.framedata.frame_cels(frame) {
+            let layer = self.layer(layer_id);
+            if !layer.is_visible() || layer.opacity() == 0 {
+                continue;
+            }
+            let cel_id = CelId {
+                frame,
+                layer: layer_id as u16,
+            };
+            let rect = match self.cel_content_bounds(cel_id, cel) {
+                Some(rect) => rect.intersection(&canvas),
+                None => None,
+            };
+            if let Some(rect) = rect {
+                bounds = Some(bounds.map_or(rect, |bounds| bounds.union(&rect)));
+            }
+        }
+        bounds
+    }
+
+    // Bounds of the non-transparent pixels of a cel in canvas coordinates.
+    pub(crate) fn cel_content_bounds(&self, cel_id: CelId, cel: &RawCel<Pixels>) -> Option<Rect> {
+        if cel.data.opacity == 0 {
+            return None;
+        }
+        match &cel.content {
+            CelContent::Raw(ImageContent { size, pixels }) => {
+                let pixels = pixels.clone_as_image_rgba();
+                let rect =
+                    geometry::opaque_bounds_of(size.width as u32, pixels.iter().map(|p| p[3]))?;
+                Some(Rect::new(
+                    rect.x + cel.data.x as i32,
+                    rect.y + cel.data.y as i32,
+                    rect.w,
+                    rect.h,
+                ))
+            }
+            // Tiles may be partially transparent, so render the cel.
+            CelContent::Tilemap(_) => geometry::opaque_bounds(&self.layer_image(cel_id)),
+            CelContent::Linked(frame) => {
+                let linked_id = CelId {
+                    frame: *frame,
+                    layer: cel_id.layer,
+                };
+                let linked = self.framedata.cel(linked_id)?;
+                if let CelContent::Linked(_) = linked.content {
+                    return None;
+                }
+                // The linked cel's opacity and position apply.
+                self.cel_content_bounds(linked_id, linked)
+            }
+        }
+    }
+
+    fn write_cel(&self, image: &mut RgbaImage, cel: &RawCel<Pixels>, options: &RenderOptions) {
         let RawCel { data, content, .. } = cel;
         let layer = self.layer(data.layer_index as u32);
-        let blend_mode = layer.blend_mode();
+        let blend_fn = blend_mode_to_blend_fn(layer.blend_mode(), options);
         // let resolver_data = pixel::IndexResolverData {
         //     palette: self.palette.as_ref(),
         //     transparent_color_index: self.pixel_format.transparent_color_index(),
@@ -344,7 +1524,7 @@ impl AsepriteFile {
                     data,
                     size,
                     image_pixels.as_ref(),
-                    &blend_mode,
+                    &blend_fn,
                     layer.opacity(),
                 );
             }
@@ -373,7 +1553,7 @@ impl AsepriteFile {
                     tilemap_data,
                     tileset,
                     rgba_pixels.as_ref(),
-                    &blend_mode,
+                    &blend_fn,
                     layer.opacity(),
                 );
             }
@@ -388,17 +1568,21 @@ impl AsepriteFile {
                         );
                     } else {
                         // Recurse once with the source non-Linked cel
-                        self.write_cel(image, cel);
+                        self.write_cel(image, cel, options);
                     }
                 }
             }
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(cel = %cel_id))
+    )]
     pub(crate) fn layer_image(&self, cel_id: CelId) -> RgbaImage {
         let mut image = RgbaImage::new(self.width as u32, self.height as u32);
         if let Some(cel) = self.framedata.cel(cel_id) {
-            self.write_cel(&mut image, cel);
+            self.write_cel(&mut image, cel, &RenderOptions::default());
         }
         image
     }
@@ -411,18 +1595,61 @@ impl AsepriteFile {
     // }
 }
 
+fn no_palette() -> AsepriteParseError {
+    AsepriteParseError::InvalidEdit("The file has no palette".to_owned())
+}
+
+// Identifies the buffer of `pixels`. Cels may share buffers.
+fn pixels_address(pixels: &Pixels) -> usize {
+    match pixels {
+        Pixels::Rgba(data) => Arc::as_ptr(data) as usize,
+        Pixels::Grayscale(data) => Arc::as_ptr(data) as usize,
+        Pixels::Indexed { data, .. } => Arc::as_ptr(data) as usize,
+    }
+}
+
+// Frame durations are stored as a WORD in the file format.
+fn clamp_frame_duration(ms: u128) -> Duration {
+    Duration::from_millis(ms.clamp(1, u16::MAX as u128) as u64)
+}
+
+/// Compares the parsed content of two files, i.e., everything that
+/// [content_hash](AsepriteFile::content_hash) covers.
+///
+/// Differences in how the content is encoded are ignored: compressed and
+/// uncompressed cels with the same pixels are equal, as are linked cels and
+/// copies of the linked cel. [Extensions](AsepriteFile::extensions) are not
+/// compared.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// let a = AsepriteFile::read_file(Path::new("./tests/data/basic-16x16.aseprite")).unwrap();
+/// let b = AsepriteFile::read_file(Path::new("./tests/data/basic-16x16.aseprite")).unwrap();
+/// assert!(a == b);
+/// ```
+impl PartialEq for AsepriteFile {
+    fn eq(&self, other: &Self) -> bool {
+        content::files_equal(self, other)
+    }
+}
+
+impl Eq for AsepriteFile {}
+
 /// An iterator over layers. See [AsepriteFile::layers].
 #[derive(Debug)]
 pub struct LayersIter<'a> {
     file: &'a AsepriteFile,
+    // Remaining layers are `next..end`.
     next: u32,
+    end: u32,
 }
 
 impl<'a> Iterator for LayersIter<'a> {
     type Item = Layer<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next < self.file.num_layers() {
+        if self.next < self.end {
             let item = self.file.layer(self.next);
             self.next += 1;
             Some(item)
@@ -430,15 +1657,41 @@ impl<'a> Iterator for LayersIter<'a> {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.next) as usize;
+        (len, Some(len))
+    }
 }
 
+impl DoubleEndedIterator for LayersIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.end -= 1;
+            Some(self.file.layer(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for LayersIter<'_> {}
+
+impl FusedIterator for LayersIter<'_> {}
+
 impl<'a> Frame<'a> {
     /// Construct the image belonging to the specific animation frame. Combines
     /// layers according to their blend mode. Skips invisible layers (i.e.,
     /// layers with a deactivated eye icon).
     ///
     pub fn image(&self) -> RgbaImage {
-        self.file.frame_image(self.index as u16)
+        self.image_with_options(&RenderOptions::default())
+    }
+
+    /// Like [image](Self::image), but with custom rendering behavior. See
+    /// [RenderOptions].
+    pub fn image_with_options(&self, options: &RenderOptions) -> RgbaImage {
+        self.file.frame_image(self.index as u16, options)
     }
 
     /// Frame ID, i.e., the frame number.
@@ -447,7 +1700,7 @@ impl<'a> Frame<'a> {
     }
 
     /// Get cel corresponding to the given layer in this frame.
-    pub fn layer(&self, layer_id: u32) -> Cel {
+    pub fn layer(&self, layer_id: u32) -> Cel<'_> {
         assert!(layer_id < self.file.num_layers());
         let cel_id = CelId {
             frame: self.index as u16,
@@ -459,15 +1712,105 @@ impl<'a> Frame<'a> {
         }
     }
 
+    /// The smallest rectangle containing all non-transparent pixels of the
+    /// visible layers, or `None` if the frame is empty. The result is clamped
+    /// to the canvas.
+    ///
+    /// This does not compose the frame. Image cels are scanned directly, so
+    /// it is much cheaper than computing the bounds of [image](Self::image).
+    /// Blend modes are not taken into account, so a pixel that ends up
+    /// transparent after blending may still be included.
+    pub fn content_bounds(&self) -> Option<Rect> {
+        self.file.frame_content_bounds(self.index as u16)
+    }
+
+    /// How long this frame is displayed.
+    pub fn duration(&self) -> Duration {
+        self.file.frame_times[self.index as usize]
+    }
+
     /// Frame duration in milliseconds.
-    pub fn duration(&self) -> u32 {
-        self.file.frame_times[self.index as usize] as u32
+    pub fn duration_ms(&self) -> u32 {
+        self.duration().as_millis() as u32
+    }
+
+    /// A hash of everything that affects the images of this frame and its
+    /// cels: the cel pixels and positions, the properties of all layers, the
+    /// palette, referenced tilesets and the frame duration. Frames with equal
+    /// content have the same hash, even if they are in different files.
+    ///
+    /// See [AsepriteFile::content_hash].
+    pub fn content_hash(&self) -> u64 {
+        content::frame_hash(self.file, self.index)
+    }
+
+    /// The pivot of the first slice with the given name at this frame,
+    /// normalized to the canvas size. See [SliceKey::normalized_pivot_in_canvas].
+    ///
+    /// Returns `None` if there is no such slice, the slice does not exist at
+    /// this frame or it has no pivot.
+    pub fn pivot(&self, slice_name: &str) -> Option<(f32, f32)> {
+        self.file
+            .slice_by_name(slice_name)?
+            .key_for_frame(self.index)?
+            .normalized_pivot_in_canvas(self.file.width() as u32, self.file.height() as u32)
     }
 }
 
 type BlendFn = Box<dyn Fn(Color8, Color8, u8) -> Color8>;
 
-fn blend_mode_to_blend_fn(mode: BlendMode) -> BlendFn {
+// The bounds of the pixels in `area` that differ between two images of the
+// same size.
+fn changed_bounds(a: &RgbaImage, b: &RgbaImage, area: Rect) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            if a.get_pixel(x as u32, y as u32) != b.get_pixel(x as u32, y as u32) {
+                let pixel = Rect::new(x, y, 1, 1);
+                bounds = Some(bounds.map_or(pixel, |bounds| bounds.union(&pixel)));
+            }
+        }
+    }
+    bounds
+}
+
+// Matches `text` against a pattern where `*` matches any sequence of
+// characters and `?` matches a single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was
+    // tried at. Used to backtrack when the rest of the pattern fails to match.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn blend_mode_to_blend_fn(mode: BlendMode, options: &RenderOptions) -> BlendFn {
+    let bug_compatible = options.saturation_bug_compatible;
+    if options.legacy_blending {
+        return legacy_blend_mode_to_blend_fn(mode, bug_compatible);
+    }
     // TODO: Make these statically allocated
     match mode {
         BlendMode::Normal => Box::new(blend::normal),
@@ -482,8 +1825,12 @@ fn blend_mode_to_blend_fn(mode: BlendMode) -> BlendFn {
         BlendMode::SoftLight => Box::new(blend::soft_light),
         BlendMode::Difference => Box::new(blend::difference),
         BlendMode::Exclusion => Box::new(blend::exclusion),
-        BlendMode::Hue => Box::new(blend::hsl_hue),
-        BlendMode::Saturation => Box::new(blend::hsl_saturation),
+        BlendMode::Hue => Box::new(move |backdrop, src, opacity| {
+            blend::hsl_hue(backdrop, src, opacity, bug_compatible)
+        }),
+        BlendMode::Saturation => Box::new(move |backdrop, src, opacity| {
+            blend::hsl_saturation(backdrop, src, opacity, bug_compatible)
+        }),
         BlendMode::Color => Box::new(blend::hsl_color),
         BlendMode::Luminosity => Box::new(blend::hsl_luminosity),
         BlendMode::Addition => Box::new(blend::addition),
@@ -492,6 +1839,37 @@ fn blend_mode_to_blend_fn(mode: BlendMode) -> BlendFn {
     }
 }
 
+// The blend functions of Aseprite's old layer blending method (the
+// `rgba_blender_*` functions without the `_n` suffix). They blend the colors
+// without taking the alpha of the backdrop into account.
+fn legacy_blend_mode_to_blend_fn(mode: BlendMode, bug_compatible: bool) -> BlendFn {
+    match mode {
+        BlendMode::Normal => Box::new(blend::normal),
+        BlendMode::Multiply => Box::new(blend::multiply_baseline),
+        BlendMode::Screen => Box::new(blend::screen_baseline),
+        BlendMode::Overlay => Box::new(blend::overlay_baseline),
+        BlendMode::Darken => Box::new(blend::darken_baseline),
+        BlendMode::Lighten => Box::new(blend::lighten_baseline),
+        BlendMode::ColorDodge => Box::new(blend::color_dodge_baseline),
+        BlendMode::ColorBurn => Box::new(blend::color_burn_baseline),
+        BlendMode::HardLight => Box::new(blend::hard_light_baseline),
+        BlendMode::SoftLight => Box::new(blend::soft_light_baseline),
+        BlendMode::Difference => Box::new(blend::difference_baseline),
+        BlendMode::Exclusion => Box::new(blend::exclusion_baseline),
+        BlendMode::Hue => Box::new(move |backdrop, src, opacity| {
+            blend::hsl_hue_baseline(backdrop, src, opacity, bug_compatible)
+        }),
+        BlendMode::Saturation => Box::new(move |backdrop, src, opacity| {
+            blend::hsl_saturation_baseline(backdrop, src, opacity, bug_compatible)
+        }),
+        BlendMode::Color => Box::new(blend::hsl_color_baseline),
+        BlendMode::Luminosity => Box::new(blend::hsl_luminosity_baseline),
+        BlendMode::Addition => Box::new(blend::addition_baseline),
+        BlendMode::Subtract => Box::new(blend::subtract_baseline),
+        BlendMode::Divide => Box::new(blend::divide_baseline),
+    }
+}
+
 fn tile_slice<'a, T>(pixels: &'a [T], tile_size: &TileSize, tile_id: &TileId) -> &'a [T] {
     let pixels_per_tile = tile_size.pixels_per_tile() as usize;
     let start = pixels_per_tile * (tile_id.0 as usize);
@@ -499,13 +1877,29 @@ fn tile_slice<'a, T>(pixels: &'a [T], tile_size: &TileSize, tile_id: &TileId) ->
     &pixels[start..end]
 }
 
+// Finds the pixel inside the tile that ends up at `(x, y)` after applying the
+// tile's transform flags. Like Tiled, Aseprite applies the diagonal flip
+// (called 90 degree rotation in the file format spec) first, then the
+// horizontal and vertical flips.
+fn tile_source_pixel(tile: &Tile, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+    let x = if tile.flip_x { width - 1 - x } else { x };
+    let y = if tile.flip_y { height - 1 - y } else { y };
+    // A diagonal flip only makes sense for square tiles. It is ignored for
+    // other tiles, see `Tile::rotate_90cw`.
+    if tile.rotate_90cw && width == height {
+        (y, x)
+    } else {
+        (x, y)
+    }
+}
+
 fn write_tilemap_cel_to_image(
     image: &mut RgbaImage,
     cel_data: &CelCommon,
     tilemap_data: &TilemapData,
     tileset: &Tileset,
     pixels: &[Rgba<u8>],
-    blend_mode: &BlendMode,
+    blend_fn: &BlendFn,
     outer_opacity: u8,
 ) {
     let CelCommon {
@@ -525,20 +1919,22 @@ fn write_tilemap_cel_to_image(
     let tile_size = tileset.tile_size();
     let tile_width = tile_size.width() as i32;
     let tile_height = tile_size.height() as i32;
-    // pixels
-    let blend_fn = blend_mode_to_blend_fn(*blend_mode);
 
     for tile_y in 0..tilemap_height {
         for tile_x in 0..tilemap_width {
-            // TODO: support tile transform flags
             let tile = tilemap_data
                 .tile(tile_x as u16, tile_y as u16)
                 .expect("Invalid tile index");
+            if tileset.is_empty_tile_id(tile.id.0) {
+                continue;
+            }
             let tile_id = &tile.id;
             let tile_pixels = tile_slice(pixels, &tile_size, tile_id);
             for pixel_y in 0..tile_height {
                 for pixel_x in 0..tile_width {
-                    let pixel_idx = ((pixel_y * tile_width) + pixel_x) as usize;
+                    let (src_x, src_y) =
+                        tile_source_pixel(tile, pixel_x, pixel_y, tile_width, tile_height);
+                    let pixel_idx = ((src_y * tile_width) + src_x) as usize;
                     let image_pixel = tile_pixels[pixel_idx];
                     let image_x = (tile_x * tile_width) + pixel_x + cel_x;
                     let image_y = (tile_y * tile_height) + pixel_y + cel_y;
@@ -563,7 +1959,7 @@ fn write_raw_cel_to_image(
     cel_data: &CelCommon,
     image_size: &ImageSize,
     pixels: &[Rgba<u8>],
-    blend_mode: &BlendMode,
+    blend_fn: &BlendFn,
     outer_opacity: u8,
 ) {
     let ImageSize { width, height } = image_size;
@@ -574,7 +1970,6 @@ fn write_raw_cel_to_image(
         ..
     } = cel_data;
     let opacity = mul_un8(outer_opacity as i32, *cel_opacity as i32);
-    let blend_fn = blend_mode_to_blend_fn(*blend_mode);
     let x0 = *x as i32;
     let y0 = *y as i32;
     let x_end = x0 + (*width as i32);
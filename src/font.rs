@@ -0,0 +1,241 @@
+//! Build bitmap fonts from slices.
+//!
+//! Each glyph of the font is a slice in the Aseprite file. The slice bounds
+//! define the glyph image and the slice pivot marks the pen position on the
+//! baseline. Without a pivot, the baseline is the bottom edge of the slice and
+//! the pen starts at its left edge.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/slice_advanced.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::font::BitmapFont;
+//!
+//! // Slices are named "Slice 1", "Slice 2", ...
+//! let font = BitmapFont::from_slice_names(&ase, 0, "Slice ");
+//! assert!(font.glyph('1').is_some());
+//! let mut fnt = Vec::new();
+//! font.write_bmfont("digits", "digits.png", &mut fnt).unwrap();
+//! ```
+
+use std::io::{self, Write};
+
+use image::RgbaImage;
+
+use crate::{
+    geometry,
+    sheet::{self, SheetLayout, SheetOptions},
+    AsepriteFile, Point, Slice,
+};
+
+/// A single character of a [BitmapFont].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    /// The character this glyph represents.
+    pub character: char,
+    /// Left edge of the glyph in the font image.
+    pub x: u32,
+    /// Top edge of the glyph in the font image.
+    pub y: u32,
+    /// Width of the glyph in pixels.
+    pub width: u32,
+    /// Height of the glyph in pixels.
+    pub height: u32,
+    /// Horizontal offset from the pen position to the left edge of the glyph.
+    pub x_offset: i32,
+    /// Vertical offset from the top of the line to the top edge of the glyph.
+    pub y_offset: i32,
+    /// How far to move the pen after drawing this glyph.
+    pub x_advance: i32,
+}
+
+/// A font image plus the location and metrics of each glyph.
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    image: RgbaImage,
+    glyphs: Vec<Glyph>,
+    line_height: u32,
+    base: u32,
+}
+
+impl BitmapFont {
+    /// Build a font from the slices of the given frame.
+    ///
+    /// `glyph_char` decides which character a slice represents. Slices for
+    /// which it returns `None` are skipped. If several slices map to the same
+    /// character, the first one is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn from_slices<F>(file: &AsepriteFile, frame: u32, mut glyph_char: F) -> Self
+    where
+        F: FnMut(&Slice) -> Option<char>,
+    {
+        let frame_image = file.frame(frame).image();
+        // (character, image, pivot)
+        let mut sources: Vec<(char, RgbaImage, Point)> = Vec::new();
+        for slice in file.slices() {
+            let Some(character) = glyph_char(slice) else {
+                continue;
+            };
+            let Some(key) = slice.key_for_frame(frame) else {
+                continue;
+            };
+            if sources.iter().any(|(c, _, _)| *c == character) {
+                continue;
+            }
+            let pivot = key.pivot.unwrap_or(Point::new(0, key.size.h as i32));
+            sources.push((character, geometry::crop(&frame_image, key.bounds()), pivot));
+        }
+        sources.sort_by_key(|(c, _, _)| *c);
+
+        let base = sources
+            .iter()
+            .map(|(_, _, p)| p.y)
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        let line_height = sources
+            .iter()
+            .map(|(_, img, p)| base - p.y + img.height() as i32)
+            .max()
+            .unwrap_or(0)
+            .max(0);
+
+        let sizes: Vec<(u32, u32)> = sources.iter().map(|(_, img, _)| img.dimensions()).collect();
+        let options = SheetOptions {
+            layout: SheetLayout::Packed,
+            spacing: 1,
+            ..Default::default()
+        };
+        let (positions, (width, height)) = sheet::layout(&sizes, &options);
+        let mut image = RgbaImage::new(width, height);
+        let mut glyphs = Vec::with_capacity(sources.len());
+        for ((character, glyph_image, pivot), (x, y)) in sources.into_iter().zip(positions) {
+            image::imageops::replace(&mut image, &glyph_image, x as i64, y as i64);
+            glyphs.push(Glyph {
+                character,
+                x,
+                y,
+                width: glyph_image.width(),
+                height: glyph_image.height(),
+                x_offset: -pivot.x,
+                y_offset: base - pivot.y,
+                x_advance: glyph_image.width() as i32 - pivot.x,
+            });
+        }
+        BitmapFont {
+            image,
+            glyphs,
+            line_height: line_height as u32,
+            base: base as u32,
+        }
+    }
+
+    /// Build a font from all slices whose name starts with `prefix`.
+    ///
+    /// The rest of the name is the character, e.g., `"glyph_A"` with prefix
+    /// `"glyph_"`. Characters that are hard to type in a slice name can be
+    /// given as code points, e.g., `"glyph_U+0020"` for space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn from_slice_names(file: &AsepriteFile, frame: u32, prefix: &str) -> Self {
+        Self::from_slices(file, frame, |slice| {
+            parse_glyph_name(slice.name.strip_prefix(prefix)?)
+        })
+    }
+
+    /// The image containing all glyphs.
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Consume the font and return its image.
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// All glyphs, sorted by character.
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    /// The glyph for the given character.
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs
+            .binary_search_by_key(&character, |g| g.character)
+            .ok()
+            .map(|i| &self.glyphs[i])
+    }
+
+    /// Distance between two lines of text.
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    /// Distance from the top of a line to the baseline.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Write the font description in the text format of
+    /// [BMFont](https://www.angelcode.com/products/bmfont/), which most game
+    /// engines can load.
+    ///
+    /// `image_file` is the path of the font image relative to the output.
+    pub fn write_bmfont<W: Write>(
+        &self,
+        face: &str,
+        image_file: &str,
+        mut output: W,
+    ) -> io::Result<()> {
+        writeln!(
+            output,
+            r#"info face="{}" size={} bold=0 italic=0 charset="" unicode=1 stretchH=100 smooth=0 aa=1 padding=0,0,0,0 spacing=1,1"#,
+            face, self.line_height
+        )?;
+        writeln!(
+            output,
+            "common lineHeight={} base={} scaleW={} scaleH={} pages=1 packed=0",
+            self.line_height,
+            self.base,
+            self.image.width(),
+            self.image.height()
+        )?;
+        writeln!(output, r#"page id=0 file="{}""#, image_file)?;
+        writeln!(output, "chars count={}", self.glyphs.len())?;
+        for glyph in &self.glyphs {
+            writeln!(
+                output,
+                "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} page=0 chnl=15",
+                glyph.character as u32,
+                glyph.x,
+                glyph.y,
+                glyph.width,
+                glyph.height,
+                glyph.x_offset,
+                glyph.y_offset,
+                glyph.x_advance
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A single character or a code point such as `U+0041`.
+fn parse_glyph_name(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => {
+            let hex = name
+                .strip_prefix("U+")
+                .or_else(|| name.strip_prefix("u+"))?;
+            char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+        }
+    }
+}
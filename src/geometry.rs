@@ -0,0 +1,204 @@
+use image::{Rgba, RgbaImage};
+
+/// A position in pixel coordinates.
+///
+/// Converts to and from `(x, y)` tuples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    #[allow(missing_docs)]
+    pub x: i32,
+    #[allow(missing_docs)]
+    pub y: i32,
+}
+
+impl Point {
+    /// Create a new point.
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+}
+
+impl From<(i32, i32)> for Point {
+    fn from((x, y): (i32, i32)) -> Self {
+        Point { x, y }
+    }
+}
+
+impl From<Point> for (i32, i32) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+/// The width and height of an image or area in pixels.
+///
+/// Converts to and from `(w, h)` tuples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Size {
+    /// Width in pixels.
+    pub w: u32,
+    /// Height in pixels.
+    pub h: u32,
+}
+
+impl Size {
+    /// Create a new size.
+    pub fn new(w: u32, h: u32) -> Self {
+        Size { w, h }
+    }
+}
+
+impl From<(u32, u32)> for Size {
+    fn from((w, h): (u32, u32)) -> Self {
+        Size { w, h }
+    }
+}
+
+impl From<Size> for (u32, u32) {
+    fn from(size: Size) -> Self {
+        (size.w, size.h)
+    }
+}
+
+/// An axis-aligned rectangle in pixel coordinates.
+///
+/// `(x, y)` is the top left corner. The rectangle covers the pixels from `x`
+/// up to (but excluding) `x + w` horizontally, and likewise vertically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    /// Left edge.
+    pub x: i32,
+    /// Top edge.
+    pub y: i32,
+    /// Width in pixels.
+    pub w: u32,
+    /// Height in pixels.
+    pub h: u32,
+}
+
+impl Rect {
+    /// Create a new rectangle.
+    pub fn new(x: i32, y: i32, w: u32, h: u32) -> Self {
+        Rect { x, y, w, h }
+    }
+
+    /// Create a rectangle from its top left corner and size.
+    pub fn from_origin_size(origin: Point, size: Size) -> Self {
+        Rect::new(origin.x, origin.y, size.w, size.h)
+    }
+
+    /// The top left corner.
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    /// Width and height.
+    pub fn size(&self) -> Size {
+        Size::new(self.w, self.h)
+    }
+
+    /// The first column to the right of the rectangle.
+    pub fn right(&self) -> i32 {
+        self.x + self.w as i32
+    }
+
+    /// The first row below the rectangle.
+    pub fn bottom(&self) -> i32 {
+        self.y + self.h as i32
+    }
+
+    /// Does the rectangle cover no pixels?
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    /// Is the given pixel inside the rectangle?
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The area covered by both rectangles, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return None;
+        }
+        Some(Rect::new(x, y, (right - x) as u32, (bottom - y) as u32))
+    }
+
+    /// The smallest rectangle containing both rectangles. Empty rectangles
+    /// are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(x, y, (right - x) as u32, (bottom - y) as u32)
+    }
+
+    /// The part of the rectangle that lies inside a canvas of the given size,
+    /// or `None` if it lies completely outside.
+    pub fn clamp_to_canvas(&self, width: u32, height: u32) -> Option<Rect> {
+        self.intersection(&Rect::new(0, 0, width, height))
+    }
+}
+
+// Copies the pixels of `rect` out of `image`. Pixels outside of the image are
+// transparent.
+pub(crate) fn crop(image: &RgbaImage, rect: Rect) -> RgbaImage {
+    RgbaImage::from_fn(rect.w, rect.h, |x, y| {
+        let sx = rect.x + x as i32;
+        let sy = rect.y + y as i32;
+        if sx < 0 || sy < 0 || sx as u32 >= image.width() || sy as u32 >= image.height() {
+            Rgba([0, 0, 0, 0])
+        } else {
+            *image.get_pixel(sx as u32, sy as u32)
+        }
+    })
+}
+
+// The smallest rectangle containing all pixels with non-zero alpha, or `None`
+// if the image is fully transparent.
+pub(crate) fn opaque_bounds(image: &RgbaImage) -> Option<Rect> {
+    opaque_bounds_of(image.width(), image.as_raw().chunks_exact(4).map(|p| p[3]))
+}
+
+// Like `opaque_bounds`, but takes the alpha values of an image with the given
+// width, row by row.
+pub(crate) fn opaque_bounds_of<I>(width: u32, alpha: I) -> Option<Rect>
+where
+    I: IntoIterator<Item = u8>,
+{
+    let (mut min_x, mut min_y) = (u32::MAX, u32::MAX);
+    let (mut max_x, mut max_y) = (0, 0);
+    for (index, alpha) in alpha.into_iter().enumerate() {
+        if alpha != 0 {
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    if min_x > max_x {
+        return None;
+    }
+    Some(Rect::new(
+        min_x as i32,
+        min_y as i32,
+        max_x - min_x + 1,
+        max_y - min_y + 1,
+    ))
+}
@@ -0,0 +1,104 @@
+//! Create [ggez](https://ggez.rs/) images and animations. (Requires feature
+//! `ggez`.)
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["ggez"] }
+//! ```
+//!
+//! Images are uploaded as sRGB RGBA textures. Use
+//! `canvas.set_sampler(Sampler::nearest_clamp())` to keep pixel art crisp.
+//!
+//! ```no_run
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # fn example(ctx: &mut ggez::Context, canvas: &mut ggez::graphics::Canvas) {
+//! # let ase = AsepriteFile::read_file(Path::new("player.aseprite")).unwrap();
+//! use asefile::ggez::AnimatedSprite;
+//! use ggez::graphics::DrawParam;
+//! use std::time::Duration;
+//!
+//! let mut sprite = AnimatedSprite::from_tag_name(ctx, &ase, "walk").unwrap();
+//! // Once per update:
+//! sprite.update(Duration::from_millis(16));
+//! // Once per draw:
+//! canvas.draw(&sprite, DrawParam::new().dest([100.0, 100.0]));
+//! # }
+//! ```
+
+use ::ggez::{
+    context::Has,
+    graphics::{Canvas, DrawParam, Drawable, GraphicsContext, Image, ImageFormat, Rect},
+};
+use image::RgbaImage;
+
+use crate::{animation::AnimatedFrames, sheet::SpriteSheet, AsepriteFile, Tag};
+
+/// Upload an RGBA image as a ggez image.
+pub fn image_from_rgba(gfx: &impl Has<GraphicsContext>, image: &RgbaImage) -> Image {
+    Image::from_pixels(
+        gfx,
+        image.as_raw(),
+        ImageFormat::Rgba8UnormSrgb,
+        image.width(),
+        image.height(),
+    )
+}
+
+/// Compose the given frame and upload it as a ggez image.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn frame_image(gfx: &impl Has<GraphicsContext>, file: &AsepriteFile, frame: u32) -> Image {
+    image_from_rgba(gfx, &file.frame(frame).image())
+}
+
+/// Upload the image of a sprite sheet. Use the sheet's frame rects as the
+/// `src` of a `DrawParam` to draw individual frames.
+pub fn sheet_image(gfx: &impl Has<GraphicsContext>, sheet: &SpriteSheet) -> Image {
+    image_from_rgba(gfx, sheet.image())
+}
+
+/// An animation that can be drawn directly onto a ggez canvas.
+///
+/// Combines an [AnimationPlayer](crate::animation::AnimationPlayer) with one
+/// uploaded image per frame of the tag. Call
+/// [update](AnimatedFrames::update) once per game tick and draw the sprite
+/// like any other [Drawable].
+pub type AnimatedSprite = AnimatedFrames<Image>;
+
+impl AnimatedFrames<Image> {
+    /// Play the frames of the given tag. If `tag` is `None`, plays all frames
+    /// of the file in a loop. See
+    /// [AnimationPlayer::new](crate::animation::AnimationPlayer::new).
+    pub fn new(gfx: &impl Has<GraphicsContext>, file: &AsepriteFile, tag: Option<&Tag>) -> Self {
+        Self::from_fn(file, tag, |frame| frame_image(gfx, file, frame))
+    }
+
+    /// Play the frames of the first tag with the given name, or return `None`
+    /// if there is no such tag.
+    pub fn from_tag_name(
+        gfx: &impl Has<GraphicsContext>,
+        file: &AsepriteFile,
+        tag_name: &str,
+    ) -> Option<Self> {
+        let tag = file.tag_by_name(tag_name)?;
+        Some(Self::new(gfx, file, Some(tag)))
+    }
+
+    /// The image of the frame that is displayed.
+    pub fn image(&self) -> &Image {
+        self.current()
+    }
+}
+
+impl Drawable for AnimatedFrames<Image> {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        self.image().draw(canvas, param)
+    }
+
+    fn dimensions(&self, gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        self.image().dimensions(gfx)
+    }
+}
@@ -0,0 +1,217 @@
+//! Export slice metadata as JSON. (Requires feature `json`.)
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["json"] }
+//! ```
+//!
+//! Two formats are supported:
+//!
+//! - [AsepriteSlices] uses the same schema as the `"slices"` entry in the
+//!   `"meta"` section of Aseprite's own JSON export. Existing loaders for
+//!   Aseprite's format can read it directly.
+//! - [FlatSlice] has one entry per slice and frame with the bounds already
+//!   resolved, which is easier to consume if you just want to look up the
+//!   slice for the current frame.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/slice_advanced.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::json::{AsepriteSlices, FlatSlice};
+//!
+//! let json = AsepriteSlices::new(&ase).to_json_string().unwrap();
+//! let flat = FlatSlice::to_json_string(&FlatSlice::all(&ase)).unwrap();
+//! ```
+
+use image::Rgba;
+use serde::{Deserialize, Serialize};
+
+use crate::{AsepriteFile, Point, Rect, Slice, SliceKey};
+
+/// Slices in the format of Aseprite's JSON export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AsepriteSlices {
+    /// All slices in the order they appear in the file.
+    pub slices: Vec<SliceJson>,
+}
+
+/// A single slice in Aseprite's JSON format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceJson {
+    /// Slice name.
+    pub name: String,
+    /// User data color as `#rrggbbaa`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// User data text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// The shape of the slice over time.
+    pub keys: Vec<SliceKeyJson>,
+}
+
+/// The shape of a [SliceJson] starting at a given frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceKeyJson {
+    /// First frame this key applies to.
+    pub frame: u32,
+    /// Bounds of the slice in canvas coordinates.
+    pub bounds: RectJson,
+    /// 9-slice center, relative to the bounds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center: Option<RectJson>,
+    /// Pivot, relative to the bounds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<PointJson>,
+}
+
+/// A rectangle as written by Aseprite.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RectJson {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// A point as written by Aseprite.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointJson {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A slice at a single frame, with all positions resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlatSlice {
+    /// Slice name.
+    pub name: String,
+    /// Frame index.
+    pub frame: u32,
+    /// Left edge of the slice in canvas coordinates.
+    pub x: i32,
+    /// Top edge of the slice in canvas coordinates.
+    pub y: i32,
+    /// Width of the slice.
+    pub w: u32,
+    /// Height of the slice.
+    pub h: u32,
+    /// 9-slice center, relative to the slice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center: Option<RectJson>,
+    /// Pivot, relative to the slice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<PointJson>,
+    /// User data color as `#rrggbbaa`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// User data text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+impl AsepriteSlices {
+    /// Collect all slices of the given file.
+    pub fn new(file: &AsepriteFile) -> Self {
+        AsepriteSlices {
+            slices: file.slices().iter().map(SliceJson::from).collect(),
+        }
+    }
+
+    /// Write the slices as pretty-printed JSON.
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl From<&Slice> for SliceJson {
+    fn from(slice: &Slice) -> Self {
+        let (color, data) = user_data(slice);
+        SliceJson {
+            name: slice.name.clone(),
+            color,
+            data,
+            keys: slice
+                .keys
+                .iter()
+                .map(|key| SliceKeyJson {
+                    frame: key.from_frame,
+                    bounds: bounds(key),
+                    center: center(key),
+                    pivot: pivot(key),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FlatSlice {
+    /// One entry for every slice at every frame where it exists, ordered by
+    /// slice and then by frame.
+    pub fn all(file: &AsepriteFile) -> Vec<FlatSlice> {
+        file.slices()
+            .iter()
+            .flat_map(|slice| {
+                (0..file.num_frames()).filter_map(move |frame| Self::new(slice, frame))
+            })
+            .collect()
+    }
+
+    /// The given slice at the given frame, or `None` if the slice does not
+    /// exist at that frame.
+    pub fn new(slice: &Slice, frame: u32) -> Option<FlatSlice> {
+        let key = slice.key_for_frame(frame)?;
+        let (color, data) = user_data(slice);
+        let RectJson { x, y, w, h } = bounds(key);
+        Some(FlatSlice {
+            name: slice.name.clone(),
+            frame,
+            x,
+            y,
+            w,
+            h,
+            center: center(key),
+            pivot: pivot(key),
+            color,
+            data,
+        })
+    }
+
+    /// Write a list of slices as a pretty-printed JSON array.
+    pub fn to_json_string(slices: &[FlatSlice]) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(slices)
+    }
+}
+
+fn bounds(key: &SliceKey) -> RectJson {
+    let Rect { x, y, w, h } = key.bounds();
+    RectJson { x, y, w, h }
+}
+
+fn center(key: &SliceKey) -> Option<RectJson> {
+    key.slice9.as_ref().map(|s| {
+        let Rect { x, y, w, h } = s.center();
+        RectJson { x, y, w, h }
+    })
+}
+
+fn pivot(key: &SliceKey) -> Option<PointJson> {
+    key.pivot.map(|Point { x, y }| PointJson { x, y })
+}
+
+fn user_data(slice: &Slice) -> (Option<String>, Option<String>) {
+    match &slice.user_data {
+        Some(user_data) => (user_data.color.map(hex_color), user_data.text.clone()),
+        None => (None, None),
+    }
+}
+
+// Aseprite writes colors as `#rrggbbaa`.
+fn hex_color(color: Rgba<u8>) -> String {
+    let [r, g, b, a] = color.0;
+    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
@@ -1,12 +1,12 @@
 use crate::{
     cel::{Cel, CelId},
     reader::AseReader,
-    tileset::TilesetsById,
+    tileset::{TilesetId, TilesetsById},
     user_data::UserData,
     AsepriteFile, AsepriteParseError, Result,
 };
 use bitflags::bitflags;
-use std::{io::Read, ops::Index};
+use std::{fmt, io::Read, ops::Index, str::FromStr};
 
 /// Types of layer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,7 +19,7 @@ pub enum LayerType {
     /// A tilemap layer. Contains the index of the tileset used for the tiles.
     ///
     /// In Aseprite these are represented by a grid icon.
-    Tilemap(u32),
+    Tilemap(TilesetId),
 }
 
 bitflags! {
@@ -49,8 +49,34 @@ bitflags! {
     }
 }
 
+/// A stable identifier for a layer.
+///
+/// Only stored by newer versions of Aseprite, see [Layer::uuid]. Formats as
+/// the usual hyphenated lowercase hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerUuid([u8; 16]);
+
+impl LayerUuid {
+    /// The raw bytes of the UUID, in the order they are stored in the file.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl fmt::Display for LayerUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
 /// A reference to a single layer.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Layer<'a> {
     pub(crate) file: &'a AsepriteFile,
     pub(crate) layer_id: u32,
@@ -102,13 +128,50 @@ impl<'a> Layer<'a> {
     ///
     /// Does not indicate the blend order of layers (i.e., which layers are
     /// above or below).
-    pub fn parent(&self) -> Option<Layer> {
+    pub fn parent(&self) -> Option<Layer<'_>> {
         self.file.layers.parents[self.layer_id as usize].map(|id| Layer {
             file: self.file,
             layer_id: id,
         })
     }
 
+    /// The direct children of this layer, ordered by ID.
+    ///
+    /// Only [group layers](LayerType::Group) have children.
+    pub fn children(&self) -> impl Iterator<Item = Layer<'a>> + 'a {
+        let id = self.layer_id;
+        let file = self.file;
+        file.layers()
+            .filter(move |l| file.layers.parents[l.layer_id as usize] == Some(id))
+    }
+
+    /// All layers nested inside this layer, i.e., its children, their
+    /// children, and so on. Ordered by ID.
+    pub fn descendants(&self) -> impl Iterator<Item = Layer<'a>> + 'a {
+        let id = self.layer_id;
+        let file = self.file;
+        file.layers()
+            .skip(id as usize + 1)
+            .filter(move |l| file.layers.is_ancestor(id, l.layer_id))
+    }
+
+    /// The full path of this layer, i.e., the names of all its parent groups
+    /// and its own name, separated by `/`.
+    ///
+    /// For a layer "Left" inside group "Arms", which is itself inside group
+    /// "Body", this returns `"Body/Arms/Left"`. Layer names are not escaped,
+    /// so a name that contains `/` will look like an extra level.
+    pub fn path(&self) -> String {
+        let mut names = vec![self.name()];
+        let mut parent = self.file.layers.parents[self.layer_id as usize];
+        while let Some(id) = parent {
+            names.push(&self.file.layers[id].name);
+            parent = self.file.layers.parents[id as usize];
+        }
+        names.reverse();
+        names.join("/")
+    }
+
     /// Returns if this layer is visible. This requires that this layer and all
     /// of its parent layers are visible.
     pub fn is_visible(&self) -> bool {
@@ -118,7 +181,7 @@ impl<'a> Layer<'a> {
     }
 
     /// Get a reference to the Cel for this frame in the layer.
-    pub fn frame(&self, frame_id: u32) -> Cel {
+    pub fn frame(&self, frame_id: u32) -> Cel<'_> {
         assert!(frame_id < self.file.num_frames());
         let cel_id = CelId {
             frame: frame_id as u16,
@@ -134,9 +197,168 @@ impl<'a> Layer<'a> {
     pub fn user_data(&self) -> Option<&UserData> {
         self.data().user_data.as_ref()
     }
+
+    /// The layer's UUID. Unlike the layer's ID or name, this stays the same
+    /// when the layer is moved or renamed.
+    ///
+    /// Returns `None` if the file was saved without layer UUIDs (e.g., by an
+    /// older version of Aseprite).
+    pub fn uuid(&self) -> Option<LayerUuid> {
+        self.data().uuid
+    }
 }
 
+/// A mutable reference to a single layer. Created via
+/// [AsepriteFile::layer_mut].
+///
+/// Changes only affect the file in memory, e.g., the images returned by
+/// [Frame::image](crate::Frame::image).
+///
+/// ```
+/// # use asefile::{AsepriteFile, BlendMode};
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// let mut ase = AsepriteFile::read_file(&path).unwrap();
+/// let mut layer = ase.layer_mut(1);
+/// layer.set_name("Shadow");
+/// layer.set_opacity(128);
+/// layer.set_blend_mode(BlendMode::Multiply);
+/// assert_eq!(ase.layer(1).name(), "Shadow");
+/// ```
 #[derive(Debug)]
+pub struct LayerMut<'a> {
+    pub(crate) file: &'a mut AsepriteFile,
+    pub(crate) layer_id: u32,
+}
+
+impl<'a> LayerMut<'a> {
+    fn data_mut(&mut self) -> &mut LayerData {
+        &mut self.file.layers.layers[self.layer_id as usize]
+    }
+
+    /// A read-only reference to the same layer.
+    pub fn as_layer(&self) -> Layer<'_> {
+        self.file.layer(self.layer_id)
+    }
+
+    /// This layer's ID.
+    pub fn id(&self) -> u32 {
+        self.layer_id
+    }
+
+    /// Rename the layer.
+    pub fn set_name(&mut self, name: &str) {
+        self.data_mut().name = name.to_owned();
+    }
+
+    /// Replace all of the layer's flags.
+    pub fn set_flags(&mut self, flags: LayerFlags) {
+        self.data_mut().flags = flags;
+    }
+
+    /// Show or hide the layer, i.e., set or clear [LayerFlags::VISIBLE].
+    ///
+    /// Children of a hidden group stay hidden, see [Layer::is_visible].
+    pub fn set_visible(&mut self, visible: bool) {
+        self.data_mut().flags.set(LayerFlags::VISIBLE, visible);
+    }
+
+    /// Change the layer opacity.
+    pub fn set_opacity(&mut self, opacity: u8) {
+        self.data_mut().opacity = opacity;
+    }
+
+    /// Change how the layer is combined with the layers underneath it.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.data_mut().blend_mode = blend_mode;
+    }
+
+    /// Replace the layer's [UserData]. `None` removes it.
+    pub fn set_user_data(&mut self, user_data: Option<UserData>) {
+        self.data_mut().user_data = user_data;
+    }
+}
+
+/// The layer hierarchy of a file, as shown in Aseprite's timeline panel.
+///
+/// Created via [AsepriteFile::layer_tree]. Nodes at every level are ordered by
+/// layer ID, i.e., from the bottom of the timeline to the top.
+#[derive(Debug, Clone)]
+pub struct LayerTree<'a> {
+    roots: Vec<LayerNode<'a>>,
+}
+
+/// A single layer in a [LayerTree], together with its children.
+#[derive(Debug, Clone)]
+pub struct LayerNode<'a> {
+    layer: Layer<'a>,
+    children: Vec<LayerNode<'a>>,
+}
+
+impl<'a> LayerTree<'a> {
+    pub(crate) fn new(file: &'a AsepriteFile) -> Self {
+        // Parents always have a lower ID than their children, so building
+        // the tree back to front means all children are complete by the
+        // time their parent is reached.
+        let mut pending: Vec<Vec<LayerNode<'a>>> = vec![Vec::new(); file.num_layers() as usize];
+        let mut roots = Vec::new();
+        for layer in file.layers().rev() {
+            let mut children = std::mem::take(&mut pending[layer.id() as usize]);
+            children.reverse();
+            let node = LayerNode { layer, children };
+            match file.layers.parents[layer.id() as usize] {
+                Some(parent) => pending[parent as usize].push(node),
+                None => roots.push(node),
+            }
+        }
+        roots.reverse();
+        LayerTree { roots }
+    }
+
+    /// Layers that are not part of any group.
+    pub fn roots(&self) -> &[LayerNode<'a>] {
+        &self.roots
+    }
+
+    /// All nodes in depth-first order (a group comes before its children).
+    pub fn iter(&self) -> impl Iterator<Item = &LayerNode<'a>> {
+        let mut stack: Vec<&LayerNode<'a>> = self.roots.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// All group layers in depth-first order.
+    pub fn groups(&self) -> impl Iterator<Item = &LayerNode<'a>> {
+        self.iter().filter(|node| node.is_group())
+    }
+
+    /// All layers that are not groups, in depth-first order.
+    pub fn leaves(&self) -> impl Iterator<Item = &LayerNode<'a>> {
+        self.iter().filter(|node| !node.is_group())
+    }
+}
+
+impl<'a> LayerNode<'a> {
+    /// The layer at this node.
+    pub fn layer(&self) -> Layer<'a> {
+        self.layer
+    }
+
+    /// Direct children of this node. Empty unless this is a group.
+    pub fn children(&self) -> &[LayerNode<'a>] {
+        &self.children
+    }
+
+    /// Is this node a [group layer](LayerType::Group)?
+    pub fn is_group(&self) -> bool {
+        self.layer.layer_type() == LayerType::Group
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LayerData {
     pub(crate) flags: LayerFlags,
     pub(crate) name: String,
@@ -144,16 +366,31 @@ pub struct LayerData {
     pub(crate) opacity: u8,
     pub(crate) layer_type: LayerType,
     pub(crate) user_data: Option<UserData>,
+    pub(crate) uuid: Option<LayerUuid>,
     child_level: u16,
 }
 
 impl LayerData {
+    // An empty, visible and editable image layer at the root level.
+    pub(crate) fn new_image(name: &str) -> Self {
+        LayerData {
+            flags: LayerFlags::VISIBLE | LayerFlags::EDITABLE,
+            name: name.to_owned(),
+            blend_mode: BlendMode::Normal,
+            opacity: 255,
+            layer_type: LayerType::Image,
+            user_data: None,
+            uuid: None,
+            child_level: 0,
+        }
+    }
+
     pub(crate) fn is_background(&self) -> bool {
         self.flags.contains(LayerFlags::BACKGROUND)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LayersData {
     // Sorted back to front (or bottom to top in the GUI, but groups occur
     // before their children, i.e., lower index)
@@ -166,12 +403,9 @@ impl LayersData {
         for l in &self.layers {
             if let LayerType::Tilemap(id) = l.layer_type {
                 // Validate that all Tilemap layers reference an existing Tileset.
-                tilesets.get(id).ok_or_else(|| {
-                    AsepriteParseError::InvalidInput(format!(
-                        "Tilemap layer references a missing tileset (id {}",
-                        id
-                    ))
-                })?;
+                tilesets
+                    .get(id)
+                    .ok_or(AsepriteParseError::MissingTileset { tileset: id })?;
             }
         }
         Ok(())
@@ -182,6 +416,132 @@ impl LayersData {
         let parents = compute_parents(&layers);
         Ok(LayersData { layers, parents })
     }
+
+    // Adds `layer` as the topmost child of `parent`, or as the topmost root
+    // layer. Returns the new ID and the mapping from old to new IDs.
+    pub(crate) fn insert(
+        &mut self,
+        parent: Option<u32>,
+        mut layer: LayerData,
+    ) -> (u32, Vec<Option<u32>>) {
+        let (id, child_level) = match parent {
+            Some(parent) => (
+                self.subtree_end(parent),
+                self.layers[parent as usize].child_level + 1,
+            ),
+            None => (self.layers.len() as u32, 0),
+        };
+        layer.child_level = child_level;
+        self.layers.insert(id as usize, layer);
+        self.parents = compute_parents(&self.layers);
+        let mapping = (0..self.layers.len() as u32 - 1)
+            .map(|old| Some(if old < id { old } else { old + 1 }))
+            .collect();
+        (id, mapping)
+    }
+
+    // Removes the layer and all its descendants. Returns the mapping from old
+    // to new IDs.
+    pub(crate) fn remove(&mut self, layer_id: u32) -> Vec<Option<u32>> {
+        let end = self.subtree_end(layer_id);
+        let count = end - layer_id;
+        let mapping = (0..self.layers.len() as u32)
+            .map(|old| match old {
+                _ if old < layer_id => Some(old),
+                _ if old < end => None,
+                _ => Some(old - count),
+            })
+            .collect();
+        self.layers.drain(layer_id as usize..end as usize);
+        self.parents = compute_parents(&self.layers);
+        mapping
+    }
+
+    // Moves the layer (with its descendants) to `position` among its
+    // siblings. Returns the mapping from old to new IDs.
+    pub(crate) fn move_within_parent(
+        &mut self,
+        layer_id: u32,
+        position: u32,
+    ) -> Result<Vec<Option<u32>>> {
+        if self.layers[layer_id as usize].is_background() {
+            return Err(AsepriteParseError::InvalidEdit(
+                "The background layer cannot be moved".to_owned(),
+            ));
+        }
+        let parent = self.parents[layer_id as usize];
+        let (start, end) = match parent {
+            Some(parent) => (parent + 1, self.subtree_end(parent)),
+            None => (0, self.layers.len() as u32),
+        };
+        // Each sibling with its descendants, from bottom to top.
+        let mut blocks = Vec::new();
+        let mut sibling = start;
+        while sibling < end {
+            let sibling_end = self.subtree_end(sibling);
+            blocks.push(sibling..sibling_end);
+            sibling = sibling_end;
+        }
+        let index = blocks
+            .iter()
+            .position(|block| block.start == layer_id)
+            .expect("Layer is a child of its parent");
+        let block = blocks.remove(index);
+        if position as usize > blocks.len() {
+            return Err(AsepriteParseError::InvalidEdit(format!(
+                "Invalid layer position {}, layer has {} siblings",
+                position,
+                blocks.len()
+            )));
+        }
+        if position == 0
+            && blocks
+                .first()
+                .is_some_and(|b| self[b.start].is_background())
+        {
+            return Err(AsepriteParseError::InvalidEdit(
+                "Layers cannot be moved below the background layer".to_owned(),
+            ));
+        }
+        blocks.insert(position as usize, block);
+
+        let order: Vec<u32> = (0..start)
+            .chain(blocks.into_iter().flatten())
+            .chain(end..self.layers.len() as u32)
+            .collect();
+        let mut mapping = vec![None; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            mapping[old as usize] = Some(new as u32);
+        }
+        self.layers = order
+            .iter()
+            .map(|&old| self.layers[old as usize].clone())
+            .collect();
+        self.parents = compute_parents(&self.layers);
+        Ok(mapping)
+    }
+
+    // The first ID after the layer's descendants.
+    fn subtree_end(&self, layer_id: u32) -> u32 {
+        let level = self.layers[layer_id as usize].child_level;
+        let mut end = layer_id as usize + 1;
+        while end < self.layers.len() && self.layers[end].child_level > level {
+            end += 1;
+        }
+        end as u32
+    }
+
+    // Is `ancestor` a (direct or indirect) parent of `layer_id`?
+    fn is_ancestor(&self, ancestor: u32, layer_id: u32) -> bool {
+        let mut parent = self.parents[layer_id as usize];
+        while let Some(id) = parent {
+            if id == ancestor {
+                return true;
+            }
+            parent = self.parents[id as usize];
+        }
+        false
+    }
 }
 
 impl Index<u32> for LayersData {
@@ -198,6 +558,22 @@ impl Index<u32> for LayersData {
 /// Blend modes use Aseprite's "new layer blending method", i.e., we assume that
 /// the source Aseprite has a checkmark under "Edit > Preferences > Experimental >
 /// New Layer Blending Method (#1096)". This is the default as of Aseprite 1.2.25.
+/// For files authored without it, see
+/// [RenderOptions::legacy_blending](crate::RenderOptions::legacy_blending).
+///
+/// Blend modes convert to and from the names used for `blendMode` in
+/// Aseprite's JSON export, e.g., `"color_dodge"` or `"hsl_hue"`, and to and
+/// from their ID in the file format. Parsing also accepts the names without
+/// the `hsl_` prefix, e.g., `"hue"`.
+///
+/// ```
+/// # use asefile::BlendMode;
+/// let mode: BlendMode = "hard_light".parse().unwrap();
+/// assert_eq!(mode, BlendMode::HardLight);
+/// assert_eq!(mode.to_string(), "hard_light");
+/// assert_eq!(u16::from(mode), 8);
+/// assert_eq!(BlendMode::try_from(8).unwrap(), mode);
+/// ```
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlendMode {
@@ -222,7 +598,106 @@ pub enum BlendMode {
     Divide,
 }
 
-pub(crate) fn parse_chunk(data: &[u8]) -> Result<LayerData> {
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BlendMode::Normal => "normal",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::Darken => "darken",
+            BlendMode::Lighten => "lighten",
+            BlendMode::ColorDodge => "color_dodge",
+            BlendMode::ColorBurn => "color_burn",
+            BlendMode::HardLight => "hard_light",
+            BlendMode::SoftLight => "soft_light",
+            BlendMode::Difference => "difference",
+            BlendMode::Exclusion => "exclusion",
+            BlendMode::Hue => "hsl_hue",
+            BlendMode::Saturation => "hsl_saturation",
+            BlendMode::Color => "hsl_color",
+            BlendMode::Luminosity => "hsl_luminosity",
+            BlendMode::Addition => "addition",
+            BlendMode::Subtract => "subtract",
+            BlendMode::Divide => "divide",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = AsepriteParseError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "normal" => Ok(BlendMode::Normal),
+            "multiply" => Ok(BlendMode::Multiply),
+            "screen" => Ok(BlendMode::Screen),
+            "overlay" => Ok(BlendMode::Overlay),
+            "darken" => Ok(BlendMode::Darken),
+            "lighten" => Ok(BlendMode::Lighten),
+            "color_dodge" => Ok(BlendMode::ColorDodge),
+            "color_burn" => Ok(BlendMode::ColorBurn),
+            "hard_light" => Ok(BlendMode::HardLight),
+            "soft_light" => Ok(BlendMode::SoftLight),
+            "difference" => Ok(BlendMode::Difference),
+            "exclusion" => Ok(BlendMode::Exclusion),
+            "hsl_hue" | "hue" => Ok(BlendMode::Hue),
+            "hsl_saturation" | "saturation" => Ok(BlendMode::Saturation),
+            "hsl_color" | "color" => Ok(BlendMode::Color),
+            "hsl_luminosity" | "luminosity" => Ok(BlendMode::Luminosity),
+            "addition" => Ok(BlendMode::Addition),
+            "subtract" => Ok(BlendMode::Subtract),
+            "divide" => Ok(BlendMode::Divide),
+            _ => Err(AsepriteParseError::InvalidInput(format!(
+                "Unknown blend mode: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl From<BlendMode> for u16 {
+    fn from(mode: BlendMode) -> Self {
+        // The variants are declared in the order of their IDs.
+        mode as u16
+    }
+}
+
+impl TryFrom<u16> for BlendMode {
+    type Error = AsepriteParseError;
+
+    fn try_from(id: u16) -> Result<Self> {
+        match id {
+            0 => Ok(BlendMode::Normal),
+            1 => Ok(BlendMode::Multiply),
+            2 => Ok(BlendMode::Screen),
+            3 => Ok(BlendMode::Overlay),
+            4 => Ok(BlendMode::Darken),
+            5 => Ok(BlendMode::Lighten),
+            6 => Ok(BlendMode::ColorDodge),
+            7 => Ok(BlendMode::ColorBurn),
+            8 => Ok(BlendMode::HardLight),
+            9 => Ok(BlendMode::SoftLight),
+            10 => Ok(BlendMode::Difference),
+            11 => Ok(BlendMode::Exclusion),
+            12 => Ok(BlendMode::Hue),
+            13 => Ok(BlendMode::Saturation),
+            14 => Ok(BlendMode::Color),
+            15 => Ok(BlendMode::Luminosity),
+            16 => Ok(BlendMode::Addition),
+            17 => Ok(BlendMode::Subtract),
+            18 => Ok(BlendMode::Divide),
+            _ => Err(AsepriteParseError::InvalidInput(format!(
+                "Invalid/Unsupported blend mode: {}",
+                id
+            ))),
+        }
+    }
+}
+
+// `has_uuid` is set if the file header says that layers store a UUID.
+pub(crate) fn parse_chunk(data: &[u8], has_uuid: bool) -> Result<LayerData> {
     let mut reader = AseReader::new(data);
 
     let flags = reader.word()?;
@@ -236,10 +711,17 @@ pub(crate) fn parse_chunk(data: &[u8]) -> Result<LayerData> {
     let _reserved2 = reader.word()?;
     let name = reader.string()?;
     let layer_type = parse_layer_type(layer_type, &mut reader)?;
+    let uuid = if has_uuid {
+        let mut bytes = [0_u8; 16];
+        reader.read_exact(&mut bytes)?;
+        Some(LayerUuid(bytes))
+    } else {
+        None
+    };
 
     let flags = LayerFlags::from_bits_truncate(flags as u32);
 
-    let blend_mode = parse_blend_mode(blend_mode)?;
+    let blend_mode = BlendMode::try_from(blend_mode)?;
 
     // println!(
     //     "Layer {}: flags={:?} type={:?} blend_mode={:?}, opacity={}",
@@ -254,6 +736,7 @@ pub(crate) fn parse_chunk(data: &[u8]) -> Result<LayerData> {
         layer_type,
         child_level,
         user_data: None,
+        uuid,
     })
 }
 
@@ -261,7 +744,9 @@ fn parse_layer_type<R: Read>(id: u16, reader: &mut AseReader<R>) -> Result<Layer
     match id {
         0 => Ok(LayerType::Image),
         1 => Ok(LayerType::Group),
-        2 => reader.dword().map(LayerType::Tilemap),
+        2 => reader
+            .dword()
+            .map(|id| LayerType::Tilemap(TilesetId::new(id))),
         _ => Err(AsepriteParseError::InvalidInput(format!(
             "Invalid layer type: {}",
             id
@@ -269,34 +754,6 @@ fn parse_layer_type<R: Read>(id: u16, reader: &mut AseReader<R>) -> Result<Layer
     }
 }
 
-fn parse_blend_mode(id: u16) -> Result<BlendMode> {
-    match id {
-        0 => Ok(BlendMode::Normal),
-        1 => Ok(BlendMode::Multiply),
-        2 => Ok(BlendMode::Screen),
-        3 => Ok(BlendMode::Overlay),
-        4 => Ok(BlendMode::Darken),
-        5 => Ok(BlendMode::Lighten),
-        6 => Ok(BlendMode::ColorDodge),
-        7 => Ok(BlendMode::ColorBurn),
-        8 => Ok(BlendMode::HardLight),
-        9 => Ok(BlendMode::SoftLight),
-        10 => Ok(BlendMode::Difference),
-        11 => Ok(BlendMode::Exclusion),
-        12 => Ok(BlendMode::Hue),
-        13 => Ok(BlendMode::Saturation),
-        14 => Ok(BlendMode::Color),
-        15 => Ok(BlendMode::Luminosity),
-        16 => Ok(BlendMode::Addition),
-        17 => Ok(BlendMode::Subtract),
-        18 => Ok(BlendMode::Divide),
-        _ => Err(AsepriteParseError::InvalidInput(format!(
-            "Invalid/Unsupported blend mode: {}",
-            id
-        ))),
-    }
-}
-
 fn compute_parents(layers: &[LayerData]) -> Vec<Option<u32>> {
     let mut result = Vec::with_capacity(layers.len());
 
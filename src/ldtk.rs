@@ -0,0 +1,271 @@
+//! Export tilemaps to the [LDtk](https://ldtk.io/) level editor format.
+//! (Requires feature `ldtk`.)
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["ldtk"] }
+//! ```
+//!
+//! The output contains one level with a layer instance per tilemap layer and
+//! a tileset definition for each tileset used by those layers. The tileset
+//! images must be exported separately using
+//! [`tileset.image_grid(columns)`](crate::Tileset::image_grid) with the
+//! columns given in [TilesetDefinition::c_wid].
+//!
+//! LDtk supports horizontal and vertical flips, but not the diagonal flip
+//! (90 degree rotation) of Aseprite tiles. Rotated tiles are exported without
+//! rotation.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/tilemap.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::ldtk::LdtkExport;
+//!
+//! let export = LdtkExport::new(&ase, 0, |tileset| format!("tileset{}.png", tileset.id().value()));
+//! let json = export.to_json_string().unwrap();
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AsepriteFile, LayerType, Tileset, TilesetId};
+
+// UIDs must be unique across all definitions in an LDtk project.
+const TILESET_UID_BASE: i64 = 1000;
+const LAYER_UID_BASE: i64 = 2000;
+const LEVEL_UID: i64 = 1;
+
+/// A level plus the definitions of the tilesets it uses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LdtkExport {
+    /// Tilesets used by the level, ordered by tileset ID.
+    pub tilesets: Vec<TilesetDefinition>,
+    /// The exported level.
+    pub level: Level,
+}
+
+/// An LDtk tileset definition.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TilesetDefinition {
+    /// Number of tile columns in the image.
+    #[serde(rename = "__cWid")]
+    pub c_wid: u32,
+    /// Number of tile rows in the image.
+    #[serde(rename = "__cHei")]
+    pub c_hei: u32,
+    pub identifier: String,
+    pub uid: i64,
+    /// Path of the tileset image.
+    pub rel_path: String,
+    pub px_wid: u32,
+    pub px_hei: u32,
+    pub tile_grid_size: u32,
+    pub spacing: u32,
+    pub padding: u32,
+}
+
+/// An LDtk level.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Level {
+    pub identifier: String,
+    pub uid: i64,
+    pub px_wid: u32,
+    pub px_hei: u32,
+    /// Layers from top to bottom, as LDtk expects.
+    pub layer_instances: Vec<LayerInstance>,
+}
+
+/// A single tile layer of a [Level].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerInstance {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    #[serde(rename = "__type")]
+    pub layer_type: String,
+    #[serde(rename = "__cWid")]
+    pub c_wid: u32,
+    #[serde(rename = "__cHei")]
+    pub c_hei: u32,
+    #[serde(rename = "__gridSize")]
+    pub grid_size: u32,
+    #[serde(rename = "__opacity")]
+    pub opacity: f32,
+    #[serde(rename = "__tilesetDefUid")]
+    pub tileset_def_uid: i64,
+    #[serde(rename = "__tilesetRelPath")]
+    pub tileset_rel_path: String,
+    pub layer_def_uid: i64,
+    pub level_id: i64,
+    pub visible: bool,
+    pub px_offset_x: i32,
+    pub px_offset_y: i32,
+    /// All non-empty tiles.
+    pub grid_tiles: Vec<GridTile>,
+}
+
+/// A single tile of a [LayerInstance].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridTile {
+    /// Pixel position of the tile in the layer.
+    pub px: [i32; 2],
+    /// Pixel position of the tile in the tileset image.
+    pub src: [u32; 2],
+    /// Flip flags: bit 0 is horizontal flip, bit 1 is vertical flip.
+    pub f: u8,
+    /// Tile ID in the tileset.
+    pub t: u32,
+    /// Cell index within the layer (`x + y * c_wid`).
+    pub d: Vec<u32>,
+}
+
+impl LdtkExport {
+    /// Export all tilemap layers of the given frame.
+    ///
+    /// `tileset_rel_path` is called once for each tileset used by the frame
+    /// and should return the path of the tileset image relative to the LDtk
+    /// project.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames`.
+    pub fn new<F>(file: &AsepriteFile, frame: u32, mut tileset_rel_path: F) -> Self
+    where
+        F: FnMut(&Tileset) -> String,
+    {
+        assert!(frame < file.num_frames());
+        let mut tileset_ids: Vec<TilesetId> = file
+            .layers()
+            .filter_map(|layer| match layer.layer_type() {
+                LayerType::Tilemap(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+        tileset_ids.sort_unstable();
+        tileset_ids.dedup();
+        let tilesets: Vec<TilesetDefinition> = tileset_ids
+            .iter()
+            .filter_map(|&id| file.tilesets().get(id))
+            .map(|tileset| tileset_definition(tileset, tileset_rel_path(tileset)))
+            .collect();
+
+        let mut layer_instances = Vec::new();
+        for layer in file.layers().rev() {
+            let tileset_id = match layer.layer_type() {
+                LayerType::Tilemap(id) => id,
+                _ => continue,
+            };
+            let (tileset, definition) = match tileset_ids
+                .iter()
+                .position(|&id| id == tileset_id)
+                .and_then(|index| Some((file.tilesets().get(tileset_id)?, &tilesets[index])))
+            {
+                Some(found) => found,
+                None => continue,
+            };
+            let grid_size = definition.tile_grid_size;
+            let (tile_width, tile_height): (u32, u32) = tileset.tile_size().into();
+            let c_wid = (file.width() as u32).div_ceil(tile_width);
+            let c_hei = (file.height() as u32).div_ceil(tile_height);
+
+            let mut grid_tiles = Vec::new();
+            if let Some(tilemap) = file.tilemap(layer.id(), frame) {
+                for y in 0..c_hei.min(tilemap.height()) {
+                    for x in 0..c_wid.min(tilemap.width()) {
+                        let tile = tilemap.tile(x, y);
+                        let id = tile.id();
+                        if tileset.is_empty_tile_id(id) {
+                            continue;
+                        }
+                        grid_tiles.push(GridTile {
+                            px: [(x * tile_width) as i32, (y * tile_height) as i32],
+                            src: [
+                                (id % definition.c_wid) * tile_width,
+                                (id / definition.c_wid) * tile_height,
+                            ],
+                            f: tile.flip_x as u8 | (tile.flip_y as u8) << 1,
+                            t: id,
+                            d: vec![x + y * c_wid],
+                        });
+                    }
+                }
+            }
+            layer_instances.push(LayerInstance {
+                identifier: identifier(layer.name()),
+                layer_type: "Tiles".to_owned(),
+                c_wid,
+                c_hei,
+                grid_size,
+                opacity: layer.opacity() as f32 / 255.0,
+                tileset_def_uid: definition.uid,
+                tileset_rel_path: definition.rel_path.clone(),
+                layer_def_uid: LAYER_UID_BASE + layer.id() as i64,
+                level_id: LEVEL_UID,
+                visible: layer.is_visible(),
+                px_offset_x: 0,
+                px_offset_y: 0,
+                grid_tiles,
+            });
+        }
+
+        LdtkExport {
+            tilesets,
+            level: Level {
+                identifier: "Level_0".to_owned(),
+                uid: LEVEL_UID,
+                px_wid: file.width() as u32,
+                px_hei: file.height() as u32,
+                layer_instances,
+            },
+        }
+    }
+
+    /// Write the export as pretty-printed JSON.
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+fn tileset_definition(tileset: &Tileset, rel_path: String) -> TilesetDefinition {
+    let (tile_width, tile_height): (u32, u32) = tileset.tile_size().into();
+    let c_wid = ((tileset.tile_count() as f64).sqrt().ceil() as u32).max(1);
+    let c_hei = tileset.tile_count().div_ceil(c_wid);
+    let name = if tileset.name().is_empty() {
+        format!("Tileset_{}", tileset.id().value())
+    } else {
+        identifier(tileset.name())
+    };
+    TilesetDefinition {
+        c_wid,
+        c_hei,
+        identifier: name,
+        uid: TILESET_UID_BASE + tileset.id().value() as i64,
+        rel_path,
+        px_wid: c_wid * tile_width,
+        px_hei: c_hei * tile_height,
+        // LDtk only supports square tiles, so non-square tiles will not
+        // line up.
+        tile_grid_size: tile_width.max(tile_height),
+        spacing: 0,
+        padding: 0,
+    }
+}
+
+// LDtk identifiers may only contain letters, digits and underscores and must
+// not start with a digit.
+fn identifier(name: &str) -> String {
+    let mut result: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !result.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        result.insert(0, '_');
+    }
+    result
+}
@@ -102,14 +102,14 @@ You access each tile separately, or export them all as one image which is one
 tile wide.
 
 ```
-# use asefile::AsepriteFile;
+# use asefile::{AsepriteFile, TilesetId};
 # use std::path::Path;
 # use image::RgbaImage;
 # let path = Path::new("./tests/data/tileset.aseprite");
 # let ase = AsepriteFile::read_file(&path).unwrap();
 
 let num_tilesets = ase.tilesets().len();
-let tileset = ase.tilesets().get(0).unwrap();
+let tileset = ase.tilesets().get(TilesetId::new(0)).unwrap();
 
 let all_tiles: RgbaImage = tileset.image();
 let single_tile: RgbaImage = tileset.tile_image(1);
@@ -147,7 +147,7 @@ let tilemap_image = tilemap.image();
 
 let num_tiles_x = tilemap.width();
 let num_tiles_y = tilemap.height();
-let (tile_width, tile_height) = tilemap.tile_size();
+let tile_size = tilemap.tile_size(); // tile_size.w, tile_size.h
 // Get a specific tile. Always succeeds. If the tile is out of bounds returns
 // the empty tile (id 0).
 let tile = tilemap.tile(0, 1);
@@ -156,6 +156,45 @@ println!("tile at (0, 1) references tile from tileset: {}", tile.id());
 let image = tilemap.tileset().tile_image(tile.id());
 ```
 
+## Animation
+
+[animation::AnimationPlayer] keeps track of the current frame of a tag (or
+of the whole file) as time passes.
+
+```
+# use asefile::AsepriteFile;
+# use std::path::Path;
+# let path = Path::new("./tests/data/layers_and_tags.aseprite");
+# let ase = AsepriteFile::read_file(&path).unwrap();
+use asefile::animation::AnimationPlayer;
+use std::time::Duration;
+
+let mut player = AnimationPlayer::new(&ase, ase.tag_by_name("T1"));
+player.advance(Duration::from_millis(16));
+let image = ase.frame(player.current_frame()).image();
+```
+
+## Sprite sheets
+
+The [sheet] module combines several frames into one image, similar to
+Aseprite's "Export Sprite Sheet" dialog.
+
+```
+# use asefile::AsepriteFile;
+# use std::path::Path;
+# let path = Path::new("./tests/data/layers_and_tags.aseprite");
+# let ase = AsepriteFile::read_file(&path).unwrap();
+use asefile::sheet::{SheetLayout, SheetOptions, SpriteSheet};
+
+let options = SheetOptions {
+    layout: SheetLayout::HorizontalStrip,
+    ..Default::default()
+};
+let sheet = SpriteSheet::new(&ase, &options);
+let frame_1 = sheet.frame(1).unwrap();
+println!("frame 1 is at ({}, {})", frame_1.x, frame_1.y);
+```
+
 ## User data
 
 Aseprite gives you the option to annotate certain entities with custom data.
@@ -164,41 +203,84 @@ Usually, that's a color and a text field. Each of those entities has a
 
 */
 
+pub mod animation;
 pub(crate) mod blend;
+pub mod bundle;
+pub mod cache;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub(crate) mod cel;
 pub(crate) mod color_profile;
+mod content;
+mod diff;
 pub(crate) mod error;
+mod extensions;
 pub(crate) mod external_file;
 pub(crate) mod file;
+pub mod font;
+mod geometry;
+#[cfg(feature = "ggez")]
+pub mod ggez;
+#[cfg(feature = "json")]
+pub mod json;
 pub(crate) mod layer;
+#[cfg(feature = "ldtk")]
+pub mod ldtk;
+mod lint;
+#[cfg(feature = "macroquad")]
+pub mod macroquad;
+#[cfg(feature = "serde")]
+pub mod manifest;
+mod owned;
 pub(crate) mod palette;
 pub(crate) mod parse;
 mod pixel;
+mod push;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod raw;
 mod reader;
+pub mod sheet;
 pub(crate) mod slice;
 pub(crate) mod tags;
 #[cfg(test)]
 mod tests;
+pub mod texture;
 mod tile;
+#[cfg(feature = "tiled")]
+pub mod tiled;
 mod tilemap;
 mod tileset;
 pub(crate) mod user_data;
 #[cfg(feature = "utils")]
 pub mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 /// A specialized `Result` type for Aseprite parsing functions.
 pub type Result<T> = std::result::Result<T, AsepriteParseError>;
 
-pub use cel::Cel;
+pub use cel::{Cel, CelMut};
 // pub use color_profile::ColorProfile;
+pub use diff::{Changes, FileDiff};
 pub use error::AsepriteParseError;
+pub use extensions::Extensions;
 pub use external_file::{ExternalFile, ExternalFileId, ExternalFilesById};
-pub use file::{AsepriteFile, Frame, LayersIter, PixelFormat};
-pub use layer::{BlendMode, Layer, LayerFlags, LayerType};
-pub use palette::{ColorPalette, ColorPaletteEntry};
+pub use file::{AsepriteFile, Frame, LayersIter, PixelFormat, RenderOptions};
+pub use geometry::{Point, Rect, Size};
+pub use layer::{
+    BlendMode, Layer, LayerFlags, LayerMut, LayerNode, LayerTree, LayerType, LayerUuid,
+};
+pub use lint::LintWarning;
+pub use owned::{OwnedCel, OwnedFrame, OwnedLayer};
+pub use palette::{ColorDistance, ColorPalette, ColorPaletteEntry, ColorPaletteIter};
+pub use parse::{ParseOptions, ParseProgress};
+pub use push::{ParseEvent, PushParser};
 pub use slice::{Slice, Slice9, SliceKey};
 pub use tags::{AnimationDirection, Tag};
 pub use tile::Tile;
-pub use tilemap::Tilemap;
-pub use tileset::{ExternalTilesetReference, TileSize, Tileset, TilesetImageError, TilesetsById};
-pub use user_data::UserData;
+pub use tilemap::{TileChange, Tilemap};
+pub use tileset::{
+    ExternalTilesetReference, TileSize, Tileset, TilesetId, TilesetImageError, TilesetsById,
+};
+pub use user_data::{Properties, PropertyValue, UserData};
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    cel::{CelContent, CelId},
+    AsepriteFile, LayerType, Rect, TilesetId,
+};
+
+/// A suspicious but valid piece of content found by [AsepriteFile::lint].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintWarning {
+    /// Several layers have the same name, so looking them up by name is
+    /// ambiguous.
+    DuplicateLayerName {
+        /// The shared name.
+        name: String,
+        /// IDs of all layers with that name.
+        layers: Vec<u32>,
+    },
+    /// Several tags have the same name, so looking them up by name is
+    /// ambiguous.
+    DuplicateTagName {
+        /// The shared name.
+        name: String,
+        /// IDs of all tags with that name.
+        tags: Vec<u32>,
+    },
+    /// A tag ends before it starts or refers to frames that do not exist.
+    InvalidTagRange {
+        /// ID of the tag.
+        tag: u32,
+        /// First frame of the tag.
+        from_frame: u32,
+        /// Last frame of the tag.
+        to_frame: u32,
+    },
+    /// No tilemap layer uses the tileset.
+    UnusedTileset {
+        /// ID of the tileset.
+        tileset: TilesetId,
+    },
+    /// A cel has visible pixels, but all of them are outside of the canvas.
+    CelOutsideCanvas {
+        /// Layer ID of the cel.
+        layer: u32,
+        /// Frame index of the cel.
+        frame: u32,
+    },
+    /// An image or tilemap layer has no visible pixels in any frame.
+    EmptyLayer {
+        /// ID of the layer.
+        layer: u32,
+    },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintWarning::DuplicateLayerName { name, layers } => {
+                write!(f, "Layers {:?} share the name {:?}", layers, name)
+            }
+            LintWarning::DuplicateTagName { name, tags } => {
+                write!(f, "Tags {:?} share the name {:?}", tags, name)
+            }
+            LintWarning::InvalidTagRange {
+                tag,
+                from_frame,
+                to_frame,
+            } => write!(
+                f,
+                "Tag {} has an invalid frame range: {}..={}",
+                tag, from_frame, to_frame
+            ),
+            LintWarning::UnusedTileset { tileset } => {
+                write!(f, "{} is not used by any tilemap layer", tileset)
+            }
+            LintWarning::CelOutsideCanvas { layer, frame } => write!(
+                f,
+                "Cel of layer {} in frame {} is outside of the canvas",
+                layer, frame
+            ),
+            LintWarning::EmptyLayer { layer } => {
+                write!(f, "Layer {} has no visible pixels in any frame", layer)
+            }
+        }
+    }
+}
+
+pub(crate) fn lint(file: &AsepriteFile) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    let layer_names = file
+        .layers()
+        .map(|layer| (layer.id(), layer.name().to_owned()));
+    for (name, layers) in duplicates(layer_names) {
+        warnings.push(LintWarning::DuplicateLayerName { name, layers });
+    }
+    let tag_names = file
+        .tags()
+        .enumerate()
+        .map(|(id, tag)| (id as u32, tag.name().to_owned()));
+    for (name, tags) in duplicates(tag_names) {
+        warnings.push(LintWarning::DuplicateTagName { name, tags });
+    }
+    for (id, tag) in file.tags().enumerate() {
+        if tag.from_frame() > tag.to_frame() || tag.to_frame() >= file.num_frames() {
+            warnings.push(LintWarning::InvalidTagRange {
+                tag: id as u32,
+                from_frame: tag.from_frame(),
+                to_frame: tag.to_frame(),
+            });
+        }
+    }
+
+    for tileset in file.tilesets().iter() {
+        let used = file
+            .layers()
+            .any(|layer| layer.layer_type() == LayerType::Tilemap(tileset.id()));
+        if !used {
+            warnings.push(LintWarning::UnusedTileset {
+                tileset: tileset.id(),
+            });
+        }
+    }
+
+    let canvas = Rect::new(0, 0, file.width() as u32, file.height() as u32);
+    let mut empty_layers = Vec::new();
+    for layer in file.layers() {
+        if layer.layer_type() == LayerType::Group {
+            continue;
+        }
+        let mut empty = true;
+        for frame in 0..file.num_frames() {
+            let cel_id = CelId {
+                frame: frame as u16,
+                layer: layer.id() as u16,
+            };
+            let Some(cel) = file.framedata.cel(cel_id) else {
+                continue;
+            };
+            let bounds = file.cel_content_bounds(cel_id, cel);
+            let visible = bounds.and_then(|bounds| bounds.intersection(&canvas));
+            // Linked cels are reported at the cel they link to.
+            let linked = matches!(cel.content, CelContent::Linked(_));
+            if bounds.is_some() && visible.is_none() && !linked {
+                warnings.push(LintWarning::CelOutsideCanvas {
+                    layer: layer.id(),
+                    frame,
+                });
+            }
+            empty &= visible.is_none();
+        }
+        if empty {
+            empty_layers.push(LintWarning::EmptyLayer { layer: layer.id() });
+        }
+    }
+    warnings.extend(empty_layers);
+    warnings
+}
+
+// Names that occur more than once, together with the IDs that use them, in
+// order of first occurrence.
+fn duplicates<I>(names: I) -> Vec<(String, Vec<u32>)>
+where
+    I: Iterator<Item = (u32, String)>,
+{
+    let mut order = Vec::new();
+    let mut ids: HashMap<String, Vec<u32>> = HashMap::new();
+    for (id, name) in names {
+        let entry = ids.entry(name.clone()).or_default();
+        if entry.is_empty() {
+            order.push(name);
+        }
+        entry.push(id);
+    }
+    order
+        .into_iter()
+        .filter_map(|name| {
+            let ids = ids.remove(&name)?;
+            (ids.len() > 1).then_some((name, ids))
+        })
+        .collect()
+}
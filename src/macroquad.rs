@@ -0,0 +1,110 @@
+//! Create [macroquad](https://macroquad.rs/) textures and animations.
+//! (Requires feature `macroquad`.)
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["macroquad"] }
+//! ```
+//!
+//! All textures use nearest neighbor filtering, so pixel art stays crisp when
+//! scaled. Textures can only be created while the macroquad window is
+//! running.
+//!
+//! ```no_run
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # fn example() {
+//! # let ase = AsepriteFile::read_file(Path::new("player.aseprite")).unwrap();
+//! use asefile::macroquad::AnimatedSprite;
+//! use macroquad::color::WHITE;
+//! use std::time::Duration;
+//!
+//! let mut sprite = AnimatedSprite::from_tag_name(&ase, "walk").unwrap();
+//! // Once per frame:
+//! sprite.update(Duration::from_secs_f32(macroquad::time::get_frame_time()));
+//! sprite.draw(100.0, 100.0, WHITE);
+//! # }
+//! ```
+
+use ::macroquad::{
+    color::Color,
+    texture::{draw_texture_ex, DrawTextureParams, FilterMode, Texture2D},
+};
+use image::RgbaImage;
+
+use crate::{animation::AnimatedFrames, sheet::SpriteSheet, AsepriteFile, Tag, Tileset};
+
+/// Upload an RGBA image as a texture.
+///
+/// # Panics
+///
+/// Panics if the image is wider or taller than 65535 pixels.
+pub fn texture_from_rgba(image: &RgbaImage) -> Texture2D {
+    let width = u16::try_from(image.width()).expect("Image too wide for a texture");
+    let height = u16::try_from(image.height()).expect("Image too tall for a texture");
+    let texture = Texture2D::from_rgba8(width, height, image.as_raw());
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+/// Compose the given frame and upload it as a texture.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn frame_texture(file: &AsepriteFile, frame: u32) -> Texture2D {
+    texture_from_rgba(&file.frame(frame).image())
+}
+
+/// Upload the tiles of a tileset as one long vertical texture. See
+/// [Tileset::image].
+pub fn tileset_texture(tileset: &Tileset) -> Texture2D {
+    texture_from_rgba(&tileset.image())
+}
+
+/// Upload the image of a sprite sheet. Use the sheet's frame rects as the
+/// `source` of `DrawTextureParams` to draw individual frames.
+pub fn sheet_texture(sheet: &SpriteSheet) -> Texture2D {
+    texture_from_rgba(sheet.image())
+}
+
+/// An animation with one texture per frame.
+///
+/// Combines an [AnimationPlayer](crate::animation::AnimationPlayer) with the
+/// textures of the frames of a tag. Call [update](AnimatedFrames::update)
+/// once per game frame and [draw](AnimatedFrames::draw) to render the current
+/// frame.
+pub type AnimatedSprite = AnimatedFrames<Texture2D>;
+
+impl AnimatedFrames<Texture2D> {
+    /// Play the frames of the given tag. If `tag` is `None`, plays all frames
+    /// of the file in a loop. See
+    /// [AnimationPlayer::new](crate::animation::AnimationPlayer::new).
+    pub fn new(file: &AsepriteFile, tag: Option<&Tag>) -> Self {
+        Self::from_fn(file, tag, |frame| frame_texture(file, frame))
+    }
+
+    /// Play the frames of the first tag with the given name, or return `None`
+    /// if there is no such tag.
+    pub fn from_tag_name(file: &AsepriteFile, tag_name: &str) -> Option<Self> {
+        let tag = file.tag_by_name(tag_name)?;
+        Some(Self::new(file, Some(tag)))
+    }
+
+    /// The texture of the frame that is displayed.
+    pub fn texture(&self) -> &Texture2D {
+        self.current()
+    }
+
+    /// Draw the current frame with its top left corner at the given
+    /// position.
+    pub fn draw(&self, x: f32, y: f32, color: Color) {
+        self.draw_ex(x, y, color, DrawTextureParams::default());
+    }
+
+    /// Like [draw](Self::draw), but with additional parameters for scaling,
+    /// rotation and flipping.
+    pub fn draw_ex(&self, x: f32, y: f32, color: Color, params: DrawTextureParams) {
+        draw_texture_ex(self.texture(), x, y, color, params);
+    }
+}
@@ -0,0 +1,147 @@
+//! Animation metadata in a serde-friendly format. (Requires feature `serde`.)
+//!
+//! A [Manifest] describes the frames, tags and slices of a file without any
+//! pixel data. It can be written out at build time and loaded by the game
+//! instead of parsing the Aseprite file at runtime. Enable feature `ron` or
+//! `toml` for the corresponding output helpers, or use any other serde
+//! format.
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["ron"] }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AnimationDirection, AsepriteFile, Slice, Tag};
+
+/// Frames, tags and slices of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Canvas width in pixels.
+    pub width: u32,
+    /// Canvas height in pixels.
+    pub height: u32,
+    /// One entry per frame, in frame order.
+    pub frames: Vec<FrameManifest>,
+    /// All tags in the order they appear in the file.
+    pub tags: Vec<TagManifest>,
+    /// All slices in the order they appear in the file.
+    pub slices: Vec<SliceManifest>,
+}
+
+/// A single frame in a [Manifest].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameManifest {
+    /// Frame index.
+    pub index: u32,
+    /// Frame duration in milliseconds.
+    pub duration: u32,
+}
+
+/// A single tag in a [Manifest].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagManifest {
+    /// Tag name.
+    pub name: String,
+    /// First frame included in the tag.
+    pub from_frame: u32,
+    /// Last frame included in the tag.
+    pub to_frame: u32,
+    /// Playback direction.
+    pub direction: AnimationDirection,
+    /// Repeat count. `None` repeats forever.
+    pub repeat: Option<u32>,
+}
+
+/// A single slice in a [Manifest].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceManifest {
+    /// Slice name.
+    pub name: String,
+    /// Shape of the slice over time.
+    pub keys: Vec<SliceKeyManifest>,
+}
+
+/// The shape of a [SliceManifest] starting at a given frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceKeyManifest {
+    /// First frame this key applies to.
+    pub from_frame: u32,
+    /// Origin of the slice.
+    pub origin: (i32, i32),
+    /// Size of the slice.
+    pub size: (u32, u32),
+    /// Pivot, relative to the origin.
+    pub pivot: Option<(i32, i32)>,
+    /// 9-slice center as `(x, y, width, height)`, relative to the origin.
+    pub center: Option<(i32, i32, u32, u32)>,
+}
+
+impl Manifest {
+    /// Collect the metadata of the given file.
+    pub fn new(file: &AsepriteFile) -> Self {
+        let frames = (0..file.num_frames())
+            .map(|index| FrameManifest {
+                index,
+                duration: file.frame(index).duration_ms(),
+            })
+            .collect();
+        let tags = file.tags.iter().map(TagManifest::from).collect();
+        let slices = file.slices().iter().map(SliceManifest::from).collect();
+        Manifest {
+            width: file.width() as u32,
+            height: file.height() as u32,
+            frames,
+            tags,
+            slices,
+        }
+    }
+
+    /// Write the manifest as pretty-printed RON. (Requires feature `ron`.)
+    #[cfg(feature = "ron")]
+    pub fn to_ron_string(&self) -> std::result::Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Write the manifest as TOML. (Requires feature `toml`.)
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> std::result::Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+}
+
+impl From<&Tag> for TagManifest {
+    fn from(tag: &Tag) -> Self {
+        TagManifest {
+            name: tag.name().to_owned(),
+            from_frame: tag.from_frame(),
+            to_frame: tag.to_frame(),
+            direction: tag.animation_direction(),
+            repeat: tag.repeat().map(|r| r.get()),
+        }
+    }
+}
+
+impl From<&Slice> for SliceManifest {
+    fn from(slice: &Slice) -> Self {
+        let keys = slice
+            .keys
+            .iter()
+            .map(|key| SliceKeyManifest {
+                from_frame: key.from_frame,
+                origin: key.origin.into(),
+                size: key.size.into(),
+                pivot: key.pivot.map(Into::into),
+                center: key
+                    .slice9
+                    .as_ref()
+                    .map(|s| (s.center_x, s.center_y, s.center_width, s.center_height)),
+            })
+            .collect();
+        SliceManifest {
+            name: slice.name.clone(),
+            keys,
+        }
+    }
+}
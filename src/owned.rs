@@ -0,0 +1,294 @@
+use std::{sync::Arc, time::Duration};
+
+use image::RgbaImage;
+
+use crate::{
+    AsepriteFile, BlendMode, Cel, Frame, Layer, LayerFlags, LayerType, LayerUuid, Point, Rect,
+    RenderOptions, UserData,
+};
+
+/// A frame that keeps its file alive through an [Arc].
+///
+/// Unlike [Frame], this does not borrow the [AsepriteFile], so it is `'static`
+/// and can be stored in long-lived structs or sent to other threads. Cloning
+/// only clones the [Arc].
+///
+/// ```
+/// # use asefile::{AsepriteFile, OwnedFrame};
+/// # use std::path::Path;
+/// use std::sync::Arc;
+///
+/// let ase = Arc::new(AsepriteFile::read_file(Path::new("./tests/data/basic-16x16.aseprite")).unwrap());
+/// let frame = OwnedFrame::new(ase, 0);
+/// let image = std::thread::spawn(move || frame.image()).join().unwrap();
+/// assert_eq!(image.dimensions(), (16, 16));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OwnedFrame {
+    file: Arc<AsepriteFile>,
+    index: u32,
+}
+
+impl OwnedFrame {
+    /// A handle to the frame with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `num_frames`.
+    pub fn new(file: Arc<AsepriteFile>, index: u32) -> Self {
+        assert!(index < file.num_frames());
+        OwnedFrame { file, index }
+    }
+
+    /// All frames of the file, in order.
+    pub fn all(file: &Arc<AsepriteFile>) -> impl Iterator<Item = OwnedFrame> + '_ {
+        (0..file.num_frames()).map(move |index| OwnedFrame::new(file.clone(), index))
+    }
+
+    /// The file this frame belongs to.
+    pub fn file(&self) -> &Arc<AsepriteFile> {
+        &self.file
+    }
+
+    /// A borrowed handle to the same frame.
+    pub fn as_frame(&self) -> Frame<'_> {
+        self.file.frame(self.index)
+    }
+
+    /// See [Frame::image].
+    pub fn image(&self) -> RgbaImage {
+        self.as_frame().image()
+    }
+
+    /// See [Frame::image_with_options].
+    pub fn image_with_options(&self, options: &RenderOptions) -> RgbaImage {
+        self.as_frame().image_with_options(options)
+    }
+
+    /// Frame ID, i.e., the frame number.
+    pub fn id(&self) -> u32 {
+        self.index
+    }
+
+    /// Get cel corresponding to the given layer in this frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `layer_id` is not less than `num_layers`.
+    pub fn layer(&self, layer_id: u32) -> OwnedCel {
+        OwnedCel::new(self.file.clone(), self.index, layer_id)
+    }
+
+    /// See [Frame::content_bounds].
+    pub fn content_bounds(&self) -> Option<Rect> {
+        self.as_frame().content_bounds()
+    }
+
+    /// How long this frame is displayed.
+    pub fn duration(&self) -> Duration {
+        self.as_frame().duration()
+    }
+
+    /// Frame duration in milliseconds.
+    pub fn duration_ms(&self) -> u32 {
+        self.as_frame().duration_ms()
+    }
+
+    /// See [Frame::pivot].
+    pub fn pivot(&self, slice_name: &str) -> Option<(f32, f32)> {
+        self.as_frame().pivot(slice_name)
+    }
+}
+
+/// A cel that keeps its file alive through an [Arc]. See [OwnedFrame].
+#[derive(Debug, Clone)]
+pub struct OwnedCel {
+    file: Arc<AsepriteFile>,
+    frame: u32,
+    layer: u32,
+}
+
+impl OwnedCel {
+    /// A handle to the cel at the given frame and layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames` or if `layer` is not
+    /// less than `num_layers`.
+    pub fn new(file: Arc<AsepriteFile>, frame: u32, layer: u32) -> Self {
+        assert!(frame < file.num_frames() && layer < file.num_layers());
+        OwnedCel { file, frame, layer }
+    }
+
+    /// The file this cel belongs to.
+    pub fn file(&self) -> &Arc<AsepriteFile> {
+        &self.file
+    }
+
+    /// A borrowed handle to the same cel.
+    pub fn as_cel(&self) -> Cel<'_> {
+        self.file.cel(self.frame, self.layer)
+    }
+
+    /// See [Cel::image].
+    pub fn image(&self) -> RgbaImage {
+        self.as_cel().image()
+    }
+
+    /// Returns `true` if the cel contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.as_cel().is_empty()
+    }
+
+    /// The frame coordinate of this cel.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// The layer coordinate of this cel.
+    pub fn layer(&self) -> u32 {
+        self.layer
+    }
+
+    /// Returns the cel's user data, if any is present.
+    pub fn user_data(&self) -> Option<&UserData> {
+        self.file
+            .framedata
+            .cel(self.as_cel().cel_id)
+            .and_then(|c| c.user_data.as_ref())
+    }
+
+    /// See [Cel::top_left].
+    pub fn top_left(&self) -> Point {
+        self.as_cel().top_left()
+    }
+
+    /// See [Cel::bounds].
+    pub fn bounds(&self) -> Option<Rect> {
+        self.as_cel().bounds()
+    }
+
+    /// Does this cel include a tilemap.
+    pub fn is_tilemap(&self) -> bool {
+        self.as_cel().is_tilemap()
+    }
+}
+
+/// A layer that keeps its file alive through an [Arc]. See [OwnedFrame].
+#[derive(Debug, Clone)]
+pub struct OwnedLayer {
+    file: Arc<AsepriteFile>,
+    layer_id: u32,
+}
+
+impl OwnedLayer {
+    /// A handle to the layer with the given ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not less than `num_layers`.
+    pub fn new(file: Arc<AsepriteFile>, id: u32) -> Self {
+        assert!(id < file.num_layers());
+        OwnedLayer { file, layer_id: id }
+    }
+
+    /// All layers of the file, ordered by ID.
+    pub fn all(file: &Arc<AsepriteFile>) -> impl Iterator<Item = OwnedLayer> + '_ {
+        (0..file.num_layers()).map(move |id| OwnedLayer::new(file.clone(), id))
+    }
+
+    /// The file this layer belongs to.
+    pub fn file(&self) -> &Arc<AsepriteFile> {
+        &self.file
+    }
+
+    /// A borrowed handle to the same layer.
+    pub fn as_layer(&self) -> Layer<'_> {
+        self.file.layer(self.layer_id)
+    }
+
+    /// This layer's ID.
+    pub fn id(&self) -> u32 {
+        self.layer_id
+    }
+
+    /// Layer's flags
+    pub fn flags(&self) -> LayerFlags {
+        self.as_layer().flags()
+    }
+
+    /// Name of the layer
+    pub fn name(&self) -> &str {
+        &self.file.layers[self.layer_id].name
+    }
+
+    /// See [Layer::blend_mode].
+    pub fn blend_mode(&self) -> BlendMode {
+        self.as_layer().blend_mode()
+    }
+
+    /// Layer opacity
+    pub fn opacity(&self) -> u8 {
+        self.as_layer().opacity()
+    }
+
+    /// Describes whether this is a regular, group, or tilemap layer.
+    pub fn layer_type(&self) -> LayerType {
+        self.as_layer().layer_type()
+    }
+
+    /// Is this a tilemap layer?
+    pub fn is_tilemap(&self) -> bool {
+        self.as_layer().is_tilemap()
+    }
+
+    /// See [Layer::parent].
+    pub fn parent(&self) -> Option<OwnedLayer> {
+        self.as_layer()
+            .parent()
+            .map(|l| OwnedLayer::new(self.file.clone(), l.id()))
+    }
+
+    /// See [Layer::children].
+    pub fn children(&self) -> impl Iterator<Item = OwnedLayer> + '_ {
+        self.as_layer()
+            .children()
+            .map(|l| OwnedLayer::new(self.file.clone(), l.id()))
+    }
+
+    /// See [Layer::descendants].
+    pub fn descendants(&self) -> impl Iterator<Item = OwnedLayer> + '_ {
+        self.as_layer()
+            .descendants()
+            .map(|l| OwnedLayer::new(self.file.clone(), l.id()))
+    }
+
+    /// See [Layer::path].
+    pub fn path(&self) -> String {
+        self.as_layer().path()
+    }
+
+    /// See [Layer::is_visible].
+    pub fn is_visible(&self) -> bool {
+        self.as_layer().is_visible()
+    }
+
+    /// Get the cel for this frame in the layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame_id` is not less than `num_frames`.
+    pub fn frame(&self, frame_id: u32) -> OwnedCel {
+        OwnedCel::new(self.file.clone(), frame_id, self.layer_id)
+    }
+
+    /// Returns a reference to the layer's [UserData], if any exists.
+    pub fn user_data(&self) -> Option<&UserData> {
+        self.file.layers[self.layer_id].user_data.as_ref()
+    }
+
+    /// See [Layer::uuid].
+    pub fn uuid(&self) -> Option<LayerUuid> {
+        self.as_layer().uuid()
+    }
+}
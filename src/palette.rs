@@ -1,15 +1,21 @@
 use crate::{reader::AseReader, AsepriteParseError, Result};
+use image::Rgba;
 use nohash::IntMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read, Write},
+    iter::FusedIterator,
+};
 
 /// The color palette embedded in the file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ColorPalette {
     //entries: Vec<ColorPaletteEntry>,
     pub(crate) entries: IntMap<u32, ColorPaletteEntry>,
 }
 
 /// A single entry in a [ColorPalette].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ColorPaletteEntry {
     id: u32,
     rgba8: [u8; 4],
@@ -17,11 +23,213 @@ pub struct ColorPaletteEntry {
 }
 
 impl ColorPalette {
+    /// Create a palette from a list of colors. The first color gets index 0.
+    pub fn from_colors<I>(colors: I) -> Self
+    where
+        I: IntoIterator<Item = [u8; 4]>,
+    {
+        let entries = colors
+            .into_iter()
+            .enumerate()
+            .map(|(index, rgba8)| {
+                let id = index as u32;
+                (
+                    id,
+                    ColorPaletteEntry {
+                        id,
+                        rgba8,
+                        name: None,
+                    },
+                )
+            })
+            .collect();
+        ColorPalette { entries }
+    }
+
+    /// Reduce `colors` to a palette of at most `max_colors` colors using the
+    /// median cut algorithm. Colors that occur more often have a larger
+    /// influence on the result.
+    ///
+    /// If there are no more than `max_colors` distinct colors, the palette
+    /// contains exactly these colors. All four channels are taken into
+    /// account.
+    ///
+    /// ```
+    /// # use asefile::ColorPalette;
+    /// # use image::Rgba;
+    /// let colors = [Rgba([255, 0, 0, 255]), Rgba([250, 0, 0, 255]), Rgba([0, 0, 255, 255])];
+    /// let palette = ColorPalette::quantize(colors, 2);
+    /// assert_eq!(palette.num_colors(), 2);
+    /// ```
+    pub fn quantize<I>(colors: I, max_colors: u32) -> Self
+    where
+        I: IntoIterator<Item = Rgba<u8>>,
+    {
+        let mut counts: HashMap<[u8; 4], u64> = HashMap::new();
+        for color in colors {
+            *counts.entry(color.0).or_default() += 1;
+        }
+        if max_colors == 0 {
+            return ColorPalette::from_colors([]);
+        }
+        let mut histogram: Vec<([u8; 4], u64)> = counts.into_iter().collect();
+        // Sort so the result does not depend on hash map order.
+        histogram.sort_unstable();
+        ColorPalette::from_colors(median_cut(histogram, max_colors as usize))
+    }
+
+    /// Read a palette from a GIMP (`.gpl`), JASC (`.pal`) or hex (`.hex`)
+    /// file. The format is detected from the content.
+    ///
+    /// Hex files contain one color per line as `RRGGBB` or `RRGGBBAA`, with
+    /// an optional leading `#`. This is the format used by
+    /// [Lospec](https://lospec.com/palette-list).
+    ///
+    /// ```
+    /// # use asefile::ColorPalette;
+    /// let palette = ColorPalette::read("#ff0000\n00ff0080\n".as_bytes()).unwrap();
+    /// assert_eq!(palette.num_colors(), 2);
+    /// assert_eq!(palette.color(1).unwrap().raw_rgba8(), [0, 255, 0, 128]);
+    /// ```
+    pub fn read<R: Read>(mut input: R) -> Result<Self> {
+        let mut text = String::new();
+        input.read_to_string(&mut text)?;
+        let mut lines = text.lines().map(str::trim);
+        match lines.next() {
+            Some("GIMP Palette") => parse_gpl(lines),
+            Some("JASC-PAL") => parse_jasc_pal(lines),
+            _ => parse_hex(text.lines().map(str::trim)),
+        }
+    }
+
+    /// Change the color at `index`. Adds a new entry if there is none at that
+    /// index yet.
+    ///
+    /// Use [AsepriteFile::set_palette_color](crate::AsepriteFile::set_palette_color)
+    /// to change the palette of a file.
+    pub fn set_color(&mut self, index: u32, rgba8: [u8; 4]) {
+        self.entries
+            .entry(index)
+            .and_modify(|entry| entry.rgba8 = rgba8)
+            .or_insert(ColorPaletteEntry {
+                id: index,
+                rgba8,
+                name: None,
+            });
+    }
+
+    /// Add a color after the entry with the largest index. Returns the index
+    /// of the new entry.
+    pub fn add_color(&mut self, rgba8: [u8; 4]) -> u32 {
+        let index = self.entries.keys().max().map_or(0, |max| max + 1);
+        self.set_color(index, rgba8);
+        index
+    }
+
+    // The palette without the entry at `index`. Entries above it move down.
+    pub(crate) fn without(&self, index: u32) -> ColorPalette {
+        let entries = self
+            .entries
+            .values()
+            .filter(|entry| entry.id != index)
+            .map(|entry| {
+                let id = if entry.id > index {
+                    entry.id - 1
+                } else {
+                    entry.id
+                };
+                (
+                    id,
+                    ColorPaletteEntry {
+                        id,
+                        ..entry.clone()
+                    },
+                )
+            })
+            .collect();
+        ColorPalette { entries }
+    }
+
+    // The palette with the entry at `order[i]` moved to index `i`. `order`
+    // must contain each index exactly once.
+    pub(crate) fn reordered(&self, order: &[u32]) -> Result<ColorPalette> {
+        let mut used = HashSet::new();
+        let is_permutation = order.len() == self.entries.len()
+            && order
+                .iter()
+                .all(|index| self.entries.contains_key(index) && used.insert(*index));
+        if !is_permutation {
+            return Err(AsepriteParseError::InvalidEdit(format!(
+                "Palette order {:?} is not a permutation of the palette indices",
+                order
+            )));
+        }
+        let entries = order
+            .iter()
+            .enumerate()
+            .map(|(new_index, old_index)| {
+                let id = new_index as u32;
+                let entry = &self.entries[old_index];
+                (
+                    id,
+                    ColorPaletteEntry {
+                        id,
+                        ..entry.clone()
+                    },
+                )
+            })
+            .collect();
+        Ok(ColorPalette { entries })
+    }
+
     /// Total number of colors in the palette.
     pub fn num_colors(&self) -> u32 {
         self.entries.len() as u32
     }
 
+    /// All entries, ordered by index.
+    pub fn iter(&self) -> ColorPaletteIter<'_> {
+        let mut entries: Vec<&ColorPaletteEntry> = self.entries.values().collect();
+        entries.sort_unstable_by_key(|entry| entry.id);
+        ColorPaletteIter {
+            inner: entries.into_iter(),
+        }
+    }
+
+    /// The colors of all entries, ordered by index.
+    pub fn colors(&self) -> impl Iterator<Item = Rgba<u8>> + '_ {
+        self.iter().map(|entry| Rgba(entry.rgba8))
+    }
+
+    /// The index of the palette color closest to `color` according to
+    /// `metric`. If several colors are equally close, returns the lowest
+    /// index. Returns `None` if the palette is empty.
+    ///
+    /// Only the red, green and blue channels are compared.
+    pub fn nearest(&self, color: Rgba<u8>, metric: ColorDistance) -> Option<u32> {
+        self.nearest_by(color, |a, b| metric.distance(a, b))
+    }
+
+    /// Like [nearest](Self::nearest), but with a custom distance function.
+    pub fn nearest_by<F>(&self, color: Rgba<u8>, distance: F) -> Option<u32>
+    where
+        F: FnMut(Rgba<u8>, Rgba<u8>) -> f64,
+    {
+        nearest_among(self.iter(), color, distance)
+    }
+
+    // Like `nearest`, but never returns `excluded`, e.g., the transparent
+    // color index for opaque pixels.
+    pub(crate) fn nearest_except(
+        &self,
+        color: Rgba<u8>,
+        metric: ColorDistance,
+        excluded: u32,
+    ) -> Option<u32> {
+        let entries = self.iter().filter(|entry| entry.id != excluded);
+        nearest_among(entries, color, |a, b| metric.distance(a, b))
+    }
+
     /// Look up entry at given index.
     ///
     /// The Aseprite file format spec does not guarantee the color indices to
@@ -31,6 +239,81 @@ impl ColorPalette {
         self.entries.get(&index)
     }
 
+    /// Write the palette in GIMP's `.gpl` format.
+    ///
+    /// Colors are written in index order. Gaps in the indices are filled with
+    /// black so that indices stay the same. Alpha is not supported by the
+    /// format and is dropped.
+    pub fn write_gpl<W: Write>(&self, name: &str, mut output: W) -> io::Result<()> {
+        writeln!(output, "GIMP Palette")?;
+        writeln!(output, "Name: {}", name)?;
+        writeln!(output, "Columns: 16")?;
+        writeln!(output, "#")?;
+        for (index, entry) in self.dense_entries().into_iter().enumerate() {
+            let [r, g, b, _] = entry.map_or([0, 0, 0, 255], |e| e.rgba8);
+            let name = entry
+                .and_then(|e| e.name())
+                .filter(|name| !name.is_empty())
+                .map_or_else(|| format!("Index {}", index), str::to_owned);
+            writeln!(output, "{:3} {:3} {:3}\t{}", r, g, b, name)?;
+        }
+        Ok(())
+    }
+
+    /// Write the palette in the JASC `.pal` format used by Paint Shop Pro and
+    /// many other tools.
+    ///
+    /// Gaps in the indices are filled with black. Alpha is dropped.
+    pub fn write_jasc_pal<W: Write>(&self, mut output: W) -> io::Result<()> {
+        let entries = self.dense_entries();
+        write!(output, "JASC-PAL\r\n0100\r\n{}\r\n", entries.len())?;
+        for entry in entries {
+            let [r, g, b, _] = entry.map_or([0, 0, 0, 255], |e| e.rgba8);
+            write!(output, "{} {} {}\r\n", r, g, b)?;
+        }
+        Ok(())
+    }
+
+    /// Write the palette in Photoshop's `.act` format.
+    ///
+    /// The format stores exactly 256 colors followed by the number of used
+    /// colors and the index of the transparent color. The first fully
+    /// transparent entry is used as the transparent color.
+    ///
+    /// Returns an error of kind [io::ErrorKind::InvalidInput] if the palette
+    /// has more than 256 colors.
+    pub fn write_act<W: Write>(&self, mut output: W) -> io::Result<()> {
+        let entries = self.dense_entries();
+        if entries.len() > 256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "ACT palettes are limited to 256 colors, got {}",
+                    entries.len()
+                ),
+            ));
+        }
+        let mut data = [0u8; 772];
+        for (index, entry) in entries.iter().enumerate() {
+            if let Some(entry) = entry {
+                data[index * 3..index * 3 + 3].copy_from_slice(&entry.rgba8[0..3]);
+            }
+        }
+        let transparent = entries
+            .iter()
+            .position(|entry| entry.is_some_and(|e| e.alpha() == 0))
+            .map_or(0xffff, |index| index as u16);
+        data[768..770].copy_from_slice(&(entries.len() as u16).to_be_bytes());
+        data[770..772].copy_from_slice(&transparent.to_be_bytes());
+        output.write_all(&data)
+    }
+
+    // All indices from 0 to the largest index. Missing entries are `None`.
+    fn dense_entries(&self) -> Vec<Option<&ColorPaletteEntry>> {
+        let len = self.entries.keys().max().map_or(0, |max| max + 1);
+        (0..len).map(|index| self.entries.get(&index)).collect()
+    }
+
     pub(crate) fn validate_indexed_pixels(&self, indexed_pixels: &[u8]) -> Result<()> {
         // TODO: Make way more efficient at least for the common case where
         // the palette goes from `0..num_colors`. Just search for a value >=
@@ -38,14 +321,263 @@ impl ColorPalette {
         // after parsing.
         for pixel in indexed_pixels {
             let color = self.color(*pixel as u32);
-            color.ok_or_else(|| {
-                AsepriteParseError::InvalidInput(format!("Palette index invalid: {}", pixel,))
-            })?;
+            color.ok_or(AsepriteParseError::InvalidPaletteIndex { index: *pixel })?;
         }
         Ok(())
     }
 }
 
+// The ID of the entry closest to `color`. Ties go to the first entry.
+fn nearest_among<'a, I, F>(entries: I, color: Rgba<u8>, mut distance: F) -> Option<u32>
+where
+    I: Iterator<Item = &'a ColorPaletteEntry>,
+    F: FnMut(Rgba<u8>, Rgba<u8>) -> f64,
+{
+    let mut best: Option<(u32, f64)> = None;
+    for entry in entries {
+        let d = distance(color, Rgba(entry.rgba8));
+        let better = match best {
+            Some((_, best_d)) => d < best_d,
+            None => true,
+        };
+        if better {
+            best = Some((entry.id, d));
+        }
+    }
+    best.map(|(id, _)| id)
+}
+
+// Splits the color space into at most `count` boxes and returns the average
+// color of each box, weighted by how often each color occurs. The result is
+// sorted and contains no duplicates.
+pub(crate) fn median_cut(histogram: Vec<([u8; 4], u64)>, count: usize) -> Vec<[u8; 4]> {
+    if histogram.is_empty() {
+        return Vec::new();
+    }
+    let channel_range = |colors: &[([u8; 4], u64)], channel: usize| {
+        let min = colors.iter().map(|c| c.0[channel]).min().unwrap_or(0);
+        let max = colors.iter().map(|c| c.0[channel]).max().unwrap_or(0);
+        max - min
+    };
+    let widest_channel = |colors: &[([u8; 4], u64)]| {
+        (0..4)
+            .map(|channel| (channel_range(colors, channel), channel))
+            .max_by_key(|&(range, channel)| (range, std::cmp::Reverse(channel)))
+            .unwrap()
+    };
+    let mut boxes = vec![histogram];
+    while boxes.len() < count {
+        // Split the box with the widest range along its widest channel.
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| (widest_channel(colors), index))
+            .max_by_key(|&((range, _), index)| (range, std::cmp::Reverse(index)))
+            .map(|((_, channel), index)| (index, channel))
+        else {
+            break;
+        };
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|c| (c.0[channel], c.0));
+        // Split at the weighted median. If two split points are equally
+        // balanced, prefer the one with the larger gap between colors.
+        let total: i64 = colors.iter().map(|c| c.1 as i64).sum();
+        let mut seen = 0;
+        let mut best = (i64::MAX, 0, 1);
+        for i in 0..colors.len() - 1 {
+            seen += colors[i].1 as i64;
+            let imbalance = (2 * seen - total).abs();
+            let gap = (colors[i + 1].0[channel] - colors[i].0[channel]) as i64;
+            if (imbalance, -gap) < (best.0, -best.1) {
+                best = (imbalance, gap, i + 1);
+            }
+        }
+        let split = best.2;
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    let mut result: Vec<[u8; 4]> = boxes
+        .iter()
+        .map(|colors| {
+            let total: u64 = colors.iter().map(|c| c.1).sum();
+            let mut sum = [0u64; 4];
+            for (color, count) in colors {
+                for channel in 0..4 {
+                    sum[channel] += color[channel] as u64 * *count;
+                }
+            }
+            sum.map(|s| ((s + total / 2) / total) as u8)
+        })
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// How to measure the difference between two colors. See
+/// [ColorPalette::nearest].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDistance {
+    /// Euclidean distance in RGB space. Fast, but does not match human
+    /// perception very well.
+    Rgb,
+    /// Euclidean distance in RGB space with weights depending on the amount
+    /// of red ("redmean"). A cheap approximation of perceived difference.
+    WeightedRgb,
+    /// The CIEDE2000 color difference in CIELAB space. The most accurate
+    /// option, but also the slowest.
+    Ciede2000,
+}
+
+impl ColorDistance {
+    /// The distance between two colors. Alpha is ignored.
+    pub fn distance(&self, a: Rgba<u8>, b: Rgba<u8>) -> f64 {
+        let [r1, g1, b1, _] = a.0.map(f64::from);
+        let [r2, g2, b2, _] = b.0.map(f64::from);
+        let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+        match self {
+            ColorDistance::Rgb => (dr * dr + dg * dg + db * db).sqrt(),
+            ColorDistance::WeightedRgb => {
+                let mean_r = (r1 + r2) / 2.0;
+                ((2.0 + mean_r / 256.0) * dr * dr
+                    + 4.0 * dg * dg
+                    + (2.0 + (255.0 - mean_r) / 256.0) * db * db)
+                    .sqrt()
+            }
+            ColorDistance::Ciede2000 => ciede2000(to_lab(a), to_lab(b)),
+        }
+    }
+}
+
+// Converts an sRGB color to CIELAB using the D65 white point.
+fn to_lab(color: Rgba<u8>) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = [linear(color[0]), linear(color[1]), linear(color[2])];
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// See Sharma et al., "The CIEDE2000 Color-Difference Formula: Implementation
+// Notes, Supplementary Test Data, and Mathematical Observations".
+pub(crate) fn ciede2000([l1, a1, b1]: [f64; 3], [l2, a2, b2]: [f64; 3]) -> f64 {
+    use std::f64::consts::PI;
+    let deg = |rad: f64| rad * 180.0 / PI;
+    let rad = |deg: f64| deg * PI / 180.0;
+
+    let c1 = a1.hypot(b1);
+    let c2 = a2.hypot(b2);
+    let c_mean = (c1 + c2) / 2.0;
+    let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt());
+    let a1p = (1.0 + g) * a1;
+    let a2p = (1.0 + g) * a2;
+    let c1p = a1p.hypot(b1);
+    let c2p = a2p.hypot(b2);
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            deg(b.atan2(a)).rem_euclid(360.0)
+        }
+    };
+    let h1p = hue(b1, a1p);
+    let h2p = hue(b2, a2p);
+
+    let dl = l2 - l1;
+    let dc = c2p - c1p;
+    let dh = if c1p * c2p == 0.0 {
+        0.0
+    } else if (h2p - h1p).abs() <= 180.0 {
+        h2p - h1p
+    } else if h2p - h1p > 180.0 {
+        h2p - h1p - 360.0
+    } else {
+        h2p - h1p + 360.0
+    };
+    let dh = 2.0 * (c1p * c2p).sqrt() * rad(dh / 2.0).sin();
+
+    let l_mean = (l1 + l2) / 2.0;
+    let c_mean_p = (c1p + c2p) / 2.0;
+    let h_mean = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+    let t = 1.0 - 0.17 * rad(h_mean - 30.0).cos()
+        + 0.24 * rad(2.0 * h_mean).cos()
+        + 0.32 * rad(3.0 * h_mean + 6.0).cos()
+        - 0.20 * rad(4.0 * h_mean - 63.0).cos();
+    let d_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (c_mean_p.powi(7) / (c_mean_p.powi(7) + 25f64.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_mean_p;
+    let s_h = 1.0 + 0.015 * c_mean_p * t;
+    let r_t = -rad(2.0 * d_theta).sin() * r_c;
+
+    ((dl / s_l).powi(2) + (dc / s_c).powi(2) + (dh / s_h).powi(2) + r_t * (dc / s_c) * (dh / s_h))
+        .sqrt()
+}
+
+impl<'a> IntoIterator for &'a ColorPalette {
+    type Item = &'a ColorPaletteEntry;
+    type IntoIter = ColorPaletteIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [ColorPalette] in index order. See
+/// [ColorPalette::iter].
+#[derive(Debug)]
+pub struct ColorPaletteIter<'a> {
+    inner: std::vec::IntoIter<&'a ColorPaletteEntry>,
+}
+
+impl<'a> Iterator for ColorPaletteIter<'a> {
+    type Item = &'a ColorPaletteEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ColorPaletteIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for ColorPaletteIter<'_> {}
+
+impl FusedIterator for ColorPaletteIter<'_> {}
+
 impl ColorPaletteEntry {
     /// The id of this entry is the same as its index in the palette.
     pub fn id(&self) -> u32 {
@@ -129,6 +661,87 @@ pub(crate) fn parse_chunk(data: &[u8]) -> Result<ColorPalette> {
     Ok(ColorPalette { entries })
 }
 
+fn parse_gpl<'a>(lines: impl Iterator<Item = &'a str>) -> Result<ColorPalette> {
+    let mut entries = IntMap::default();
+    for line in lines {
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let mut channel = || -> Result<u8> {
+            parts
+                .next()
+                .and_then(|part| part.parse().ok())
+                .ok_or_else(|| invalid_line("GPL", line))
+        };
+        let rgba8 = [channel()?, channel()?, channel()?, 255];
+        let name: Vec<&str> = parts.collect();
+        let id = entries.len() as u32;
+        let name = Some(name.join(" ")).filter(|name| !name.is_empty());
+        entries.insert(id, ColorPaletteEntry { id, rgba8, name });
+    }
+    Ok(ColorPalette { entries })
+}
+
+fn parse_jasc_pal<'a>(mut lines: impl Iterator<Item = &'a str>) -> Result<ColorPalette> {
+    let _version = lines.next();
+    let count: usize = lines
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| AsepriteParseError::InvalidInput("Invalid JASC-PAL header".to_owned()))?;
+    let colors = lines
+        .filter(|line| !line.is_empty())
+        .take(count)
+        .map(|line| {
+            let channels: Vec<u8> = line
+                .split_whitespace()
+                .map(|part| part.parse().map_err(|_| invalid_line("JASC-PAL", line)))
+                .collect::<Result<_>>()?;
+            match channels[..] {
+                [r, g, b] => Ok([r, g, b, 255]),
+                [r, g, b, a] => Ok([r, g, b, a]),
+                _ => Err(invalid_line("JASC-PAL", line)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if colors.len() != count {
+        return Err(AsepriteParseError::InvalidInput(format!(
+            "JASC-PAL file declares {} colors but contains {}",
+            count,
+            colors.len()
+        )));
+    }
+    Ok(ColorPalette::from_colors(colors))
+}
+
+fn parse_hex<'a>(lines: impl Iterator<Item = &'a str>) -> Result<ColorPalette> {
+    let colors = lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let hex = line.strip_prefix('#').unwrap_or(line);
+            // from_str_radix alone would also accept a sign.
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid_line("hex", line));
+            }
+            let value = u32::from_str_radix(hex, 16).map_err(|_| invalid_line("hex", line))?;
+            match hex.len() {
+                6 => Ok((value << 8 | 0xff).to_be_bytes()),
+                8 => Ok(value.to_be_bytes()),
+                _ => Err(invalid_line("hex", line)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ColorPalette::from_colors(colors))
+}
+
+fn invalid_line(format: &str, line: &str) -> AsepriteParseError {
+    AsepriteParseError::InvalidInput(format!("Invalid {} palette line: {:?}", format, line))
+}
+
 // Note: we want to map `0 -> 0` and `63 -> 255` and evenly for the in-between
 // points so we can't simply multiply by 4.
 fn scale_6bit_to_8bit(color: u8) -> Result<u8> {
@@ -4,22 +4,24 @@ use crate::layer::{LayerData, LayersData};
 use crate::pixel::{Pixels, RawPixels};
 use crate::reader::AseReader;
 use crate::slice::Slice;
-use crate::tileset::{Tileset, TilesetsById};
+use crate::tileset::{Tileset, TilesetId, TilesetsById};
 use crate::user_data::UserData;
-use crate::{error::AsepriteParseError, AsepriteFile, PixelFormat};
+use crate::{error::AsepriteParseError, raw::RawChunk, AsepriteFile, Extensions, PixelFormat};
 use log::debug;
 use std::io::Read;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::Result;
 use crate::{cel, color_profile, layer, palette, slice, tags, user_data, Tag};
 
-struct ParseInfo {
+pub(crate) struct ParseInfo {
+    header_flags: u32,
     palette: Option<Arc<palette::ColorPalette>>,
     color_profile: Option<color_profile::ColorProfile>,
     layers: Vec<LayerData>,
     framedata: cel::CelsData<RawPixels>, // Vec<Vec<cel::RawCel>>,
-    frame_times: Vec<u16>,
+    pub(crate) frame_times: Vec<u16>,
     tags: Option<Vec<Tag>>,
     external_files: ExternalFilesById,
     tilesets: TilesetsById<RawPixels>,
@@ -29,8 +31,9 @@ struct ParseInfo {
 }
 
 impl ParseInfo {
-    fn new(num_frames: u16, default_frame_time: u16) -> Self {
+    pub(crate) fn new(num_frames: u16, default_frame_time: u16, header_flags: u32) -> Self {
         Self {
+            header_flags,
             palette: None,
             color_profile: None,
             layers: Vec::new(),
@@ -84,18 +87,15 @@ impl ParseInfo {
                 tag_index
             ))
         })?;
-        tag.set_user_data(user_data);
+        tag.set_user_data(Some(user_data));
         self.user_data_context = Some(UserDataContext::TagIndex(tag_index + 1));
         Ok(())
     }
 
     fn add_user_data(&mut self, user_data: UserData) -> Result<()> {
-        let user_data_context = self.user_data_context.ok_or_else(|| {
-            AsepriteParseError::InvalidInput(
-                "Found dangling user data chunk. Expected a previous chunk to attach user data"
-                    .into(),
-            )
-        })?;
+        let user_data_context = self
+            .user_data_context
+            .ok_or(AsepriteParseError::DanglingUserData)?;
         match user_data_context {
             UserDataContext::CelId(cel_id) => {
                 let cel = self.framedata.cel_mut(&cel_id).ok_or_else(|| {
@@ -121,6 +121,9 @@ impl ParseInfo {
             UserDataContext::TagIndex(tag_index) => {
                 self.set_tag_user_data(user_data, tag_index)?;
             }
+            UserDataContext::Tileset(tileset_id, index) => {
+                self.set_tileset_user_data(user_data, tileset_id, index)?;
+            }
             UserDataContext::SliceIndex(slice_idx) => {
                 let slice = self.slices.get_mut(slice_idx as usize).ok_or_else(|| {
                     AsepriteParseError::InternalError(format!(
@@ -134,20 +137,131 @@ impl ParseInfo {
         Ok(())
     }
 
+    fn add_tileset(&mut self, tileset: Tileset<RawPixels>) {
+        let id = tileset.id();
+        self.tilesets.add(tileset);
+        self.user_data_context = Some(UserDataContext::Tileset(id, 0));
+    }
+
+    fn set_tileset_user_data(
+        &mut self,
+        user_data: UserData,
+        tileset_id: TilesetId,
+        index: u32,
+    ) -> Result<()> {
+        let tileset = self.tilesets.get_mut(tileset_id).ok_or_else(|| {
+            AsepriteParseError::InternalError(format!(
+                "Invalid tileset id stored in chunk context: {}",
+                tileset_id
+            ))
+        })?;
+        // The first user data chunk belongs to the tileset itself, the
+        // following ones to each tile in order.
+        if index == 0 {
+            tileset.user_data = Some(user_data);
+        } else {
+            tileset.set_tile_user_data(index - 1, user_data);
+        }
+        self.user_data_context = Some(UserDataContext::Tileset(tileset_id, index + 1));
+        Ok(())
+    }
+
     fn add_slice(&mut self, slice: Slice) {
         let context_idx = self.slices.len();
         self.slices.push(slice);
         self.user_data_context = Some(UserDataContext::SliceIndex(context_idx as u32));
     }
 
+    // Loads the tilesets that are stored in external files. The result is
+    // indexed by the tileset ID in this file.
+    //
+    // `open_files` are the names of the external files that are being resolved
+    // already, i.e., this file and the files that reference it. Used to stop
+    // at files that reference themselves directly or indirectly.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn resolve_external_tilesets(
+        &self,
+        resolver: &mut ExternalResolver<'_>,
+        open_files: &[String],
+    ) -> Result<TilesetsById> {
+        let mut result = TilesetsById::new();
+        for tileset in self.tilesets.iter() {
+            let reference = match (&tileset.pixels, tileset.external_file()) {
+                (None, Some(reference)) => reference,
+                _ => continue,
+            };
+            let external_file = self
+                .external_files
+                .get(&reference.external_file_id())
+                .ok_or_else(|| {
+                    AsepriteParseError::InvalidInput(format!(
+                        "Tileset {} references a missing external file (id {})",
+                        tileset.id(),
+                        reference.external_file_id().value()
+                    ))
+                })?;
+            if open_files.iter().any(|name| name == external_file.name()) {
+                return Err(AsepriteParseError::InvalidInput(format!(
+                    "External file {} references itself",
+                    external_file.name()
+                )));
+            }
+            if open_files.len() >= MAX_EXTERNAL_FILE_DEPTH {
+                return Err(AsepriteParseError::InvalidInput(format!(
+                    "External files are nested more than {} levels deep",
+                    MAX_EXTERNAL_FILE_DEPTH
+                )));
+            }
+            let data = match resolver(external_file) {
+                Some(data) => data,
+                None => continue,
+            };
+            let mut options =
+                ParseOptions::new().external_resolver(|file: &ExternalFile| resolver(file));
+            options.open_external_files = open_files.to_vec();
+            options
+                .open_external_files
+                .push(external_file.name().to_owned());
+            let mut external = read_aseprite_with_options(&data[..], options)?;
+            // The external file was just parsed, so its tilesets are not
+            // shared yet.
+            let mut external_tileset = Arc::get_mut(&mut external.tilesets)
+                .and_then(|tilesets| tilesets.remove(reference.tileset_id()))
+                .ok_or_else(|| {
+                    AsepriteParseError::InvalidInput(format!(
+                        "External file {} does not contain {}",
+                        external_file.name(),
+                        reference.tileset_id()
+                    ))
+                })?;
+            let tile_size: (u32, u32) = tileset.tile_size().into();
+            let external_tile_size: (u32, u32) = external_tileset.tile_size().into();
+            if tile_size != external_tile_size {
+                return Err(AsepriteParseError::InvalidInput(format!(
+                    "Tile size of {} in external file {} does not match",
+                    reference.tileset_id(),
+                    external_file.name()
+                )));
+            }
+            external_tileset.id = tileset.id();
+            result.add(external_tileset);
+        }
+        Ok(result)
+    }
+
     // Validate moves the ParseInfo data into an intermediate ValidatedParseInfo struct,
     // which is then used to create the AsepriteFile.
-    fn validate(self, pixel_format: &PixelFormat) -> Result<ValidatedParseInfo> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn validate(
+        self,
+        pixel_format: &PixelFormat,
+        external_tilesets: TilesetsById,
+    ) -> Result<ValidatedParseInfo> {
         let layers = LayersData::from_vec(self.layers)?;
 
         let tilesets = self.tilesets;
         let palette = self.palette;
-        let tilesets = tilesets.validate(pixel_format, palette.clone())?;
+        let tilesets = tilesets.validate(pixel_format, palette.clone(), external_tilesets)?;
         layers.validate(&tilesets)?;
 
         //let framedata = self.framedata;
@@ -162,7 +276,11 @@ impl ParseInfo {
             external_files: self.external_files,
             palette,
             tags: self.tags.unwrap_or_default(),
-            frame_times: self.frame_times,
+            frame_times: self
+                .frame_times
+                .into_iter()
+                .map(|ms| Duration::from_millis(ms as u64))
+                .collect(),
             sprite_user_data: self.sprite_user_data,
             slices: self.slices,
         })
@@ -176,29 +294,210 @@ struct ValidatedParseInfo {
     external_files: ExternalFilesById,
     palette: Option<Arc<palette::ColorPalette>>,
     tags: Vec<Tag>,
-    frame_times: Vec<u16>,
+    frame_times: Vec<Duration>,
     sprite_user_data: Option<UserData>,
     slices: Vec<Slice>,
 }
 
+/// Callback that returns the contents of an external file.
+pub(crate) type ExternalResolver<'a> = dyn FnMut(&ExternalFile) -> Option<Vec<u8>> + 'a;
+
+/// Callback that receives the chunks of a registered type.
+pub(crate) type ChunkHandler<'a> = dyn FnMut(&RawChunk, &mut Extensions) -> Result<()> + 'a;
+
+/// Optional behavior of the parser. See [AsepriteFile::read_with_options].
+#[derive(Default)]
+pub struct ParseOptions<'a> {
+    external_resolver: Option<Box<ExternalResolver<'a>>>,
+    pub(crate) chunk_handlers: Vec<(u16, Box<ChunkHandler<'a>>)>,
+    pub(crate) progress: Option<Box<dyn FnMut(ParseProgress) + 'a>>,
+    // Names of the external files that are being resolved while parsing an
+    // external file. Empty for the file that the user reads.
+    open_external_files: Vec<String>,
+}
+
+/// How far parsing has progressed. See [ParseOptions::progress].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseProgress {
+    /// Number of bytes parsed so far.
+    pub bytes_read: u64,
+    /// Size of the file in bytes according to its header.
+    pub total_bytes: u64,
+    /// Number of frames parsed so far.
+    pub frames_parsed: u32,
+    /// Number of frames in the file.
+    pub total_frames: u32,
+}
+
+impl ParseProgress {
+    /// Fraction of the file that has been parsed, from `0.0` to `1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        (self.bytes_read as f64 / self.total_bytes as f64).min(1.0) as f32
+    }
+}
+
+impl<'a> ParseOptions<'a> {
+    /// Default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load tilesets that are stored in external files. See
+    /// [AsepriteFile::read_with_external_resolver].
+    pub fn external_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: FnMut(&ExternalFile) -> Option<Vec<u8>> + 'a,
+    {
+        self.external_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Call `handler` for every chunk of the given type, in the order the
+    /// chunks appear in the file.
+    ///
+    /// The handler can store its results in the [Extensions] of the file,
+    /// which are available via [AsepriteFile::extensions] once parsing is
+    /// done. If the handler returns an error, parsing stops with that error.
+    ///
+    /// Chunk types that this crate does not know are usually an error.
+    /// Registering a handler for such a type makes the parser skip these
+    /// chunks instead. Chunks of known types are still parsed as usual after
+    /// the handler has seen them. If several handlers are registered for the
+    /// same type, they are called in the order they were registered.
+    ///
+    /// ```
+    /// # use asefile::{AsepriteFile, ParseOptions, raw::RawChunk};
+    /// # use std::fs::File;
+    /// # let input = File::open("./tests/data/layers_and_tags.aseprite").unwrap();
+    /// #[derive(Clone)]
+    /// struct CelSizes(Vec<usize>);
+    ///
+    /// let options = ParseOptions::new().chunk_handler(RawChunk::CEL, |chunk, extensions| {
+    ///     let sizes = extensions.get_or_insert_with(|| CelSizes(Vec::new()));
+    ///     sizes.0.push(chunk.data.len());
+    ///     Ok(())
+    /// });
+    /// let ase = AsepriteFile::read_with_options(input, options).unwrap();
+    /// assert_eq!(ase.extensions().get::<CelSizes>().unwrap().0.len(), 14);
+    /// ```
+    pub fn chunk_handler<F>(mut self, chunk_type: u16, handler: F) -> Self
+    where
+        F: FnMut(&RawChunk, &mut Extensions) -> Result<()> + 'a,
+    {
+        self.chunk_handlers.push((chunk_type, Box::new(handler)));
+        self
+    }
+
+    /// Call `progress` after the file header and after each frame has been
+    /// read, e.g., to update a loading screen.
+    ///
+    /// Most of the work happens while reading frames, so this is called
+    /// `num_frames + 1` times. Resolving external files and validating the
+    /// file happen after the last call.
+    ///
+    /// ```
+    /// # use asefile::{AsepriteFile, ParseOptions};
+    /// # use std::fs::File;
+    /// # let input = File::open("./tests/data/layers_and_tags.aseprite").unwrap();
+    /// let options = ParseOptions::new().progress(|progress| {
+    ///     println!(
+    ///         "{}/{} frames, {:.0}%",
+    ///         progress.frames_parsed,
+    ///         progress.total_frames,
+    ///         100.0 * progress.fraction()
+    ///     );
+    /// });
+    /// let ase = AsepriteFile::read_with_options(input, options).unwrap();
+    /// ```
+    pub fn progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(ParseProgress) + 'a,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+}
+
+pub fn read_aseprite<R: Read>(input: R) -> Result<AsepriteFile> {
+    read_aseprite_with_options(input, ParseOptions::default())
+}
+
 // file format docs: https://github.com/aseprite/aseprite/blob/master/docs/ase-file-specs.md
 // v1.3 spec diff doc: https://gist.github.com/dacap/35f3b54fbcd021d099e0166a4f295bab
-pub fn read_aseprite<R: Read>(input: R) -> Result<AsepriteFile> {
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "read_aseprite", level = "info", skip_all)
+)]
+pub(crate) fn read_aseprite_with_options<R: Read>(
+    input: R,
+    mut options: ParseOptions<'_>,
+) -> Result<AsepriteFile> {
     let mut reader = AseReader::with(input);
-    let _size = reader.dword()?;
+    let header = read_header(&mut reader)?;
+
+    let mut parse_info = ParseInfo::new(header.num_frames, header.default_frame_time, header.flags);
+    let mut extensions = Extensions::new();
+
+    let mut progress = ParseProgress {
+        bytes_read: HEADER_SIZE,
+        total_bytes: header.file_size as u64,
+        frames_parsed: 0,
+        total_frames: header.num_frames as u32,
+    };
+    if let Some(callback) = options.progress.as_deref_mut() {
+        callback(progress);
+    }
+
+    for frame_id in 0..header.num_frames {
+        // println!("--- Frame {} -------", frame_id);
+        let frame_size = parse_frame(
+            &mut reader,
+            frame_id,
+            header.pixel_format,
+            &mut parse_info,
+            &mut options.chunk_handlers,
+            &mut extensions,
+        )?;
+        if let Some(callback) = options.progress.as_deref_mut() {
+            progress.bytes_read += frame_size as u64;
+            progress.frames_parsed += 1;
+            callback(progress);
+        }
+    }
+
+    finish(&header, parse_info, extensions, &mut options)
+}
+
+pub(crate) struct Header {
+    pub(crate) file_size: u32,
+    pub(crate) num_frames: u16,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) pixel_format: PixelFormat,
+    pub(crate) flags: u32,
+    pub(crate) default_frame_time: u16,
+}
+
+pub(crate) fn read_header<R: Read>(reader: &mut AseReader<R>) -> Result<Header> {
+    #[cfg(feature = "tracing")]
+    let _header_span = tracing::debug_span!("header").entered();
+    let file_size = reader.dword()?;
     let magic_number = reader.word()?;
     if magic_number != 0xA5E0 {
-        return Err(AsepriteParseError::InvalidInput(format!(
-            "Invalid magic number for header: {:x} != {:x}",
-            magic_number, 0xA5E0
-        )));
+        return Err(AsepriteParseError::BadMagicNumber {
+            expected: 0xA5E0,
+            found: magic_number,
+        });
     }
 
     let num_frames = reader.word()?;
     let width = reader.word()?;
     let height = reader.word()?;
     let color_depth = reader.word()?;
-    let _flags = reader.dword()?;
+    let flags = reader.dword()?;
     let default_frame_time = reader.word()?;
     let _placeholder1 = reader.dword()?;
     let _placeholder2 = reader.dword()?;
@@ -222,14 +521,43 @@ pub fn read_aseprite<R: Read>(input: R) -> Result<AsepriteFile> {
         ));
     }
 
-    let mut parse_info = ParseInfo::new(num_frames, default_frame_time);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        file_size,
+        num_frames,
+        width,
+        height,
+        color_depth,
+        "Read header"
+    );
 
     let pixel_format = parse_pixel_format(color_depth, transparent_color_index)?;
 
-    for frame_id in 0..num_frames {
-        // println!("--- Frame {} -------", frame_id);
-        parse_frame(&mut reader, frame_id, pixel_format, &mut parse_info)?;
-    }
+    Ok(Header {
+        file_size,
+        num_frames,
+        width,
+        height,
+        pixel_format,
+        flags,
+        default_frame_time,
+    })
+}
+
+// Resolves external files and builds the file once all frames have been
+// parsed.
+pub(crate) fn finish(
+    header: &Header,
+    parse_info: ParseInfo,
+    extensions: Extensions,
+    options: &mut ParseOptions<'_>,
+) -> Result<AsepriteFile> {
+    let external_tilesets = match options.external_resolver.as_deref_mut() {
+        Some(resolver) => {
+            parse_info.resolve_external_tilesets(resolver, &options.open_external_files)?
+        }
+        None => TilesetsById::new(),
+    };
 
     let ValidatedParseInfo {
         layers,
@@ -241,127 +569,200 @@ pub fn read_aseprite<R: Read>(input: R) -> Result<AsepriteFile> {
         frame_times,
         sprite_user_data,
         slices,
-    } = parse_info.validate(&pixel_format)?;
+    } = parse_info.validate(&header.pixel_format, external_tilesets)?;
 
     Ok(AsepriteFile {
-        width,
-        height,
-        num_frames,
-        pixel_format,
+        width: header.width,
+        height: header.height,
+        num_frames: header.num_frames,
+        pixel_format: header.pixel_format,
         palette,
         layers,
         frame_times,
         tags,
-        framedata,
+        framedata: Arc::new(framedata),
         external_files,
-        tilesets,
+        tilesets: Arc::new(tilesets),
         sprite_user_data,
         slices,
+        extensions: Arc::new(extensions),
     })
 }
 
-fn parse_frame<R: Read>(
-    reader: &mut AseReader<R>,
-    frame_id: u16,
-    pixel_format: PixelFormat,
-    parse_info: &mut ParseInfo,
-) -> Result<()> {
+pub(crate) struct FrameHeader {
+    pub(crate) num_bytes: u32,
+    pub(crate) num_chunks: u32,
+    pub(crate) duration_ms: u16,
+}
+
+pub(crate) fn read_frame_header<R: Read>(reader: &mut AseReader<R>) -> Result<FrameHeader> {
     let num_bytes = reader.dword()?;
     let magic_number = reader.word()?;
     if magic_number != 0xF1FA {
-        return Err(AsepriteParseError::InvalidInput(format!(
-            "Invalid magic number for frame: {:x} != {:x}",
-            magic_number, 0xF1FA
-        )));
+        return Err(AsepriteParseError::BadFrameMagicNumber {
+            expected: 0xF1FA,
+            found: magic_number,
+        });
     }
     let old_num_chunks = reader.word()?;
-    let frame_duration_ms = reader.word()?;
+    let duration_ms = reader.word()?;
     let _placeholder = reader.word()?;
     let new_num_chunks = reader.dword()?;
 
-    parse_info.frame_times[frame_id as usize] = frame_duration_ms;
-
     let num_chunks = if new_num_chunks == 0 {
         old_num_chunks as u32
     } else {
         new_num_chunks
     };
+    Ok(FrameHeader {
+        num_bytes,
+        num_chunks,
+        duration_ms,
+    })
+}
 
-    let bytes_available = num_bytes as i64 - FRAME_HEADER_SIZE;
+// Returns the size of the frame in bytes, including the frame header.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "frame", level = "debug", skip_all, fields(frame = frame_id))
+)]
+fn parse_frame<R: Read>(
+    reader: &mut AseReader<R>,
+    frame_id: u16,
+    pixel_format: PixelFormat,
+    parse_info: &mut ParseInfo,
+    chunk_handlers: &mut [(u16, Box<ChunkHandler<'_>>)],
+    extensions: &mut Extensions,
+) -> Result<u32> {
+    let FrameHeader {
+        num_bytes,
+        num_chunks,
+        duration_ms,
+    } = read_frame_header(reader)?;
+
+    parse_info.frame_times[frame_id as usize] = duration_ms;
+
+    let mut bytes_available = num_bytes as i64 - FRAME_HEADER_SIZE;
+
+    for _ in 0..num_chunks {
+        let (chunk_type_code, data) = read_raw_chunk(&mut bytes_available, reader)?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "chunk",
+            chunk_type = chunk_type_code,
+            size = data.len() + CHUNK_HEADER_SIZE
+        )
+        .entered();
+        parse_chunk(
+            frame_id,
+            chunk_type_code,
+            data,
+            pixel_format,
+            parse_info,
+            chunk_handlers,
+            extensions,
+        )?;
+    }
 
-    let chunks = Chunk::read_all(num_chunks, bytes_available, reader)?;
+    Ok(num_bytes)
+}
 
-    for chunk in chunks {
-        let Chunk { chunk_type, data } = chunk;
-        match chunk_type {
-            ChunkType::ColorProfile => {
-                let profile = color_profile::parse_chunk(&data)?;
-                parse_info.color_profile = Some(profile);
+// Interprets a single chunk of the given frame.
+pub(crate) fn parse_chunk(
+    frame_id: u16,
+    chunk_type_code: u16,
+    data: Vec<u8>,
+    pixel_format: PixelFormat,
+    parse_info: &mut ParseInfo,
+    chunk_handlers: &mut [(u16, Box<ChunkHandler<'_>>)],
+    extensions: &mut Extensions,
+) -> Result<()> {
+    let mut handled = false;
+    let mut chunk = None;
+    for (handler_type, handler) in chunk_handlers.iter_mut() {
+        if *handler_type == chunk_type_code {
+            let chunk = chunk.get_or_insert_with(|| RawChunk {
+                frame: frame_id as u32,
+                chunk_type: chunk_type_code,
+                data: data.clone(),
+            });
+            handler(chunk, extensions)?;
+            handled = true;
+        }
+    }
+    let chunk_type = match parse_chunk_type(chunk_type_code) {
+        Ok(chunk_type) => chunk_type,
+        Err(_) if handled => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    match chunk_type {
+        ChunkType::ColorProfile => {
+            let profile = color_profile::parse_chunk(&data)?;
+            parse_info.color_profile = Some(profile);
+        }
+        ChunkType::Palette => {
+            let palette = palette::parse_chunk(&data)?;
+            parse_info.palette = Some(Arc::new(palette));
+        }
+        ChunkType::Layer => {
+            let has_uuid = parse_info.header_flags & HEADER_FLAG_LAYER_UUID != 0;
+            let layer_data = layer::parse_chunk(&data, has_uuid)?;
+            parse_info.add_layer(layer_data);
+        }
+        ChunkType::Cel => {
+            let cel = cel::parse_chunk(&data, pixel_format)?;
+            parse_info.add_cel(frame_id, cel)?;
+        }
+        ChunkType::ExternalFiles => {
+            let files = ExternalFile::parse_chunk(&data)?;
+            parse_info.add_external_files(files);
+        }
+        ChunkType::Tags => {
+            let tags = tags::parse_chunk(&data)?;
+            if frame_id == 0 {
+                parse_info.add_tags(tags);
+            } else {
+                debug!("Ignoring tags outside of frame 0");
             }
-            ChunkType::Palette => {
-                let palette = palette::parse_chunk(&data)?;
+        }
+        ChunkType::Slice => {
+            let slice = slice::parse_chunk(&data)?;
+            parse_info.add_slice(slice);
+            //println!("Slice: {:#?}", slice);
+        }
+        ChunkType::UserData => {
+            let user_data = user_data::parse_userdata_chunk(&data)?;
+            parse_info.add_user_data(user_data)?;
+            //println!("Userdata: {:#?}", ud);
+        }
+        ChunkType::OldPalette04 => {
+            // An old palette chunk precedes the sprite UserData chunk.
+            // Update the chunk context to reflect the OldPalette chunk.
+            parse_info.user_data_context = Some(UserDataContext::OldPalette);
+
+            if parse_info.palette.is_none() {
+                let palette = palette::parse_old_chunk_04(&data)?;
                 parse_info.palette = Some(Arc::new(palette));
             }
-            ChunkType::Layer => {
-                let layer_data = layer::parse_chunk(&data)?;
-                parse_info.add_layer(layer_data);
-            }
-            ChunkType::Cel => {
-                let cel = cel::parse_chunk(&data, pixel_format)?;
-                parse_info.add_cel(frame_id, cel)?;
-            }
-            ChunkType::ExternalFiles => {
-                let files = ExternalFile::parse_chunk(&data)?;
-                parse_info.add_external_files(files);
-            }
-            ChunkType::Tags => {
-                let tags = tags::parse_chunk(&data)?;
-                if frame_id == 0 {
-                    parse_info.add_tags(tags);
-                } else {
-                    debug!("Ignoring tags outside of frame 0");
-                }
-            }
-            ChunkType::Slice => {
-                let slice = slice::parse_chunk(&data)?;
-                parse_info.add_slice(slice);
-                //println!("Slice: {:#?}", slice);
-            }
-            ChunkType::UserData => {
-                let user_data = user_data::parse_userdata_chunk(&data)?;
-                parse_info.add_user_data(user_data)?;
-                //println!("Userdata: {:#?}", ud);
-            }
-            ChunkType::OldPalette04 => {
-                // An old palette chunk precedes the sprite UserData chunk.
-                // Update the chunk context to reflect the OldPalette chunk.
-                parse_info.user_data_context = Some(UserDataContext::OldPalette);
-
-                if parse_info.palette.is_none() {
-                    let palette = palette::parse_old_chunk_04(&data)?;
-                    parse_info.palette = Some(Arc::new(palette));
-                }
-            }
-            ChunkType::OldPalette11 => {
-                // An old palette chunk precedes the sprite UserData chunk.
-                // Update the chunk context to reflect the OldPalette chunk.
-                parse_info.user_data_context = Some(UserDataContext::OldPalette);
-
-                if parse_info.palette.is_none() {
-                    let palette = palette::parse_old_chunk_11(&data)?;
-                    parse_info.palette = Some(Arc::new(palette));
-                }
-            }
-            ChunkType::Tileset => {
-                let tileset = Tileset::<RawPixels>::parse_chunk(&data, pixel_format)?;
-                parse_info.tilesets.add(tileset);
-            }
-            ChunkType::CelExtra | ChunkType::Mask | ChunkType::Path => {
-                debug!("Ignoring unsupported chunk type: {:?}", chunk_type);
+        }
+        ChunkType::OldPalette11 => {
+            // An old palette chunk precedes the sprite UserData chunk.
+            // Update the chunk context to reflect the OldPalette chunk.
+            parse_info.user_data_context = Some(UserDataContext::OldPalette);
+
+            if parse_info.palette.is_none() {
+                let palette = palette::parse_old_chunk_11(&data)?;
+                parse_info.palette = Some(Arc::new(palette));
             }
         }
+        ChunkType::Tileset => {
+            let tileset = Tileset::<RawPixels>::parse_chunk(&data, pixel_format)?;
+            parse_info.add_tileset(tileset);
+        }
+        ChunkType::CelExtra | ChunkType::Mask | ChunkType::Path => {
+            debug!("Ignoring unsupported chunk type: {:?}", chunk_type);
+        }
     }
-
     Ok(())
 }
 
@@ -372,6 +773,9 @@ enum UserDataContext {
     OldPalette,
     TagIndex(u16),
     SliceIndex(u32),
+    // Tileset id and index of the next user data chunk. Index 0 is the
+    // tileset itself, `n + 1` is tile `n`.
+    Tileset(TilesetId, u32),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -415,54 +819,40 @@ fn parse_chunk_type(chunk_type: u16) -> Result<ChunkType> {
     }
 }
 
-const CHUNK_HEADER_SIZE: usize = 6;
-const FRAME_HEADER_SIZE: i64 = 16;
+pub(crate) const HEADER_SIZE: u64 = 128;
+pub(crate) const CHUNK_HEADER_SIZE: usize = 6;
+pub(crate) const FRAME_HEADER_SIZE: i64 = 16;
 
-struct Chunk {
-    chunk_type: ChunkType,
-    data: Vec<u8>,
-}
+// Header flag: layer chunks contain a UUID.
+const HEADER_FLAG_LAYER_UUID: u32 = 4;
 
-impl Chunk {
-    fn read<R: Read>(bytes_available: &mut i64, reader: &mut AseReader<R>) -> Result<Self> {
-        let chunk_size = reader.dword()?;
-        let chunk_type_code = reader.word()?;
-        let chunk_type = parse_chunk_type(chunk_type_code)?;
+// External files may reference tilesets in other external files. Limits how
+// deep these references are followed.
+const MAX_EXTERNAL_FILE_DEPTH: usize = 32;
 
-        check_chunk_bytes(chunk_size, *bytes_available)?;
+/// Read the type and data of the next chunk without interpreting it.
+pub(crate) fn read_raw_chunk<R: Read>(
+    bytes_available: &mut i64,
+    reader: &mut AseReader<R>,
+) -> Result<(u16, Vec<u8>)> {
+    let chunk_size = reader.dword()?;
+    let chunk_type = reader.word()?;
 
-        let chunk_data_bytes = chunk_size as usize - CHUNK_HEADER_SIZE;
-        let mut data = vec![0_u8; chunk_data_bytes];
-        reader.read_exact(&mut data)?;
-        *bytes_available -= chunk_size as i64;
-        Ok(Chunk { chunk_type, data })
-    }
-    fn read_all<R: Read>(
-        count: u32,
-        mut bytes_available: i64,
-        reader: &mut AseReader<R>,
-    ) -> Result<Vec<Self>> {
-        let mut chunks: Vec<Chunk> = Vec::new();
-        for _idx in 0..count {
-            let chunk = Self::read(&mut bytes_available, reader)?;
-            chunks.push(chunk);
-        }
-        Ok(chunks)
-    }
+    check_chunk_bytes(chunk_size, *bytes_available)?;
+
+    let chunk_data_bytes = chunk_size as usize - CHUNK_HEADER_SIZE;
+    let mut data = vec![0_u8; chunk_data_bytes];
+    reader.read_exact(&mut data)?;
+    *bytes_available -= chunk_size as i64;
+    Ok((chunk_type, data))
 }
 
-fn check_chunk_bytes(chunk_size: u32, bytes_available: i64) -> Result<()> {
-    if (chunk_size as usize) < CHUNK_HEADER_SIZE {
-        return Err(AsepriteParseError::InvalidInput(format!(
-            "Chunk size is too small {}, minimum_size: {}",
-            chunk_size, CHUNK_HEADER_SIZE
-        )));
-    }
-    if chunk_size as i64 > bytes_available {
-        return Err(AsepriteParseError::InvalidInput(format!(
-            "Trying to read chunk of size {}, but there are only {} bytes available in the frame",
-            chunk_size, bytes_available
-        )));
+pub(crate) fn check_chunk_bytes(chunk_size: u32, bytes_available: i64) -> Result<()> {
+    if (chunk_size as usize) < CHUNK_HEADER_SIZE || chunk_size as i64 > bytes_available {
+        return Err(AsepriteParseError::BadChunkSize {
+            size: chunk_size,
+            bytes_available,
+        });
     }
     Ok(())
 }
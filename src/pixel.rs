@@ -1,7 +1,16 @@
 use image::Rgba;
 
-use crate::{reader::AseReader, AsepriteParseError, ColorPalette, PixelFormat, Result};
-use std::{borrow::Cow, io::Read, sync::Arc};
+use crate::{
+    reader::AseReader, AsepriteParseError, ColorDistance, ColorPalette, PixelFormat, Result,
+};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io::Read,
+    ops::Range,
+    sync::Arc,
+};
 
 // From Aseprite file spec:
 // PIXEL: One pixel, depending on the image pixel format:
@@ -18,7 +27,7 @@ fn read_rgba(chunk: &[u8]) -> Result<Rgba<u8>> {
     Ok(Rgba([red, green, blue, alpha]))
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Grayscale {
     value: u8,
     alpha: u8,
@@ -36,6 +45,15 @@ impl Grayscale {
         let Self { value, alpha } = self;
         Rgba([value, value, value, alpha])
     }
+
+    // Same luma weights as Aseprite.
+    pub(crate) fn from_rgba(Rgba([r, g, b, alpha]): Rgba<u8>) -> Self {
+        let value = (r as u32 * 2126 + g as u32 * 7152 + b as u32 * 722) / 10000;
+        Self {
+            value: value as u8,
+            alpha,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -68,15 +86,17 @@ fn output_size(pixel_format: PixelFormat, expected_pixel_count: usize) -> usize
     pixel_format.bytes_per_pixel() * expected_pixel_count
 }
 
-#[derive(Debug)]
+// The buffers are Arcs, so that cels with identical pixels can share them.
+// See PixelInterner.
+#[derive(Debug, Clone)]
 pub enum Pixels {
-    Rgba(Vec<Rgba<u8>>),
-    Grayscale(Vec<Grayscale>),
+    Rgba(Arc<Vec<Rgba<u8>>>),
+    Grayscale(Arc<Vec<Grayscale>>),
     Indexed {
         palette: Arc<ColorPalette>,
         transparent_color_index: u8,
         layer_is_background: bool,
-        data: Vec<u8>,
+        data: Arc<Vec<u8>>,
     },
 }
 
@@ -97,21 +117,23 @@ impl RawPixels {
                 Ok(Self::Indexed(bytes))
             }
             PixelFormat::Grayscale => {
-                if bytes.len() % 2 != 0 {
+                let chunks = bytes.chunks_exact(2);
+                if !chunks.remainder().is_empty() {
                     return Err(AsepriteParseError::InvalidInput(
                         "Incorrect length of bytes for Grayscale image data".to_string(),
                     ));
                 }
-                let pixels: Result<Vec<_>> = bytes.chunks_exact(2).map(Grayscale::new).collect();
+                let pixels: Result<Vec<_>> = chunks.map(Grayscale::new).collect();
                 pixels.map(Self::Grayscale)
             }
             PixelFormat::Rgba => {
-                if bytes.len() % 4 != 0 {
+                let chunks = bytes.chunks_exact(4);
+                if !chunks.remainder().is_empty() {
                     return Err(AsepriteParseError::InvalidInput(
                         "Incorrect length of bytes for RGBA image data".to_string(),
                     ));
                 }
-                let pixels: Result<Vec<_>> = bytes.chunks_exact(4).map(read_rgba).collect();
+                let pixels: Result<Vec<_>> = chunks.map(read_rgba).collect();
                 pixels.map(Self::Rgba)
             }
         }
@@ -154,8 +176,8 @@ impl RawPixels {
         layer_is_background: bool,
     ) -> Result<Pixels> {
         match self {
-            RawPixels::Rgba(data) => Ok(Pixels::Rgba(data)),
-            RawPixels::Grayscale(data) => Ok(Pixels::Grayscale(data)),
+            RawPixels::Rgba(data) => Ok(Pixels::Rgba(Arc::new(data))),
+            RawPixels::Grayscale(data) => Ok(Pixels::Grayscale(Arc::new(data))),
             RawPixels::Indexed(data) => {
                 if let Some(palette) = palette {
                     palette.validate_indexed_pixels(&data)?;
@@ -167,7 +189,7 @@ impl RawPixels {
                             palette,
                             transparent_color_index: *transparent_color_index,
                             layer_is_background,
-                            data,
+                            data: Arc::new(data),
                         })
                     } else {
                         Err(AsepriteParseError::InvalidInput(format!(
@@ -176,9 +198,7 @@ impl RawPixels {
                         )))
                     }
                 } else {
-                    Err(AsepriteParseError::InvalidInput(
-                        "Indexed colors without a palette".to_string(),
-                    ))
+                    Err(AsepriteParseError::MissingPalette)
                 }
             }
         }
@@ -186,9 +206,66 @@ impl RawPixels {
 }
 
 impl Pixels {
+    // Converts RGBA pixels to the pixel format of a file. For indexed files,
+    // transparent pixels become the transparent color and all others the
+    // palette color with the same RGBA value, or the nearest color if there
+    // is none. Outside of background layers, the transparent color is not
+    // used for other pixels, since it would hide them.
+    pub(crate) fn from_rgba(
+        pixels: Vec<Rgba<u8>>,
+        pixel_format: PixelFormat,
+        palette: Option<&Arc<ColorPalette>>,
+        layer_is_background: bool,
+    ) -> Result<Self> {
+        match pixel_format {
+            PixelFormat::Rgba => Ok(Pixels::Rgba(Arc::new(pixels))),
+            PixelFormat::Grayscale => Ok(Pixels::Grayscale(Arc::new(
+                pixels.into_iter().map(Grayscale::from_rgba).collect(),
+            ))),
+            PixelFormat::Indexed {
+                transparent_color_index,
+            } => {
+                let palette = palette.ok_or(AsepriteParseError::MissingPalette)?;
+                let mut exact: HashMap<[u8; 4], u8> = HashMap::new();
+                for entry in palette.iter().rev() {
+                    if layer_is_background || entry.id() != transparent_color_index as u32 {
+                        exact.insert(entry.raw_rgba8(), entry.id() as u8);
+                    }
+                }
+                let data = pixels
+                    .into_iter()
+                    .map(|px| {
+                        if px.0[3] == 0 && !layer_is_background {
+                            return transparent_color_index;
+                        }
+                        if let Some(&index) = exact.get(&px.0) {
+                            return index;
+                        }
+                        let nearest = if layer_is_background {
+                            palette.nearest(px, ColorDistance::Rgb)
+                        } else {
+                            palette.nearest_except(
+                                px,
+                                ColorDistance::Rgb,
+                                transparent_color_index as u32,
+                            )
+                        };
+                        nearest.map_or(transparent_color_index, |index| index as u8)
+                    })
+                    .collect();
+                Ok(Pixels::Indexed {
+                    palette: palette.clone(),
+                    transparent_color_index,
+                    layer_is_background,
+                    data: Arc::new(data),
+                })
+            }
+        }
+    }
+
     // Returns a Borrowed Cow if the Pixels struct already contains Rgba pixels.
     // Otherwise clones them to create an Owned Cow.
-    pub(crate) fn clone_as_image_rgba(&self) -> Cow<Vec<image::Rgba<u8>>> {
+    pub(crate) fn clone_as_image_rgba(&self) -> Cow<'_, [image::Rgba<u8>]> {
         match self {
             Pixels::Rgba(rgba) => Cow::Borrowed(rgba),
             Pixels::Grayscale(grayscale) => {
@@ -212,4 +289,57 @@ impl Pixels {
             }
         }
     }
+
+    // Returns true if all pixels in the given range have zero alpha. Avoids
+    // converting the pixels to RGBA first.
+    pub(crate) fn is_transparent(&self, range: Range<usize>) -> bool {
+        match self {
+            Pixels::Rgba(rgba) => rgba[range].iter().all(|px| px.0[3] == 0),
+            Pixels::Grayscale(grayscale) => grayscale[range].iter().all(|px| px.alpha == 0),
+            Pixels::Indexed {
+                palette,
+                transparent_color_index,
+                layer_is_background,
+                data,
+            } => data[range].iter().all(|&index| {
+                Indexed(index)
+                    .as_rgba(palette, *transparent_color_index, *layer_is_background)
+                    .filter(|px| px.0[3] != 0)
+                    .is_none()
+            }),
+        }
+    }
+}
+
+// Detects pixel buffers with identical content, so that only one copy of them
+// is kept in memory. Artists often duplicate frames instead of linking them.
+#[derive(Default)]
+pub(crate) struct PixelInterner {
+    rgba: Buffers<Rgba<u8>>,
+    grayscale: Buffers<Grayscale>,
+    indexed: Buffers<u8>,
+}
+
+// Buffers by the hash of their content.
+type Buffers<T> = HashMap<u64, Vec<Arc<Vec<T>>>>;
+
+impl PixelInterner {
+    // Replaces the buffer of `pixels` with an identical one seen before, if any.
+    pub(crate) fn intern(&mut self, pixels: &mut Pixels) {
+        match pixels {
+            Pixels::Rgba(data) => intern_buffer(&mut self.rgba, data),
+            Pixels::Grayscale(data) => intern_buffer(&mut self.grayscale, data),
+            Pixels::Indexed { data, .. } => intern_buffer(&mut self.indexed, data),
+        }
+    }
+}
+
+fn intern_buffer<T: Hash + Eq>(buffers: &mut Buffers<T>, data: &mut Arc<Vec<T>>) {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let candidates = buffers.entry(hasher.finish()).or_default();
+    match candidates.iter().find(|candidate| **candidate == *data) {
+        Some(existing) => *data = existing.clone(),
+        None => candidates.push(data.clone()),
+    }
 }
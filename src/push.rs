@@ -0,0 +1,277 @@
+use crate::{
+    parse::{
+        self, check_chunk_bytes, read_frame_header, read_header, read_raw_chunk, FrameHeader,
+        Header, ParseInfo, FRAME_HEADER_SIZE, HEADER_SIZE,
+    },
+    reader::AseReader,
+    AsepriteFile, AsepriteParseError, Extensions, ParseOptions, ParseProgress, Result,
+};
+use std::ops::Range;
+
+/// What a [PushParser] found in its input so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseEvent {
+    /// The buffered input ends in the middle of a header or chunk. Call
+    /// [feed](PushParser::feed) with more data.
+    NeedMoreData,
+    /// The file header was read.
+    Header {
+        /// Canvas width in pixels.
+        width: u32,
+        /// Canvas height in pixels.
+        height: u32,
+        /// Number of frames in the file.
+        num_frames: u32,
+    },
+    /// A frame was read completely.
+    Frame(ParseProgress),
+    /// All frames were read. Call [finish](PushParser::finish) to get the file.
+    Done,
+}
+
+/// A parser that is fed the file contents piece by piece instead of reading
+/// them from an input.
+///
+/// The parser does not perform any IO itself, so it works with any transport,
+/// e.g., network streams, archive readers or async runtimes. Input is dropped
+/// as soon as it has been parsed, so the parser only buffers the part of the
+/// input that does not form a complete header or chunk yet, not the whole file.
+///
+/// [feed](Self::feed) adds data and returns the next event. A single call may
+/// complete several events, so keep calling `feed` with an empty slice until
+/// it returns [ParseEvent::NeedMoreData] or [ParseEvent::Done].
+///
+/// ```
+/// # use asefile::{AsepriteFile, ParseEvent, PushParser};
+/// # let bytes = std::fs::read("./tests/data/layers_and_tags.aseprite").unwrap();
+/// let mut parser = PushParser::new();
+/// 'outer: for packet in bytes.chunks(100) {
+///     let mut event = parser.feed(packet).unwrap();
+///     loop {
+///         match event {
+///             ParseEvent::NeedMoreData => break,
+///             ParseEvent::Done => break 'outer,
+///             ParseEvent::Frame(progress) => println!("{:.0}%", 100.0 * progress.fraction()),
+///             ParseEvent::Header { .. } => {}
+///         }
+///         event = parser.feed(&[]).unwrap();
+///     }
+/// }
+/// let ase = parser.finish().unwrap();
+/// assert_eq!(ase.num_frames(), 4);
+/// ```
+///
+/// Once `feed` returns an error, the parser should not be used any more.
+pub struct PushParser<'a> {
+    options: ParseOptions<'a>,
+    buffer: Vec<u8>,
+    // Start of the input that has not been consumed yet.
+    pos: usize,
+    state: State,
+    file: Option<(Header, ParseInfo)>,
+    extensions: Extensions,
+    progress: ParseProgress,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Header,
+    FrameHeader {
+        frame: u16,
+    },
+    Chunks {
+        frame: u16,
+        frame_size: u32,
+        chunks_left: u32,
+        bytes_available: i64,
+    },
+    Done,
+}
+
+impl Default for PushParser<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> PushParser<'a> {
+    /// A parser with default options.
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::new())
+    }
+
+    /// A parser with custom behavior. See [ParseOptions].
+    ///
+    /// The [progress](ParseOptions::progress) callback is called along with
+    /// the corresponding [ParseEvent]s.
+    pub fn with_options(options: ParseOptions<'a>) -> Self {
+        PushParser {
+            options,
+            buffer: Vec::new(),
+            pos: 0,
+            state: State::Header,
+            file: None,
+            extensions: Extensions::new(),
+            progress: ParseProgress {
+                bytes_read: 0,
+                total_bytes: 0,
+                frames_parsed: 0,
+                total_frames: 0,
+            },
+        }
+    }
+
+    /// Append `data` to the input and parse it up to the next event.
+    pub fn feed(&mut self, data: &[u8]) -> Result<ParseEvent> {
+        self.buffer.extend_from_slice(data);
+        let event = self.next_event();
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+        event
+    }
+
+    /// Returns `true` once all frames have been read.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Build the file from the parsed frames. Resolves external files and
+    /// validates the file, like [AsepriteFile::read_with_options].
+    ///
+    /// Fails if the parser has not returned [ParseEvent::Done] yet.
+    pub fn finish(mut self) -> Result<AsepriteFile> {
+        match (self.state, self.file.take()) {
+            (State::Done, Some((header, parse_info))) => {
+                parse::finish(&header, parse_info, self.extensions, &mut self.options)
+            }
+            _ => Err(AsepriteParseError::InvalidInput(
+                "Unexpected end of input".to_owned(),
+            )),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<ParseEvent> {
+        // Headers and chunks that do not produce an event are parsed in a
+        // loop until there is something to report.
+        loop {
+            match self.state {
+                State::Header => {
+                    let Some(range) = self.take(HEADER_SIZE as usize) else {
+                        return Ok(ParseEvent::NeedMoreData);
+                    };
+                    let header = read_header(&mut AseReader::new(&self.buffer[range]))?;
+                    let event = ParseEvent::Header {
+                        width: header.width as u32,
+                        height: header.height as u32,
+                        num_frames: header.num_frames as u32,
+                    };
+                    self.progress = ParseProgress {
+                        bytes_read: HEADER_SIZE,
+                        total_bytes: header.file_size as u64,
+                        frames_parsed: 0,
+                        total_frames: header.num_frames as u32,
+                    };
+                    let parse_info =
+                        ParseInfo::new(header.num_frames, header.default_frame_time, header.flags);
+                    self.file = Some((header, parse_info));
+                    self.state = State::FrameHeader { frame: 0 };
+                    self.report_progress();
+                    return Ok(event);
+                }
+                State::FrameHeader { frame } => {
+                    let (header, _) = self.file.as_ref().expect("Header was read");
+                    if frame == header.num_frames {
+                        self.state = State::Done;
+                        return Ok(ParseEvent::Done);
+                    }
+                    let Some(range) = self.take(FRAME_HEADER_SIZE as usize) else {
+                        return Ok(ParseEvent::NeedMoreData);
+                    };
+                    let FrameHeader {
+                        num_bytes,
+                        num_chunks,
+                        duration_ms,
+                    } = read_frame_header(&mut AseReader::new(&self.buffer[range]))?;
+                    let (_, parse_info) = self.file.as_mut().expect("Header was read");
+                    parse_info.frame_times[frame as usize] = duration_ms;
+                    self.state = State::Chunks {
+                        frame,
+                        frame_size: num_bytes,
+                        chunks_left: num_chunks,
+                        bytes_available: num_bytes as i64 - FRAME_HEADER_SIZE,
+                    };
+                    continue;
+                }
+                State::Chunks {
+                    frame,
+                    frame_size,
+                    chunks_left: 0,
+                    ..
+                } => {
+                    self.progress.bytes_read += frame_size as u64;
+                    self.progress.frames_parsed += 1;
+                    self.state = State::FrameHeader { frame: frame + 1 };
+                    self.report_progress();
+                    return Ok(ParseEvent::Frame(self.progress));
+                }
+                State::Chunks {
+                    frame,
+                    frame_size,
+                    chunks_left,
+                    mut bytes_available,
+                } => {
+                    let input = &self.buffer[self.pos..];
+                    if input.len() < 4 {
+                        return Ok(ParseEvent::NeedMoreData);
+                    }
+                    let chunk_size = u32::from_le_bytes([input[0], input[1], input[2], input[3]]);
+                    // Fail early instead of waiting for data that is never going
+                    // to be part of this frame.
+                    check_chunk_bytes(chunk_size, bytes_available)?;
+                    let Some(range) = self.take(chunk_size as usize) else {
+                        return Ok(ParseEvent::NeedMoreData);
+                    };
+                    let (chunk_type, data) = read_raw_chunk(
+                        &mut bytes_available,
+                        &mut AseReader::new(&self.buffer[range]),
+                    )?;
+                    let (header, parse_info) = self.file.as_mut().expect("Header was read");
+                    parse::parse_chunk(
+                        frame,
+                        chunk_type,
+                        data,
+                        header.pixel_format,
+                        parse_info,
+                        &mut self.options.chunk_handlers,
+                        &mut self.extensions,
+                    )?;
+                    self.state = State::Chunks {
+                        frame,
+                        frame_size,
+                        chunks_left: chunks_left - 1,
+                        bytes_available,
+                    };
+                    continue;
+                }
+                State::Done => return Ok(ParseEvent::Done),
+            }
+        }
+    }
+
+    // Consumes the next `count` bytes if they are available and returns
+    // their position in the buffer.
+    fn take(&mut self, count: usize) -> Option<Range<usize>> {
+        let range = self.pos..self.pos.checked_add(count)?;
+        if range.end > self.buffer.len() {
+            return None;
+        }
+        self.pos = range.end;
+        Some(range)
+    }
+
+    fn report_progress(&mut self) {
+        if let Some(callback) = self.options.progress.as_deref_mut() {
+            callback(self.progress);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Python bindings. (Requires feature `python`.)
+//!
+//! Builds a Python extension module named `asefile` using
+//! [PyO3](https://pyo3.rs). The easiest way to build and install it is
+//! [maturin](https://www.maturin.rs), which picks up the settings in
+//! `pyproject.toml`:
+//!
+//! ```text
+//! maturin develop --release
+//! ```
+//!
+//! ```python
+//! import asefile
+//!
+//! ase = asefile.AsepriteFile("player.aseprite")
+//! print(ase.width, ase.height, ase.num_frames)
+//! for tag in ase.tags():
+//!     print(tag.name, tag.from_frame, tag.to_frame)
+//! rgba = ase.frame_image(0)  # bytes, 4 per pixel
+//! ```
+
+use std::path::PathBuf;
+
+use pyo3::{
+    exceptions::{PyIOError, PyIndexError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+use crate::{AnimationDirection, AsepriteFile, AsepriteParseError, BlendMode, LayerType};
+
+fn to_py_err(error: AsepriteParseError) -> PyErr {
+    match error {
+        AsepriteParseError::IoError(error) => PyIOError::new_err(error.to_string()),
+        error => PyValueError::new_err(error.to_string()),
+    }
+}
+
+pub(crate) fn layer_kind(layer_type: &LayerType) -> &'static str {
+    match layer_type {
+        LayerType::Image => "image",
+        LayerType::Group => "group",
+        LayerType::Tilemap(_) => "tilemap",
+    }
+}
+
+pub(crate) fn blend_mode_name(blend_mode: BlendMode) -> String {
+    blend_mode.to_string()
+}
+
+pub(crate) fn direction_name(direction: AnimationDirection) -> &'static str {
+    match direction {
+        AnimationDirection::Forward => "forward",
+        AnimationDirection::Reverse => "reverse",
+        AnimationDirection::PingPong => "pingpong",
+    }
+}
+
+/// An Aseprite file.
+#[pyclass(name = "AsepriteFile", module = "asefile", frozen)]
+pub struct PyAsepriteFile {
+    file: AsepriteFile,
+}
+
+impl PyAsepriteFile {
+    fn check_frame(&self, frame: u32) -> PyResult<()> {
+        if frame < self.file.num_frames() {
+            Ok(())
+        } else {
+            Err(PyIndexError::new_err("frame index out of range"))
+        }
+    }
+}
+
+#[pymethods]
+impl PyAsepriteFile {
+    /// Load the file at the given path.
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = AsepriteFile::read_file(&path).map_err(to_py_err)?;
+        Ok(PyAsepriteFile { file })
+    }
+
+    /// Parse a file from its contents.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        let file = AsepriteFile::read(data).map_err(to_py_err)?;
+        Ok(PyAsepriteFile { file })
+    }
+
+    /// Canvas width in pixels.
+    #[getter]
+    fn width(&self) -> usize {
+        self.file.width()
+    }
+
+    /// Canvas height in pixels.
+    #[getter]
+    fn height(&self) -> usize {
+        self.file.height()
+    }
+
+    /// Number of frames.
+    #[getter]
+    fn num_frames(&self) -> u32 {
+        self.file.num_frames()
+    }
+
+    /// Number of layers.
+    #[getter]
+    fn num_layers(&self) -> u32 {
+        self.file.num_layers()
+    }
+
+    /// Duration of the given frame in milliseconds.
+    fn frame_duration(&self, frame: u32) -> PyResult<u32> {
+        self.check_frame(frame)?;
+        Ok(self.file.frame(frame).duration_ms())
+    }
+
+    /// The composited image of a frame as RGBA bytes, row by row.
+    fn frame_image<'py>(&self, py: Python<'py>, frame: u32) -> PyResult<Bound<'py, PyBytes>> {
+        self.check_frame(frame)?;
+        let image = self.file.frame(frame).image();
+        Ok(PyBytes::new(py, image.as_raw()))
+    }
+
+    /// The image of a single layer in a frame as RGBA bytes, row by row.
+    fn cel_image<'py>(
+        &self,
+        py: Python<'py>,
+        frame: u32,
+        layer: u32,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        self.check_frame(frame)?;
+        if layer >= self.file.num_layers() {
+            return Err(PyIndexError::new_err("layer index out of range"));
+        }
+        let image = self.file.cel(frame, layer).image();
+        Ok(PyBytes::new(py, image.as_raw()))
+    }
+
+    /// All layers, from bottom to top.
+    fn layers(&self) -> Vec<PyLayer> {
+        self.file
+            .layers()
+            .map(|layer| PyLayer {
+                id: layer.id(),
+                name: layer.name().to_owned(),
+                kind: layer_kind(&layer.layer_type()),
+                parent: layer.parent().map(|parent| parent.id()),
+                visible: layer.is_visible(),
+                opacity: layer.opacity(),
+                blend_mode: blend_mode_name(layer.blend_mode()),
+            })
+            .collect()
+    }
+
+    /// All tags, in the order they appear in the file.
+    fn tags(&self) -> Vec<PyTag> {
+        self.file
+            .tags()
+            .map(|tag| PyTag {
+                name: tag.name().to_owned(),
+                from_frame: tag.from_frame(),
+                to_frame: tag.to_frame(),
+                direction: direction_name(tag.animation_direction()),
+                repeat: tag.repeat().map(|repeat| repeat.get()),
+            })
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AsepriteFile(width={}, height={}, frames={}, layers={})",
+            self.file.width(),
+            self.file.height(),
+            self.file.num_frames(),
+            self.file.num_layers()
+        )
+    }
+}
+
+/// A layer of an [AsepriteFile](PyAsepriteFile).
+#[pyclass(name = "Layer", module = "asefile", frozen, get_all)]
+pub struct PyLayer {
+    id: u32,
+    name: String,
+    /// `"image"`, `"group"` or `"tilemap"`.
+    kind: &'static str,
+    parent: Option<u32>,
+    visible: bool,
+    opacity: u8,
+    /// Aseprite's name of the blend mode, e.g., `"normal"` or `"hsl_hue"`.
+    blend_mode: String,
+}
+
+/// A tag of an [AsepriteFile](PyAsepriteFile).
+#[pyclass(name = "Tag", module = "asefile", frozen, get_all)]
+pub struct PyTag {
+    name: String,
+    from_frame: u32,
+    to_frame: u32,
+    /// `"forward"`, `"reverse"` or `"pingpong"`.
+    direction: &'static str,
+    repeat: Option<u32>,
+}
+
+#[pymodule]
+fn asefile(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyAsepriteFile>()?;
+    module.add_class::<PyLayer>()?;
+    module.add_class::<PyTag>()?;
+    Ok(())
+}
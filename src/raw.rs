@@ -0,0 +1,202 @@
+//! Low-level access to the chunks of a file.
+//!
+//! An Aseprite file consists of a header followed by a list of frames, each
+//! of which contains a list of chunks. [chunks] returns the chunks of a file
+//! in the order they are stored, without interpreting them. This is useful
+//! for inspecting files, collecting statistics, or prototyping support for
+//! chunk types that this crate does not understand yet.
+//!
+//! See the [file format specification](https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md)
+//! for the layout of each chunk type.
+//!
+//! ```
+//! # use std::fs::File;
+//! use asefile::raw::{self, RawChunk};
+//!
+//! let input = File::open("./tests/data/layers_and_tags.aseprite").unwrap();
+//! let mut num_cels = 0;
+//! for chunk in raw::chunks(input).unwrap() {
+//!     let chunk = chunk.unwrap();
+//!     if chunk.chunk_type == RawChunk::CEL {
+//!         num_cels += 1;
+//!     }
+//! }
+//! assert_eq!(num_cels, 14);
+//! ```
+//!
+//! [AseReader] decodes the data types used by the specification, so chunk
+//! data can be interpreted the same way this crate does it:
+//!
+//! ```
+//! # use std::fs::File;
+//! use asefile::raw::{self, AseReader, RawChunk};
+//!
+//! let input = File::open("./tests/data/layers_and_tags.aseprite").unwrap();
+//! for chunk in raw::chunks(input).unwrap() {
+//!     let chunk = chunk.unwrap();
+//!     if chunk.chunk_type == RawChunk::LAYER {
+//!         let mut reader = AseReader::new(&chunk.data);
+//!         let _flags = reader.word().unwrap();
+//!         let layer_type = reader.word().unwrap();
+//!         let _child_level = reader.word().unwrap();
+//!         reader.skip_reserved(4).unwrap(); // default width and height
+//!         let _blend_mode = reader.word().unwrap();
+//!         let _opacity = reader.byte().unwrap();
+//!         reader.skip_reserved(3).unwrap();
+//!         let name = reader.string().unwrap();
+//!         println!("layer {:?} of type {}", name, layer_type);
+//!     }
+//! }
+//! ```
+
+use std::io::Read;
+
+use crate::{
+    parse::{self, FrameHeader},
+    AsepriteParseError, Result,
+};
+
+pub use crate::reader::AseReader;
+
+/// A chunk as stored in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawChunk {
+    /// Index of the frame that contains the chunk.
+    pub frame: u32,
+    /// The chunk type ID, e.g., [RawChunk::LAYER].
+    pub chunk_type: u16,
+    /// The chunk data, not including the 6 byte chunk header (size and
+    /// type).
+    pub data: Vec<u8>,
+}
+
+#[allow(missing_docs)]
+impl RawChunk {
+    pub const OLD_PALETTE_04: u16 = 0x0004;
+    pub const OLD_PALETTE_11: u16 = 0x0011;
+    pub const LAYER: u16 = 0x2004;
+    pub const CEL: u16 = 0x2005;
+    pub const CEL_EXTRA: u16 = 0x2006;
+    pub const COLOR_PROFILE: u16 = 0x2007;
+    pub const EXTERNAL_FILES: u16 = 0x2008;
+    pub const MASK: u16 = 0x2016;
+    pub const PATH: u16 = 0x2017;
+    pub const TAGS: u16 = 0x2018;
+    pub const PALETTE: u16 = 0x2019;
+    pub const USER_DATA: u16 = 0x2020;
+    pub const SLICE: u16 = 0x2022;
+    pub const TILESET: u16 = 0x2023;
+}
+
+impl RawChunk {
+    /// Human readable name of the chunk type, or `None` if the type is not
+    /// part of the file format specification.
+    pub fn type_name(&self) -> Option<&'static str> {
+        chunk_type_name(self.chunk_type)
+    }
+}
+
+/// Human readable name of a chunk type, e.g., `"Layer"` for
+/// [RawChunk::LAYER], or `None` if the type is not part of the file format
+/// specification.
+pub fn chunk_type_name(chunk_type: u16) -> Option<&'static str> {
+    let name = match chunk_type {
+        RawChunk::OLD_PALETTE_04 | RawChunk::OLD_PALETTE_11 => "Old palette",
+        RawChunk::LAYER => "Layer",
+        RawChunk::CEL => "Cel",
+        RawChunk::CEL_EXTRA => "Cel extra",
+        RawChunk::COLOR_PROFILE => "Color profile",
+        RawChunk::EXTERNAL_FILES => "External files",
+        RawChunk::MASK => "Mask",
+        RawChunk::PATH => "Path",
+        RawChunk::TAGS => "Tags",
+        RawChunk::PALETTE => "Palette",
+        RawChunk::USER_DATA => "User data",
+        RawChunk::SLICE => "Slice",
+        RawChunk::TILESET => "Tileset",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Read the file header and return an iterator over all chunks of the file.
+///
+/// Only the header and the frame and chunk boundaries are checked, so this
+/// also works for files that [AsepriteFile::read](crate::AsepriteFile::read)
+/// rejects, e.g., because they contain unknown chunk types. The iterator
+/// stops after the first error.
+pub fn chunks<R: Read>(input: R) -> Result<Chunks<R>> {
+    let mut reader = AseReader::with(input);
+    let _size = reader.dword()?;
+    let magic_number = reader.word()?;
+    if magic_number != 0xA5E0 {
+        return Err(AsepriteParseError::BadMagicNumber {
+            expected: 0xA5E0,
+            found: magic_number,
+        });
+    }
+    let num_frames = reader.word()?;
+    reader.skip_reserved(120)?;
+    Ok(Chunks {
+        reader,
+        num_frames,
+        next_frame: 0,
+        chunks_left: 0,
+        bytes_available: 0,
+        done: false,
+    })
+}
+
+/// Iterator over the chunks of a file. Created by [chunks].
+pub struct Chunks<R: Read> {
+    reader: AseReader<R>,
+    num_frames: u16,
+    next_frame: u16,
+    chunks_left: u32,
+    bytes_available: i64,
+    done: bool,
+}
+
+impl<R: Read> Chunks<R> {
+    /// Number of frames according to the file header.
+    pub fn num_frames(&self) -> u32 {
+        self.num_frames as u32
+    }
+
+    fn next_chunk(&mut self) -> Result<Option<RawChunk>> {
+        while self.chunks_left == 0 {
+            if self.next_frame == self.num_frames {
+                return Ok(None);
+            }
+            let FrameHeader {
+                num_bytes,
+                num_chunks,
+                ..
+            } = parse::read_frame_header(&mut self.reader)?;
+            self.next_frame += 1;
+            self.chunks_left = num_chunks;
+            self.bytes_available = num_bytes as i64 - parse::FRAME_HEADER_SIZE;
+        }
+        let (chunk_type, data) =
+            parse::read_raw_chunk(&mut self.bytes_available, &mut self.reader)?;
+        self.chunks_left -= 1;
+        Ok(Some(RawChunk {
+            frame: self.next_frame as u32 - 1,
+            chunk_type,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for Chunks<R> {
+    type Item = Result<RawChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_chunk().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
@@ -7,12 +7,19 @@ fn to_ase(e: std::io::Error) -> AsepriteParseError {
     e.into()
 }
 
-pub(crate) struct AseReader<T: Read> {
+/// Reads the data types of the Aseprite file format specification, e.g.,
+/// `WORD` or `STRING`. All numbers are little endian.
+///
+/// Errors are reported as [AsepriteParseError], so custom chunk parsers can
+/// use the `?` operator in handlers (see
+/// [ParseOptions::chunk_handler](crate::ParseOptions::chunk_handler)).
+pub struct AseReader<T: Read> {
     input: T,
 }
 
 impl AseReader<Cursor<&[u8]>> {
-    pub(crate) fn new(data: &[u8]) -> AseReader<Cursor<&[u8]>> {
+    /// Read from a byte slice, e.g., the data of a [RawChunk](crate::raw::RawChunk).
+    pub fn new(data: &[u8]) -> AseReader<Cursor<&[u8]>> {
         let input = Cursor::new(data);
         AseReader { input }
     }
@@ -22,31 +29,58 @@ impl<T: Read> AseReader<T>
 where
     T: Read,
 {
-    pub(crate) fn with(input: T) -> Self {
+    /// Read from any [Read] implementation.
+    pub fn with(input: T) -> Self {
         Self { input }
     }
 
-    pub(crate) fn byte(&mut self) -> Result<u8> {
+    /// Read a `BYTE` (8-bit unsigned integer).
+    pub fn byte(&mut self) -> Result<u8> {
         self.input.read_u8().map_err(to_ase)
     }
 
-    pub(crate) fn word(&mut self) -> Result<u16> {
+    /// Read a `WORD` (16-bit unsigned integer).
+    pub fn word(&mut self) -> Result<u16> {
         self.input.read_u16::<LittleEndian>().map_err(to_ase)
     }
 
-    pub(crate) fn short(&mut self) -> Result<i16> {
+    /// Read a `SHORT` (16-bit signed integer).
+    pub fn short(&mut self) -> Result<i16> {
         self.input.read_i16::<LittleEndian>().map_err(to_ase)
     }
 
-    pub(crate) fn dword(&mut self) -> Result<u32> {
+    /// Read a `DWORD` (32-bit unsigned integer).
+    pub fn dword(&mut self) -> Result<u32> {
         self.input.read_u32::<LittleEndian>().map_err(to_ase)
     }
 
-    pub(crate) fn long(&mut self) -> Result<i32> {
+    /// Read a `LONG` (32-bit signed integer).
+    pub fn long(&mut self) -> Result<i32> {
         self.input.read_i32::<LittleEndian>().map_err(to_ase)
     }
 
-    pub(crate) fn string(&mut self) -> Result<String> {
+    /// Read a `QWORD` (64-bit unsigned integer).
+    pub fn qword(&mut self) -> Result<u64> {
+        self.input.read_u64::<LittleEndian>().map_err(to_ase)
+    }
+
+    /// Read a `LONG64` (64-bit signed integer).
+    pub fn long64(&mut self) -> Result<i64> {
+        self.input.read_i64::<LittleEndian>().map_err(to_ase)
+    }
+
+    /// Read a `FLOAT` (32-bit floating point number).
+    pub fn float(&mut self) -> Result<f32> {
+        self.input.read_f32::<LittleEndian>().map_err(to_ase)
+    }
+
+    /// Read a `DOUBLE` (64-bit floating point number).
+    pub fn double(&mut self) -> Result<f64> {
+        self.input.read_f64::<LittleEndian>().map_err(to_ase)
+    }
+
+    /// Read a `STRING`: a `WORD` length followed by that many bytes of UTF-8.
+    pub fn string(&mut self) -> Result<String> {
         let str_len = self.input.read_u16::<LittleEndian>()?;
         let mut str_bytes = vec![0_u8; str_len as usize];
         self.input.read_exact(&mut str_bytes)?;
@@ -54,18 +88,32 @@ where
         Ok(s)
     }
 
-    pub(crate) fn read_exact(&mut self, buffer: &mut [u8]) -> Result<()> {
+    /// Fill `buffer` with the next bytes of the input.
+    pub fn read_exact(&mut self, buffer: &mut [u8]) -> Result<()> {
         self.input.read_exact(buffer).map_err(to_ase)
     }
 
-    pub(crate) fn skip_reserved(&mut self, count: usize) -> Result<()> {
+    /// Skip `count` bytes, e.g., reserved fields.
+    pub fn skip_reserved(&mut self, count: usize) -> Result<()> {
         let mut ignored = vec![0_u8; count];
         self.input.read_exact(&mut ignored).map_err(to_ase)
     }
 
-    pub(crate) fn take_bytes(self, limit: usize) -> Result<Vec<u8>> {
-        let mut output = Vec::with_capacity(limit);
-        self.input.take(limit as u64).read_to_end(&mut output)?;
+    /// Read exactly `limit` bytes. Fails if the input ends before that.
+    pub fn take_bytes(mut self, limit: usize) -> Result<Vec<u8>> {
+        self.bytes(limit)
+    }
+
+    /// Read exactly `limit` bytes. Fails if the input ends before that.
+    ///
+    /// The buffer only grows as data is read, so a corrupt length does not
+    /// allocate more memory than the input contains.
+    pub fn bytes(&mut self, limit: usize) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.input
+            .by_ref()
+            .take(limit as u64)
+            .read_to_end(&mut output)?;
         if output.len() != limit {
             Err(AsepriteParseError::InvalidInput(format!(
                 "Invalid data size. Expected: {}, Actual: {}",
@@ -77,7 +125,10 @@ where
         }
     }
 
-    pub(crate) fn unzip(self, expected_output_size: usize) -> Result<Vec<u8>> {
+    /// Decompress the remaining input with zlib, as used by compressed
+    /// cels and tilesets. `expected_output_size` is only used to preallocate
+    /// the output.
+    pub fn unzip(self, expected_output_size: usize) -> Result<Vec<u8>> {
         let mut decoder = ZlibDecoder::new(self.input);
         let mut buffer = Vec::with_capacity(expected_output_size);
         decoder.read_to_end(&mut buffer)?;
@@ -0,0 +1,476 @@
+//! Combine the frames of a file into a single sprite sheet image.
+//!
+//! This mirrors the "Export Sprite Sheet" dialog in Aseprite. Frames are
+//! always listed in order of their frame index, regardless of where the
+//! layout places them in the image.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::sheet::{SheetLayout, SheetOptions, SpriteSheet};
+//!
+//! let options = SheetOptions {
+//!     layout: SheetLayout::Grid { columns: 3 },
+//!     ..Default::default()
+//! };
+//! let sheet = SpriteSheet::new(&ase, &options);
+//! assert_eq!(sheet.frames().len(), 4);
+//! assert_eq!(sheet.image().dimensions(), (48, 32));
+//! ```
+
+use image::RgbaImage;
+
+use crate::{AsepriteFile, Point, Rect, Tileset, TilesetId};
+
+/// Describes how frames are arranged in a [SpriteSheet].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetLayout {
+    /// Place frames so that the sheet is roughly square. Frames are placed in
+    /// rows, tallest frames first.
+    Packed,
+    /// All frames in a single row, from left to right.
+    HorizontalStrip,
+    /// All frames in a single column, from top to bottom.
+    VerticalStrip,
+    /// A fixed number of columns. Frames are placed left to right, top to
+    /// bottom. Called "By Rows" in Aseprite.
+    #[allow(missing_docs)]
+    Grid { columns: u32 },
+}
+
+/// Configuration for building a [SpriteSheet].
+#[derive(Debug, Clone)]
+pub struct SheetOptions {
+    /// How frames are arranged. Default: [SheetLayout::Packed].
+    pub layout: SheetLayout,
+    /// Empty pixels between two adjacent frames. Default: `0`.
+    pub spacing: u32,
+    /// Empty pixels around the outside of the sheet. Default: `0`.
+    pub border: u32,
+    /// Record the bounds of every slice in every frame as a named
+    /// [SheetRegion]. Default: `false`.
+    pub include_slices: bool,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        Self {
+            layout: SheetLayout::Packed,
+            spacing: 0,
+            border: 0,
+            include_slices: false,
+        }
+    }
+}
+
+/// Location of a single frame inside a [SpriteSheet].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetFrame {
+    /// Frame index in the source file.
+    pub frame: u32,
+    /// Left edge of the frame in the sheet image.
+    pub x: u32,
+    /// Top edge of the frame in the sheet image.
+    pub y: u32,
+    /// Width of the frame in pixels.
+    pub width: u32,
+    /// Height of the frame in pixels.
+    pub height: u32,
+    /// Frame duration in milliseconds.
+    pub duration: u32,
+}
+
+impl SheetFrame {
+    /// Location of the frame in the sheet image.
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x as i32, self.y as i32, self.width, self.height)
+    }
+}
+
+/// Location of a slice inside a [SpriteSheet].
+///
+/// Only created if [SheetOptions::include_slices] is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetRegion {
+    /// Name of the slice.
+    pub name: String,
+    /// Frame index in the source file.
+    pub frame: u32,
+    /// Left edge of the region in the sheet image.
+    pub x: u32,
+    /// Top edge of the region in the sheet image.
+    pub y: u32,
+    /// Width of the region in pixels.
+    pub width: u32,
+    /// Height of the region in pixels.
+    pub height: u32,
+    /// Pivot of the slice, relative to the top left of the region.
+    pub pivot: Option<Point>,
+}
+
+impl SheetRegion {
+    /// Location of the region in the sheet image.
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x as i32, self.y as i32, self.width, self.height)
+    }
+}
+
+/// A single image containing several frames, plus the location of each frame.
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    image: RgbaImage,
+    frames: Vec<SheetFrame>,
+    regions: Vec<SheetRegion>,
+}
+
+impl SpriteSheet {
+    /// Build a sheet that contains every frame of the file.
+    pub fn new(file: &AsepriteFile, options: &SheetOptions) -> Self {
+        Self::from_frames(file, 0..file.num_frames(), options)
+    }
+
+    /// Build a sheet from the given frames.
+    ///
+    /// Frames are sorted by index and duplicates are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any frame index is not less than `num_frames`.
+    pub fn from_frames<I>(file: &AsepriteFile, frames: I, options: &SheetOptions) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let mut indices: Vec<u32> = frames.into_iter().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let images: Vec<(u32, RgbaImage)> = indices
+            .into_iter()
+            .map(|index| (index, file.frame(index).image()))
+            .collect();
+        let sizes: Vec<(u32, u32)> = images.iter().map(|(_, img)| img.dimensions()).collect();
+        let (positions, (width, height)) = layout(&sizes, options);
+
+        let mut image = RgbaImage::new(width, height);
+        let mut frames = Vec::with_capacity(images.len());
+        let mut regions = Vec::new();
+        for ((index, frame_image), (x, y)) in images.into_iter().zip(positions) {
+            image::imageops::replace(&mut image, &frame_image, x as i64, y as i64);
+            if options.include_slices {
+                regions.extend(slice_regions(file, index, (x, y), frame_image.dimensions()));
+            }
+            frames.push(SheetFrame {
+                frame: index,
+                x,
+                y,
+                width: frame_image.width(),
+                height: frame_image.height(),
+                duration: file.frame(index).duration_ms(),
+            });
+        }
+        SpriteSheet {
+            image,
+            frames,
+            regions,
+        }
+    }
+
+    /// The combined image.
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Consume the sheet and return the combined image.
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Location of each frame, sorted by frame index.
+    pub fn frames(&self) -> &[SheetFrame] {
+        &self.frames
+    }
+
+    /// Location of the given frame, if it is part of this sheet.
+    pub fn frame(&self, index: u32) -> Option<&SheetFrame> {
+        self.frames
+            .binary_search_by_key(&index, |f| f.frame)
+            .ok()
+            .map(|i| &self.frames[i])
+    }
+
+    /// Location of every slice in every frame, sorted by frame index and
+    /// then in the order the slices appear in the file. Empty unless
+    /// [SheetOptions::include_slices] is set.
+    ///
+    /// Slices are clipped to the frame. Slices that do not exist at a frame
+    /// or lie completely outside of it are skipped.
+    pub fn regions(&self) -> &[SheetRegion] {
+        &self.regions
+    }
+
+    /// Location of the first slice with the given name in the given frame.
+    pub fn region(&self, name: &str, frame: u32) -> Option<&SheetRegion> {
+        self.regions
+            .iter()
+            .find(|region| region.frame == frame && region.name == name)
+    }
+}
+
+fn slice_regions(
+    file: &AsepriteFile,
+    frame: u32,
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> impl Iterator<Item = SheetRegion> + '_ {
+    file.slices_at_frame(frame).filter_map(move |(slice, key)| {
+        let bounds = key.bounds().clamp_to_canvas(width, height)?;
+        Some(SheetRegion {
+            name: slice.name.clone(),
+            frame,
+            x: x + bounds.x as u32,
+            y: y + bounds.y as u32,
+            width: bounds.w,
+            height: bounds.h,
+            pivot: key.pivot.map(|pivot| {
+                Point::new(
+                    pivot.x + key.origin.x - bounds.x,
+                    pivot.y + key.origin.y - bounds.y,
+                )
+            }),
+        })
+    })
+}
+
+/// Location of a single tileset inside a [TilesetAtlas].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasTileset {
+    /// ID of the tileset in its source file.
+    pub tileset: TilesetId,
+    /// Left edge of the tileset in the atlas image.
+    pub x: u32,
+    /// Top edge of the tileset in the atlas image.
+    pub y: u32,
+    /// Number of tiles per row. Tiles are stored left to right, top to bottom.
+    pub columns: u32,
+    /// Width of each tile in pixels.
+    pub tile_width: u32,
+    /// Height of each tile in pixels.
+    pub tile_height: u32,
+    /// Number of tiles in the tileset.
+    pub tile_count: u32,
+}
+
+impl AtlasTileset {
+    /// Location of a tile in the atlas image.
+    pub fn tile_rect(&self, tile_id: u32) -> Rect {
+        Rect::new(
+            (self.x + (tile_id % self.columns) * self.tile_width) as i32,
+            (self.y + (tile_id / self.columns) * self.tile_height) as i32,
+            self.tile_width,
+            self.tile_height,
+        )
+    }
+}
+
+/// A single image containing the tiles of several tilesets.
+///
+/// Useful for engines that want to bind a single texture for all tilemaps.
+/// The tilesets may come from different files.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/tilemap_multi.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::sheet::{SheetOptions, TilesetAtlas};
+///
+/// let mut tilesets: Vec<_> = ase.tilesets().iter().collect();
+/// tilesets.sort_by_key(|tileset| tileset.id());
+/// let atlas = TilesetAtlas::new(tilesets, &SheetOptions::default());
+/// // Texture coordinates of tile 3 of the second tileset.
+/// let [u0, v0, u1, v1] = atlas.tile_uv(1, 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TilesetAtlas {
+    image: RgbaImage,
+    tilesets: Vec<AtlasTileset>,
+}
+
+impl TilesetAtlas {
+    /// Pack the given tilesets into a single image.
+    ///
+    /// Each tileset is arranged as a roughly square grid of tiles. The
+    /// tilesets are then placed according to `options`. Spacing and border
+    /// only apply between tilesets, not between the tiles of a tileset.
+    pub fn new<'a, I>(tilesets: I, options: &SheetOptions) -> Self
+    where
+        I: IntoIterator<Item = &'a Tileset>,
+    {
+        let images: Vec<(AtlasTileset, RgbaImage)> = tilesets
+            .into_iter()
+            .map(|tileset| {
+                let (tile_width, tile_height) = tileset.tile_size().into();
+                let tile_count = tileset.tile_count();
+                let columns = ((tile_count as f64).sqrt().ceil() as u32).max(1);
+                let entry = AtlasTileset {
+                    tileset: tileset.id(),
+                    x: 0,
+                    y: 0,
+                    columns,
+                    tile_width,
+                    tile_height,
+                    tile_count,
+                };
+                (entry, tileset.image_grid(columns))
+            })
+            .collect();
+        let sizes: Vec<(u32, u32)> = images.iter().map(|(_, img)| img.dimensions()).collect();
+        let (positions, (width, height)) = layout(&sizes, options);
+
+        let mut image = RgbaImage::new(width, height);
+        let mut tilesets = Vec::with_capacity(images.len());
+        for ((mut entry, tileset_image), (x, y)) in images.into_iter().zip(positions) {
+            image::imageops::replace(&mut image, &tileset_image, x as i64, y as i64);
+            entry.x = x;
+            entry.y = y;
+            tilesets.push(entry);
+        }
+        TilesetAtlas { image, tilesets }
+    }
+
+    /// The combined image.
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Consume the atlas and return the combined image.
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Location of each tileset, in the order they were passed to
+    /// [new](Self::new).
+    pub fn tilesets(&self) -> &[AtlasTileset] {
+        &self.tilesets
+    }
+
+    /// Top-left corner of the tileset at position `index` in texture
+    /// coordinates, i.e., relative to the size of the atlas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than the number of tilesets.
+    pub fn uv_offset(&self, index: usize) -> (f32, f32) {
+        let entry = &self.tilesets[index];
+        self.to_uv(entry.x, entry.y)
+    }
+
+    /// Texture coordinates of a tile as `[left, top, right, bottom]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than the number of tilesets.
+    pub fn tile_uv(&self, index: usize, tile_id: u32) -> [f32; 4] {
+        let rect = self.tilesets[index].tile_rect(tile_id);
+        let (left, top) = self.to_uv(rect.x as u32, rect.y as u32);
+        let (right, bottom) = self.to_uv(rect.right() as u32, rect.bottom() as u32);
+        [left, top, right, bottom]
+    }
+
+    fn to_uv(&self, x: u32, y: u32) -> (f32, f32) {
+        let (width, height) = self.image.dimensions();
+        (
+            x as f32 / width.max(1) as f32,
+            y as f32 / height.max(1) as f32,
+        )
+    }
+}
+
+// Computes the top-left corner of each item and the total size of the sheet.
+pub(crate) fn layout(
+    sizes: &[(u32, u32)],
+    options: &SheetOptions,
+) -> (Vec<(u32, u32)>, (u32, u32)) {
+    let columns = match options.layout {
+        SheetLayout::HorizontalStrip => sizes.len().max(1) as u32,
+        SheetLayout::VerticalStrip => 1,
+        SheetLayout::Grid { columns } => columns.max(1),
+        SheetLayout::Packed => return layout_packed(sizes, options),
+    };
+    layout_grid(sizes, columns, options)
+}
+
+fn layout_grid(
+    sizes: &[(u32, u32)],
+    columns: u32,
+    options: &SheetOptions,
+) -> (Vec<(u32, u32)>, (u32, u32)) {
+    let SheetOptions {
+        spacing, border, ..
+    } = *options;
+    // All cells in a grid have the same size so that rows and columns line up.
+    let cell_w = sizes.iter().map(|s| s.0).max().unwrap_or(0);
+    let cell_h = sizes.iter().map(|s| s.1).max().unwrap_or(0);
+    let positions = (0..sizes.len() as u32)
+        .map(|i| {
+            let col = i % columns;
+            let row = i / columns;
+            (
+                border + col * (cell_w + spacing),
+                border + row * (cell_h + spacing),
+            )
+        })
+        .collect();
+    let used_columns = columns.min(sizes.len() as u32);
+    let rows = (sizes.len() as u32).div_ceil(columns);
+    let size = (
+        extent(used_columns, cell_w, options),
+        extent(rows, cell_h, options),
+    );
+    (positions, size)
+}
+
+// Simple shelf packer. Targets a roughly square output.
+fn layout_packed(sizes: &[(u32, u32)], options: &SheetOptions) -> (Vec<(u32, u32)>, (u32, u32)) {
+    let SheetOptions {
+        spacing, border, ..
+    } = *options;
+    let area: u64 = sizes
+        .iter()
+        .map(|&(w, h)| (w + spacing) as u64 * (h + spacing) as u64)
+        .sum();
+    let widest = sizes.iter().map(|s| s.0).max().unwrap_or(0);
+    let target_width = ((area as f64).sqrt().ceil() as u32).max(widest);
+
+    // Place tallest items first. Ties are broken by index to keep the result
+    // deterministic.
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(sizes[i].1), i));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y) = (0u32, 0u32);
+    let mut shelf_height = 0;
+    let (mut width, mut height) = (0u32, 0u32);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x > 0 && x + w > target_width {
+            x = 0;
+            y += shelf_height + spacing;
+            shelf_height = 0;
+        }
+        positions[i] = (border + x, border + y);
+        width = width.max(x + w);
+        height = height.max(y + h);
+        shelf_height = shelf_height.max(h);
+        x += w + spacing;
+    }
+    (positions, (width + 2 * border, height + 2 * border))
+}
+
+fn extent(count: u32, cell: u32, options: &SheetOptions) -> u32 {
+    if count == 0 {
+        return 2 * options.border;
+    }
+    count * cell + (count - 1) * options.spacing + 2 * options.border
+}
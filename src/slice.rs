@@ -1,6 +1,6 @@
 use std::io::Read;
 
-use crate::{reader::AseReader, user_data::UserData, Result};
+use crate::{reader::AseReader, user_data::UserData, Point, Rect, Result, Size};
 
 /// A slice is a region of the sprite with some attributes.
 ///
@@ -18,6 +18,28 @@ pub struct Slice {
     pub user_data: Option<UserData>,
 }
 
+impl Slice {
+    /// The [SliceKey] active at the given frame, i.e., the key with the
+    /// greatest `from_frame` that is not after `frame`.
+    ///
+    /// Returns `None` if the slice does not exist yet at that frame, i.e.,
+    /// if its first key starts after `frame`.
+    pub fn key_for_frame(&self, frame: u32) -> Option<&SliceKey> {
+        self.keys
+            .iter()
+            .filter(|key| key.from_frame <= frame)
+            .max_by_key(|key| key.from_frame)
+    }
+
+    /// The bounds of the slice at the given frame in canvas coordinates.
+    ///
+    /// Returns `None` if the slice does not exist at that frame. Use
+    /// [Rect::clamp_to_canvas] to restrict the result to the sprite.
+    pub fn bounds_at(&self, frame: u32) -> Option<Rect> {
+        self.key_for_frame(frame).map(SliceKey::bounds)
+    }
+}
+
 /// A devision of a [Slice] into nine regions for 9-slice scaling.
 #[derive(Debug, Clone)]
 pub struct Slice9 {
@@ -32,6 +54,16 @@ pub struct Slice9 {
 }
 
 impl Slice9 {
+    /// The center area, relative to the slice bounds.
+    pub fn center(&self) -> Rect {
+        Rect::new(
+            self.center_x,
+            self.center_y,
+            self.center_width,
+            self.center_height,
+        )
+    }
+
     fn read<R: Read>(reader: &mut AseReader<R>) -> Result<Self> {
         let center_x = reader.long()?;
         let center_y = reader.long()?;
@@ -53,24 +85,52 @@ pub struct SliceKey {
     /// frame to the end of the animation or the next slice key.
     pub from_frame: u32,
     /// Origin of the slice.
-    pub origin: (i32, i32),
+    pub origin: Point,
     /// Size of the slice.
-    pub size: (u32, u32),
+    pub size: Size,
     /// 9-slicing information.
     pub slice9: Option<Slice9>,
     /// Pivot information. Relative to the origin.
-    pub pivot: Option<(i32, i32)>,
+    pub pivot: Option<Point>,
 }
 
 impl SliceKey {
+    /// The bounds of the slice in canvas coordinates.
+    pub fn bounds(&self) -> Rect {
+        Rect::from_origin_size(self.origin, self.size)
+    }
+
+    /// The pivot relative to the slice bounds, where `(0.0, 0.0)` is the top
+    /// left and `(1.0, 1.0)` the bottom right corner of the slice.
+    ///
+    /// Returns `None` if the key has no pivot. Values may be outside of
+    /// `0.0..=1.0` if the pivot lies outside the slice.
+    pub fn normalized_pivot(&self) -> Option<(f32, f32)> {
+        let pivot = self.pivot?;
+        Some((
+            normalize(pivot.x, self.size.w),
+            normalize(pivot.y, self.size.h),
+        ))
+    }
+
+    /// Like [normalized_pivot](Self::normalized_pivot), but relative to a
+    /// canvas of the given size, usually the size of the sprite.
+    pub fn normalized_pivot_in_canvas(&self, width: u32, height: u32) -> Option<(f32, f32)> {
+        let pivot = self.pivot?;
+        Some((
+            normalize(self.origin.x + pivot.x, width),
+            normalize(self.origin.y + pivot.y, height),
+        ))
+    }
+
     fn read<R: Read>(reader: &mut AseReader<R>, flags: u32) -> Result<Self> {
         let from_frame = reader.dword()?;
         let origin_x = reader.long()?;
         let origin_y = reader.long()?;
-        let origin = (origin_x, origin_y);
+        let origin = Point::new(origin_x, origin_y);
         let slice_width = reader.dword()?;
         let slice_height = reader.dword()?;
-        let size = (slice_width, slice_height);
+        let size = Size::new(slice_width, slice_height);
         let slice9 = if flags & 1 != 0 {
             Some(Slice9::read(reader)?)
         } else {
@@ -79,7 +139,7 @@ impl SliceKey {
         let pivot = if flags & 2 != 0 {
             let x = reader.long()?;
             let y = reader.long()?;
-            Some((x, y))
+            Some(Point::new(x, y))
         } else {
             None
         };
@@ -94,6 +154,15 @@ impl SliceKey {
     }
 }
 
+// An empty range maps everything to 0.0 instead of NaN.
+fn normalize(value: i32, size: u32) -> f32 {
+    if size == 0 {
+        0.0
+    } else {
+        value as f32 / size as f32
+    }
+}
+
 pub(crate) fn parse_chunk(data: &[u8]) -> Result<Slice> {
     let mut reader = AseReader::new(data);
 
@@ -1,6 +1,8 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, time::Duration};
 
-use crate::{reader::AseReader, user_data::UserData, AsepriteParseError, Result};
+use crate::{
+    reader::AseReader, user_data::UserData, AsepriteFile, AsepriteParseError, Frame, Result,
+};
 
 /// A tag is a grouping of one or more frames.
 ///
@@ -17,6 +19,43 @@ pub struct Tag {
 }
 
 impl Tag {
+    /// A tag that plays frames `from_frame..=to_frame` forward and repeats
+    /// forever. Use [AsepriteFile::add_tag] to add it to a file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from_frame` is greater than `to_frame`.
+    pub fn new(name: &str, from_frame: u32, to_frame: u32) -> Self {
+        let mut tag = Tag {
+            name: name.to_owned(),
+            from_frame: 0,
+            to_frame: 0,
+            repeat: 0,
+            animation_direction: AnimationDirection::Forward,
+            user_data: None,
+        };
+        tag.set_range(from_frame, to_frame);
+        tag
+    }
+
+    #[cfg(test)]
+    pub(crate) fn raw(
+        name: &str,
+        from_frame: u16,
+        to_frame: u16,
+        animation_direction: AnimationDirection,
+        repeat: u16,
+    ) -> Self {
+        Tag {
+            name: name.to_owned(),
+            from_frame,
+            to_frame,
+            repeat,
+            animation_direction,
+            user_data: None,
+        }
+    }
+
     /// Tag name. May not be unique among all tags.
     pub fn name(&self) -> &str {
         &self.name
@@ -32,6 +71,22 @@ impl Tag {
         self.to_frame as u32
     }
 
+    /// Number of frames in the tag's range.
+    pub fn len(&self) -> u32 {
+        (self.to_frame() + 1).saturating_sub(self.from_frame())
+    }
+
+    /// Returns `true` if the tag's range contains no frames. This can only
+    /// happen for malformed files.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if `frame` is in the range `from_frame..=to_frame`.
+    pub fn contains_frame(&self, frame: u32) -> bool {
+        (self.from_frame()..=self.to_frame()).contains(&frame)
+    }
+
     /// See [AnimationDirection] for details.
     pub fn animation_direction(&self) -> AnimationDirection {
         self.animation_direction
@@ -44,18 +99,124 @@ impl Tag {
         NonZeroU32::new(self.repeat as u32)
     }
 
+    /// The frame indices of one playthrough of the tag, in playback order.
+    ///
+    /// Takes the [animation direction](Self::animation_direction) into
+    /// account. For [AnimationDirection::PingPong] the end points are not
+    /// duplicated, i.e., frames `0..=2` expand to `[0, 1, 2, 1, 0]` when
+    /// the tag is repeated twice.
+    ///
+    /// If the tag has a finite [repeat count](Self::repeat) the result contains
+    /// all repetitions. Each direction of a ping-pong tag counts as one
+    /// repetition (same as in Aseprite). If the tag repeats forever, returns a
+    /// single loop which can be repeated seamlessly.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// # let ase = AsepriteFile::read_file(&path).unwrap();
+    /// let tag = ase.tag_by_name("T3").unwrap();
+    /// assert_eq!(tag.frame_sequence(), vec![1, 2, 3]);
+    /// ```
+    pub fn frame_sequence(&self) -> Vec<u32> {
+        let from = self.from_frame();
+        let to = self.to_frame().max(from);
+        let repeat = self.repeat().map(|r| r.get());
+        let mut playback = Playback::new(from, to, self.animation_direction, repeat);
+        let mut result = vec![playback.current];
+        if repeat.is_some() {
+            loop {
+                playback.step();
+                if playback.finished {
+                    break;
+                }
+                result.push(playback.current);
+            }
+        } else {
+            // One full cycle. For ping-pong it stops before returning to
+            // `from_frame`, so that it loops without stutter.
+            let len = match self.animation_direction {
+                AnimationDirection::PingPong if from < to => 2 * (to - from),
+                AnimationDirection::PingPong => 1,
+                AnimationDirection::Forward | AnimationDirection::Reverse => to - from + 1,
+            };
+            for _ in 1..len {
+                playback.step();
+                result.push(playback.current);
+            }
+        }
+        result
+    }
+
+    /// The frames in the tag's range (`from_frame..=to_frame`) together with
+    /// their durations.
+    ///
+    /// Frames are returned in index order, regardless of the animation
+    /// direction. Use [Self::frame_sequence] for playback order.
+    pub fn frames<'a>(
+        &self,
+        file: &'a AsepriteFile,
+    ) -> impl Iterator<Item = (Frame<'a>, Duration)> + 'a {
+        let last_frame = file.num_frames().saturating_sub(1);
+        let from = self.from_frame().min(last_frame);
+        let to = self.to_frame().min(last_frame);
+        (from..=to).map(move |index| {
+            let frame = file.frame(index);
+            let duration = frame.duration();
+            (frame, duration)
+        })
+    }
+
+    /// The sum of the durations of all frames in the tag's range.
+    ///
+    /// This is the length of a single pass through the tag and does not take
+    /// repeat counts into account.
+    pub fn total_duration(&self, file: &AsepriteFile) -> Duration {
+        self.frames(file).map(|(_, duration)| duration).sum()
+    }
+
     /// Returns the user data for the tag, if any exists.
     pub fn user_data(&self) -> Option<&UserData> {
         self.user_data.as_ref()
     }
 
-    pub(crate) fn set_user_data(&mut self, user_data: UserData) {
-        self.user_data = Some(user_data);
+    /// Rename the tag.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    /// Change the frames included in the tag to `from_frame..=to_frame`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from_frame` is greater than `to_frame`.
+    pub fn set_range(&mut self, from_frame: u32, to_frame: u32) {
+        assert!(from_frame <= to_frame);
+        self.from_frame = from_frame.min(u16::MAX as u32) as u16;
+        self.to_frame = to_frame.min(u16::MAX as u32) as u16;
+    }
+
+    /// Change how the tag's frames are played.
+    pub fn set_animation_direction(&mut self, animation_direction: AnimationDirection) {
+        self.animation_direction = animation_direction;
+    }
+
+    /// Change how often the tag is played. `None` repeats forever. Counts
+    /// above 65535 are clamped.
+    pub fn set_repeat(&mut self, repeat: Option<NonZeroU32>) {
+        self.repeat = repeat.map_or(0, |r| r.get().min(u16::MAX as u32) as u16);
+    }
+
+    /// Replace the tag's [UserData]. `None` removes it.
+    pub fn set_user_data(&mut self, user_data: Option<UserData>) {
+        self.user_data = user_data;
     }
 }
 
 /// Describes how the tag's frames should be animated.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AnimationDirection {
     /// Start at `from_frame` and count up to `to_frame`.
     Forward,
@@ -65,6 +226,95 @@ pub enum AnimationDirection {
     PingPong,
 }
 
+// Steps through the frames `from..=to` in the order given by an animation
+// direction and repeat count. Shared by `Tag::frame_sequence` and
+// `AnimationPlayer`.
+#[derive(Debug, Clone)]
+pub(crate) struct Playback {
+    pub(crate) from: u32,
+    pub(crate) to: u32,
+    direction: AnimationDirection,
+    repeat: Option<u32>,
+    pub(crate) current: u32,
+    // Only used for ping-pong. Are we currently moving towards `to`?
+    moving_forward: bool,
+    // Number of completed passes through the frames.
+    passes: u32,
+    pub(crate) finished: bool,
+}
+
+impl Playback {
+    pub(crate) fn new(
+        from: u32,
+        to: u32,
+        direction: AnimationDirection,
+        repeat: Option<u32>,
+    ) -> Self {
+        let mut playback = Playback {
+            from,
+            to,
+            direction,
+            repeat,
+            current: from,
+            moving_forward: true,
+            passes: 0,
+            finished: false,
+        };
+        playback.reset();
+        playback
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.current = match self.direction {
+            AnimationDirection::Reverse => self.to,
+            AnimationDirection::Forward | AnimationDirection::PingPong => self.from,
+        };
+        self.moving_forward = true;
+        self.passes = 0;
+        self.finished = false;
+    }
+
+    // Moves to the next frame. Sets `finished` instead if that would start a
+    // pass beyond the repeat count.
+    pub(crate) fn step(&mut self) {
+        let (from, to) = (self.from, self.to);
+        let at_end = match self.direction {
+            AnimationDirection::Forward => self.current >= to,
+            AnimationDirection::Reverse => self.current <= from,
+            AnimationDirection::PingPong if self.moving_forward => self.current >= to,
+            AnimationDirection::PingPong => self.current <= from,
+        };
+        if at_end {
+            self.passes += 1;
+            if self.repeat.is_some_and(|r| self.passes >= r) {
+                self.finished = true;
+                return;
+            }
+        }
+        match self.direction {
+            AnimationDirection::Forward => {
+                self.current = if at_end { from } else { self.current + 1 };
+            }
+            AnimationDirection::Reverse => {
+                self.current = if at_end { to } else { self.current - 1 };
+            }
+            AnimationDirection::PingPong => {
+                if from == to {
+                    return;
+                }
+                if at_end {
+                    self.moving_forward = !self.moving_forward;
+                }
+                if self.moving_forward {
+                    self.current += 1;
+                } else {
+                    self.current -= 1;
+                }
+            }
+        }
+    }
+}
+
 pub(crate) fn parse_chunk(data: &[u8]) -> Result<Vec<Tag>> {
     let mut reader = AseReader::new(data);
 
@@ -10,6 +10,150 @@ fn load_test_file(name: &str) -> AsepriteFile {
     AsepriteFile::read_file(&path).unwrap()
 }
 
+// Builds a minimal RGBA file with a single 100ms frame that contains the given
+// `(chunk_type, data)` chunks. Used for features that none of the test files
+// exercise.
+fn build_test_file(
+    width: u16,
+    height: u16,
+    header_flags: u32,
+    chunks: &[(u16, Vec<u8>)],
+) -> Vec<u8> {
+    use byteorder::{LittleEndian, WriteBytesExt};
+
+    let mut frame = Vec::new();
+    for (chunk_type, data) in chunks {
+        frame
+            .write_u32::<LittleEndian>(data.len() as u32 + 6)
+            .unwrap();
+        frame.write_u16::<LittleEndian>(*chunk_type).unwrap();
+        frame.extend_from_slice(data);
+    }
+
+    let mut out = Vec::new();
+    out.write_u32::<LittleEndian>(128 + 16 + frame.len() as u32)
+        .unwrap();
+    out.write_u16::<LittleEndian>(0xA5E0).unwrap();
+    out.write_u16::<LittleEndian>(1).unwrap(); // frames
+    out.write_u16::<LittleEndian>(width).unwrap();
+    out.write_u16::<LittleEndian>(height).unwrap();
+    out.write_u16::<LittleEndian>(32).unwrap(); // color depth
+    out.write_u32::<LittleEndian>(header_flags).unwrap();
+    out.write_u16::<LittleEndian>(100).unwrap(); // speed
+    out.resize(128, 0);
+
+    out.write_u32::<LittleEndian>(16 + frame.len() as u32)
+        .unwrap();
+    out.write_u16::<LittleEndian>(0xF1FA).unwrap();
+    out.write_u16::<LittleEndian>(chunks.len() as u16).unwrap();
+    out.write_u16::<LittleEndian>(100).unwrap(); // duration
+    out.write_u16::<LittleEndian>(0).unwrap();
+    out.write_u32::<LittleEndian>(chunks.len() as u32).unwrap();
+    out.extend_from_slice(&frame);
+    out
+}
+
+// Appends a string with its length, as stored in Aseprite files.
+fn push_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u16).to_le_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+// User data chunk containing only text.
+fn user_data_chunk(text: &str) -> (u16, Vec<u8>) {
+    let mut data = vec![1, 0, 0, 0, text.len() as u8, 0];
+    data.extend_from_slice(text.as_bytes());
+    (0x2020, data)
+}
+
+// Tileset chunk with id 0 and the given RGBA pixel data.
+fn tileset_chunk(tile_count: u32, tile_size: (u16, u16), pixels: &[u8]) -> (u16, Vec<u8>) {
+    use std::io::Write;
+
+    let mut data = vec![
+        0, 0, 0, 0, // id
+        6, 0, 0, 0, // flags: includes tiles, tile 0 is empty
+    ];
+    data.extend_from_slice(&tile_count.to_le_bytes());
+    data.extend_from_slice(&tile_size.0.to_le_bytes());
+    data.extend_from_slice(&tile_size.1.to_le_bytes());
+    data.extend_from_slice(&[1, 0]); // base index
+    data.extend_from_slice(&[0; 14]);
+    data.extend_from_slice(&[2, 0, b't', b's']); // name
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(pixels).unwrap();
+    let pixels = encoder.finish().unwrap();
+    data.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+    data.extend_from_slice(&pixels);
+    (0x2023, data)
+}
+
+// Visible image layer with the given name.
+fn image_layer_chunk(name: &str) -> (u16, Vec<u8>) {
+    let mut data = vec![
+        1, 0, // flags
+        0, 0, // image layer
+        0, 0, 0, 0, 0, 0, 0, 0, // child level, default size, blend mode
+        255, 0, 0, 0, // opacity, reserved
+    ];
+    data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    data.extend_from_slice(name.as_bytes());
+    (0x2004, data)
+}
+
+// Raw 1x1 cel of the given layer at (x, y).
+fn raw_cel_chunk(layer: u16, x: i16, y: i16, pixel: [u8; 4]) -> (u16, Vec<u8>) {
+    let mut data = Vec::new();
+    data.extend_from_slice(&layer.to_le_bytes());
+    data.extend_from_slice(&x.to_le_bytes());
+    data.extend_from_slice(&y.to_le_bytes());
+    data.extend_from_slice(&[
+        255, 0, 0, // opacity, raw cel
+        0, 0, 0, 0, 0, 0, 0, // reserved
+        1, 0, 1, 0, // size
+    ]);
+    data.extend_from_slice(&pixel);
+    (0x2005, data)
+}
+
+// Tilemap layer named "t" that uses tileset 0.
+fn tilemap_layer_chunk() -> (u16, Vec<u8>) {
+    let mut data = vec![
+        1, 0, // flags
+        2, 0, // tilemap layer
+        0, 0, 0, 0, 0, 0, 0, 0, // child level, default size, blend mode
+        255, 0, 0, 0, // opacity, reserved
+        1, 0, b't', // name
+    ];
+    data.extend_from_slice(&0u32.to_le_bytes()); // tileset
+    (0x2004, data)
+}
+
+// Tilemap cel for layer 0 at the top left. Tiles use the same flip bits as
+// Aseprite.
+fn tilemap_cel_chunk(width: u16, height: u16, tiles: &[u32]) -> (u16, Vec<u8>) {
+    use std::io::Write;
+
+    let mut data = vec![
+        0, 0, 0, 0, 0, 0, // layer, x, y
+        255, 3, 0, // opacity, compressed tilemap
+        0, 0, 0, 0, 0, 0, 0, // reserved
+    ];
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes()); // bits per tile
+    for mask in [0x1fff_ffffu32, 0x8000_0000, 0x4000_0000, 0x2000_0000] {
+        data.extend_from_slice(&mask.to_le_bytes());
+    }
+    data.extend_from_slice(&[0; 10]);
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    for tile in tiles {
+        encoder.write_all(&tile.to_le_bytes()).unwrap();
+    }
+    data.extend_from_slice(&encoder.finish().unwrap());
+    (0x2005, data)
+}
+
 // Takes the `img` and saves it under `tests/data/<filename>.actual.png`. Then
 // compares it against the reference image `tests/data/<filename>.png`.
 fn compare_with_reference_image(img: image::RgbaImage, filename: &str) {
@@ -70,6 +214,7 @@ fn test_user_data(s: &str, c: [u8; 4]) -> UserData {
     UserData {
         text: Some(s.to_string()),
         color: Some(image::Rgba(c)),
+        ..Default::default()
     }
 }
 
@@ -365,7 +510,10 @@ fn tilemap() {
     let f = load_test_file("tilemap");
     let img = f.frame(0).image();
     assert_eq!(f.size(), (32, 32));
-    let ts = f.tilesets().get(0).expect("No tileset found");
+    let ts = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .expect("No tileset found");
     assert_eq!(ts.name(), "test_tileset");
 
     compare_with_reference_image(img, "tilemap");
@@ -376,7 +524,10 @@ fn tilemap_indexed() {
     let f = load_test_file("tilemap_indexed");
     let img = f.frame(0).image();
     assert_eq!(f.size(), (32, 32));
-    let ts = f.tilesets().get(0).expect("No tileset found");
+    let ts = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .expect("No tileset found");
     assert_eq!(ts.name(), "test_tileset");
 
     compare_with_reference_image(img, "tilemap_indexed");
@@ -387,7 +538,10 @@ fn tilemap_grayscale() {
     let f = load_test_file("tilemap_grayscale");
     let img = f.frame(0).image();
     assert_eq!(f.size(), (32, 32));
-    let ts = f.tilesets().get(0).expect("No tileset found");
+    let ts = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .expect("No tileset found");
     assert_eq!(ts.name(), "test_tileset");
 
     compare_with_reference_image(img, "tilemap_grayscale");
@@ -409,7 +563,10 @@ fn tilemap_empty_edges() {
 #[test]
 fn tileset_export() {
     let f = load_test_file("tileset");
-    let tileset = f.tilesets().get(0).expect("No tileset found");
+    let tileset = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .expect("No tileset found");
     let img = tileset.image();
 
     compare_with_reference_image(img, "tileset");
@@ -418,7 +575,10 @@ fn tileset_export() {
 #[test]
 fn tileset_export_single() {
     let f = load_test_file("tileset");
-    let tileset = f.tilesets().get(0).expect("No tileset found");
+    let tileset = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .expect("No tileset found");
 
     let img = tileset.tile_image(1);
 
@@ -428,7 +588,7 @@ fn tileset_export_single() {
 #[test]
 fn tileset_multi() {
     let f = load_test_file("tilemap_multi");
-    //let tileset = f.tilesets().get(0).expect("No tileset found");
+    //let tileset = f.tilesets().get(TilesetId::new(0)).expect("No tileset found");
     let img = f.frame(0).image();
     compare_with_reference_image(img, "tilemap_multi");
 
@@ -477,7 +637,7 @@ fn slices() {
             .collect::<Vec<_>>(),
         &[0, 1, 2, 3]
     );
-    assert_eq!(slice_1.keys[0].pivot.unwrap().0, 4);
+    assert_eq!(slice_1.keys[0].pivot.unwrap().x, 4);
     let slice_2 = &f.slices()[1];
     assert_eq!(
         slice_2
@@ -524,7 +684,7 @@ fn user_data_cel() {
 fn user_data_tags() {
     let f = load_test_file("user_data");
     let tags = f.tags;
-    let first = tags.get(0).and_then(|t| t.user_data()).unwrap();
+    let first = tags.first().and_then(|t| t.user_data()).unwrap();
     let second = tags.get(1).and_then(|t| t.user_data()).unwrap();
     let third = tags.get(2).and_then(|t| t.user_data()).unwrap();
 
@@ -534,6 +694,7 @@ fn user_data_tags() {
     let expected_second = UserData {
         text: None,
         color: Some(image::Rgba([0, 0, 0, 255])),
+        ..Default::default()
     };
     assert_eq!(*second, expected_second);
 
@@ -591,6 +752,7 @@ fn compute_indexed() {
         util::MappingOptions {
             transparent: f.transparent_color_index(),
             failure: 0,
+            match_alpha: false,
         },
     );
     let ((w, h), data) = util::to_indexed_image(img, &mapper);
@@ -602,6 +764,3534 @@ fn compute_indexed() {
     assert_eq!(data[7], 13);
 }
 
+#[test]
+fn sheet_layouts() {
+    use crate::sheet::{SheetLayout, SheetOptions, SpriteSheet};
+    let f = load_test_file("layers_and_tags");
+    let build = |layout| {
+        let options = SheetOptions {
+            layout,
+            ..Default::default()
+        };
+        SpriteSheet::new(&f, &options)
+    };
+
+    let sheet = build(SheetLayout::HorizontalStrip);
+    assert_eq!(sheet.image().dimensions(), (64, 16));
+    let xs: Vec<_> = sheet.frames().iter().map(|fr| (fr.frame, fr.x)).collect();
+    assert_eq!(xs, &[(0, 0), (1, 16), (2, 32), (3, 48)]);
+
+    let sheet = build(SheetLayout::VerticalStrip);
+    assert_eq!(sheet.image().dimensions(), (16, 64));
+    assert_eq!(sheet.frame(2).map(|fr| (fr.x, fr.y)), Some((0, 32)));
+
+    let sheet = build(SheetLayout::Grid { columns: 3 });
+    assert_eq!(sheet.image().dimensions(), (48, 32));
+    assert_eq!(sheet.frame(3).map(|fr| (fr.x, fr.y)), Some((0, 16)));
+
+    let sheet = build(SheetLayout::Packed);
+    assert_eq!(sheet.image().dimensions(), (32, 32));
+    let frame_img = f.frame(3).image();
+    let fr = sheet.frame(3).unwrap();
+    let sub = image::imageops::crop_imm(sheet.image(), fr.x, fr.y, fr.width, fr.height);
+    assert_eq!(sub.to_image(), frame_img);
+}
+
+#[test]
+fn sheet_spacing_and_border() {
+    use crate::sheet::{SheetLayout, SheetOptions, SpriteSheet};
+    let f = load_test_file("layers_and_tags");
+    let options = SheetOptions {
+        layout: SheetLayout::HorizontalStrip,
+        spacing: 2,
+        border: 1,
+        ..Default::default()
+    };
+    let sheet = SpriteSheet::from_frames(&f, [2, 0], &options);
+    assert_eq!(sheet.image().dimensions(), (1 + 16 + 2 + 16 + 1, 18));
+    let frames: Vec<_> = sheet.frames().iter().map(|fr| (fr.frame, fr.x)).collect();
+    assert_eq!(frames, &[(0, 1), (2, 19)]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn manifest() {
+    use crate::manifest::Manifest;
+    let f = load_test_file("slice_advanced");
+    let m = Manifest::new(&f);
+    assert_eq!(m.frames.len(), f.num_frames() as usize);
+    assert_eq!(m.slices.len(), 2);
+    assert_eq!(m.slices[0].keys[0].pivot.map(|p| p.0), Some(4));
+    assert_eq!(m.slices[1].keys[0].center, Some((3, 3, 2, 2)));
+
+    let f = load_test_file("layers_and_tags");
+    let m = Manifest::new(&f);
+    assert_eq!(m.tags.len(), 3);
+    assert_eq!(m.tags[0].name, f.tag(0).name());
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn manifest_ron_roundtrip() {
+    use crate::manifest::Manifest;
+    let f = load_test_file("slice_advanced");
+    let m = Manifest::new(&f);
+    let text = m.to_ron_string().unwrap();
+    let parsed: Manifest = ron::from_str(&text).unwrap();
+    assert_eq!(parsed, m);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn manifest_toml_roundtrip() {
+    use crate::manifest::Manifest;
+    let f = load_test_file("layers_and_tags");
+    let m = Manifest::new(&f);
+    let text = m.to_toml_string().unwrap();
+    let parsed: Manifest = toml::from_str(&text).unwrap();
+    assert_eq!(parsed, m);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn write_ico_and_cur() {
+    use crate::util::{write_cur, write_ico};
+    let f = load_test_file("basic-16x16");
+    let mut ico = Vec::new();
+    write_ico(&f, &[0], &mut ico).unwrap();
+    assert_eq!(&ico[0..6], &[0, 0, 1, 0, 1, 0]);
+    assert_eq!(ico[6], 16);
+    assert_eq!(ico.len(), 6 + 16 + 40 + 16 * 16 * 4 + 4 * 16);
+
+    let f = load_test_file("slice_advanced");
+    let key = &f.slices()[0].keys[1];
+    let pivot = key.pivot.unwrap();
+    let expected = (key.origin.x + pivot.x, key.origin.y + pivot.y);
+    let mut cur = Vec::new();
+    write_cur(&f, &[1], "Slice 1", &mut cur).unwrap();
+    assert_eq!(&cur[0..6], &[0, 0, 2, 0, 1, 0]);
+    let hotspot_x = u16::from_le_bytes([cur[10], cur[11]]) as i32;
+    let hotspot_y = u16::from_le_bytes([cur[12], cur[13]]) as i32;
+    assert_eq!((hotspot_x, hotspot_y), expected);
+
+    assert!(write_cur(&f, &[0], "No such slice", &mut Vec::new()).is_err());
+    let err = write_cur(&f, &[f.num_frames()], "Slice 1", &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = write_ico(&f, &[f.num_frames()], &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn animation_player_tag() {
+    use crate::animation::AnimationPlayer;
+    use std::time::Duration;
+    let f = load_test_file("layers_and_tags");
+    let ms = Duration::from_millis;
+    let mut player = AnimationPlayer::new(&f, f.tag_by_name("T3"));
+    assert_eq!(player.current_frame(), 1);
+    player.advance(ms(99));
+    assert_eq!(player.current_frame(), 1);
+    player.advance(ms(1));
+    assert_eq!(player.current_frame(), 2);
+    player.advance(ms(150));
+    assert_eq!(player.current_frame(), 3);
+    assert_eq!(player.elapsed_in_frame(), ms(50));
+    player.advance(ms(50));
+    assert_eq!(player.current_frame(), 1);
+    player.advance(ms(1000));
+    assert_eq!(player.current_frame(), 2);
+    player.reset();
+    assert_eq!(player.current_frame(), 1);
+
+    let mut player = AnimationPlayer::new(&f, None);
+    let frames: Vec<u32> = (0..6)
+        .map(|_| {
+            let frame = player.current_frame();
+            player.advance(ms(100));
+            frame
+        })
+        .collect();
+    assert_eq!(frames, &[0, 1, 2, 3, 0, 1]);
+}
+
+#[test]
+fn animation_player_directions() {
+    use crate::animation::AnimationPlayer;
+    use std::time::Duration;
+    let f = load_test_file("layers_and_tags");
+    let play = |tag: Tag, steps: usize| -> Vec<u32> {
+        let mut player = AnimationPlayer::new(&f, Some(&tag));
+        (0..steps)
+            .map(|_| {
+                let frame = player.current_frame();
+                player.advance(Duration::from_millis(100));
+                frame
+            })
+            .collect()
+    };
+    let reverse = Tag::raw("r", 1, 3, AnimationDirection::Reverse, 0);
+    assert_eq!(play(reverse, 5), &[3, 2, 1, 3, 2]);
+    let ping_pong = Tag::raw("p", 0, 3, AnimationDirection::PingPong, 0);
+    assert_eq!(play(ping_pong, 9), &[0, 1, 2, 3, 2, 1, 0, 1, 2]);
+    let single = Tag::raw("s", 2, 2, AnimationDirection::PingPong, 0);
+    assert_eq!(play(single, 3), &[2, 2, 2]);
+}
+
+#[test]
+fn animation_player_repeat() {
+    use crate::animation::AnimationPlayer;
+    use std::time::Duration;
+    let f = load_test_file("layers_and_tags");
+    let play = |tag: Tag, steps: usize| -> (Vec<u32>, bool) {
+        let mut player = AnimationPlayer::new(&f, Some(&tag));
+        let frames = (0..steps)
+            .map(|_| {
+                let frame = player.current_frame();
+                player.advance(Duration::from_millis(100));
+                frame
+            })
+            .collect();
+        (frames, player.is_finished())
+    };
+    let once = Tag::raw("once", 1, 3, AnimationDirection::Forward, 1);
+    assert_eq!(play(once, 5), (vec![1, 2, 3, 3, 3], true));
+    let twice = Tag::raw("twice", 1, 2, AnimationDirection::Reverse, 2);
+    assert_eq!(play(twice, 6), (vec![2, 1, 2, 1, 1, 1], true));
+    let ping_pong = Tag::raw("pp", 0, 2, AnimationDirection::PingPong, 2);
+    assert_eq!(play(ping_pong, 7), (vec![0, 1, 2, 1, 0, 0, 0], true));
+    let forever = Tag::raw("forever", 0, 1, AnimationDirection::Forward, 0);
+    assert_eq!(play(forever, 5), (vec![0, 1, 0, 1, 0], false));
+
+    // A large time step stops at the last frame.
+    let once = Tag::raw("once", 0, 3, AnimationDirection::Forward, 1);
+    let mut player = AnimationPlayer::new(&f, Some(&once));
+    player.advance(Duration::from_secs(10));
+    assert!(player.is_finished());
+    assert_eq!(player.current_frame(), 3);
+}
+
+#[test]
+fn tag_frame_sequence() {
+    use crate::animation::AnimationPlayer;
+    use std::time::Duration;
+    use AnimationDirection::*;
+    let f = load_test_file("layers_and_tags");
+    let cases = [
+        (Tag::raw("a", 1, 3, Forward, 0), vec![1, 2, 3]),
+        (Tag::raw("b", 1, 3, Forward, 2), vec![1, 2, 3, 1, 2, 3]),
+        (Tag::raw("c", 1, 3, Reverse, 0), vec![3, 2, 1]),
+        (Tag::raw("d", 0, 3, PingPong, 0), vec![0, 1, 2, 3, 2, 1]),
+        (Tag::raw("e", 0, 2, PingPong, 1), vec![0, 1, 2]),
+        (Tag::raw("f", 0, 2, PingPong, 3), vec![0, 1, 2, 1, 0, 1, 2]),
+        (Tag::raw("g", 2, 2, PingPong, 2), vec![2, 2]),
+    ];
+    for (tag, expected) in cases {
+        assert_eq!(tag.frame_sequence(), expected, "tag {}", tag.name());
+
+        // The player must agree with the expanded sequence.
+        let mut player = AnimationPlayer::new(&f, Some(&tag));
+        let played: Vec<u32> = (0..expected.len())
+            .map(|_| {
+                let frame = player.current_frame();
+                player.advance(Duration::from_millis(100));
+                frame
+            })
+            .collect();
+        assert_eq!(played, expected, "tag {}", tag.name());
+    }
+}
+
+#[test]
+fn tag_frames_and_duration() {
+    use std::time::Duration;
+    let f = load_test_file("layers_and_tags");
+    let tag = f.tag_by_name("T3").unwrap();
+    let frames: Vec<(u32, Duration)> = tag
+        .frames(&f)
+        .map(|(frame, duration)| (frame.id(), duration))
+        .collect();
+    let ms = Duration::from_millis;
+    assert_eq!(frames, &[(1, ms(100)), (2, ms(100)), (3, ms(100))]);
+    assert_eq!(tag.total_duration(&f), ms(300));
+}
+
+#[test]
+fn frame_durations() {
+    use std::time::Duration;
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(f.frame(1).duration(), Duration::from_millis(100));
+    assert_eq!(f.frame(1).duration_ms(), 100);
+    assert_eq!(f.frame_durations().len(), 4);
+    assert_eq!(f.total_duration(), Duration::from_millis(400));
+}
+
+#[test]
+fn animation_retime() {
+    use crate::animation::{retime, retime_tag};
+    use std::time::Duration;
+    let ms = Duration::from_millis;
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(retime_tag(&f, None, 20), &[0, 0, 1, 1, 2, 2, 3, 3]);
+    assert_eq!(retime_tag(&f, f.tag_by_name("T3"), 60).len(), 18);
+
+    // 150ms at 30 Hz is 4.5 ticks, which rounds up.
+    assert_eq!(retime([(0, ms(100)), (1, ms(50))], 30), &[0, 0, 0, 1, 1]);
+    // Frames shorter than a tick may be skipped.
+    assert_eq!(
+        retime([(0, ms(90)), (1, ms(10)), (2, ms(100))], 10),
+        &[0, 2]
+    );
+    assert_eq!(retime([(5, ms(0))], 60), &[5]);
+    assert!(retime([], 60).is_empty());
+}
+
+#[test]
+fn tag_ranges() {
+    let f = load_test_file("layers_and_tags");
+    let t3 = f.tag_by_name("T3").unwrap();
+    assert_eq!(t3.len(), 3);
+    assert!(!t3.is_empty());
+    assert!(!t3.contains_frame(0));
+    assert!(t3.contains_frame(1));
+    assert!(t3.contains_frame(3));
+
+    let names = |frame| f.tags_at_frame(frame).map(|t| t.name()).collect::<Vec<_>>();
+    assert_eq!(names(0), &["T1"]);
+    assert_eq!(names(1), &["T1", "T3"]);
+    assert_eq!(names(3), &["T3", "T2"]);
+}
+
+#[test]
+fn tags_iter() {
+    let f = load_test_file("layers_and_tags");
+    let names: Vec<_> = f.tags().map(|t| t.name()).collect();
+    assert_eq!(names, &["T1", "T3", "T2"]);
+    assert_eq!(f.tags().len(), f.num_tags() as usize);
+    assert_eq!(f.tags_by_name("T3").count(), 1);
+    assert_eq!(f.tags_by_name("T4").count(), 0);
+}
+
+#[test]
+fn layer_lookup_all_matches() {
+    let f = load_test_file("layers_and_tags");
+    let ids = |iter: &mut dyn Iterator<Item = Layer>| iter.map(|l| l.id()).collect::<Vec<_>>();
+    assert_eq!(ids(&mut f.layers_by_name("Layer 4")), &[5]);
+    assert!(ids(&mut f.layers_by_name("layer 4")).is_empty());
+    assert_eq!(ids(&mut f.layers_by_name_ignore_case("layer 4")), &[5]);
+    assert_eq!(ids(&mut f.layers_matching("*")), &[0, 1, 2, 3, 4, 5]);
+    assert_eq!(ids(&mut f.layers_matching("Layer *")), &[0, 1, 4, 5]);
+    assert_eq!(ids(&mut f.layers_matching("*i*i*")), &[2]);
+    assert_eq!(ids(&mut f.layers_matching("Group?1")), &[3]);
+    assert!(ids(&mut f.layers_matching("Group")).is_empty());
+}
+
+#[test]
+fn layer_path() {
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(f.layer(1).path(), "Layer 1");
+    assert_eq!(f.layer(3).path(), "Group 1");
+    assert_eq!(f.layer(4).path(), "Group 1/Layer 5");
+    assert_eq!(f.layer(5).path(), "Group 1/Layer 4");
+}
+
+#[test]
+fn layer_by_path() {
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(f.layer_by_path("Group 1/Layer 4").map(|l| l.id()), Some(5));
+    assert_eq!(f.layer_by_path("Group 1").map(|l| l.id()), Some(3));
+    assert_eq!(f.layer_by_path("Layer 1").map(|l| l.id()), Some(1));
+    assert!(f.layer_by_path("Layer 4").is_none());
+    assert!(f.layer_by_path("Group 1/Layer 1").is_none());
+}
+
+#[test]
+fn layer_children() {
+    let f = load_test_file("layers_and_tags");
+    let ids = |iter: &mut dyn Iterator<Item = Layer>| iter.map(|l| l.id()).collect::<Vec<_>>();
+    assert_eq!(ids(&mut f.layer(3).children()), &[4, 5]);
+    assert_eq!(ids(&mut f.layer(3).descendants()), &[4, 5]);
+    assert!(ids(&mut f.layer(1).children()).is_empty());
+    assert!(ids(&mut f.layer(4).descendants()).is_empty());
+}
+
+#[test]
+fn layer_tree() {
+    let f = load_test_file("layers_and_tags");
+    let tree = f.layer_tree();
+    let roots: Vec<_> = tree.roots().iter().map(|n| n.layer().id()).collect();
+    assert_eq!(roots, &[0, 1, 2, 3]);
+    let group = &tree.roots()[3];
+    assert!(group.is_group());
+    let children: Vec<_> = group.children().iter().map(|n| n.layer().id()).collect();
+    assert_eq!(children, &[4, 5]);
+
+    let all: Vec<_> = tree.iter().map(|n| n.layer().id()).collect();
+    assert_eq!(all, &[0, 1, 2, 3, 4, 5]);
+    let groups: Vec<_> = tree.groups().map(|n| n.layer().id()).collect();
+    assert_eq!(groups, &[3]);
+    assert_eq!(tree.leaves().count(), 5);
+}
+
+#[test]
+fn layer_uuid() {
+    let f = load_test_file("layers_and_tags");
+    assert!(f.layers().all(|l| l.uuid().is_none()));
+
+    // No test file has UUIDs yet, so build a layer chunk by hand.
+    let mut chunk: Vec<u8> = vec![
+        1, 0, // flags
+        0, 0, // image layer
+        0, 0, // child level
+        0, 0, 0, 0, // default size
+        0, 0,   // blend mode
+        255, // opacity
+        0, 0, 0, // reserved
+        3, 0, b'a', b'b', b'c', // name
+    ];
+    chunk.extend(0..16u8);
+    let layer = layer::parse_chunk(&chunk, true).unwrap();
+    assert_eq!(layer.name, "abc");
+    let uuid = layer.uuid.unwrap();
+    assert_eq!(uuid.as_bytes()[15], 15);
+    assert_eq!(uuid.to_string(), "00010203-0405-0607-0809-0a0b0c0d0e0f");
+
+    let without = layer::parse_chunk(&chunk[..chunk.len() - 16], false).unwrap();
+    assert!(without.uuid.is_none());
+}
+
+#[test]
+fn layers_iter_traits() {
+    let f = load_test_file("layers_and_tags");
+    let mut iter = f.layers();
+    assert_eq!(iter.len(), 6);
+    let top_down: Vec<_> = f.layers().rev().map(|l| l.id()).collect();
+    assert_eq!(top_down, &[5, 4, 3, 2, 1, 0]);
+
+    assert_eq!(iter.next().map(|l| l.id()), Some(0));
+    assert_eq!(iter.next_back().map(|l| l.id()), Some(5));
+    assert_eq!(iter.len(), 4);
+    let rest: Vec<_> = iter.by_ref().map(|l| l.id()).collect();
+    assert_eq!(rest, &[1, 2, 3, 4]);
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+}
+
+#[test]
+fn tileset_ids() {
+    let f = load_test_file("tilemap_multi");
+    let id = TilesetId::new(1);
+    assert_eq!(id.value(), 1);
+    assert_eq!(TilesetId::from(1), id);
+    let tileset = f.tilesets().get(id).unwrap();
+    assert_eq!(tileset.id(), id);
+    for layer in f.layers() {
+        if let LayerType::Tilemap(tileset_id) = layer.layer_type() {
+            assert!(f.tilesets().get(tileset_id).is_some());
+            let tilemap = f.tilemap(layer.id(), 0).unwrap();
+            assert_eq!(tilemap.tileset_id(), tileset_id);
+        }
+    }
+}
+
+#[test]
+fn tile_user_data() {
+    let f = load_test_file("tileset");
+    let ts = f.tilesets().get(TilesetId::new(0)).unwrap();
+    assert!(ts.user_data().is_none());
+    assert!(ts.tile_user_data(1).is_none());
+
+    // Tileset with three 2x2 tiles, followed by user data for the tileset and
+    // the first two tiles.
+    let tileset = tileset_chunk(3, (2, 2), &[0xff; 3 * 2 * 2 * 4]);
+    let data = build_test_file(
+        4,
+        4,
+        0,
+        &[
+            tileset,
+            user_data_chunk("tileset"),
+            user_data_chunk("tile 0"),
+            user_data_chunk("tile 1"),
+        ],
+    );
+
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let ts = f.tilesets().get(TilesetId::new(0)).unwrap();
+    let text = |ud: Option<&UserData>| ud.and_then(|ud| ud.text.clone());
+    assert_eq!(text(ts.user_data()).as_deref(), Some("tileset"));
+    assert_eq!(text(ts.tile_user_data(0)).as_deref(), Some("tile 0"));
+    assert_eq!(text(ts.tile_user_data(1)).as_deref(), Some("tile 1"));
+    assert_eq!(ts.tile_user_data(2), None);
+}
+
+#[test]
+fn tileset_image_grid() {
+    let f = load_test_file("tilemap_multi");
+    // 13 tiles of 16x16.
+    let ts = f.tilesets().get(TilesetId::new(1)).unwrap();
+    let (tw, th) = (16, 16);
+    assert_eq!(ts.tile_count(), 13);
+
+    let grid = ts.image_grid(4);
+    assert_eq!(grid.dimensions(), (4 * tw, 4 * th));
+    let spaced = ts.image_grid_spaced(4, 2, 1);
+    assert_eq!(
+        spaced.dimensions(),
+        (4 * tw + 3 * 2 + 2, 4 * th + 3 * 2 + 2)
+    );
+    // A single column matches the strip layout of `image`.
+    assert_eq!(ts.image_grid(1), ts.image());
+
+    for tile in 0..ts.tile_count() {
+        let expected = ts.tile_image(tile);
+        let (col, row) = (tile % 4, tile / 4);
+        let actual = image::imageops::crop_imm(&grid, col * tw, row * th, tw, th).to_image();
+        assert_eq!(actual, expected);
+        let (x, y) = (1 + col * (tw + 2), 1 + row * (th + 2));
+        let actual = image::imageops::crop_imm(&spaced, x, y, tw, th).to_image();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn dedup_tileset() {
+    let (empty, red, blue) = ([0; 4], [255, 0, 0, 255], [0, 0, 255, 255]);
+    let pixels: Vec<u8> = [empty, red, empty, red, blue]
+        .iter()
+        .flat_map(|color| color.repeat(4))
+        .collect();
+    let data = build_test_file(4, 4, 0, &[tileset_chunk(5, (2, 2), &pixels)]);
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let tileset = f.tilesets().get(TilesetId::new(0)).unwrap();
+
+    let deduped = util::dedup_tileset(tileset);
+    assert_eq!(deduped.remap, &[0, 1, 0, 1, 2]);
+    assert_eq!(deduped.tile_count(), 3);
+    assert_eq!(deduped.image.dimensions(), (2, 6));
+    assert_eq!(deduped.image.get_pixel(1, 3).0, red);
+    assert_eq!(deduped.image.get_pixel(0, 5).0, blue);
+
+    // Flags are kept when remapping.
+    let data = build_test_file(
+        4,
+        2,
+        0,
+        &[
+            tileset_chunk(5, (2, 2), &pixels),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(2, 1, &[3, 0xE000_0004]),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let tiles = deduped.remap_tilemap(&f.tilemap(0, 0).unwrap());
+    let flags = |tile: &Tile| (tile.id(), tile.flip_x(), tile.flip_y(), tile.rotate_90cw());
+    assert_eq!(flags(&tiles[0]), (1, false, false, false));
+    assert_eq!(flags(&tiles[1]), (2, true, true, true));
+
+    // Real files rarely have duplicates, but remapping must preserve pixels.
+    let f = load_test_file("tilemap_empty_edges");
+    let tileset = f.tilesets().get(TilesetId::new(0)).unwrap();
+    let deduped = util::dedup_tileset(tileset);
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let tiles = deduped.remap_tilemap(&tilemap);
+    assert_eq!(tiles.len() as u32, tilemap.width() * tilemap.height());
+    for (i, tile) in tiles.iter().enumerate() {
+        let (x, y) = (i as u32 % tilemap.width(), i as u32 / tilemap.width());
+        let old = tileset.tile_image(tilemap.tile(x, y).id());
+        let new = image::imageops::crop_imm(&deduped.image, 0, tile.id() * 16, 16, 16).to_image();
+        assert_eq!(old, new);
+    }
+}
+
+#[cfg(feature = "tiled")]
+#[test]
+fn tiled_tsx() {
+    let pixels = [0xff; 3 * 2 * 2 * 4];
+    let data = build_test_file(
+        4,
+        4,
+        0,
+        &[
+            tileset_chunk(3, (2, 2), &pixels),
+            user_data_chunk(""),
+            user_data_chunk("wall & <door>"),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let tileset = f.tilesets().get(TilesetId::new(0)).unwrap();
+    let mut tsx = Vec::new();
+    tiled::write_tsx(tileset, "ts.png", 2, &mut tsx).unwrap();
+    let tsx = String::from_utf8(tsx).unwrap();
+    assert!(tsx.contains(
+        r#"<tileset version="1.10" name="ts" tilewidth="2" tileheight="2" tilecount="3" columns="2">"#
+    ));
+    assert!(tsx.contains(r#"<image source="ts.png" width="4" height="4"/>"#));
+    assert!(tsx.contains(r#"<tile id="0">"#));
+    assert!(tsx.contains(r#"<property name="text" value="wall &amp; &lt;door&gt;"/>"#));
+    assert!(!tsx.contains(r#"<tile id="1">"#));
+}
+
+#[test]
+fn external_tileset() {
+    let red = [255, 0, 0, 255];
+    let pixels: Vec<u8> = [[0; 4], red].iter().flat_map(|c| c.repeat(4)).collect();
+    let tiles_file = build_test_file(4, 4, 0, &[tileset_chunk(2, (2, 2), &pixels)]);
+
+    let mut external_files = vec![
+        1, 0, 0, 0, // number of entries
+        0, 0, 0, 0, 0, 0, 0, 0, // reserved
+        7, 0, 0, 0, // file id
+        1, 0, 0, 0, 0, 0, 0, 0, // type (tileset) + reserved
+        5, 0,
+    ];
+    external_files.extend_from_slice(b"tiles");
+    let mut tileset = vec![
+        0, 0, 0, 0, // id
+        5, 0, 0, 0, // flags: external file, tile 0 is empty
+        2, 0, 0, 0, // tile count
+        2, 0, 2, 0, // tile size
+        1, 0, // base index
+    ];
+    tileset.extend_from_slice(&[0; 14]);
+    tileset.extend_from_slice(&[2, 0, b'e', b'x']);
+    tileset.extend_from_slice(&[7, 0, 0, 0, 0, 0, 0, 0]); // file id, tileset id
+    let data = build_test_file(4, 4, 0, &[(0x2008, external_files), (0x2023, tileset)]);
+
+    match AsepriteFile::read(&data[..]) {
+        Err(AsepriteParseError::UnsupportedFeature(_)) => {}
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let mut requested = Vec::new();
+    let f = AsepriteFile::read_with_external_resolver(&data[..], |external| {
+        requested.push(external.name().to_owned());
+        Some(tiles_file.clone())
+    })
+    .unwrap();
+    assert_eq!(requested, &["tiles"]);
+    let ts = f.tilesets().get(TilesetId::new(0)).unwrap();
+    assert_eq!(ts.name(), "ex");
+    assert_eq!(ts.tile_count(), 2);
+    assert_eq!(ts.tile_image(1).get_pixel(1, 1).0, red);
+}
+
+#[test]
+fn external_tileset_cycle() {
+    let mut external_files = vec![
+        1, 0, 0, 0, // number of entries
+        0, 0, 0, 0, 0, 0, 0, 0, // reserved
+        7, 0, 0, 0, // file id
+        1, 0, 0, 0, 0, 0, 0, 0, // type (tileset) + reserved
+        4, 0,
+    ];
+    external_files.extend_from_slice(b"self");
+    let mut tileset = vec![
+        0, 0, 0, 0, // id
+        5, 0, 0, 0, // flags: external file, tile 0 is empty
+        2, 0, 0, 0, // tile count
+        2, 0, 2, 0, // tile size
+        1, 0, // base index
+    ];
+    tileset.extend_from_slice(&[0; 14]);
+    tileset.extend_from_slice(&[2, 0, b'e', b'x']);
+    tileset.extend_from_slice(&[7, 0, 0, 0, 0, 0, 0, 0]); // file id, tileset id
+    let data = build_test_file(4, 4, 0, &[(0x2008, external_files), (0x2023, tileset)]);
+
+    // The file references a tileset in itself.
+    let mut requests = 0;
+    let err = AsepriteFile::read_with_external_resolver(&data[..], |_| {
+        requests += 1;
+        Some(data.clone())
+    })
+    .unwrap_err();
+    assert!(err.is_invalid_input(), "{}", err);
+    assert_eq!(requests, 1);
+}
+
+#[test]
+fn tileset_empty_tiles() {
+    for name in ["tileset", "tilemap_indexed", "tilemap_grayscale"] {
+        let f = load_test_file(name);
+        let ts = f.tilesets().get(TilesetId::new(0)).unwrap();
+        assert!(ts.is_tile_empty(0), "{}", name);
+        for tile in 0..ts.tile_count() {
+            let expected = ts.tile_image(tile).pixels().all(|px| px.0[3] == 0);
+            assert_eq!(ts.is_tile_empty(tile), expected, "{} tile {}", name, tile);
+        }
+        assert!(!(1..ts.tile_count()).all(|tile| ts.is_tile_empty(tile)));
+    }
+}
+
+#[test]
+fn tileset_atlas() {
+    use crate::sheet::{SheetLayout, SheetOptions, TilesetAtlas};
+
+    let f = load_test_file("tilemap_multi");
+    let ts0 = f.tilesets().get(TilesetId::new(0)).unwrap();
+    let ts1 = f.tilesets().get(TilesetId::new(1)).unwrap();
+    let options = SheetOptions {
+        layout: SheetLayout::HorizontalStrip,
+        spacing: 1,
+        ..Default::default()
+    };
+    let atlas = TilesetAtlas::new([ts0, ts1], &options);
+    let entries = atlas.tilesets();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].tileset, TilesetId::new(1));
+    // 5 tiles of 20x16 in a 3x2 grid, 13 tiles of 16x16 in a 4x4 grid.
+    assert_eq!((entries[0].columns, entries[1].columns), (3, 4));
+    // Strip layouts use cells of the same size.
+    assert_eq!(atlas.image().dimensions(), (2 * 4 * 16 + 1, 4 * 16));
+    assert_eq!(atlas.uv_offset(1), (65.0 / 129.0, 0.0));
+
+    for (index, tileset) in [ts0, ts1].into_iter().enumerate() {
+        for tile in 0..tileset.tile_count() {
+            let rect = entries[index].tile_rect(tile);
+            let actual = image::imageops::crop_imm(
+                atlas.image(),
+                rect.x as u32,
+                rect.y as u32,
+                rect.w,
+                rect.h,
+            )
+            .to_image();
+            assert_eq!(actual, tileset.tile_image(tile));
+        }
+    }
+    let [u0, v0, u1, v1] = atlas.tile_uv(0, 4);
+    assert_eq!((u0, v0), (20.0 / 129.0, 16.0 / 64.0));
+    assert_eq!((u1, v1), (40.0 / 129.0, 32.0 / 64.0));
+}
+
+#[cfg(feature = "tiled")]
+#[test]
+fn tiled_tmx() {
+    let f = load_test_file("tilemap");
+    let mut tmx = Vec::new();
+    tiled::write_tmx(&f, 0, |ts| format!("ts{}.tsx", ts.id().value()), &mut tmx).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    assert!(tmx.contains(r#"width="2" height="2" tilewidth="16" tileheight="16""#));
+    assert!(tmx.contains(r#"<tileset firstgid="1" source="ts0.tsx"/>"#));
+    assert!(tmx.contains(r#"<layer id="1" name="Tilemap 1" width="2" height="2""#));
+
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let gid = |x, y| match tilemap.tile(x, y).id() {
+        0 => 0,
+        id => id + 1,
+    };
+    let expected = format!(
+        "{},{},\n{},{}\n",
+        gid(0, 0),
+        gid(1, 0),
+        gid(0, 1),
+        gid(1, 1)
+    );
+    assert!(tmx.contains(&expected));
+
+    // Tilesets with different tile sizes cannot be combined.
+    let f = load_test_file("tilemap_multi");
+    let result = tiled::write_tmx(&f, 0, |_| String::new(), std::io::sink());
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "ldtk")]
+#[test]
+fn ldtk_export() {
+    let f = load_test_file("tilemap_multi");
+    let export = ldtk::LdtkExport::new(&f, 0, |ts| format!("ts{}.png", ts.id().value()));
+    assert_eq!(export.tilesets.len(), 2);
+    assert_eq!(export.tilesets[1].rel_path, "ts1.png");
+    assert_eq!(export.tilesets[1].c_wid, 4);
+    let tilemap_layers = f.layers().filter(|l| l.is_tilemap()).count();
+    assert_eq!(export.level.layer_instances.len(), tilemap_layers);
+
+    // Layers are listed top to bottom.
+    let top = f.layers().rev().find(|l| l.is_tilemap()).unwrap();
+    let instance = &export.level.layer_instances[0];
+    assert_eq!(instance.layer_def_uid, 2000 + top.id() as i64);
+    let tilemap = f.tilemap(top.id(), 0).unwrap();
+    let (tw, th) = tilemap.tile_size().into();
+    for tile in &instance.grid_tiles {
+        let (x, y) = (tile.px[0] as u32 / tw, tile.px[1] as u32 / th);
+        assert_eq!(tilemap.tile(x, y).id(), tile.t);
+        assert_eq!(tile.d, &[x + y * instance.c_wid]);
+    }
+    assert!(!instance.grid_tiles.is_empty());
+
+    let json = export.to_json_string().unwrap();
+    assert!(json.contains(r#""__type": "Tiles""#));
+    assert!(json.contains(r#""gridTiles""#));
+}
+
+#[test]
+fn tilemap_tiles_iter() {
+    let f = load_test_file("tilemap_empty_edges");
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let all: Vec<_> = tilemap.tiles().collect();
+    assert_eq!(all.len() as u32, tilemap.width() * tilemap.height());
+    assert_eq!((all[1].0, all[1].1), (1, 0));
+    let w = tilemap.width() as usize;
+    assert_eq!((all[w].0, all[w].1), (0, 1));
+    for &(x, y, tile) in &all {
+        assert_eq!(tile.id(), tilemap.tile(x, y).id());
+    }
+    let non_empty: Vec<_> = tilemap.non_empty_tiles().map(|(x, y, _)| (x, y)).collect();
+    let expected: Vec<_> = all
+        .iter()
+        .filter(|(_, _, tile)| tile.id() != 0)
+        .map(|&(x, y, _)| (x, y))
+        .collect();
+    assert_eq!(non_empty, expected);
+    assert!(non_empty.len() < all.len());
+}
+
+#[test]
+fn tilemap_to_vec() {
+    let f = load_test_file("tilemap_empty_edges");
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let ids = tilemap.to_vec();
+    let tiles = tilemap.to_tile_vec();
+    let w = tilemap.width();
+    assert_eq!(ids.len() as u32, w * tilemap.height());
+    assert_eq!(tiles.len(), ids.len());
+    for y in 0..tilemap.height() {
+        for x in 0..w {
+            let index = (y * w + x) as usize;
+            assert_eq!(ids[index], tilemap.tile(x, y).id());
+            assert_eq!(tiles[index].id(), ids[index]);
+        }
+    }
+}
+
+#[test]
+fn tilemap_csv() {
+    let f = load_test_file("tilemap_empty_edges");
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let mut csv = Vec::new();
+    tilemap.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<Vec<u32>> = csv
+        .lines()
+        .map(|line| line.split(',').map(|id| id.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(rows.len() as u32, tilemap.height());
+    for (y, row) in rows.iter().enumerate() {
+        assert_eq!(row.len() as u32, tilemap.width());
+        for (x, &id) in row.iter().enumerate() {
+            assert_eq!(id, tilemap.tile(x as u32, y as u32).id());
+        }
+    }
+}
+
+#[test]
+fn tilemap_flips() {
+    let (r, g, b, w) = (
+        [255, 0, 0, 255],
+        [0, 255, 0, 255],
+        [0, 0, 255, 255],
+        [255, 255, 255, 255],
+    );
+    // Tile 0 is empty, tile 1 has a different color in each corner.
+    let pixels: Vec<u8> = [[0; 4], [0; 4], [0; 4], [0; 4], r, g, b, w].concat();
+
+    let data = build_test_file(
+        8,
+        2,
+        0,
+        &[
+            tileset_chunk(2, (2, 2), &pixels),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(4, 1, &[1, 0x8000_0001, 0x4000_0001, 0x2000_0001]),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let img = f.frame(0).image();
+    let px = |x, y| img.get_pixel(x, y).0;
+    // No flags.
+    assert_eq!([px(0, 0), px(1, 0), px(0, 1), px(1, 1)], [r, g, b, w]);
+    // Horizontal flip.
+    assert_eq!([px(2, 0), px(3, 0), px(2, 1), px(3, 1)], [g, r, w, b]);
+    // Vertical flip.
+    assert_eq!([px(4, 0), px(5, 0), px(4, 1), px(5, 1)], [b, w, r, g]);
+    // Diagonal flip.
+    assert_eq!([px(6, 0), px(7, 0), px(6, 1), px(7, 1)], [r, b, g, w]);
+
+    let tilemap = f.tilemap(0, 0).unwrap();
+    let flags = |x| {
+        let tile = tilemap.tile(x, 0);
+        (tile.flip_x(), tile.flip_y(), tile.rotate_90cw())
+    };
+    assert_eq!(flags(0), (false, false, false));
+    assert_eq!(flags(1), (true, false, false));
+    assert_eq!(flags(2), (false, true, false));
+    assert_eq!(flags(3), (false, false, true));
+
+    // Combined flags. The diagonal flip is applied first.
+    let data = build_test_file(
+        8,
+        2,
+        0,
+        &[
+            tileset_chunk(2, (2, 2), &pixels),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(4, 1, &[0xA000_0001, 0x6000_0001, 0xC000_0001, 0xE000_0001]),
+        ],
+    );
+    let img = AsepriteFile::read(&data[..]).unwrap().frame(0).image();
+    let px = |x, y| img.get_pixel(x, y).0;
+    // Diagonal and horizontal flip: 90 degrees clockwise.
+    assert_eq!([px(0, 0), px(1, 0), px(0, 1), px(1, 1)], [b, r, w, g]);
+    // Diagonal and vertical flip: 90 degrees counterclockwise.
+    assert_eq!([px(2, 0), px(3, 0), px(2, 1), px(3, 1)], [g, w, r, b]);
+    // Horizontal and vertical flip: 180 degrees.
+    assert_eq!([px(4, 0), px(5, 0), px(4, 1), px(5, 1)], [w, b, g, r]);
+    // All three: mirrored along the other diagonal.
+    assert_eq!([px(6, 0), px(7, 0), px(6, 1), px(7, 1)], [w, g, b, r]);
+}
+
+#[test]
+fn tilemap_collision_mask() {
+    let pixels = vec![255; 3 * 4 * 4];
+    let data = build_test_file(
+        8,
+        2,
+        0,
+        &[
+            tileset_chunk(3, (2, 2), &pixels),
+            user_data_chunk("tileset"),
+            user_data_chunk("solid"),
+            user_data_chunk("solid"),
+            user_data_chunk("water"),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(4, 1, &[1, 2, 0, 0x8000_0001]),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let tilemap = f.tilemap(0, 0).unwrap();
+    // Tile 0 is the empty tile, so its user data is ignored.
+    assert_eq!(tilemap.collision_mask("solid"), [true, false, false, true]);
+    let texts = tilemap.classify(|user_data| user_data.and_then(|u| u.text.clone()));
+    assert_eq!(
+        texts,
+        [
+            Some("solid".to_owned()),
+            Some("water".to_owned()),
+            None,
+            Some("solid".to_owned())
+        ]
+    );
+}
+
+#[test]
+fn tilemap_diff() {
+    let pixels = vec![255; 3 * 4 * 4];
+    let read = |tiles: &[u32], width| {
+        let data = build_test_file(
+            8,
+            2,
+            0,
+            &[
+                tileset_chunk(3, (2, 2), &pixels),
+                tilemap_layer_chunk(),
+                tilemap_cel_chunk(width, 1, tiles),
+            ],
+        );
+        AsepriteFile::read(&data[..]).unwrap()
+    };
+    let a = read(&[1, 2, 1, 2], 4);
+    let b = read(&[1, 1, 0x8000_0001], 3);
+    let (a, b) = (a.tilemap(0, 0).unwrap(), b.tilemap(0, 0).unwrap());
+
+    assert!(a.diff(&a).is_empty());
+    let changes: Vec<_> = a
+        .diff(&b)
+        .iter()
+        .map(|c| (c.x, c.y, c.old.id(), c.new.id(), c.new.flip_x()))
+        .collect();
+    assert_eq!(
+        changes,
+        [(1, 0, 2, 1, false), (2, 0, 1, 1, true), (3, 0, 2, 0, false)]
+    );
+}
+
+#[test]
+fn slice_key_for_frame() {
+    let f = load_test_file("slice_advanced");
+    let slice_1 = &f.slices()[0];
+    for frame in 0..4 {
+        assert_eq!(slice_1.key_for_frame(frame).unwrap().from_frame, frame);
+    }
+    assert_eq!(slice_1.key_for_frame(100).unwrap().from_frame, 3);
+
+    let slice = Slice {
+        name: "late".to_owned(),
+        keys: vec![SliceKey {
+            from_frame: 2,
+            origin: Point::new(0, 0),
+            size: Size::new(1, 1),
+            slice9: None,
+            pivot: None,
+        }],
+        user_data: None,
+    };
+    assert!(slice.key_for_frame(0).is_none());
+    assert_eq!(slice.key_for_frame(2).unwrap().from_frame, 2);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn nine_slice_scaling() {
+    use image::{Rgba, RgbaImage};
+
+    // Each pixel stores its own coordinates.
+    let image = RgbaImage::from_fn(3, 3, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+    let slice9 = Slice9 {
+        center_x: 1,
+        center_y: 1,
+        center_width: 1,
+        center_height: 1,
+    };
+    let coords = |img: &RgbaImage| {
+        img.enumerate_pixels()
+            .map(|(_, _, p)| (p.0[0], p.0[1]))
+            .collect::<Vec<_>>()
+    };
+    let scaled = util::nine_slice(&image, &slice9, 5, 4);
+    assert_eq!(scaled.dimensions(), (5, 4));
+    let xs: Vec<u8> = coords(&scaled)[..5].iter().map(|c| c.0).collect();
+    let ys: Vec<u8> = coords(&scaled).iter().step_by(5).map(|c| c.1).collect();
+    assert_eq!(xs, [0, 1, 1, 1, 2]);
+    assert_eq!(ys, [0, 1, 1, 2]);
+    // Too small for both corners.
+    assert_eq!(
+        coords(&util::nine_slice(&image, &slice9, 1, 2)),
+        [(2, 0), (2, 2)]
+    );
+    // Empty sources give a transparent image.
+    for empty in [RgbaImage::new(0, 3), RgbaImage::new(3, 0)] {
+        let scaled = util::nine_slice(&empty, &slice9, 4, 2);
+        assert_eq!(scaled.dimensions(), (4, 2));
+        assert!(scaled.pixels().all(|p| p.0 == [0; 4]));
+    }
+
+    let f = load_test_file("slice_advanced");
+    let slices = f.slices();
+    assert!(util::nine_slice_frame(&f, 0, &slices[0], 10, 10).is_none());
+    let img = util::nine_slice_frame(&f, 0, &slices[1], 20, 12).unwrap();
+    assert_eq!(img.dimensions(), (20, 12));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn slices_json() {
+    use json::{AsepriteSlices, FlatSlice};
+
+    let f = load_test_file("slice_advanced");
+    let slices = AsepriteSlices::new(&f);
+    let value: serde_json::Value = serde_json::from_str(&slices.to_json_string().unwrap()).unwrap();
+    let slice_2 = &value["slices"][1];
+    assert_eq!(slice_2["keys"][0]["frame"], 0);
+    assert_eq!(
+        slice_2["keys"][0]["center"],
+        serde_json::json!({"x": 3, "y": 3, "w": 2, "h": 2})
+    );
+    assert_eq!(value["slices"][0]["keys"][0]["pivot"]["x"], 4);
+
+    let flat = FlatSlice::all(&f);
+    assert_eq!(flat.len(), 2 * f.num_frames() as usize);
+    let key = &f.slices()[0].keys[2];
+    let entry = &flat[2];
+    assert_eq!(
+        (entry.frame, entry.x, entry.y),
+        (2, key.origin.x, key.origin.y)
+    );
+    let parsed: Vec<FlatSlice> =
+        serde_json::from_str(&FlatSlice::to_json_string(&flat).unwrap()).unwrap();
+    assert_eq!(parsed, flat);
+}
+
+#[test]
+fn slice_normalized_pivot() {
+    let key = SliceKey {
+        from_frame: 0,
+        origin: Point::new(4, 8),
+        size: Size::new(8, 4),
+        slice9: None,
+        pivot: Some(Point::new(2, 4)),
+    };
+    assert_eq!(key.normalized_pivot(), Some((0.25, 1.0)));
+    assert_eq!(key.normalized_pivot_in_canvas(12, 24), Some((0.5, 0.5)));
+    let key = SliceKey { pivot: None, ..key };
+    assert_eq!(key.normalized_pivot(), None);
+
+    let f = load_test_file("slice_advanced");
+    let key = &f.slices()[0].keys[1];
+    let pivot = key.pivot.unwrap();
+    let expected = (
+        (key.origin.x + pivot.x) as f32 / f.width() as f32,
+        (key.origin.y + pivot.y) as f32 / f.height() as f32,
+    );
+    assert_eq!(f.frame(1).pivot("Slice 1"), Some(expected));
+    assert_eq!(f.frame(1).pivot("missing"), None);
+}
+
+#[test]
+fn slice_bounds_at() {
+    let f = load_test_file("slice_advanced");
+    let slice = &f.slices()[0];
+    for frame in 0..4 {
+        let key = &slice.keys[frame as usize];
+        let bounds = slice.bounds_at(frame).unwrap();
+        assert_eq!(bounds.origin(), key.origin);
+        assert_eq!(bounds.size(), key.size);
+    }
+
+    let rect = Rect::new(-2, 3, 6, 4);
+    assert_eq!((rect.right(), rect.bottom()), (4, 7));
+    assert!(rect.contains(-2, 6) && !rect.contains(4, 3));
+    assert_eq!(rect.clamp_to_canvas(3, 5), Some(Rect::new(0, 3, 3, 2)));
+    assert_eq!(rect.clamp_to_canvas(10, 3), None);
+}
+
+#[test]
+fn bitmap_font_from_slices() {
+    let f = load_test_file("slice_advanced");
+    let font = font::BitmapFont::from_slice_names(&f, 0, "Slice ");
+    let chars: Vec<char> = font.glyphs().iter().map(|g| g.character).collect();
+    assert_eq!(chars, ['1', '2']);
+    assert!(font.glyph('3').is_none());
+
+    let pivot = |key: &SliceKey| key.pivot.unwrap_or(Point::new(0, key.size.h as i32));
+    let keys = [&f.slices()[0].keys[0], &f.slices()[1].keys[0]];
+    let base = keys.iter().map(|k| pivot(k).y).max().unwrap();
+    assert_eq!(font.base(), base as u32);
+    for (glyph, key) in font.glyphs().iter().zip(keys) {
+        let Point { x: px, y: py } = pivot(key);
+        assert_eq!(Size::new(glyph.width, glyph.height), key.size);
+        assert_eq!(glyph.x_offset, -px);
+        assert_eq!(glyph.y_offset, base - py);
+        let glyph_image =
+            image::imageops::crop_imm(font.image(), glyph.x, glyph.y, glyph.width, glyph.height)
+                .to_image();
+        let frame_image = f.frame(0).image();
+        let expected = image::imageops::crop_imm(
+            &frame_image,
+            key.origin.x as u32,
+            key.origin.y as u32,
+            key.size.w,
+            key.size.h,
+        )
+        .to_image();
+        assert_eq!(glyph_image, expected);
+    }
+
+    let mut fnt = Vec::new();
+    font.write_bmfont("test", "test.png", &mut fnt).unwrap();
+    let fnt = String::from_utf8(fnt).unwrap();
+    assert!(fnt.contains("chars count=2"));
+    assert!(fnt.contains("char id=49 "));
+
+    let font =
+        font::BitmapFont::from_slices(&f, 0, |slice| (slice.name == "Slice 2").then_some(' '));
+    assert_eq!(font.glyphs().len(), 1);
+    assert_eq!(font.glyphs()[0].character, ' ');
+}
+
+#[test]
+fn sheet_slice_regions() {
+    use crate::sheet::{SheetLayout, SheetOptions, SpriteSheet};
+    let f = load_test_file("slice_advanced");
+    let options = SheetOptions {
+        layout: SheetLayout::HorizontalStrip,
+        ..Default::default()
+    };
+    assert!(SpriteSheet::new(&f, &options).regions().is_empty());
+
+    let options = SheetOptions {
+        include_slices: true,
+        ..options
+    };
+    let sheet = SpriteSheet::new(&f, &options);
+    assert_eq!(sheet.regions().len(), 2 * f.num_frames() as usize);
+    let frame = sheet.frame(2).unwrap();
+    let key = &f.slices()[0].keys[2];
+    let region = sheet.region("Slice 1", 2).unwrap();
+    assert_eq!(region.x, frame.x + key.origin.x as u32);
+    assert_eq!(region.y, frame.y + key.origin.y as u32);
+    assert_eq!(region.rect().size(), key.size);
+    assert_eq!(region.pivot, key.pivot);
+    assert!(sheet.region("Slice 3", 0).is_none());
+}
+
+#[test]
+fn slice_lookup() {
+    let f = load_test_file("slice_advanced");
+    assert_eq!(f.slice_by_name("Slice 2").unwrap().name, "Slice 2");
+    assert!(f.slice_by_name("Slice 3").is_none());
+    assert_eq!(f.slices_by_name("Slice 1").count(), 1);
+    let at_frame: Vec<_> = f
+        .slices_at_frame(2)
+        .map(|(slice, key)| (slice.name.as_str(), key.from_frame))
+        .collect();
+    assert_eq!(at_frame, [("Slice 1", 2), ("Slice 2", 0)]);
+}
+
+#[test]
+fn geometry_types() {
+    assert_eq!(Point::from((1, -2)), Point::new(1, -2));
+    assert_eq!(<(u32, u32)>::from(Size::new(3, 4)), (3, 4));
+    let rect = Rect::from_origin_size(Point::new(1, 2), Size::new(3, 4));
+    assert_eq!(
+        (rect.origin(), rect.size()),
+        (Point::new(1, 2), Size::new(3, 4))
+    );
+
+    let f = load_test_file("layers_and_tags");
+    for frame in 0..f.num_frames() {
+        let frame = f.frame(frame);
+        for layer in f.layers().filter(|l| l.layer_type() != LayerType::Group) {
+            let cel = frame.layer(layer.id());
+            match cel.bounds() {
+                Some(bounds) => {
+                    assert_eq!(bounds.origin(), cel.top_left());
+                    assert!(!bounds.is_empty());
+                }
+                None => assert!(cel.is_empty()),
+            }
+        }
+    }
+
+    let data = build_test_file(
+        8,
+        2,
+        0,
+        &[
+            tileset_chunk(2, (2, 2), &[255; 2 * 4 * 4]),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(4, 1, &[1, 1, 1, 1]),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    assert_eq!(f.frame(0).layer(0).bounds(), Some(Rect::new(0, 0, 8, 2)));
+    let tilemap = f.tilemap(0, 0).unwrap();
+    assert_eq!(tilemap.tile_size(), Size::new(2, 2));
+    assert_eq!(tilemap.tile_offsets(), Point::new(0, 0));
+}
+
+#[test]
+fn user_data_properties() {
+    let mut maps = Vec::new();
+    // User properties.
+    maps.extend_from_slice(&0u32.to_le_bytes());
+    maps.extend_from_slice(&4u32.to_le_bytes());
+    push_string(&mut maps, "solid");
+    maps.extend_from_slice(&[1, 0, 1]);
+    push_string(&mut maps, "speed");
+    maps.extend_from_slice(&0x000Bu16.to_le_bytes());
+    maps.extend_from_slice(&1.5f32.to_le_bytes());
+    push_string(&mut maps, "spawn");
+    maps.extend_from_slice(&0x000Eu16.to_le_bytes());
+    maps.extend_from_slice(&(-3i32).to_le_bytes());
+    maps.extend_from_slice(&4i32.to_le_bytes());
+    push_string(&mut maps, "list");
+    maps.extend_from_slice(&0x0011u16.to_le_bytes());
+    maps.extend_from_slice(&2u32.to_le_bytes());
+    maps.extend_from_slice(&0u16.to_le_bytes()); // mixed types
+    maps.extend_from_slice(&0x0003u16.to_le_bytes());
+    maps.push(7);
+    maps.extend_from_slice(&0x000Du16.to_le_bytes());
+    push_string(&mut maps, "x");
+    // Extension properties with a nested map.
+    maps.extend_from_slice(&3u32.to_le_bytes());
+    maps.extend_from_slice(&1u32.to_le_bytes());
+    push_string(&mut maps, "nested");
+    maps.extend_from_slice(&0x0012u16.to_le_bytes());
+    maps.extend_from_slice(&1u32.to_le_bytes());
+    push_string(&mut maps, "n");
+    maps.extend_from_slice(&0x0006u16.to_le_bytes());
+    maps.extend_from_slice(&(-5i32).to_le_bytes());
+
+    let mut user_data = vec![5, 0, 0, 0];
+    push_string(&mut user_data, "text");
+    user_data.extend_from_slice(&(maps.len() as u32 + 8).to_le_bytes());
+    user_data.extend_from_slice(&2u32.to_le_bytes());
+    user_data.extend_from_slice(&maps);
+
+    let data = build_test_file(
+        4,
+        4,
+        0,
+        &[tileset_chunk(1, (2, 2), &[0; 16]), (0x2020, user_data)],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let user_data = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .unwrap()
+        .user_data()
+        .unwrap();
+    assert_eq!(user_data.text.as_deref(), Some("text"));
+    let props = &user_data.properties;
+    assert_eq!(props.len(), 4);
+    assert_eq!(props.get("solid").and_then(|v| v.as_bool()), Some(true));
+    assert_eq!(props.get("speed").and_then(|v| v.as_f64()), Some(1.5));
+    assert_eq!(
+        props.get("spawn"),
+        Some(&PropertyValue::Point(Point::new(-3, 4)))
+    );
+    assert_eq!(
+        props.get("list"),
+        Some(&PropertyValue::Vector(vec![
+            PropertyValue::U8(7),
+            PropertyValue::String("x".to_owned())
+        ]))
+    );
+    let ext = user_data.extension(ExternalFileId::new(3)).unwrap();
+    let Some(PropertyValue::Properties(nested)) = ext.get("nested") else {
+        panic!("expected nested properties");
+    };
+    assert_eq!(nested.get("n").and_then(|v| v.as_i64()), Some(-5));
+}
+
+#[test]
+fn user_data_unknown_property_type() {
+    let mut maps = Vec::new();
+    maps.extend_from_slice(&0u32.to_le_bytes());
+    maps.extend_from_slice(&2u32.to_le_bytes());
+    push_string(&mut maps, "solid");
+    maps.extend_from_slice(&[1, 0, 1]);
+    // A type from a newer Aseprite version with a value of unknown size.
+    push_string(&mut maps, "future");
+    maps.extend_from_slice(&0x00FFu16.to_le_bytes());
+    maps.extend_from_slice(&[1, 2, 3, 4, 5]);
+    // Never reached.
+    maps.extend_from_slice(&3u32.to_le_bytes());
+    maps.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut user_data = vec![5, 0, 0, 0];
+    push_string(&mut user_data, "text");
+    user_data.extend_from_slice(&(maps.len() as u32 + 8).to_le_bytes());
+    user_data.extend_from_slice(&2u32.to_le_bytes());
+    user_data.extend_from_slice(&maps);
+
+    let data = build_test_file(
+        4,
+        4,
+        0,
+        &[tileset_chunk(1, (2, 2), &[0; 16]), (0x2020, user_data)],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let user_data = f
+        .tilesets()
+        .get(TilesetId::new(0))
+        .unwrap()
+        .user_data()
+        .unwrap();
+    assert_eq!(user_data.text.as_deref(), Some("text"));
+    assert_eq!(user_data.properties.len(), 1);
+    assert_eq!(
+        user_data.properties.get("solid").and_then(|v| v.as_bool()),
+        Some(true)
+    );
+    assert!(user_data.properties.get("future").is_none());
+    assert!(user_data.extension(ExternalFileId::new(3)).is_none());
+}
+
+#[test]
+fn user_data_nested_too_deep() {
+    // Properties with a single entry, nested in itself.
+    let user_data = |depth: usize| {
+        let mut maps = Vec::new();
+        maps.extend_from_slice(&0u32.to_le_bytes());
+        for _ in 0..depth {
+            maps.extend_from_slice(&1u32.to_le_bytes());
+            push_string(&mut maps, "n");
+            maps.extend_from_slice(&0x0012u16.to_le_bytes());
+        }
+        maps.extend_from_slice(&0u32.to_le_bytes());
+        let mut data = vec![4, 0, 0, 0];
+        data.extend_from_slice(&(maps.len() as u32 + 8).to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&maps);
+        build_test_file(
+            4,
+            4,
+            0,
+            &[tileset_chunk(1, (2, 2), &[0; 16]), (0x2020, data)],
+        )
+    };
+    assert!(AsepriteFile::read(&user_data(10)[..]).is_ok());
+    let err = AsepriteFile::read(&user_data(100_000)[..]).unwrap_err();
+    assert!(err.is_invalid_input(), "{}", err);
+}
+
+#[test]
+fn user_data_key_values() {
+    let user_data = UserData {
+        text: Some("a=1\n b = two ;flag\n\n".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(
+        user_data.key_values(),
+        [("a", "1"), ("b", "two"), ("flag", "")]
+    );
+    assert_eq!(user_data.value("b"), Some("two"));
+    assert_eq!(user_data.value("c"), None);
+    assert!(UserData::default().key_values().is_empty());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn user_data_json() {
+    let user_data = UserData {
+        text: Some(r#"{"solid": true, "tags": ["a", "b"]}"#.to_owned()),
+        ..Default::default()
+    };
+    let value = user_data.json().unwrap().unwrap();
+    assert_eq!(value["tags"][1], "b");
+    let map: std::collections::HashMap<String, serde_json::Value> =
+        user_data.parse().unwrap().unwrap();
+    assert_eq!(map["solid"], true);
+    assert!(UserData::default().json().is_none());
+    let invalid = UserData {
+        text: Some("solid=true".to_owned()),
+        ..Default::default()
+    };
+    assert!(invalid.json().unwrap().is_err());
+}
+
+#[test]
+fn palette_export() {
+    let f = load_test_file("palette");
+    let pal = f.palette().unwrap();
+
+    let mut gpl = Vec::new();
+    pal.write_gpl("Test", &mut gpl).unwrap();
+    let gpl = String::from_utf8(gpl).unwrap();
+    let lines: Vec<&str> = gpl.lines().collect();
+    assert_eq!(
+        lines[..4],
+        ["GIMP Palette", "Name: Test", "Columns: 16", "#"]
+    );
+    assert_eq!(lines.len(), 4 + 85);
+    assert_eq!(lines[4], " 46  34  47\tIndex 0");
+
+    let mut pal_file = Vec::new();
+    pal.write_jasc_pal(&mut pal_file).unwrap();
+    let pal_file = String::from_utf8(pal_file).unwrap();
+    assert!(pal_file.starts_with("JASC-PAL\r\n0100\r\n85\r\n46 34 47\r\n"));
+
+    let mut act = Vec::new();
+    pal.write_act(&mut act).unwrap();
+    assert_eq!(act.len(), 772);
+    assert_eq!(act[0..3], [46, 34, 47]);
+    assert_eq!(act[768..770], [0, 85]);
+    let transparent = (0..85).find(|&i| pal.color(i).unwrap().alpha() == 0);
+    let expected = transparent.map_or(0xffff, |i| i as u16).to_be_bytes();
+    assert_eq!(act[770..772], expected);
+}
+
+#[test]
+fn palette_import() {
+    let f = load_test_file("palette");
+    let pal = f.palette().unwrap();
+    let opaque = |p: &ColorPalette| -> Vec<[u8; 3]> {
+        (0..p.num_colors())
+            .map(|i| {
+                let [r, g, b, _] = p.color(i).unwrap().raw_rgba8();
+                [r, g, b]
+            })
+            .collect()
+    };
+
+    let mut gpl = Vec::new();
+    pal.write_gpl("Test", &mut gpl).unwrap();
+    let from_gpl = ColorPalette::read(&gpl[..]).unwrap();
+    assert_eq!(opaque(&from_gpl), opaque(pal));
+    assert_eq!(from_gpl.color(3).unwrap().name(), Some("Index 3"));
+
+    let mut jasc = Vec::new();
+    pal.write_jasc_pal(&mut jasc).unwrap();
+    assert_eq!(opaque(&ColorPalette::read(&jasc[..]).unwrap()), opaque(pal));
+
+    let hex = ColorPalette::read("2e222f\n#3e3546\n\n".as_bytes()).unwrap();
+    assert_eq!(hex.num_colors(), 2);
+    assert_eq!(hex.color(1).unwrap().raw_rgba8(), [0x3e, 0x35, 0x46, 255]);
+
+    assert!(ColorPalette::read("zzz".as_bytes()).is_err());
+    assert!(ColorPalette::read("+2e222f".as_bytes()).is_err());
+    assert!(ColorPalette::read("#+2e222f".as_bytes()).is_err());
+    assert!(ColorPalette::read("+2e222f0".as_bytes()).is_err());
+    assert!(ColorPalette::read("JASC-PAL\n0100\n2\n1 2 3\n".as_bytes()).is_err());
+}
+
+#[test]
+fn palette_iteration() {
+    let f = load_test_file("palette");
+    let pal = f.palette().unwrap();
+    let ids: Vec<u32> = pal.iter().map(|e| e.id()).collect();
+    assert_eq!(ids, (0..85).collect::<Vec<_>>());
+    assert_eq!(pal.iter().len(), 85);
+    assert_eq!(pal.iter().next_back().unwrap().id(), 84);
+    assert_eq!(pal.colors().next().unwrap().0, [46, 34, 47, 255]);
+    assert_eq!(pal.into_iter().count(), 85);
+}
+
+#[test]
+fn palette_nearest() {
+    use image::Rgba;
+
+    let pal = ColorPalette::from_colors([
+        [0, 0, 0, 255],
+        [255, 0, 0, 255],
+        [0, 0, 255, 255],
+        [255, 0, 0, 255],
+    ]);
+    for metric in [
+        ColorDistance::Rgb,
+        ColorDistance::WeightedRgb,
+        ColorDistance::Ciede2000,
+    ] {
+        assert_eq!(pal.nearest(Rgba([250, 10, 10, 255]), metric), Some(1));
+        assert_eq!(pal.nearest(Rgba([20, 20, 200, 0]), metric), Some(2));
+        assert_eq!(
+            metric.distance(Rgba([1, 2, 3, 4]), Rgba([1, 2, 3, 255])),
+            0.0
+        );
+    }
+    assert_eq!(
+        pal.nearest_by(Rgba([0, 0, 0, 255]), |_, b| -(b[2] as f64)),
+        Some(2)
+    );
+    assert_eq!(
+        ColorPalette::from_colors([]).nearest(Rgba([0; 4]), ColorDistance::Rgb),
+        None
+    );
+
+    // Test data from Sharma et al.
+    let cases = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+        ([2.0776, 0.0795, -1.135], [0.9033, -0.0636, -0.5514], 0.9082),
+    ];
+    for (lab1, lab2, expected) in cases {
+        let actual = palette::ciede2000(lab1, lab2);
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn palette_mapper_alpha() {
+    use crate::util::{MappingOptions, PaletteMapper};
+
+    let pal = ColorPalette::from_colors([
+        [0, 0, 0, 0],
+        [255, 0, 0, 255],
+        [255, 0, 0, 128],
+        [0, 255, 0, 64],
+    ]);
+    let opaque_only = PaletteMapper::new(
+        &pal,
+        MappingOptions {
+            failure: 9,
+            transparent: Some(0),
+            ..Default::default()
+        },
+    );
+    assert_eq!(opaque_only.lookup(255, 0, 0, 255), 1);
+    assert_eq!(opaque_only.lookup(255, 0, 0, 128), 0);
+    // Translucent entries are matched as if they were opaque.
+    assert_eq!(opaque_only.lookup(0, 255, 0, 255), 3);
+
+    let mapper = PaletteMapper::new(
+        &pal,
+        MappingOptions {
+            failure: 9,
+            transparent: Some(0),
+            match_alpha: true,
+        },
+    );
+    assert_eq!(mapper.lookup(255, 0, 0, 255), 1);
+    assert_eq!(mapper.lookup(255, 0, 0, 128), 2);
+    assert_eq!(mapper.lookup(0, 255, 0, 64), 3);
+    assert_eq!(mapper.lookup(0, 255, 0, 255), 9);
+    assert_eq!(mapper.lookup(12, 34, 56, 0), 0);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn palette_mapper_duplicate_colors() {
+    use crate::util::{MappingOptions, PaletteMapper};
+
+    let red = [255, 0, 0, 255];
+    let pal = ColorPalette::from_colors([[0, 0, 0, 255], red, [0, 0, 255, 255], red, red]);
+    let mapper = PaletteMapper::new(&pal, MappingOptions::default());
+    assert_eq!(mapper.lookup(255, 0, 0, 255), 1);
+    assert_eq!(mapper.lookup(0, 0, 255, 255), 2);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn quantize_images() {
+    let mut image = image::RgbaImage::new(4, 1);
+    image.put_pixel(1, 0, image::Rgba([250, 0, 0, 255]));
+    image.put_pixel(2, 0, image::Rgba([255, 0, 0, 255]));
+    image.put_pixel(3, 0, image::Rgba([0, 0, 255, 255]));
+    let quantized = crate::util::quantize(&[image.clone(), image], 3);
+    let palette = &quantized.palette;
+    assert_eq!(palette.num_colors(), 3);
+    assert_eq!(palette.color(0).unwrap().raw_rgba8(), [0, 0, 0, 0]);
+    assert_eq!(quantized.images.len(), 2);
+    let ((w, h), data) = &quantized.images[0];
+    assert_eq!((*w, *h), (4, 1));
+    assert_eq!(data[0], 0);
+    assert_eq!(data[1], data[2]);
+    assert_ne!(data[1], data[3]);
+    let red = palette.color(data[1] as u32).unwrap().raw_rgba8();
+    assert_eq!(red, [253, 0, 0, 255]);
+    let blue = palette.color(data[3] as u32).unwrap().raw_rgba8();
+    assert_eq!(blue, [0, 0, 255, 255]);
+
+    let ase = load_test_file("layers_and_tags");
+    let frames: Vec<_> = (0..ase.num_frames())
+        .map(|f| ase.frame(f).image())
+        .collect();
+    let quantized = crate::util::quantize(&frames, 2);
+    assert!(quantized.palette.num_colors() <= 2);
+    assert!(quantized
+        .images
+        .iter()
+        .all(|(_, d)| d.iter().all(|&i| i < 2)));
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn trim_transparent() {
+    use crate::util::{opaque_bounds, trim_transparent};
+    let mut image = image::RgbaImage::new(5, 4);
+    assert!(trim_transparent(&image).is_none());
+    image.put_pixel(1, 2, image::Rgba([255, 0, 0, 255]));
+    image.put_pixel(3, 1, image::Rgba([0, 255, 0, 1]));
+    let trimmed = trim_transparent(&image).unwrap();
+    assert_eq!(trimmed.rect, Rect::new(1, 1, 3, 2));
+    assert_eq!(opaque_bounds(&image), Some(trimmed.rect));
+    assert_eq!(trimmed.image.dimensions(), (3, 2));
+    assert_eq!(trimmed.image.get_pixel(0, 1).0, [255, 0, 0, 255]);
+    assert_eq!(trimmed.image.get_pixel(2, 0).0, [0, 255, 0, 1]);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn premultiply_alpha() {
+    use crate::util::{premultiply_alpha, premultiply_alpha_buffer, unpremultiply_alpha_buffer};
+    let mut data = [255, 128, 1, 128, 10, 20, 30, 0, 200, 100, 50, 255];
+    premultiply_alpha_buffer(&mut data);
+    assert_eq!(data, [128, 64, 1, 128, 0, 0, 0, 0, 200, 100, 50, 255]);
+    unpremultiply_alpha_buffer(&mut data);
+    assert_eq!(data, [255, 128, 2, 128, 0, 0, 0, 0, 200, 100, 50, 255]);
+
+    // Matches exact rounding for all inputs.
+    for c in 0..=255u32 {
+        for a in 0..=255u32 {
+            let mut pixel = [c as u8, 0, 0, a as u8];
+            premultiply_alpha_buffer(&mut pixel);
+            assert_eq!(pixel[0] as u32, (c * a + 127) / 255);
+        }
+    }
+
+    let mut image = image::RgbaImage::from_pixel(2, 2, image::Rgba([100, 200, 255, 51]));
+    premultiply_alpha(&mut image);
+    assert_eq!(image.get_pixel(1, 1).0, [20, 40, 51, 51]);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn outline() {
+    use crate::util::{outline, OutlineOptions, OutlinePlace, OutlineShape};
+    let red = image::Rgba([255, 0, 0, 255]);
+    let black = image::Rgba([0, 0, 0, 255]);
+    let mut image = image::RgbaImage::new(5, 5);
+    image.put_pixel(2, 2, red);
+    let mask = |image: &image::RgbaImage| -> Vec<String> {
+        (0..5)
+            .map(|y| {
+                (0..5)
+                    .map(|x| match image.get_pixel(x, y).0 {
+                        [255, 0, 0, 255] => 'r',
+                        [0, 0, 0, 255] => 'o',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+
+    let circle = outline(&image, &OutlineOptions::default());
+    assert_eq!(mask(&circle), [".....", "..o..", ".oro.", "..o..", "....."]);
+
+    let square = outline(
+        &image,
+        &OutlineOptions {
+            shape: OutlineShape::Square,
+            thickness: 2,
+            ..Default::default()
+        },
+    );
+    assert_eq!(mask(&square), ["ooooo", "ooooo", "ooroo", "ooooo", "ooooo"]);
+
+    let horizontal = outline(
+        &image,
+        &OutlineOptions {
+            shape: OutlineShape::Horizontal,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        mask(&horizontal),
+        [".....", ".....", ".oro.", ".....", "....."]
+    );
+
+    let full = image::RgbaImage::from_pixel(5, 5, red);
+    let inside = outline(
+        &full,
+        &OutlineOptions {
+            place: OutlinePlace::Inside,
+            color: black,
+            ..Default::default()
+        },
+    );
+    assert_eq!(mask(&inside), ["ooooo", "orrro", "orrro", "orrro", "ooooo"]);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn adjust_colors() {
+    use crate::util::{adjust_colors, ColorAdjustment};
+    use image::Rgba;
+    let red = Rgba([255, 0, 0, 255]);
+    assert_eq!(
+        ColorAdjustment::HueShift(120.0).apply(red),
+        Rgba([0, 255, 0, 255])
+    );
+    assert_eq!(
+        ColorAdjustment::HueShift(-120.0).apply(red),
+        Rgba([0, 0, 255, 255])
+    );
+    assert_eq!(
+        ColorAdjustment::Multiply(Rgba([128, 255, 255, 128])).apply(red),
+        Rgba([128, 0, 0, 128])
+    );
+    assert_eq!(
+        ColorAdjustment::Tint(Rgba([0, 0, 255, 255])).apply(red),
+        Rgba([0, 0, 255, 255])
+    );
+    assert_eq!(
+        ColorAdjustment::Grayscale.apply(red),
+        Rgba([76, 76, 76, 255])
+    );
+    assert_eq!(
+        ColorAdjustment::Brightness(0.5).apply(Rgba([0, 200, 0, 10])),
+        Rgba([128, 255, 128, 10])
+    );
+    assert_eq!(
+        ColorAdjustment::Contrast(0.0).apply(red),
+        Rgba([128, 128, 128, 255])
+    );
+
+    let mut image = image::RgbaImage::from_pixel(2, 1, red);
+    image.put_pixel(1, 0, Rgba([255, 0, 0, 0]));
+    adjust_colors(
+        &mut image,
+        &[
+            ColorAdjustment::Grayscale,
+            ColorAdjustment::Brightness(-1.0),
+        ],
+    );
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0, 0]);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn alpha_mask() {
+    let mut image = image::RgbaImage::new(3, 2);
+    image.put_pixel(0, 0, image::Rgba([0, 0, 0, 255]));
+    image.put_pixel(2, 1, image::Rgba([0, 0, 0, 100]));
+    let mask = crate::util::alpha_mask(&image, 100);
+    assert_eq!((mask.width(), mask.height()), (3, 2));
+    assert_eq!(mask.as_slice(), &[true, false, false, false, false, true]);
+    assert_eq!(mask.count(), 2);
+    assert!(mask.get(2, 1));
+    assert!(!mask.get(-1, 0));
+    assert!(!mask.get(3, 1));
+    assert_eq!(mask.to_image().get_pixel(0, 0).0, [255]);
+    assert_eq!(crate::util::alpha_mask(&image, 101).count(), 1);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn collision_polygons() {
+    use crate::util::alpha_mask;
+    let mask_from = |rows: &[&str]| {
+        let image = image::RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            let set = rows[y as usize].as_bytes()[x as usize] == b'#';
+            image::Rgba([0, 0, 0, if set { 255 } else { 0 }])
+        });
+        alpha_mask(&image, 1)
+    };
+    let points =
+        |coords: &[(i32, i32)]| -> Vec<Point> { coords.iter().map(|&c| Point::from(c)).collect() };
+
+    let empty = mask_from(&["..", ".."]);
+    assert!(empty.convex_hull().is_empty());
+    assert!(empty.outlines(0.0).is_empty());
+
+    let single = mask_from(&["#"]);
+    assert_eq!(
+        single.convex_hull(),
+        points(&[(0, 0), (1, 0), (1, 1), (0, 1)])
+    );
+    assert_eq!(
+        single.outlines(0.0),
+        vec![points(&[(0, 0), (1, 0), (1, 1), (0, 1)])]
+    );
+
+    // An L shape.
+    let l_shape = mask_from(&["#..", "#..", "###"]);
+    assert_eq!(
+        l_shape.convex_hull(),
+        points(&[(0, 0), (1, 0), (3, 2), (3, 3), (0, 3)])
+    );
+    assert_eq!(
+        l_shape.outlines(0.0),
+        vec![points(&[(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 3)])]
+    );
+    // With enough tolerance, the inner corner disappears.
+    assert_eq!(l_shape.outlines(1.0).len(), 1);
+    assert!(l_shape.outlines(1.0)[0].len() < 6);
+
+    // A ring has an outer boundary and a hole.
+    let ring = mask_from(&["###", "#.#", "###"]);
+    let outlines = ring.outlines(0.0);
+    assert_eq!(outlines.len(), 2);
+    assert_eq!(outlines[0], points(&[(0, 0), (3, 0), (3, 3), (0, 3)]));
+    assert_eq!(outlines[1], points(&[(2, 1), (1, 1), (1, 2), (2, 2)]));
+
+    // Diagonal neighbors are separate regions.
+    let diagonal = mask_from(&["#.", ".#"]);
+    assert_eq!(diagonal.outlines(0.0).len(), 2);
+}
+
+#[test]
+fn frame_content_bounds() {
+    let names = [
+        "layers_and_tags",
+        "linked_cels",
+        "tilemap",
+        "cel_overflow",
+        "indexed",
+        "background",
+    ];
+    for name in names {
+        let f = load_test_file(name);
+        for frame in 0..f.num_frames() {
+            let frame = f.frame(frame);
+            assert_eq!(
+                frame.content_bounds(),
+                crate::geometry::opaque_bounds(&frame.image()),
+                "{} frame {}",
+                name,
+                frame.id()
+            );
+        }
+    }
+    assert_eq!(
+        Rect::new(0, 0, 2, 2).union(&Rect::new(3, -1, 1, 1)),
+        Rect::new(0, -1, 4, 3)
+    );
+    assert_eq!(
+        Rect::new(0, 0, 0, 2).union(&Rect::new(3, 3, 1, 1)),
+        Rect::new(3, 3, 1, 1)
+    );
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn pixel_art_scaling() {
+    use crate::util::{eagle, scale2x, scale3x, scale_nearest};
+    let (x, o) = (image::Rgba([0, 0, 0, 255]), image::Rgba([0, 0, 0, 0]));
+    let image_from = |rows: &[&str]| {
+        image::RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |cx, cy| {
+            if rows[cy as usize].as_bytes()[cx as usize] == b'#' {
+                x
+            } else {
+                o
+            }
+        })
+    };
+    let rows_of = |image: &image::RgbaImage| -> Vec<String> {
+        (0..image.height())
+            .map(|cy| {
+                (0..image.width())
+                    .map(|cx| {
+                        if image.get_pixel(cx, cy)[3] != 0 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    // A diagonal line.
+    let diagonal = image_from(&["#..", ".#.", "..#"]);
+
+    assert_eq!(
+        rows_of(&scale_nearest(&diagonal, 2)),
+        ["##....", "##....", "..##..", "..##..", "....##", "....##"]
+    );
+    assert_eq!(
+        rows_of(&scale2x(&diagonal)),
+        ["##....", "#.#...", ".###..", "..###.", "...#.#", "....##"]
+    );
+    assert_eq!(
+        rows_of(&scale3x(&diagonal)),
+        [
+            "###......",
+            "##.#.....",
+            "#..#.....",
+            ".#####...",
+            "...###...",
+            "...#####.",
+            ".....#..#",
+            ".....#.##",
+            "......###"
+        ]
+    );
+    assert_eq!(
+        rows_of(&eagle(&image_from(&["##", "#."]))),
+        ["####", "####", "###.", "##.."]
+    );
+
+    // Uniform images stay uniform.
+    let solid = image::RgbaImage::from_pixel(2, 2, x);
+    assert!(scale3x(&solid).pixels().all(|p| *p == x));
+    assert!(eagle(&solid).pixels().all(|p| *p == x));
+}
+
+#[test]
+fn texture_data() {
+    use crate::texture::{TextureData, WGPU_ROW_ALIGNMENT};
+    let mut image = image::RgbaImage::new(3, 2);
+    image.put_pixel(2, 1, image::Rgba([1, 2, 3, 4]));
+    let packed = TextureData::from_image(&image, 1);
+    assert_eq!(packed.bytes_per_row, 12);
+    assert_eq!(packed.data, image.as_raw().as_slice());
+
+    let padded = TextureData::from_image(&image, 8);
+    assert_eq!(padded.bytes_per_row, 16);
+    assert_eq!(padded.data.len(), 32);
+    assert_eq!(padded.row(0, 1), &image.as_raw()[12..]);
+    assert_eq!(&padded.data[28..], &[0; 4]);
+
+    let f = load_test_file("layers_and_tags");
+    let frames = TextureData::from_frames(&f, [3, 0], WGPU_ROW_ALIGNMENT);
+    assert_eq!(frames.extent(), (16, 16, 2));
+    assert_eq!(frames.data.len(), 256 * 16 * 2);
+    let frame3 = f.frame(3).image();
+    assert_eq!(frames.row(0, 5), &frame3.as_raw()[5 * 64..6 * 64]);
+
+    let f = load_test_file("tileset");
+    let tileset = f.tilesets().get(TilesetId::new(0)).unwrap();
+    let tiles = TextureData::from_tileset(tileset, 1);
+    let (w, h): (u32, u32) = tileset.tile_size().into();
+    assert_eq!(tiles.extent(), (w, h, tileset.tile_count()));
+    assert_eq!(tiles.data, tileset.image().into_raw());
+}
+
+#[cfg(feature = "capi")]
+#[test]
+fn capi() {
+    use crate::capi::*;
+    use std::ffi::{CStr, CString};
+    unsafe {
+        let missing = CString::new("./tests/data/missing.aseprite").unwrap();
+        assert!(asefile_open(missing.as_ptr()).is_null());
+        assert!(!CStr::from_ptr(asefile_last_error()).to_bytes().is_empty());
+
+        let path = CString::new("./tests/data/layers_and_tags.aseprite").unwrap();
+        let handle = asefile_open(path.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!((asefile_width(handle), asefile_height(handle)), (16, 16));
+        assert_eq!(asefile_num_frames(handle), 4);
+        assert_eq!(asefile_frame_duration_ms(handle, 0), 100);
+        assert_eq!(asefile_num_layers(handle), 6);
+        let layer_name = CStr::from_ptr(asefile_layer_name(handle, 0));
+        let f = load_test_file("layers_and_tags");
+        assert_eq!(layer_name.to_str().unwrap(), f.layer(0).name());
+        assert!(asefile_layer_name(handle, 100).is_null());
+        assert_eq!(asefile_num_tags(handle), f.num_tags());
+        let tag_name = CStr::from_ptr(asefile_tag_name(handle, 0));
+        assert_eq!(tag_name.to_str().unwrap(), f.tag(0).name());
+        assert_eq!(asefile_tag_to_frame(handle, 0), f.tag(0).to_frame());
+        assert_eq!(asefile_tag_direction(handle, 0), 0);
+        assert_eq!(asefile_tag_direction(handle, 100), u32::MAX);
+
+        let mut pixels = vec![0u8; 16 * 16 * 4];
+        assert_eq!(asefile_frame_rgba(handle, 1, pixels.as_mut_ptr(), 10), -1);
+        assert_eq!(
+            asefile_frame_rgba(handle, 1, pixels.as_mut_ptr(), pixels.len()),
+            0
+        );
+        assert_eq!(pixels, f.frame(1).image().into_raw());
+        assert_eq!(
+            asefile_frame_rgba(handle, 4, pixels.as_mut_ptr(), pixels.len()),
+            -1
+        );
+        asefile_free(handle);
+
+        let data = std::fs::read("./tests/data/basic-16x16.aseprite").unwrap();
+        let handle = asefile_open_memory(data.as_ptr(), data.len());
+        assert_eq!(asefile_num_frames(handle), 1);
+        asefile_free(handle);
+        assert_eq!(asefile_width(std::ptr::null()), 0);
+    }
+}
+
+// Only covers success paths: creating JS errors panics outside of wasm.
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm_bindings() {
+    use crate::wasm::WasmAsepriteFile;
+    let data = std::fs::read("./tests/data/layers_and_tags.aseprite").unwrap();
+    let ase = WasmAsepriteFile::new(&data).unwrap();
+    assert_eq!((ase.width(), ase.height(), ase.num_frames()), (16, 16, 4));
+    assert_eq!(ase.frame_duration(0).unwrap(), 100);
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(ase.frame_rgba(2).unwrap(), f.frame(2).image().into_raw());
+    assert_eq!(ase.layer_names().len(), f.num_layers() as usize);
+    let tags = ase.tags();
+    assert_eq!(tags.len(), f.num_tags() as usize);
+    assert_eq!(tags[0].name(), f.tag(0).name());
+    assert_eq!(tags[0].direction(), "forward");
+}
+
+#[test]
+fn raw_chunks() {
+    use crate::raw::{self, RawChunk};
+
+    let path = PathBuf::from("./tests/data/layers_and_tags.aseprite");
+    let file_data = std::fs::read(path).unwrap();
+    let chunks = raw::chunks(&file_data[..]).unwrap();
+    assert_eq!(chunks.num_frames(), 4);
+    let chunks: Vec<RawChunk> = chunks.collect::<Result<_>>().unwrap();
+    assert_eq!(chunks.len(), 24);
+    assert!(chunks.windows(2).all(|w| w[0].frame <= w[1].frame));
+    assert_eq!(chunks.last().unwrap().frame, 3);
+    let layers = chunks.iter().filter(|c| c.chunk_type == RawChunk::LAYER);
+    assert!(layers.clone().all(|c| c.frame == 0));
+    assert_eq!(layers.count(), 6);
+    assert_eq!(chunks[0].type_name(), Some("Color profile"));
+
+    // Unknown chunk types are returned as they are.
+    let data = build_test_file(1, 1, 0, &[(0x1234, vec![1, 2, 3])]);
+    assert!(AsepriteFile::read(&data[..]).is_err());
+    let chunk = raw::chunks(&data[..]).unwrap().next().unwrap().unwrap();
+    assert_eq!((chunk.frame, chunk.chunk_type), (0, 0x1234));
+    assert_eq!(chunk.data, [1, 2, 3]);
+    assert_eq!(chunk.type_name(), None);
+
+    // Truncated files yield the chunks before the error, then the error.
+    let truncated: Vec<_> = raw::chunks(&file_data[..600]).unwrap().collect();
+    assert!(truncated.last().unwrap().is_err());
+    assert!(truncated[..truncated.len() - 1].iter().all(|c| c.is_ok()));
+
+    assert!(raw::chunks(&[0_u8; 128][..]).is_err());
+}
+
+#[test]
+fn custom_chunk_handlers() {
+    use crate::raw::RawChunk;
+
+    #[derive(Debug, PartialEq, Clone)]
+    struct Custom(Vec<(u32, Vec<u8>)>);
+    #[derive(Clone)]
+    struct LayerCount(u32);
+
+    let data = build_test_file(
+        1,
+        1,
+        0,
+        &[(0x4242, vec![1, 2]), (0x4242, vec![3]), (0x1234, vec![])],
+    );
+    // Unknown chunk types without handler are still an error.
+    let options = ParseOptions::new().chunk_handler(0x4242, |_, _| Ok(()));
+    assert!(AsepriteFile::read_with_options(&data[..], options).is_err());
+
+    let options = ParseOptions::new()
+        .chunk_handler(0x4242, |chunk, extensions| {
+            let custom = extensions.get_or_insert_with(|| Custom(Vec::new()));
+            custom.0.push((chunk.frame, chunk.data.clone()));
+            Ok(())
+        })
+        .chunk_handler(0x1234, |_, _| Ok(()));
+    let f = AsepriteFile::read_with_options(&data[..], options).unwrap();
+    assert_eq!(
+        f.extensions().get::<Custom>(),
+        Some(&Custom(vec![(0, vec![1, 2]), (0, vec![3])]))
+    );
+    assert_eq!(f.extensions().len(), 1);
+
+    // Handlers for known chunk types see the chunks, which are then parsed
+    // as usual.
+    let path = PathBuf::from("./tests/data/layers_and_tags.aseprite");
+    let input = std::fs::File::open(&path).unwrap();
+    let options = ParseOptions::new().chunk_handler(RawChunk::LAYER, |_, extensions| {
+        extensions.get_or_insert_with(|| LayerCount(0)).0 += 1;
+        Ok(())
+    });
+    let mut f = AsepriteFile::read_with_options(input, options).unwrap();
+    assert_eq!(f.num_layers(), 6);
+    assert_eq!(f.extensions().get::<LayerCount>().unwrap().0, 6);
+    assert_eq!(f.extensions_mut().remove::<LayerCount>().unwrap().0, 6);
+    assert!(f.extensions().is_empty());
+
+    // Handler errors abort parsing.
+    let input = std::fs::File::open(&path).unwrap();
+    let options = ParseOptions::new().chunk_handler(RawChunk::TAGS, |_, _| {
+        Err(AsepriteParseError::InvalidInput("bad tags".to_owned()))
+    });
+    let err = AsepriteFile::read_with_options(input, options).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid Aseprite input: bad tags");
+}
+
+#[test]
+fn public_ase_reader() {
+    use crate::raw::{AseReader, RawChunk};
+
+    #[derive(Clone)]
+    struct TagNames(Vec<String>);
+
+    let path = PathBuf::from("./tests/data/layers_and_tags.aseprite");
+    let input = std::fs::File::open(path).unwrap();
+    let options = ParseOptions::new().chunk_handler(RawChunk::TAGS, |chunk, extensions| {
+        let mut reader = AseReader::new(&chunk.data);
+        let num_tags = reader.word()?;
+        reader.skip_reserved(8)?;
+        let mut names = Vec::new();
+        for _ in 0..num_tags {
+            reader.skip_reserved(17)?; // frames, direction, repeat, reserved, color
+            names.push(reader.string()?);
+        }
+        extensions.insert(TagNames(names));
+        Ok(())
+    });
+    let f = AsepriteFile::read_with_options(input, options).unwrap();
+    let names = &f.extensions().get::<TagNames>().unwrap().0;
+    assert_eq!(names, &["T1", "T3", "T2"]);
+
+    let mut reader = AseReader::new(&[0x34, 0x12, 0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(reader.word().unwrap(), 0x1234);
+    assert_eq!(reader.long().unwrap(), -1);
+    assert!(reader.byte().is_err());
+}
+
+#[test]
+fn parse_progress() {
+    let path = PathBuf::from("./tests/data/layers_and_tags.aseprite");
+    let data = std::fs::read(path).unwrap();
+    let mut updates = Vec::new();
+    let options = ParseOptions::new().progress(|progress| updates.push(progress));
+    AsepriteFile::read_with_options(&data[..], options).unwrap();
+
+    assert_eq!(updates.len(), 5);
+    assert_eq!(updates[0].bytes_read, 128);
+    assert_eq!(updates[0].frames_parsed, 0);
+    assert!(updates.iter().all(|p| p.total_frames == 4));
+    assert!(updates.iter().all(|p| p.total_bytes == data.len() as u64));
+    assert!(updates
+        .windows(2)
+        .all(|w| w[0].bytes_read < w[1].bytes_read));
+    let last = updates.last().unwrap();
+    assert_eq!(last.frames_parsed, 4);
+    assert_eq!(last.bytes_read, data.len() as u64);
+    assert_eq!(last.fraction(), 1.0);
+}
+
+#[test]
+fn lint() {
+    assert_eq!(load_test_file("layers_and_tags").lint(), []);
+    assert_eq!(
+        load_test_file("tilemap").lint(),
+        [LintWarning::UnusedTileset {
+            tileset: TilesetId::new(1)
+        }]
+    );
+
+    let layer = image_layer_chunk("a");
+    let cel = raw_cel_chunk(0, 10, 10, [255, 0, 0, 255]);
+    let tag = |from: u8, to: u8, name: u8| {
+        vec![
+            from, 0, to, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, name,
+        ]
+    };
+    let mut tags = vec![3, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    tags.extend(tag(0, 0, b'x'));
+    tags.extend(tag(0, 5, b'y'));
+    tags.extend(tag(0, 0, b'x'));
+    let data = build_test_file(4, 4, 0, &[layer.clone(), layer, cel, (0x2018, tags)]);
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let warnings = f.lint();
+    assert_eq!(
+        warnings,
+        [
+            LintWarning::DuplicateLayerName {
+                name: "a".to_owned(),
+                layers: vec![0, 1]
+            },
+            LintWarning::DuplicateTagName {
+                name: "x".to_owned(),
+                tags: vec![0, 2]
+            },
+            LintWarning::InvalidTagRange {
+                tag: 1,
+                from_frame: 0,
+                to_frame: 5
+            },
+            LintWarning::CelOutsideCanvas { layer: 0, frame: 0 },
+            LintWarning::EmptyLayer { layer: 0 },
+            LintWarning::EmptyLayer { layer: 1 },
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "Layers [0, 1] share the name \"a\""
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans() {
+    use std::sync::{Arc, Mutex};
+    use tracing::{span, subscriber, Event, Metadata, Subscriber};
+
+    // Records the names of all created spans.
+    struct Recorder(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            span::Id::from_u64(names.len() as u64)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+    subscriber::with_default(Recorder(names.clone()), || {
+        let f = load_test_file("layers_and_tags");
+        f.frame(0).image();
+    });
+    let names = names.lock().unwrap();
+    let count = |name| names.iter().filter(|n| **n == name).count();
+    assert_eq!(count("read_aseprite"), 1);
+    assert_eq!(count("header"), 1);
+    assert_eq!(count("frame"), 4);
+    assert_eq!(count("chunk"), 24);
+    assert_eq!(count("validate"), 1);
+    assert_eq!(count("frame_image"), 1);
+}
+
+#[test]
+fn structured_errors() {
+    let err = AsepriteFile::read(&[0u8; 128][..]).unwrap_err();
+    assert!(matches!(
+        err,
+        AsepriteParseError::BadMagicNumber {
+            expected: 0xA5E0,
+            found: 0
+        }
+    ));
+    assert!(err.is_invalid_input());
+    assert_eq!(
+        err.to_string(),
+        "Invalid Aseprite input: Invalid magic number for header: 0 != a5e0"
+    );
+
+    let mut data = build_test_file(16, 16, 0, &[]);
+    data[132..134].copy_from_slice(&[0, 0]);
+    let err = AsepriteFile::read(&data[..]).unwrap_err();
+    assert!(matches!(
+        err,
+        AsepriteParseError::BadFrameMagicNumber {
+            expected: 0xF1FA,
+            found: 0
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Invalid Aseprite input: Invalid magic number for frame: 0 != f1fa"
+    );
+
+    let err =
+        AsepriteFile::read(&build_test_file(16, 16, 0, &[user_data_chunk("x")])[..]).unwrap_err();
+    assert!(matches!(err, AsepriteParseError::DanglingUserData));
+
+    let err = AsepriteParseError::IoError(std::io::ErrorKind::UnexpectedEof.into());
+    assert!(!err.is_invalid_input());
+}
+
+#[test]
+fn owned_handles() {
+    fn assert_static_send_sync<T: Send + Sync + 'static>() {}
+    assert_static_send_sync::<OwnedFrame>();
+    assert_static_send_sync::<OwnedCel>();
+    assert_static_send_sync::<OwnedLayer>();
+
+    let f = std::sync::Arc::new(load_test_file("layers_and_tags"));
+    let frames: Vec<OwnedFrame> = OwnedFrame::all(&f).collect();
+    let layers: Vec<OwnedLayer> = OwnedLayer::all(&f).collect();
+    assert_eq!(frames.len() as u32, f.num_frames());
+    assert_eq!(layers.len() as u32, f.num_layers());
+
+    let frame = frames[1].clone();
+    assert_eq!(frame.image(), f.frame(1).image());
+    assert_eq!(frame.duration(), f.frame(1).duration());
+
+    for layer in &layers {
+        let borrowed = f.layer(layer.id());
+        assert_eq!(layer.name(), borrowed.name());
+        assert_eq!(layer.path(), borrowed.path());
+        assert_eq!(
+            layer.parent().map(|p| p.id()),
+            borrowed.parent().map(|p| p.id())
+        );
+        let children: Vec<u32> = layer.children().map(|l| l.id()).collect();
+        let expected: Vec<u32> = borrowed.children().map(|l| l.id()).collect();
+        assert_eq!(children, expected);
+    }
+
+    let cel = layers[0].frame(1);
+    assert_eq!(cel.frame(), 1);
+    assert_eq!(cel.layer(), 0);
+    assert_eq!(cel.image(), f.cel(1, 0).image());
+    assert_eq!(frame.layer(0).bounds(), cel.bounds());
+
+    // Handles outlive the original `Arc`.
+    drop(f);
+    let image = std::thread::spawn(move || cel.image()).join().unwrap();
+    assert_eq!(image.dimensions(), (16, 16));
+}
+
+#[test]
+fn cheap_clone() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Note(&'static str);
+
+    let mut f = load_test_file("tileset");
+    f.extensions_mut().insert(Note("original"));
+    let mut g = f.clone();
+    assert!(std::sync::Arc::ptr_eq(&f.framedata, &g.framedata));
+    assert!(std::sync::Arc::ptr_eq(&f.tilesets, &g.tilesets));
+    assert_eq!(f.frame(0).image(), g.frame(0).image());
+    assert_eq!(f.tilesets().len(), g.tilesets().len());
+
+    // Modifying the extensions of a clone does not affect the original.
+    g.extensions_mut().insert(Note("clone"));
+    assert_eq!(f.extensions().get::<Note>(), Some(&Note("original")));
+    assert_eq!(g.extensions().get::<Note>(), Some(&Note("clone")));
+
+    let handle = std::thread::spawn(move || g.frame(0).image());
+    assert_eq!(handle.join().unwrap(), f.frame(0).image());
+}
+
+#[test]
+fn content_hash() {
+    let f = load_test_file("layers_and_tags");
+    let g = load_test_file("layers_and_tags");
+    assert_eq!(f.content_hash(), g.content_hash());
+    assert_eq!(f.content_hash(), f.clone().content_hash());
+    assert_ne!(
+        f.content_hash(),
+        load_test_file("basic-16x16").content_hash()
+    );
+
+    let hashes: Vec<u64> = (0..f.num_frames())
+        .map(|frame| f.frame(frame).content_hash())
+        .collect();
+    for (a, b) in hashes.iter().zip(&hashes[1..]) {
+        assert_ne!(a, b);
+    }
+
+    // Changing user data only affects the file hash.
+    let with_user_data = |text: &str| {
+        AsepriteFile::read(
+            &build_test_file(4, 4, 0, &[image_layer_chunk("a"), user_data_chunk(text)])[..],
+        )
+        .unwrap()
+    };
+    let a = with_user_data("a");
+    let b = with_user_data("b");
+    assert_ne!(a.content_hash(), b.content_hash());
+    assert_eq!(a.frame(0).content_hash(), b.frame(0).content_hash());
+}
+
+#[test]
+fn structural_equality() {
+    let f = load_test_file("layers_and_tags");
+    assert_eq!(f, load_test_file("layers_and_tags"));
+    assert_eq!(f, f.clone());
+    assert_ne!(f, load_test_file("basic-16x16"));
+
+    // A raw cel and a compressed cel with the same pixels are equal.
+    use std::io::Write;
+    let compressed_cel = |pixel: [u8; 4]| {
+        let (chunk_type, mut data) = raw_cel_chunk(0, 1, 1, [0; 4]);
+        data.truncate(data.len() - 4);
+        data[7] = 2; // compressed image
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&pixel).unwrap();
+        data.extend(encoder.finish().unwrap());
+        (chunk_type, data)
+    };
+    let read = |cel: (u16, Vec<u8>)| {
+        AsepriteFile::read(&build_test_file(4, 4, 0, &[image_layer_chunk("a"), cel])[..]).unwrap()
+    };
+    let raw = read(raw_cel_chunk(0, 1, 1, [255, 0, 0, 255]));
+    let compressed = read(compressed_cel([255, 0, 0, 255]));
+    assert_eq!(raw, compressed);
+    assert_eq!(raw.content_hash(), compressed.content_hash());
+    assert_ne!(raw, read(compressed_cel([0, 255, 0, 255])));
+}
+
+#[test]
+fn diff() {
+    let f = load_test_file("layers_and_tags");
+    assert!(f.diff(&load_test_file("layers_and_tags")).is_empty());
+
+    let cel = |layer: u16, pixel: [u8; 4]| raw_cel_chunk(layer, 1, 1, pixel);
+    let tags = |names: &[u8]| {
+        let mut data = vec![names.len() as u8, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        for name in names {
+            data.extend([
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, *name,
+            ]);
+        }
+        (0x2018, data)
+    };
+    let read = |chunks: &[(u16, Vec<u8>)]| {
+        AsepriteFile::read(&build_test_file(4, 4, 0, chunks)[..]).unwrap()
+    };
+
+    let old = read(&[
+        image_layer_chunk("a"),
+        image_layer_chunk("b"),
+        cel(0, [255, 0, 0, 255]),
+        cel(1, [0, 0, 255, 255]),
+        tags(b"xy"),
+    ]);
+    let new = read(&[
+        image_layer_chunk("a"),
+        image_layer_chunk("b"),
+        cel(0, [255, 0, 0, 255]),
+        cel(1, [0, 255, 0, 255]),
+        tags(b"yz"),
+    ]);
+    let diff = old.diff(&new);
+    assert!(!diff.canvas_changed);
+    assert_eq!(diff.frames.changed, [0]);
+    assert_eq!(diff.layers.changed, [1]);
+    assert!(diff.layers.added.is_empty() && diff.layers.removed.is_empty());
+    assert_eq!(diff.tags.added, ["z"]);
+    assert_eq!(diff.tags.removed, ["x"]);
+    assert!(diff.tags.changed.is_empty());
+    assert!(diff.slices.is_empty() && diff.tilesets.is_empty());
+
+    let diff = old.diff(&read(&[image_layer_chunk("a"), cel(0, [255, 0, 0, 255])]));
+    assert_eq!(diff.layers.removed, [1]);
+    assert!(diff.layers.changed.is_empty());
+    assert_eq!(diff.tags.removed, ["x", "y"]);
+}
+
+#[test]
+fn cel_image_cache() {
+    use crate::cache::CelImageCache;
+
+    let f = load_test_file("layers_and_tags");
+    let image_size = 16 * 16 * 4;
+    let mut cache = CelImageCache::new(2 * image_size);
+    let image = cache.get(&f, 0, 0);
+    assert_eq!(*image, f.cel(0, 0).image());
+    assert!(std::sync::Arc::ptr_eq(&image, &cache.get(&f, 0, 0)));
+
+    cache.get(&f, 1, 0);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.bytes(), 2 * image_size);
+    // (0, 0) was used more recently than (1, 0), so (1, 0) is evicted.
+    cache.get(&f, 0, 0);
+    cache.get(&f, 2, 0);
+    assert!(cache.contains(0, 0) && cache.contains(2, 0));
+    assert!(!cache.contains(1, 0));
+    assert_eq!(cache.bytes(), 2 * image_size);
+
+    cache.set_budget(image_size);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(2, 0));
+
+    // Images larger than the budget are not stored.
+    cache.set_budget(image_size - 1);
+    assert!(cache.is_empty());
+    assert_eq!(*cache.get(&f, 3, 0), f.cel(3, 0).image());
+    assert!(cache.is_empty());
+    assert_eq!(cache.bytes(), 0);
+}
+
+#[test]
+fn interned_cel_pixels() {
+    use crate::cel::{CelContent, CelId};
+    use crate::pixel::Pixels;
+
+    let layer = image_layer_chunk("a");
+    let cel = |layer: u16, pixel: [u8; 4]| raw_cel_chunk(layer, 0, 0, pixel);
+    let chunks = [
+        layer.clone(),
+        layer.clone(),
+        layer,
+        cel(0, [255, 0, 0, 255]),
+        cel(1, [255, 0, 0, 255]),
+        cel(2, [0, 255, 0, 255]),
+    ];
+    let f = AsepriteFile::read(&build_test_file(4, 4, 0, &chunks)[..]).unwrap();
+    let buffer = |layer: u16| {
+        let cel = f.framedata.cel(CelId { frame: 0, layer }).unwrap();
+        match &cel.content {
+            CelContent::Raw(image) => match &image.pixels {
+                Pixels::Rgba(data) => data.clone(),
+                _ => panic!("Expected RGBA pixels"),
+            },
+            _ => panic!("Expected a raw cel"),
+        }
+    };
+    assert!(std::sync::Arc::ptr_eq(&buffer(0), &buffer(1)));
+    assert!(!std::sync::Arc::ptr_eq(&buffer(0), &buffer(2)));
+    assert_eq!(f.cel(0, 0).image(), f.cel(0, 1).image());
+}
+
+#[test]
+fn asset_bundle() {
+    use crate::bundle::{Bundle, BundledSprite};
+
+    let ase = load_test_file("layers_and_tags");
+    let slices = load_test_file("slice_advanced");
+    let mut bundle = Bundle::new();
+    bundle.add_aseprite("tags", &ase);
+    bundle.add_aseprite("slices", &slices);
+    let mut bytes = Vec::new();
+    bundle.write(&mut bytes).unwrap();
+
+    let loaded = Bundle::read(&bytes[..]).unwrap();
+    assert_eq!(loaded.sprites().len(), 2);
+    let sprite = loaded.sprite("tags").unwrap();
+    let expected = BundledSprite::from_file("tags", &ase);
+    assert_eq!((sprite.width, sprite.height), (16, 16));
+    assert_eq!(sprite.frames.len(), expected.frames.len());
+    for (frame, original) in sprite.frames.iter().zip(&expected.frames) {
+        assert_eq!(frame.image, original.image);
+        assert_eq!(frame.duration(), original.duration());
+    }
+    let names: Vec<_> = sprite.tags.iter().map(|t| t.name()).collect();
+    let expected_names: Vec<_> = ase.tags().map(|t| t.name()).collect();
+    assert_eq!(names, expected_names);
+    let tag = sprite.tag_by_name("T3").unwrap();
+    assert_eq!(tag.frame_sequence(), vec![1, 2, 3]);
+
+    let sprite = loaded.sprite("slices").unwrap();
+    let key = &sprite.slices[0].keys[0];
+    let original = &slices.slices()[0].keys[0];
+    assert_eq!(key.bounds(), original.bounds());
+    assert_eq!(key.pivot, original.pivot);
+    let center = sprite.slices[1].keys[0].slice9.as_ref().unwrap().center();
+    assert_eq!(center, Rect::new(3, 3, 2, 2));
+
+    assert!(loaded.sprite("missing").is_none());
+    assert!(Bundle::read(&b"ASEX"[..]).unwrap_err().is_invalid_input());
+
+    // A tags block that claims to be larger than the input.
+    let mut truncated = b"ASEB".to_vec();
+    truncated.extend_from_slice(&1u16.to_le_bytes());
+    truncated.extend_from_slice(&1u32.to_le_bytes());
+    truncated.extend_from_slice(&[1, 0, b'a']);
+    truncated.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+    truncated.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(Bundle::read(&truncated[..]).is_err());
+
+    let mut too_long = Bundle::new();
+    too_long.add_aseprite(&"x".repeat(70_000), &ase);
+    let err = too_long.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // More tags than the tag chunk can hold.
+    let mut sprite = BundledSprite::from_file("tags", &ase);
+    sprite.tags = vec![Tag::new("t", 0, 0); 0x10000];
+    let mut too_many = Bundle::new();
+    too_many.add(sprite);
+    let err = too_many.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // Keys without 9-slice or pivot stay without them.
+    let mut sprite = BundledSprite::from_file("slices", &slices);
+    let mut key = sprite.slices[1].keys[0].clone();
+    key.from_frame = 1;
+    key.slice9 = None;
+    key.pivot = None;
+    sprite.slices[1].keys.push(key);
+    sprite.slices[1].keys[0].pivot = Some(Point::new(0, 0));
+    let mut keys = Bundle::new();
+    keys.add(sprite);
+    let mut bytes = Vec::new();
+    keys.write(&mut bytes).unwrap();
+    let loaded = Bundle::read(&bytes[..]).unwrap();
+    let keys = &loaded.sprite("slices").unwrap().slices[1].keys;
+    assert!(keys[0].slice9.is_some());
+    assert_eq!(keys[0].pivot, Some(Point::new(0, 0)));
+    assert!(keys[1].slice9.is_none());
+    assert!(keys[1].pivot.is_none());
+}
+
+#[test]
+fn bundle_frame_sizes() {
+    use crate::bundle::Bundle;
+    use std::io::Write;
+
+    let bundle = |width: u32, height: u32, durations: &[u32], pixels: &[u8]| {
+        let mut data = b"ASEB".to_vec();
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[1, 0, b'a']);
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&(durations.len() as u32).to_le_bytes());
+        for duration in durations {
+            data.extend_from_slice(&duration.to_le_bytes());
+        }
+        data.extend_from_slice(&10u32.to_le_bytes()); // tags block
+        data.extend_from_slice(&[0; 10]);
+        data.extend_from_slice(&0u32.to_le_bytes()); // slices
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(pixels).unwrap();
+        let compressed = encoder.finish().unwrap();
+        data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        data.extend_from_slice(&compressed);
+        data
+    };
+
+    // Sizes that overflow or do not match the pixel data are errors.
+    let err = Bundle::read(&bundle(u32::MAX, u32::MAX, &[1], &[])[..]).unwrap_err();
+    assert!(err.is_invalid_input());
+    let err = Bundle::read(&bundle(0x10000, 0x10000, &[1, 1], &[0; 4])[..]).unwrap_err();
+    assert!(err.is_invalid_input());
+
+    // An empty canvas keeps its frames.
+    let loaded = Bundle::read(&bundle(0, 3, &[10, 20], &[])[..]).unwrap();
+    let frames = &loaded.sprite("a").unwrap().frames;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].duration_ms, 20);
+    assert_eq!(frames[1].image.dimensions(), (0, 3));
+}
+
+#[test]
+fn push_parser() {
+    let bytes = std::fs::read("./tests/data/layers_and_tags.aseprite").unwrap();
+    let expected = AsepriteFile::read(&bytes[..]).unwrap();
+
+    let mut parser = PushParser::new();
+    let mut events = Vec::new();
+    for piece in bytes.chunks(7) {
+        let mut event = parser.feed(piece).unwrap();
+        while event != ParseEvent::NeedMoreData && event != ParseEvent::Done {
+            events.push(event);
+            event = parser.feed(&[]).unwrap();
+        }
+    }
+    assert!(parser.is_done());
+    assert_eq!(
+        events[0],
+        ParseEvent::Header {
+            width: 16,
+            height: 16,
+            num_frames: 4
+        }
+    );
+    assert_eq!(events.len(), 5);
+    match events[4] {
+        ParseEvent::Frame(progress) => {
+            assert_eq!(progress.frames_parsed, 4);
+            assert_eq!(progress.bytes_read, bytes.len() as u64);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    assert_eq!(parser.finish().unwrap(), expected);
+
+    // Truncated input.
+    let mut parser = PushParser::new();
+    assert_eq!(
+        parser.feed(&bytes[..bytes.len() - 1]).unwrap(),
+        ParseEvent::Header {
+            width: 16,
+            height: 16,
+            num_frames: 4
+        }
+    );
+    while parser.feed(&[]).unwrap() != ParseEvent::NeedMoreData {}
+    assert!(parser.finish().unwrap_err().is_invalid_input());
+
+    let mut parser = PushParser::new();
+    assert!(matches!(
+        parser.feed(&[0; 128]),
+        Err(AsepriteParseError::BadMagicNumber { .. })
+    ));
+}
+
+#[test]
+fn layer_editing() {
+    let original = load_test_file("layers_and_tags");
+    let mut ase = original.clone();
+    let layer = ase.layer(1);
+    assert!(layer.is_visible());
+    assert_eq!(layer.opacity(), 255);
+
+    let mut layer = ase.layer_by_name_mut(original.layer(1).name()).unwrap();
+    assert_eq!(layer.id(), 1);
+    layer.set_name("Renamed");
+    layer.set_blend_mode(BlendMode::Multiply);
+    layer.set_opacity(100);
+    assert_eq!(layer.as_layer().name(), "Renamed");
+    let layer = ase.layer(1);
+    assert_eq!(layer.name(), "Renamed");
+    assert_eq!(layer.blend_mode(), BlendMode::Multiply);
+    assert_eq!(layer.opacity(), 100);
+    assert!(ase.layer_by_name("Renamed").is_some());
+    // The clone is not affected.
+    assert_ne!(original.layer(1).name(), "Renamed");
+
+    // Hiding every layer leaves a transparent image.
+    let mut ase = original.clone();
+    for id in 0..ase.num_layers() {
+        ase.layer_mut(id).set_visible(false);
+    }
+    assert!(!ase.layer(0).is_visible());
+    assert!(ase.frame(0).image().pixels().all(|p| p.0[3] == 0));
+    ase.layer_mut(0).set_visible(true);
+    assert!(ase.layer(0).flags().contains(LayerFlags::VISIBLE));
+    assert_ne!(ase, original);
+}
+
+#[test]
+fn cel_editing() {
+    use image::{Rgba, RgbaImage};
+
+    let original = load_test_file("linked_cels");
+    let mut f = original.clone();
+    let red = Rgba([255, 0, 0, 255]);
+    let stamp = RgbaImage::from_pixel(2, 2, red);
+
+    // Linked cels share their position.
+    f.cel_mut(1, 0).set_position(Point::new(2, 2)).unwrap();
+    assert_eq!(f.cel(0, 0).bounds(), Some(Rect::new(2, 2, 8, 8)));
+    assert_eq!(f.cel(2, 0).bounds(), Some(Rect::new(2, 2, 8, 8)));
+    f.cel_mut(2, 0).unlink();
+    f.cel_mut(2, 0).set_position(Point::new(0, 0)).unwrap();
+    assert_eq!(f.cel(0, 0).bounds(), Some(Rect::new(2, 2, 8, 8)));
+    assert_eq!(f.cel(2, 0).bounds(), Some(Rect::new(0, 0, 8, 8)));
+
+    // Removing a cel keeps the content of cels linked to it.
+    f.cel_mut(0, 1).clear();
+    assert!(f.cel(0, 1).is_empty());
+    assert_eq!(f.cel(2, 1).bounds(), Some(Rect::new(3, 5, 8, 8)));
+    assert_eq!(
+        f.frame(2).layer(1).image(),
+        original.frame(2).layer(1).image()
+    );
+
+    // New cels are created as needed.
+    let mut cel = f.cel_mut(0, 2);
+    cel.set_image(Point::new(-1, 0), &stamp).unwrap();
+    cel.set_opacity(128);
+    assert_eq!(f.cel(0, 2).bounds(), Some(Rect::new(-1, 0, 2, 2)));
+    assert_eq!(f.cel(0, 2).image().get_pixel(0, 1).0[3], 128);
+    assert!(original.cel(0, 2).is_empty());
+    assert!(f
+        .cel_mut(0, 2)
+        .set_image(Point::new(40000, 0), &stamp)
+        .is_err());
+    assert!(f
+        .cel_mut(0, 2)
+        .set_indexed_image(Point::new(0, 0), 1, 1, vec![0])
+        .is_err());
+
+    // Pixels are converted to the pixel format of the file.
+    let mut f = load_test_file("grayscale");
+    f.cel_mut(0, 0).set_image(Point::new(0, 0), &stamp).unwrap();
+    assert_eq!(
+        f.cel(0, 0).image().get_pixel(1, 1),
+        &Rgba([54, 54, 54, 255])
+    );
+
+    let mut f = load_test_file("indexed");
+    f.cel_mut(0, 0).set_image(Point::new(0, 0), &stamp).unwrap();
+    let nearest = f
+        .palette()
+        .unwrap()
+        .nearest(red, ColorDistance::Rgb)
+        .unwrap();
+    let expected = f.palette().unwrap().color(nearest).unwrap().raw_rgba8();
+    assert_eq!(f.cel(0, 0).image().get_pixel(0, 0).0, expected);
+    f.cel_mut(0, 0)
+        .set_indexed_image(Point::new(0, 0), 2, 1, vec![nearest as u8, nearest as u8])
+        .unwrap();
+    assert_eq!(f.cel(0, 0).bounds(), Some(Rect::new(0, 0, 2, 1)));
+    assert!(f
+        .cel_mut(0, 0)
+        .set_indexed_image(Point::new(0, 0), 2, 2, vec![0])
+        .is_err());
+
+    let mut f = load_test_file("tilemap");
+    let tilemap_layer = f.layers().find(|l| l.is_tilemap()).unwrap().id();
+    assert!(f
+        .cel_mut(0, tilemap_layer)
+        .set_image(Point::new(0, 0), &stamp)
+        .is_err());
+}
+
+#[test]
+fn frame_duration_editing() {
+    use std::time::Duration;
+
+    let mut f = load_test_file("layers_and_tags");
+    let durations: Vec<_> = f.frame_durations().to_vec();
+    f.set_frame_duration(1, Duration::from_millis(250));
+    assert_eq!(f.frame(1).duration_ms(), 250);
+    f.set_frame_duration(2, Duration::from_micros(1500));
+    assert_eq!(f.frame(2).duration_ms(), 1);
+    f.set_frame_duration(2, Duration::ZERO);
+    assert_eq!(f.frame(2).duration_ms(), 1);
+    f.set_frame_duration(2, Duration::from_secs(100));
+    assert_eq!(f.frame(2).duration_ms(), 65535);
+    assert_eq!(f.frame(0).duration(), durations[0]);
+
+    f.scale_durations(0.5);
+    assert_eq!(f.frame(1).duration_ms(), 125);
+    assert_eq!(f.frame(2).duration_ms(), 32768);
+    f.scale_durations(0.0);
+    assert!(f.frame_durations().iter().all(|d| d.as_millis() == 1));
+}
+
+#[test]
+fn tag_editing() {
+    use std::num::NonZeroU32;
+
+    let mut f = load_test_file("layers_and_tags");
+    let num_tags = f.num_tags();
+    let name = f.tag(0).name().to_owned();
+
+    let tag = f.tag_by_name_mut(&name).unwrap();
+    tag.set_name("Walk");
+    tag.set_range(1, 2);
+    tag.set_animation_direction(AnimationDirection::Reverse);
+    tag.set_repeat(NonZeroU32::new(2));
+    let tag = f.tag(0);
+    assert_eq!(tag.name(), "Walk");
+    assert_eq!(tag.frame_sequence(), vec![2, 1, 2, 1]);
+    f.tag_mut(0).set_repeat(None);
+    assert_eq!(f.tag(0).repeat(), None);
+
+    let mut tag = Tag::new("New", 3, 3);
+    tag.set_user_data(Some(UserData {
+        text: Some("note".to_owned()),
+        ..Default::default()
+    }));
+    let id = f.add_tag(tag);
+    assert_eq!(id, num_tags);
+    assert_eq!(f.tags_at_frame(3).last().unwrap().name(), "New");
+    assert_eq!(
+        f.tag(id).user_data().and_then(|u| u.text.as_deref()),
+        Some("note")
+    );
+
+    let removed = f.remove_tag(0);
+    assert_eq!(removed.name(), "Walk");
+    assert_eq!(f.num_tags(), num_tags);
+    assert!(f.tag_by_name("Walk").is_none());
+    assert_eq!(f.tag(num_tags - 1).name(), "New");
+}
+
+#[test]
+fn palette_editing() {
+    let original = load_test_file("indexed");
+    let image = original.frame(0).image();
+    let palette = original.palette().unwrap();
+    let num_colors = palette.num_colors();
+    let used: Vec<[u8; 4]> = image.pixels().map(|p| p.0).collect();
+    let unused = (0..num_colors)
+        .rev()
+        .find(|&i| !used.contains(&palette.color(i).unwrap().raw_rgba8()))
+        .unwrap();
+
+    // Reordering and removing unused colors keeps the image.
+    let mut f = original.clone();
+    let reversed: Vec<u32> = (0..num_colors).rev().collect();
+    f.reorder_palette(&reversed).unwrap();
+    assert_eq!(f.frame(0).image(), image);
+    assert_eq!(
+        f.palette().unwrap().color(0).unwrap().raw_rgba8(),
+        palette.color(num_colors - 1).unwrap().raw_rgba8()
+    );
+    assert!(matches!(
+        f.reorder_palette(&[0, 0]),
+        Err(AsepriteParseError::InvalidEdit(_))
+    ));
+    assert!(f.reorder_palette(&vec![0; num_colors as usize]).is_err());
+
+    let mut f = original.clone();
+    f.remove_palette_color(unused).unwrap();
+    assert_eq!(f.palette().unwrap().num_colors(), num_colors - 1);
+    assert_eq!(f.frame(0).image(), image);
+    assert!(matches!(
+        f.remove_palette_color(num_colors + 10),
+        Err(AsepriteParseError::InvalidEdit(_))
+    ));
+
+    // Removing an entry below the transparent color moves the transparent
+    // color down.
+    let mut f = original.clone();
+    let transparent = f.transparent_color_index().unwrap() as u32;
+    let order: Vec<u32> = std::iter::once(unused)
+        .chain((0..num_colors).filter(|&i| i != unused))
+        .collect();
+    f.reorder_palette(&order).unwrap();
+    assert_eq!(f.transparent_color_index(), Some(transparent as u8 + 1));
+    f.remove_palette_color(0).unwrap();
+    assert_eq!(f.transparent_color_index(), Some(transparent as u8));
+    assert_eq!(f.frame(0).image(), image);
+
+    // The transparent color cannot be removed, and never replaces a removed
+    // color.
+    let mut f = original.clone();
+    assert!(matches!(
+        f.remove_palette_color(transparent),
+        Err(AsepriteParseError::InvalidEdit(_))
+    ));
+    let opaque = image.pixels().find(|p| p.0[3] == 255).unwrap().0;
+    let used_index = (0..num_colors)
+        .find(|&i| palette.color(i).unwrap().raw_rgba8() == opaque)
+        .unwrap();
+    f.set_palette_color(transparent, [opaque[0], opaque[1], opaque[2], 0]);
+    f.remove_palette_color(used_index).unwrap();
+    let count_transparent = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] == 0).count();
+    assert_eq!(
+        count_transparent(&f.frame(0).image()),
+        count_transparent(&image)
+    );
+
+    // Changing a used color changes the image.
+    let mut f = original.clone();
+    let opaque = image.pixels().find(|p| p.0[3] == 255).unwrap().0;
+    let index = (0..num_colors)
+        .find(|&i| palette.color(i).unwrap().raw_rgba8() == opaque)
+        .unwrap();
+    f.set_palette_color(index, [1, 2, 3, 255]);
+    assert!(f.frame(0).image().pixels().any(|p| p.0 == [1, 2, 3, 255]));
+    assert_eq!(original.frame(0).image(), image);
+    assert_eq!(f.add_palette_color([4, 5, 6, 255]), num_colors);
+
+    // Replacing the palette requires all used indices.
+    let mut f = original.clone();
+    assert!(f
+        .set_palette(ColorPalette::from_colors([[0, 0, 0, 255]]))
+        .is_err());
+    let colors: Vec<[u8; 4]> = palette.iter().map(|c| c.raw_rgba8()).collect();
+    f.set_palette(ColorPalette::from_colors(colors)).unwrap();
+    assert_eq!(f.frame(0).image(), image);
+
+    // Tilesets are updated as well.
+    let mut f = load_test_file("tilemap_indexed");
+    let image = f.frame(0).image();
+    let reversed: Vec<u32> = (0..f.palette().unwrap().num_colors()).rev().collect();
+    f.reorder_palette(&reversed).unwrap();
+    assert_eq!(f.frame(0).image(), image);
+}
+
+#[test]
+fn layer_structure_editing() {
+    let original = load_test_file("layers_and_tags");
+    let names = |f: &AsepriteFile| f.layers().map(|l| l.name().to_owned()).collect::<Vec<_>>();
+    let old_names = names(&original);
+    let image = original.frame(1).image();
+
+    // New layers are empty, so the image does not change.
+    let mut f = original.clone();
+    let top = f.add_layer("Top", None).unwrap();
+    assert_eq!(top, 6);
+    let child = f.add_layer("Child", Some(3)).unwrap();
+    assert_eq!(child, 6);
+    assert_eq!(f.layer(child).parent().unwrap().id(), 3);
+    assert_eq!(f.layer(7).name(), "Top");
+    assert!(f.layer(7).parent().is_none());
+    assert!(f.cel(1, child).is_empty());
+    assert_eq!(f.frame(1).image(), image);
+    assert!(matches!(
+        f.add_layer("Nope", Some(1)),
+        Err(AsepriteParseError::InvalidEdit(_))
+    ));
+    for layer in 0..6 {
+        assert_eq!(f.cel(1, layer).image(), original.cel(1, layer).image());
+    }
+
+    // Removing a group removes its children and their cels.
+    let mut f = original.clone();
+    f.remove_layer(3);
+    assert_eq!(names(&f), &old_names[..3]);
+    f.remove_layer(1);
+    assert_eq!(f.num_layers(), 2);
+    assert_eq!(f.layer(1).name(), old_names[2]);
+    assert_eq!(f.cel(1, 1).image(), original.cel(1, 2).image());
+
+    // Moving a group moves its children along.
+    let mut f = original.clone();
+    f.move_layer(3, 1).unwrap();
+    let expected: Vec<_> = [0, 3, 4, 5, 1, 2]
+        .iter()
+        .map(|&i| old_names[i].clone())
+        .collect();
+    assert_eq!(names(&f), expected);
+    assert_eq!(f.layer(2).parent().unwrap().id(), 1);
+    assert_eq!(f.cel(1, 4).image(), original.cel(1, 1).image());
+    assert_eq!(f.cel(1, 2).image(), original.cel(1, 4).image());
+    assert!(matches!(
+        f.move_layer(2, 2),
+        Err(AsepriteParseError::InvalidEdit(_))
+    ));
+    f.move_layer(2, 1).unwrap();
+    assert_eq!(f.layer(3).name(), old_names[4]);
+    assert_eq!(f.layer(3).parent().unwrap().id(), 1);
+    f.move_layer(1, 3).unwrap();
+    f.move_layer(4, 1).unwrap();
+    assert_eq!(names(&f), old_names);
+    assert_eq!(f.frame(1).image(), image);
+}
+
+#[test]
+fn frame_editing() {
+    use std::time::Duration;
+
+    let original = load_test_file("layers_and_tags");
+    let images: Vec<_> = (0..original.num_frames())
+        .map(|i| original.frame(i).image())
+        .collect();
+    let range = |f: &AsepriteFile| {
+        let tag = f.tag_by_name("T3").unwrap();
+        (tag.from_frame(), tag.to_frame())
+    };
+    assert_eq!(range(&original), (1, 3));
+
+    // Empty frames.
+    let mut f = original.clone();
+    f.insert_frame(2, Duration::from_millis(50)).unwrap();
+    assert_eq!(f.num_frames(), 5);
+    assert_eq!(f.frame(2).duration_ms(), 50);
+    assert!(f.frame(2).image().pixels().all(|p| p.0[3] == 0));
+    assert_eq!(f.frame(3).image(), images[2]);
+    assert_eq!(range(&f), (1, 4));
+    f.insert_frame(1, Duration::from_millis(50)).unwrap();
+    assert_eq!(range(&f), (2, 5));
+    f.insert_frame(6, Duration::from_millis(50)).unwrap();
+    assert_eq!(range(&f), (2, 5));
+    f.remove_frame(6).unwrap();
+    f.remove_frame(1).unwrap();
+    f.remove_frame(2).unwrap();
+    assert_eq!(range(&f), (1, 3));
+    let after: Vec<_> = (0..f.num_frames()).map(|i| f.frame(i).image()).collect();
+    assert_eq!(after, images);
+
+    // Copies share their pixels with the original only if they are linked.
+    for linked in [false, true] {
+        let mut f = original.clone();
+        assert_eq!(f.duplicate_frame(3, linked).unwrap(), 4);
+        assert_eq!(f.frame(4).image(), images[3]);
+        assert_eq!(range(&f), (1, 4));
+        let layer = (0..f.num_layers())
+            .find(|&l| f.layer(l).is_visible() && !f.cel(4, l).is_empty())
+            .unwrap();
+        f.cel_mut(4, layer).set_opacity(0);
+        assert_eq!(f.frame(3).image() != images[3], linked);
+    }
+
+    // Removing a frame keeps the images of cels linked to it.
+    let original = load_test_file("linked_cels");
+    let images: Vec<_> = (0..original.num_frames())
+        .map(|i| original.frame(i).image())
+        .collect();
+    let mut f = original.clone();
+    f.remove_frame(0).unwrap();
+    let after: Vec<_> = (0..f.num_frames()).map(|i| f.frame(i).image()).collect();
+    assert_eq!(after, &images[1..]);
+    let mut f = original.clone();
+    f.duplicate_frame(0, true).unwrap();
+    f.remove_frame(0).unwrap();
+    let after: Vec<_> = (0..f.num_frames()).map(|i| f.frame(i).image()).collect();
+    assert_eq!(after, images);
+
+    // Slice keys move with their frames.
+    let original = load_test_file("slice_advanced");
+    let origins = |f: &AsepriteFile| -> Vec<_> {
+        (0..f.num_frames())
+            .map(|i| f.slices()[0].key_for_frame(i).map(|k| k.origin))
+            .collect()
+    };
+    let mut f = original.clone();
+    f.insert_frame(0, Duration::from_millis(100)).unwrap();
+    assert_eq!(origins(&f)[1..], origins(&original));
+    f.remove_frame(0).unwrap();
+    assert_eq!(origins(&f), origins(&original));
+
+    // Keys on a removed last frame do not point past the end.
+    let mut f = original.clone();
+    f.duplicate_frame(f.num_frames() - 1, false).unwrap();
+    let last = f.num_frames() - 1;
+    let mut key = f.slices[0].keys[0].clone();
+    key.from_frame = last;
+    key.origin = Point::new(-7, -7);
+    f.slices[0].keys.push(key.clone());
+    f.slices[1].keys = vec![key];
+    f.remove_frame(last).unwrap();
+    assert_eq!(origins(&f), origins(&original));
+    assert!(f.slices[0].keys.iter().all(|k| k.from_frame < last));
+    assert_eq!(f.slices[1].keys.len(), 1);
+    assert_eq!(f.slices[1].keys[0].from_frame, last - 1);
+
+    // Single frame tags are removed with their frame.
+    let mut f = load_test_file("basic-16x16");
+    let err = f.remove_frame(0).unwrap_err();
+    assert!(matches!(err, AsepriteParseError::InvalidEdit(_)));
+    assert_eq!(
+        err.to_string(),
+        "Invalid edit: Cannot remove the only frame"
+    );
+    f.duplicate_frame(0, false).unwrap();
+    f.add_tag(Tag::new("First", 0, 0));
+    f.remove_frame(0).unwrap();
+    assert_eq!(f.num_tags(), 0);
+}
+
+#[test]
+fn flattened() {
+    for name in [
+        "layers_and_tags",
+        "linked_cels",
+        "indexed",
+        "tilemap",
+        "slice_advanced",
+    ] {
+        let f = load_test_file(name);
+        let flat = f.flattened();
+        assert_eq!(flat.num_layers(), 1, "{}", name);
+        assert_eq!(flat.pixel_format(), PixelFormat::Rgba);
+        assert!(flat.tilesets().is_empty());
+        assert_eq!(flat.num_frames(), f.num_frames());
+        assert_eq!(flat.num_tags(), f.num_tags());
+        assert_eq!(flat.slices().len(), f.slices().len());
+        for frame in 0..f.num_frames() {
+            assert_eq!(
+                flat.frame(frame).image(),
+                f.frame(frame).image(),
+                "{}",
+                name
+            );
+            assert_eq!(flat.frame(frame).duration(), f.frame(frame).duration());
+        }
+    }
+}
+
+#[test]
+fn color_mode_conversion() {
+    use image::Rgba;
+
+    let original = load_test_file("layers_and_tags");
+    let image = original.frame(1).image();
+
+    let mut f = original.clone();
+    f.convert_pixel_format(PixelFormat::Grayscale).unwrap();
+    assert_eq!(f.pixel_format(), PixelFormat::Grayscale);
+    let gray = f.frame(1).image();
+    assert!(gray.pixels().all(|p| p.0[0] == p.0[1] && p.0[1] == p.0[2]));
+    f.convert_pixel_format(PixelFormat::Rgba).unwrap();
+    assert_eq!(f.frame(1).image(), gray);
+    assert_eq!(original.frame(1).image(), image);
+
+    // A small palette reduces the number of colors.
+    let mut f = original.clone();
+    let palette = f.generate_palette(4);
+    assert_eq!(palette.num_colors(), 4);
+    assert_eq!(palette.color(0).unwrap().raw_rgba8(), [0, 0, 0, 0]);
+    f.set_palette(palette).unwrap();
+    let indexed = PixelFormat::Indexed {
+        transparent_color_index: 0,
+    };
+    f.convert_pixel_format(indexed).unwrap();
+    let mut colors: Vec<_> = f.frame(1).image().pixels().map(|p| p.0).collect();
+    colors.sort();
+    colors.dedup();
+    assert!(colors.len() <= 4);
+    assert!(f
+        .frame(1)
+        .image()
+        .pixels()
+        .zip(image.pixels())
+        .all(|(a, b)| (a.0[3] == 0) == (b.0[3] == 0)));
+
+    // Indexed files convert to RGBA and back without changes.
+    let original = load_test_file("indexed");
+    let image = original.frame(0).image();
+    let mut f = original.clone();
+    f.convert_pixel_format(PixelFormat::Rgba).unwrap();
+    assert!(!f.is_indexed_color());
+    assert_eq!(f.frame(0).image(), image);
+    f.convert_pixel_format(original.pixel_format()).unwrap();
+    assert_eq!(f.frame(0).image(), image);
+    let err = f
+        .convert_pixel_format(PixelFormat::Indexed {
+            transparent_color_index: 255,
+        })
+        .unwrap_err();
+    assert!(matches!(err, AsepriteParseError::InvalidEdit(_)));
+    assert!(!err.is_invalid_input());
+
+    let colors = (0..1000_u32).map(|i| Rgba([(i % 256) as u8, (i / 4) as u8, 7, 255]));
+    let palette = ColorPalette::quantize(colors, 16);
+    assert_eq!(palette.num_colors(), 16);
+    let palette = ColorPalette::quantize([Rgba([1, 2, 3, 4]); 10], 16);
+    assert_eq!(palette.num_colors(), 1);
+    assert_eq!(palette.color(0).unwrap().raw_rgba8(), [1, 2, 3, 4]);
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn onion_skin() {
+    use crate::util::{onion_skin, OnionSkinOptions, OnionSkinPosition};
+
+    let f = load_test_file("layers_and_tags");
+    let current = f.frame(1).image();
+    let none = OnionSkinOptions {
+        previous: 0,
+        next: 0,
+        ..Default::default()
+    };
+    assert_eq!(onion_skin(&f, 1, &none), current);
+    let invisible = OnionSkinOptions {
+        opacity_base: 0,
+        ..Default::default()
+    };
+    assert_eq!(onion_skin(&f, 1, &invisible), current);
+
+    // Pixels that are only set in the previous frame show up faintly.
+    let (frame, x, y) = (1..f.num_frames())
+        .find_map(|frame| {
+            let current = f.frame(frame).image();
+            let previous = f.frame(frame - 1).image();
+            current
+                .enumerate_pixels()
+                .find(|&(x, y, p)| p.0[3] == 0 && previous.get_pixel(x, y).0[3] == 255)
+                .map(|(x, y, _)| (frame, x, y))
+        })
+        .unwrap();
+    let options = OnionSkinOptions {
+        next: 0,
+        tint: true,
+        position: OnionSkinPosition::Behind,
+        ..Default::default()
+    };
+    let pixel = onion_skin(&f, frame, &options).get_pixel(x, y).0;
+    assert_eq!(pixel[3], 68);
+    assert!(pixel[0] > pixel[2]);
+    // Opaque pixels of the current frame are not affected when drawn behind.
+    let image = onion_skin(&f, 1, &options);
+    for (a, b) in image.pixels().zip(current.pixels()) {
+        if b.0[3] == 255 {
+            assert_eq!(a, b);
+        }
+    }
+}
+
+#[cfg(feature = "utils")]
+#[test]
+fn tween_frames() {
+    use crate::util::{tween, TweenMode};
+
+    let original = load_test_file("layers_and_tags");
+    let image = original.frame(0).image();
+    let same = tween(&original, 0, 0, 2, TweenMode::CrossFade);
+    assert_eq!(same, vec![image.clone(), image.clone()]);
+
+    // Move a cel by 4 pixels in a copy of the first frame.
+    let mut f = original.clone();
+    f.duplicate_frame(0, false).unwrap();
+    let layer = (0..f.num_layers())
+        .find(|&l| f.layer(l).is_visible() && !f.cel(0, l).is_empty())
+        .unwrap();
+    let start = f.cel(0, layer).top_left();
+    let moved = |f: &mut AsepriteFile, dx: i32| {
+        f.cel_mut(1, layer)
+            .set_position(Point::new(start.x + dx, start.y))
+            .unwrap();
+    };
+    moved(&mut f, 2);
+    let halfway = f.frame(1).image();
+    moved(&mut f, 4);
+
+    let motion = tween(&f, 0, 1, 1, TweenMode::Motion);
+    assert_eq!(motion.len(), 1);
+    assert_eq!(motion[0], halfway);
+    let fade = tween(&f, 0, 1, 1, TweenMode::CrossFade);
+    assert_ne!(fade[0], halfway);
+    assert!(tween(&f, 0, 1, 0, TweenMode::Motion).is_empty());
+}
+
+#[test]
+fn dirty_rects() {
+    let check = |f: &AsepriteFile, from: u32, to: u32| {
+        let rects = f.dirty_rects(from, to);
+        let (a, b) = (f.frame(from).image(), f.frame(to).image());
+        for (x, y, pixel) in a.enumerate_pixels() {
+            let inside = rects.iter().any(|r| r.contains(x as i32, y as i32));
+            assert!(inside || pixel == b.get_pixel(x, y));
+        }
+        for rect in &rects {
+            let differs =
+                |x: i32, y: i32| a.get_pixel(x as u32, y as u32) != b.get_pixel(x as u32, y as u32);
+            // Each edge of a rectangle has a changed pixel.
+            assert!((rect.x..rect.right()).any(|x| differs(x, rect.y)));
+            assert!((rect.x..rect.right()).any(|x| differs(x, rect.bottom() - 1)));
+            assert!((rect.y..rect.bottom()).any(|y| differs(rect.x, y)));
+            assert!((rect.y..rect.bottom()).any(|y| differs(rect.right() - 1, y)));
+        }
+        rects
+    };
+    let f = load_test_file("layers_and_tags");
+    assert!(check(&f, 0, 0).is_empty());
+    for frame in 0..f.num_frames() - 1 {
+        check(&f, frame, frame + 1);
+    }
+    let f = load_test_file("linked_cels");
+    check(&f, 0, 1);
+    check(&f, 1, 2);
+
+    // Moving a cel only marks its old and new position as dirty.
+    let mut f = load_test_file("layers_and_tags");
+    f.duplicate_frame(1, true).unwrap();
+    assert!(f.dirty_rects(1, 2).is_empty());
+    f.duplicate_frame(1, false).unwrap();
+    assert!(check(&f, 1, 2).is_empty());
+    let layer = (0..f.num_layers())
+        .find(|&l| f.layer(l).is_visible() && !f.cel(2, l).is_empty())
+        .unwrap();
+    let bounds = f.cel(2, layer).bounds().unwrap();
+    f.cel_mut(2, layer)
+        .set_position(Point::new(bounds.x + 1, bounds.y))
+        .unwrap();
+    let rects = check(&f, 1, 2);
+    assert!(!rects.is_empty());
+    let moved = Rect::new(bounds.x, bounds.y, bounds.w + 1, bounds.h);
+    assert!(rects.iter().all(|r| moved.intersection(r) == Some(*r)));
+}
+
+#[test]
+fn duplicate_frames() {
+    use std::time::Duration;
+
+    let mut f = load_test_file("layers_and_tags");
+    assert!(f.duplicate_frames().is_empty());
+
+    // Unlinked copies are found as well as linked ones.
+    assert_eq!(f.duplicate_frame(1, false).unwrap(), 2);
+    assert_eq!(f.duplicate_frame(0, true).unwrap(), 1);
+    assert_eq!(f.duplicate_frame(3, false).unwrap(), 4);
+    assert_eq!(f.duplicate_frames(), vec![vec![0, 1], vec![2, 3, 4]]);
+
+    // Durations do not matter.
+    f.set_frame_duration(4, Duration::from_millis(500));
+    assert_eq!(f.duplicate_frames(), vec![vec![0, 1], vec![2, 3, 4]]);
+
+    let f = load_test_file("basic-16x16");
+    assert!(f.duplicate_frames().is_empty());
+}
+
+#[test]
+fn legacy_blending() {
+    let legacy = RenderOptions {
+        legacy_blending: true,
+        ..RenderOptions::default()
+    };
+
+    let f = load_test_file("blend_multiply");
+    let frame = f.frame(0);
+    assert_eq!(
+        frame.image_with_options(&RenderOptions::default()),
+        frame.image()
+    );
+    let old = frame.image_with_options(&legacy);
+    assert_ne!(old, frame.image());
+    // Both methods agree where the pixels below are opaque.
+    let mut below = f.clone();
+    for layer in 1..below.num_layers() {
+        below.layer_mut(layer).set_visible(false);
+    }
+    let below = below.frame(0).image();
+    for ((new, old), below) in frame.image().pixels().zip(old.pixels()).zip(below.pixels()) {
+        if below[3] == 255 {
+            assert_eq!(new, old);
+        }
+    }
+
+    // Normal layers are not affected.
+    let f = load_test_file("blend_normal");
+    assert_eq!(f.frame(0).image_with_options(&legacy), f.frame(0).image());
+}
+
+#[test]
+fn saturation_bug_compatibility() {
+    let spec = RenderOptions {
+        saturation_bug_compatible: false,
+        ..RenderOptions::default()
+    };
+
+    let f = load_test_file("blend_saturation_bug");
+    let frame = f.frame(0);
+    assert_eq!(
+        frame.image_with_options(&RenderOptions::default()),
+        frame.image()
+    );
+    assert_ne!(frame.image_with_options(&spec), frame.image());
+
+    // Other blend modes are not affected.
+    let f = load_test_file("blend_multiply");
+    assert_eq!(f.frame(0).image_with_options(&spec), f.frame(0).image());
+}
+
+#[test]
+fn blend_mode_conversions() {
+    for id in 0..=18 {
+        let mode = BlendMode::try_from(id).unwrap();
+        assert_eq!(u16::from(mode), id);
+        assert_eq!(mode.to_string().parse::<BlendMode>().unwrap(), mode);
+    }
+    assert!(BlendMode::try_from(19).unwrap_err().is_invalid_input());
+
+    assert_eq!(BlendMode::Normal.to_string(), "normal");
+    assert_eq!(BlendMode::ColorDodge.to_string(), "color_dodge");
+    assert_eq!(
+        "soft_light".parse::<BlendMode>().unwrap(),
+        BlendMode::SoftLight
+    );
+    assert!("Normal".parse::<BlendMode>().is_err());
+    assert!("".parse::<BlendMode>().is_err());
+
+    let f = load_test_file("blend_luminosity");
+    assert_eq!(f.layer(1).blend_mode().to_string(), "hsl_luminosity");
+    assert_eq!(
+        "luminosity".parse::<BlendMode>().unwrap(),
+        BlendMode::Luminosity
+    );
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn python_conversions() {
+    use crate::python::{blend_mode_name, direction_name, layer_kind};
+
+    assert_eq!(blend_mode_name(BlendMode::Normal), "normal");
+    assert_eq!(blend_mode_name(BlendMode::ColorDodge), "color_dodge");
+    assert_eq!(blend_mode_name(BlendMode::Luminosity), "hsl_luminosity");
+    assert_eq!(layer_kind(&LayerType::Image), "image");
+    assert_eq!(layer_kind(&LayerType::Group), "group");
+    assert_eq!(
+        layer_kind(&LayerType::Tilemap(TilesetId::new(0))),
+        "tilemap"
+    );
+    assert_eq!(direction_name(AnimationDirection::Forward), "forward");
+    assert_eq!(direction_name(AnimationDirection::Reverse), "reverse");
+    assert_eq!(direction_name(AnimationDirection::PingPong), "pingpong");
+}
+
+#[test]
+fn tilemap_empty_tiles() {
+    // All tiles are opaque white, including tile 0.
+    let pixels = vec![255; 2 * 2 * 2 * 4];
+    let data = build_test_file(
+        6,
+        2,
+        0,
+        &[
+            tileset_chunk(2, (2, 2), &pixels),
+            tilemap_layer_chunk(),
+            tilemap_cel_chunk(3, 1, &[1, 0, 7]),
+        ],
+    );
+    let f = AsepriteFile::read(&data[..]).unwrap();
+    let image = f.frame(0).image();
+    // Tile 0 is the empty tile and tile IDs outside of the tileset show
+    // nothing.
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(2, 0).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(4, 0).0, [0, 0, 0, 0]);
+}
+
+#[test]
+fn set_image_keeps_visible_indexed_pixels() {
+    let mut f = load_test_file("indexed");
+    let layer = f
+        .layers()
+        .find(|l| !l.flags().contains(LayerFlags::BACKGROUND))
+        .unwrap()
+        .id();
+    let transparent = f.transparent_color_index().unwrap() as u32;
+    let palette = f.palette().unwrap();
+    let [r, g, b, _] = palette.color(transparent).unwrap().raw_rgba8();
+    let exact = (0..palette.num_colors())
+        .filter(|&i| i != transparent)
+        .map(|i| palette.color(i).unwrap().raw_rgba8())
+        .find(|c| c[3] == 255)
+        .unwrap();
+
+    // An opaque pixel with the color of the transparent entry stays visible.
+    let stamp = image::RgbaImage::from_fn(2, 1, |x, _| {
+        image::Rgba(if x == 0 { [r, g, b, 255] } else { exact })
+    });
+    f.cel_mut(0, layer)
+        .set_image(Point::new(0, 0), &stamp)
+        .unwrap();
+    let image = f.cel(0, layer).image();
+    assert_eq!(image.get_pixel(0, 0).0[3], 255);
+    assert_eq!(image.get_pixel(1, 0).0, exact);
+}
+
 /*
 #[test]
 fn gen_random_pixels() {
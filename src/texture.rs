@@ -0,0 +1,157 @@
+//! Pixel data laid out for uploading to the GPU.
+//!
+//! Graphics APIs often require each row of pixels in an upload buffer to
+//! start at an aligned offset. For example, wgpu requires rows to be a
+//! multiple of [WGPU_ROW_ALIGNMENT] bytes when copying from a buffer to a
+//! texture. [TextureData] pads each row accordingly, so the data can be
+//! uploaded without repacking.
+//!
+//! ```
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+//! # let ase = AsepriteFile::read_file(&path).unwrap();
+//! use asefile::texture::{TextureData, WGPU_ROW_ALIGNMENT};
+//!
+//! // All frames as layers of an array texture.
+//! let texture = TextureData::from_frames(&ase, 0..ase.num_frames(), WGPU_ROW_ALIGNMENT);
+//! assert_eq!(texture.extent(), (16, 16, 4));
+//! assert_eq!(texture.bytes_per_row, 256);
+//! ```
+
+use image::RgbaImage;
+
+use crate::{AsepriteFile, Tileset};
+
+/// Row alignment in bytes required by wgpu for buffer to texture copies
+/// (`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`).
+pub const WGPU_ROW_ALIGNMENT: u32 = 256;
+
+/// RGBA8 pixel data with padded rows, for one or more texture layers.
+///
+/// Layers are stored one after another. Each layer consists of `height`
+/// rows of `bytes_per_row` bytes, of which the first `4 * width` bytes are
+/// pixels and the rest is zero padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureData {
+    /// Width of each layer in pixels.
+    pub width: u32,
+    /// Height of each layer in pixels.
+    pub height: u32,
+    /// Number of layers. 1 for a plain 2D texture.
+    pub layers: u32,
+    /// Distance between the start of two rows in bytes.
+    pub bytes_per_row: u32,
+    /// The pixel data, including padding.
+    pub data: Vec<u8>,
+}
+
+impl TextureData {
+    /// Pad the rows of an image to a multiple of `row_alignment` bytes. Use 1
+    /// for tightly packed rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_alignment` is zero.
+    pub fn from_image(image: &RgbaImage, row_alignment: u32) -> Self {
+        Self::from_layers(image.width(), image.height(), [image], row_alignment)
+    }
+
+    /// Compose the given frame. See [from_image](Self::from_image).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not less than `num_frames` or if `row_alignment`
+    /// is zero.
+    pub fn from_frame(file: &AsepriteFile, frame: u32, row_alignment: u32) -> Self {
+        Self::from_image(&file.frame(frame).image(), row_alignment)
+    }
+
+    /// Compose the given frames and store them as the layers of an array
+    /// texture, in the given order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any frame is not less than `num_frames` or if
+    /// `row_alignment` is zero.
+    pub fn from_frames<I>(file: &AsepriteFile, frames: I, row_alignment: u32) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let images: Vec<RgbaImage> = frames
+            .into_iter()
+            .map(|frame| file.frame(frame).image())
+            .collect();
+        Self::from_layers(
+            file.width() as u32,
+            file.height() as u32,
+            &images,
+            row_alignment,
+        )
+    }
+
+    /// Store each tile of a tileset as one layer of an array texture, so the
+    /// layer index is the tile ID. This is convenient for drawing tilemaps
+    /// in a shader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_alignment` is zero.
+    pub fn from_tileset(tileset: &Tileset, row_alignment: u32) -> Self {
+        // The tileset image is a vertical strip of tiles, which is already
+        // the layout of an array texture apart from the row padding.
+        let (width, height) = tileset.tile_size().into();
+        let image = tileset.image();
+        let mut texture = Self::from_layers(
+            width,
+            height * tileset.tile_count(),
+            [&image],
+            row_alignment,
+        );
+        texture.height = height;
+        texture.layers = tileset.tile_count();
+        texture
+    }
+
+    /// Width, height and number of layers, e.g., for a `wgpu::Extent3d`.
+    pub fn extent(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.layers)
+    }
+
+    /// The pixels of a single row without padding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `layer` or `y` is out of bounds.
+    pub fn row(&self, layer: u32, y: u32) -> &[u8] {
+        assert!(layer < self.layers && y < self.height);
+        let start = ((layer * self.height + y) * self.bytes_per_row) as usize;
+        &self.data[start..start + 4 * self.width as usize]
+    }
+
+    fn from_layers<'a, I>(width: u32, height: u32, layers: I, row_alignment: u32) -> Self
+    where
+        I: IntoIterator<Item = &'a RgbaImage>,
+    {
+        assert!(row_alignment > 0, "row_alignment must be greater than zero");
+        let row_bytes = 4 * width as usize;
+        let bytes_per_row = row_bytes.next_multiple_of(row_alignment as usize);
+        let mut data = Vec::new();
+        let mut count = 0;
+        for image in layers {
+            debug_assert_eq!(image.width(), width);
+            for row in image.as_raw().chunks_exact(row_bytes.max(1)) {
+                data.extend_from_slice(row);
+                data.resize(data.len() + bytes_per_row - row_bytes, 0);
+            }
+            count += 1;
+        }
+        TextureData {
+            width,
+            height,
+            layers: count,
+            bytes_per_row: bytes_per_row as u32,
+            data,
+        }
+    }
+}
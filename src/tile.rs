@@ -6,14 +6,13 @@ pub(crate) struct TileId(pub u32);
 
 /// A tile is a reference to a single tile in a tilemap.
 ///
-/// Note that the Aseprite file format also enables rotating or flipping tiles.
-/// But since the GUI does not yet support those (as of v1.3-beta5) we do not
-/// yet expose these attributes.
-#[derive(Debug, Clone)]
-#[allow(unused)]
+/// Besides the tile ID, a tile can be flipped or rotated. The transforms are
+/// applied in the same order as in [Tiled](https://www.mapeditor.org/): first
+/// [rotate_90cw](Self::rotate_90cw), then [flip_x](Self::flip_x), then
+/// [flip_y](Self::flip_y).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tile {
     pub(crate) id: TileId,
-    // These are currently (Aseprite v1.3-beta5) not supported by the GUI.
     pub(crate) flip_x: bool,
     pub(crate) flip_y: bool,
     pub(crate) rotate_90cw: bool,
@@ -32,6 +31,27 @@ impl Tile {
         self.id.0
     }
 
+    /// Is the tile mirrored horizontally?
+    pub fn flip_x(&self) -> bool {
+        self.flip_x
+    }
+
+    /// Is the tile mirrored vertically?
+    pub fn flip_y(&self) -> bool {
+        self.flip_y
+    }
+
+    /// Is the tile's diagonal flipped, i.e., are its x and y axes swapped?
+    ///
+    /// The file format calls this a 90 degree clockwise rotation. Combined
+    /// with [flip_x](Self::flip_x) it results in one.
+    ///
+    /// Swapping the axes of a tile that is not square would change its size,
+    /// so this flag is ignored when rendering tiles that are not square.
+    pub fn rotate_90cw(&self) -> bool {
+        self.rotate_90cw
+    }
+
     pub(crate) fn new(chunk: &[u8], header: &TileBitmaskHeader) -> Result<Self> {
         AseReader::new(chunk)
             .dword()
@@ -48,7 +68,7 @@ impl Tile {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Tiles(Vec<Tile>);
 
 impl Tiles {
@@ -0,0 +1,346 @@
+//! Export tilesets and tilemaps to the [Tiled](https://www.mapeditor.org/)
+//! map editor. (Requires feature `tiled`.)
+//!
+//! ```toml
+//! [dependencies]
+//! asefile = { version = "0.3", features = ["tiled"] }
+//! ```
+//!
+//! Tile IDs are kept as they are, including the empty tile with ID 0. This
+//! way a tile with ID `n` in Aseprite is tile `n` in Tiled as well.
+//!
+//! ```no_run
+//! # use asefile::AsepriteFile;
+//! # use std::path::Path;
+//! # let ase = AsepriteFile::read_file(Path::new("level.aseprite")).unwrap();
+//! // Writes level.tmx, plus one .tsx and .png file per tileset.
+//! asefile::tiled::save_tmx(&ase, 0, Path::new("level.tmx")).unwrap();
+//! ```
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use image::{ImageResult, Rgba};
+
+use crate::{AsepriteFile, LayerType, Tile, Tileset, TilesetId, UserData};
+
+// Flags stored in the upper bits of a global tile ID.
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// Write a Tiled tileset (`.tsx`) file.
+///
+/// `image_source` is the path of the tileset image relative to the `.tsx`
+/// file. The image must have been created using
+/// [`tileset.image_grid(columns)`](Tileset::image_grid) with the same number
+/// of columns.
+///
+/// [UserData] of the individual tiles is written as tile properties: text as
+/// a string property `text` and color as a color property `color`.
+///
+/// ```
+/// # use asefile::{AsepriteFile, TilesetId};
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/tileset.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// let tileset = ase.tilesets().get(TilesetId::new(0)).unwrap();
+/// let mut tsx = Vec::new();
+/// asefile::tiled::write_tsx(tileset, "tiles.png", 4, &mut tsx).unwrap();
+/// ```
+pub fn write_tsx<W: Write>(
+    tileset: &Tileset,
+    image_source: &str,
+    columns: u32,
+    mut output: W,
+) -> io::Result<()> {
+    let (tile_width, tile_height): (u32, u32) = tileset.tile_size().into();
+    let tile_count = tileset.tile_count();
+    let columns = columns.clamp(1, tile_count.max(1));
+    let rows = tile_count.div_ceil(columns);
+
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<tileset version="1.10" name="{}" tilewidth="{}" tileheight="{}" tilecount="{}" columns="{}">"#,
+        escape_xml(tileset.name()),
+        tile_width,
+        tile_height,
+        tile_count,
+        columns
+    )?;
+    writeln!(
+        output,
+        r#" <image source="{}" width="{}" height="{}"/>"#,
+        escape_xml(image_source),
+        columns * tile_width,
+        rows * tile_height
+    )?;
+    for tile_id in 0..tile_count {
+        if let Some(user_data) = tileset.tile_user_data(tile_id) {
+            writeln!(output, r#" <tile id="{}">"#, tile_id)?;
+            write_properties(&mut output, user_data)?;
+            writeln!(output, " </tile>")?;
+        }
+    }
+    writeln!(output, "</tileset>")
+}
+
+/// Save a tileset as a `.tsx` file plus a PNG image next to it.
+///
+/// The image uses the same file name as `path` with the extension replaced
+/// by `png`.
+pub fn save_tsx(tileset: &Tileset, path: &Path, columns: u32) -> ImageResult<()> {
+    let image_path = path.with_extension("png");
+    let image_source = image_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    tileset.image_grid(columns).save(&image_path)?;
+    let mut tsx = Vec::new();
+    write_tsx(tileset, &image_source, columns, &mut tsx)?;
+    fs::write(path, tsx)?;
+    Ok(())
+}
+
+/// Write all tilemap layers of the given frame as a Tiled map (`.tmx`).
+///
+/// `tsx_source` is called once for each tileset used by the map and should
+/// return the path of the corresponding `.tsx` file relative to the `.tmx`
+/// file (see [write_tsx]).
+///
+/// All tilemap layers must use tiles of the same size. Otherwise an error of
+/// kind [io::ErrorKind::InvalidInput] is returned. Tile flips are stored using
+/// Tiled's flip flags.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn write_tmx<W, F>(file: &AsepriteFile, frame: u32, tsx_source: F, output: W) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(&Tileset) -> String,
+{
+    let layers: Vec<u32> = file
+        .layers()
+        .filter(|layer| layer.is_tilemap())
+        .map(|layer| layer.id())
+        .collect();
+    write_tmx_layers(file, frame, &layers, tsx_source, output)
+}
+
+/// Like [write_tmx], but only includes the given layers.
+///
+/// Layers that are not tilemap layers are ignored.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames` or if any layer ID is not
+/// less than `num_layers`.
+pub fn write_tmx_layers<W, F>(
+    file: &AsepriteFile,
+    frame: u32,
+    layers: &[u32],
+    mut tsx_source: F,
+    mut output: W,
+) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(&Tileset) -> String,
+{
+    assert!(frame < file.num_frames());
+    let mut layers: Vec<(u32, TilesetId)> = layers
+        .iter()
+        .filter_map(|&id| match file.layer(id).layer_type() {
+            LayerType::Tilemap(tileset_id) => Some((id, tileset_id)),
+            _ => None,
+        })
+        .collect();
+    layers.sort_unstable();
+    layers.dedup();
+
+    let mut tileset_ids: Vec<TilesetId> = layers.iter().map(|&(_, id)| id).collect();
+    tileset_ids.sort_unstable();
+    tileset_ids.dedup();
+    let tilesets: Vec<&Tileset> = tileset_ids
+        .iter()
+        .filter_map(|&id| file.tilesets().get(id))
+        .collect();
+
+    let (tile_width, tile_height) = match tilesets.first() {
+        Some(tileset) => tileset.tile_size().into(),
+        None => (1, 1),
+    };
+    if tilesets
+        .iter()
+        .any(|tileset| <(u32, u32)>::from(tileset.tile_size()) != (tile_width, tile_height))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "All tilemap layers must use the same tile size",
+        ));
+    }
+    let width = (file.width() as u32).div_ceil(tile_width);
+    let height = (file.height() as u32).div_ceil(tile_height);
+
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0">"#,
+        width, height, tile_width, tile_height
+    )?;
+
+    // Global tile IDs of each tileset start after the previous tileset. 0
+    // means "no tile".
+    let mut first_gids = Vec::with_capacity(tilesets.len());
+    let mut next_gid = 1;
+    for tileset in &tilesets {
+        writeln!(
+            output,
+            r#" <tileset firstgid="{}" source="{}"/>"#,
+            next_gid,
+            escape_xml(&tsx_source(tileset))
+        )?;
+        first_gids.push((tileset.id(), next_gid));
+        next_gid += tileset.tile_count();
+    }
+
+    for (index, &(layer_id, tileset_id)) in layers.iter().enumerate() {
+        let layer = file.layer(layer_id);
+        let first_gid = first_gids
+            .iter()
+            .find(|(id, _)| *id == tileset_id)
+            .map(|&(_, gid)| gid);
+        writeln!(
+            output,
+            r#" <layer id="{}" name="{}" width="{}" height="{}" opacity="{}" visible="{}">"#,
+            index + 1,
+            escape_xml(layer.name()),
+            width,
+            height,
+            layer.opacity() as f32 / 255.0,
+            layer.is_visible() as u8
+        )?;
+        writeln!(output, r#"  <data encoding="csv">"#)?;
+        let tilemap = file.tilemap(layer_id, frame);
+        for y in 0..height {
+            let row: Vec<String> = (0..width)
+                .map(|x| {
+                    let gid = match (&tilemap, first_gid) {
+                        (Some(tilemap), Some(first_gid))
+                            if x < tilemap.width() && y < tilemap.height() =>
+                        {
+                            global_tile_id(tilemap.tile(x, y), tilemap.tileset(), first_gid)
+                        }
+                        _ => 0,
+                    };
+                    gid.to_string()
+                })
+                .collect();
+            let separator = if y + 1 < height { "," } else { "" };
+            writeln!(output, "{}{}", row.join(","), separator)?;
+        }
+        writeln!(output, "  </data>")?;
+        writeln!(output, " </layer>")?;
+    }
+    writeln!(output, "</map>")
+}
+
+/// Save all tilemap layers of a frame as a `.tmx` file, together with a
+/// `.tsx` file and tileset image for each tileset.
+///
+/// The tileset files are placed next to `path` and named after it, e.g.,
+/// `level_tileset0.tsx` and `level_tileset0.png` for `level.tmx`.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn save_tmx(file: &AsepriteFile, frame: u32, path: &Path) -> ImageResult<()> {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut result = Ok(());
+    let mut tmx = Vec::new();
+    write_tmx(
+        file,
+        frame,
+        |tileset| {
+            let name = format!("{}_tileset{}.tsx", stem, tileset.id().value());
+            let columns = (tileset.tile_count() as f64).sqrt().ceil() as u32;
+            if result.is_ok() {
+                result = save_tsx(tileset, &path.with_file_name(&name), columns);
+            }
+            name
+        },
+        &mut tmx,
+    )?;
+    result?;
+    fs::write(path, tmx)?;
+    Ok(())
+}
+
+// Converts an Aseprite tile into a Tiled global tile ID including flip flags.
+fn global_tile_id(tile: &Tile, tileset: &Tileset, first_gid: u32) -> u32 {
+    let id = tile.id();
+    if tileset.is_empty_tile_id(id) {
+        return 0;
+    }
+    // Aseprite uses the same flip flags as Tiled, including the diagonal
+    // flip, which it calls 90 degree rotation.
+    let mut gid = first_gid + id;
+    if tile.flip_x {
+        gid |= FLIPPED_HORIZONTALLY;
+    }
+    if tile.flip_y {
+        gid |= FLIPPED_VERTICALLY;
+    }
+    if tile.rotate_90cw {
+        gid |= FLIPPED_DIAGONALLY;
+    }
+    gid
+}
+
+fn write_properties<W: Write>(output: &mut W, user_data: &UserData) -> io::Result<()> {
+    writeln!(output, "  <properties>")?;
+    if let Some(text) = &user_data.text {
+        writeln!(
+            output,
+            r#"   <property name="text" value="{}"/>"#,
+            escape_xml(text)
+        )?;
+    }
+    if let Some(color) = user_data.color {
+        writeln!(
+            output,
+            r#"   <property name="color" type="color" value="{}"/>"#,
+            tiled_color(color)
+        )?;
+    }
+    writeln!(output, "  </properties>")
+}
+
+// Tiled stores colors as `#AARRGGBB`.
+fn tiled_color(color: Rgba<u8>) -> String {
+    let [r, g, b, a] = color.0;
+    format!("#{:02x}{:02x}{:02x}{:02x}", a, r, g, b)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            '\n' => result.push_str("&#10;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{self, Read, Write};
 
 use image::RgbaImage;
 
@@ -6,7 +6,7 @@ use crate::{
     cel::CelContent,
     reader::AseReader,
     tile::{self, Tile, EMPTY_TILE},
-    AsepriteParseError, Cel, Result, Tileset,
+    AsepriteParseError, Cel, Point, Result, Size, Tileset, TilesetId, UserData,
 };
 
 /// A reference to a tilemap.
@@ -40,9 +40,14 @@ impl<'a> Tilemap<'a> {
     }
 
     /// Width and height of each tile in the tilemap.
-    pub fn tile_size(&self) -> (u32, u32) {
+    pub fn tile_size(&self) -> Size {
         let sz = self.tileset.tile_size();
-        (sz.width() as u32, sz.height() as u32)
+        Size::new(sz.width() as u32, sz.height() as u32)
+    }
+
+    /// The id of the tileset used by this tilemap.
+    pub fn tileset_id(&self) -> TilesetId {
+        self.tileset.id()
     }
 
     /// The tileset used by this tilemap.
@@ -63,9 +68,9 @@ impl<'a> Tilemap<'a> {
     /// tile offsets. I.e., if your first column is empty, then the GUI shows
     /// `-1` for the x coordinate of the top-left tile.
     pub fn tile(&self, x: u32, y: u32) -> &Tile {
-        let (ofs_x, ofs_y) = self.tile_offsets();
-        let x = x as i32 - ofs_x;
-        let y = y as i32 - ofs_y;
+        let offsets = self.tile_offsets();
+        let x = x as i32 - offsets.x;
+        let y = y as i32 - offsets.y;
         // The actual tilemap data may be smaller because it does not include
         // any data for empty tiles on the outer rows or columns.
         let w = self.tilemap().width() as i32;
@@ -77,25 +82,155 @@ impl<'a> Tilemap<'a> {
         &self.tilemap().tiles[index]
     }
 
+    /// All tiles of the map as `(x, y, tile)`, row by row, starting at the top
+    /// left. Coordinates are the same as for [tile](Self::tile).
+    ///
+    /// Includes empty tiles. See [non_empty_tiles](Self::non_empty_tiles) to
+    /// skip them.
+    pub fn tiles(&self) -> impl Iterator<Item = (u32, u32, &Tile)> + '_ {
+        (0..self.height())
+            .flat_map(move |y| (0..self.width()).map(move |x| (x, y, self.tile(x, y))))
+    }
+
+    /// Like [tiles](Self::tiles), but skips empty tiles.
+    pub fn non_empty_tiles(&self) -> impl Iterator<Item = (u32, u32, &Tile)> + '_ {
+        self.tiles()
+            .filter(move |(_, _, tile)| !self.tileset.is_empty_tile_id(tile.id()))
+    }
+
+    /// The tile IDs of the whole map in row-major order, i.e., the ID of the
+    /// tile at `(x, y)` is at index `y * width() + x`. Empty cells have ID 0.
+    ///
+    /// Flip flags are not included. Use [to_tile_vec](Self::to_tile_vec) if
+    /// you need them.
+    pub fn to_vec(&self) -> Vec<u32> {
+        self.tiles().map(|(_, _, tile)| tile.id()).collect()
+    }
+
+    /// Like [to_vec](Self::to_vec), but returns the full [Tile]s.
+    pub fn to_tile_vec(&self) -> Vec<Tile> {
+        self.tiles().map(|(_, _, tile)| tile.clone()).collect()
+    }
+
+    /// Classify every cell of the map using the [UserData] of its tile in the
+    /// tileset. Useful for building collision or terrain grids.
+    ///
+    /// The result is in the same row-major order as [to_vec](Self::to_vec).
+    /// `classify` receives `None` for empty cells and for tiles without user
+    /// data.
+    pub fn classify<T, F>(&self, mut classify: F) -> Vec<T>
+    where
+        F: FnMut(Option<&UserData>) -> T,
+    {
+        self.tiles()
+            .map(|(_, _, tile)| {
+                let id = tile.id();
+                if self.tileset.is_empty_tile_id(id) {
+                    classify(None)
+                } else {
+                    classify(self.tileset.tile_user_data(id))
+                }
+            })
+            .collect()
+    }
+
+    /// A collision grid in row-major order. A cell is `true` if its tile's
+    /// user data text is exactly `solid_text` (e.g., `"solid"`).
+    ///
+    /// See [classify](Self::classify) for more control.
+    pub fn collision_mask(&self, solid_text: &str) -> Vec<bool> {
+        self.classify(|user_data| {
+            user_data
+                .and_then(|user_data| user_data.text.as_deref())
+                .is_some_and(|text| text == solid_text)
+        })
+    }
+
+    /// The cells that differ between this tilemap and `other`, e.g., the
+    /// same layer in two different frames. Useful for updating a tilemap
+    /// incrementally.
+    ///
+    /// Both maps are compared over the larger of the two sizes. Cells outside
+    /// a map count as empty.
+    pub fn diff(&self, other: &Tilemap) -> Vec<TileChange> {
+        let width = self.width().max(other.width());
+        let height = self.height().max(other.height());
+        let cell = |tilemap: &Tilemap, x, y| {
+            if x < tilemap.width() && y < tilemap.height() {
+                tilemap.tile(x, y).clone()
+            } else {
+                EMPTY_TILE.clone()
+            }
+        };
+        let mut changes = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let old = cell(self, x, y);
+                let new = cell(other, x, y);
+                if old != new {
+                    changes.push(TileChange { x, y, old, new });
+                }
+            }
+        }
+        changes
+    }
+
+    /// Write the tile IDs as comma-separated values, one line per row of
+    /// tiles. Empty cells are written as `0`.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/tilemap.aseprite");
+    /// # let ase = AsepriteFile::read_file(&path).unwrap();
+    /// let tilemap = ase.tilemap(0, 0).unwrap();
+    /// let mut csv = Vec::new();
+    /// tilemap.write_csv(&mut csv).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap(), "1,2\n3,4\n");
+    /// ```
+    pub fn write_csv<W: Write>(&self, mut output: W) -> io::Result<()> {
+        let ids = self.to_vec();
+        for row in ids.chunks(self.width().max(1) as usize) {
+            let row: Vec<String> = row.iter().map(|id| id.to_string()).collect();
+            writeln!(output, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+
     /// Describes first not-empty tile.
-    pub fn tile_offsets(&self) -> (i32, i32) {
-        let (x, y) = self.pixel_offsets();
+    pub fn tile_offsets(&self) -> Point {
+        let offsets = self.pixel_offsets();
         let size = self.tileset().tile_size();
-        (x / size.width() as i32, y / size.height() as i32)
+        Point::new(
+            offsets.x / size.width() as i32,
+            offsets.y / size.height() as i32,
+        )
     }
 
     /// Describes first non-empty tile in pixel offsets.
-    pub fn pixel_offsets(&self) -> (i32, i32) {
+    pub fn pixel_offsets(&self) -> Point {
         self.cel.top_left()
     }
 }
 
+/// A single cell that differs between two tilemaps. See [Tilemap::diff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileChange {
+    /// Column of the cell.
+    pub x: u32,
+    /// Row of the cell.
+    pub y: u32,
+    /// The tile in the first map.
+    pub old: Tile,
+    /// The tile in the second map.
+    pub new: Tile,
+}
+
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TilemapData {
     width: u16,
     height: u16,
-    //tileset_id: TilesetId,
     tiles: tile::Tiles,
     bits_per_tile: u16,
     bitmask_header: TileBitmaskHeader,
@@ -144,7 +279,7 @@ impl TilemapData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct TileBitmaskHeader {
     pub tile_id: u32,
     pub x_flip: u32,
@@ -2,6 +2,7 @@ use std::{collections::HashMap, error::Error, fmt, io::Read, sync::Arc};
 
 use crate::{
     pixel::{Pixels, RawPixels},
+    user_data::UserData,
     AsepriteParseError, ColorPalette, PixelFormat, Result,
 };
 use bitflags::bitflags;
@@ -10,19 +11,31 @@ use image::RgbaImage;
 use crate::{external_file::ExternalFileId, reader::AseReader};
 
 /// An id for a [Tileset].
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub(crate) struct TilesetId(pub(crate) u32);
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct TilesetId(u32);
 
 impl TilesetId {
     /// Create a new `TilesetId` from a raw `u32` value.
-    pub(crate) fn from_raw(value: u32) -> Self {
+    pub fn new(value: u32) -> Self {
         Self(value)
     }
 
-    // Get the underlying `u32` value.
-    // pub(crate) fn value(&self) -> u32 {
-    //     self.0
-    // }
+    /// Get the underlying `u32` value.
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for TilesetId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TilesetId> for u32 {
+    fn from(id: TilesetId) -> Self {
+        id.0
+    }
 }
 
 impl fmt::Display for TilesetId {
@@ -50,7 +63,7 @@ bitflags! {
 #[derive(Debug, Clone)]
 pub struct ExternalTilesetReference {
     external_file_id: ExternalFileId,
-    tileset_id: u32,
+    tileset_id: TilesetId,
 }
 
 impl ExternalTilesetReference {
@@ -60,14 +73,14 @@ impl ExternalTilesetReference {
     }
 
     /// The id of the [Tileset] in the [crate::ExternalFile].
-    pub fn tileset_id(&self) -> u32 {
+    pub fn tileset_id(&self) -> TilesetId {
         self.tileset_id
     }
 
     fn parse<T: Read>(reader: &mut AseReader<T>) -> Result<Self> {
         Ok(ExternalTilesetReference {
             external_file_id: reader.dword().map(ExternalFileId::new)?,
-            tileset_id: reader.dword()?,
+            tileset_id: reader.dword().map(TilesetId)?,
         })
     }
 }
@@ -108,9 +121,9 @@ impl TileSize {
 ///
 /// See [official docs for tilemaps and tilesets](https://www.aseprite.org/docs/tilemap/)
 /// for details.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tileset<P = Pixels> {
-    pub(crate) id: u32,
+    pub(crate) id: TilesetId,
     pub(crate) empty_tile_is_id_zero: bool,
     pub(crate) tile_count: u32,
     pub(crate) tile_size: TileSize,
@@ -118,11 +131,14 @@ pub struct Tileset<P = Pixels> {
     pub(crate) name: String,
     pub(crate) external_file: Option<ExternalTilesetReference>,
     pub(crate) pixels: Option<P>,
+    pub(crate) user_data: Option<UserData>,
+    // Indexed by tile id. May be shorter than `tile_count`.
+    pub(crate) tile_user_data: Vec<Option<UserData>>,
 }
 
 impl<P> Tileset<P> {
     /// Tileset id.
-    pub fn id(&self) -> u32 {
+    pub fn id(&self) -> TilesetId {
         self.id
     }
 
@@ -138,6 +154,11 @@ impl<P> Tileset<P> {
         self.tile_count
     }
 
+    // Does a tilemap cell with this tile ID show nothing?
+    pub(crate) fn is_empty_tile_id(&self, tile_id: u32) -> bool {
+        (tile_id == 0 && self.empty_tile_is_id_zero) || tile_id >= self.tile_count
+    }
+
     /// Tile width and height.
     pub fn tile_size(&self) -> TileSize {
         self.tile_size
@@ -158,6 +179,28 @@ impl<P> Tileset<P> {
     pub fn external_file(&self) -> Option<&ExternalTilesetReference> {
         self.external_file.as_ref()
     }
+
+    /// Returns a reference to the tileset's [UserData], if any exists.
+    pub fn user_data(&self) -> Option<&UserData> {
+        self.user_data.as_ref()
+    }
+
+    /// Returns a reference to the [UserData] of a single tile, if any exists.
+    ///
+    /// Per-tile user data requires Aseprite 1.3 or later.
+    pub fn tile_user_data(&self, tile_id: u32) -> Option<&UserData> {
+        self.tile_user_data
+            .get(tile_id as usize)
+            .and_then(|user_data| user_data.as_ref())
+    }
+
+    pub(crate) fn set_tile_user_data(&mut self, tile_id: u32, user_data: UserData) {
+        let index = tile_id as usize;
+        if self.tile_user_data.len() <= index {
+            self.tile_user_data.resize(index + 1, None);
+        }
+        self.tile_user_data[index] = Some(user_data);
+    }
 }
 
 impl Tileset<RawPixels> {
@@ -166,7 +209,7 @@ impl Tileset<RawPixels> {
         pixel_format: PixelFormat,
     ) -> Result<Tileset<RawPixels>> {
         let mut reader = AseReader::new(data);
-        let id = reader.dword()?;
+        let id = reader.dword().map(TilesetId)?;
         let flags = reader.dword().map(TilesetFlags::from_bits_truncate)?;
         let empty_tile_is_id_zero = flags.contains(TilesetFlags::EMPTY_TILE_IS_ID_ZERO);
         let tile_count = reader.dword()?;
@@ -206,6 +249,8 @@ impl Tileset<RawPixels> {
             name,
             external_file,
             pixels,
+            user_data: None,
+            tile_user_data: Vec::new(),
         })
     }
 }
@@ -230,6 +275,22 @@ impl Tileset<Pixels> {
         RgbaImage::from_raw(width, height, raw).expect("Mismatched image size")
     }
 
+    /// Returns `true` if every pixel of the tile is fully transparent.
+    ///
+    /// This is always the case for the empty tile (usually ID 0), but tiles
+    /// that were drawn and later erased are blank as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile_id` is not less than `tile_count()`.
+    pub fn is_tile_empty(&self, tile_id: u32) -> bool {
+        assert!(tile_id < self.tile_count());
+        let pixels = self.pixels.as_ref().expect("No pixel data in tileset");
+        let pixels_per_tile = self.tile_size.pixels_per_tile() as usize;
+        let start = tile_id as usize * pixels_per_tile;
+        pixels.is_transparent(start..start + pixels_per_tile)
+    }
+
     /// Collect all tiles into one long vertical image.
     ///
     /// The image has width equal to the tile width and height equal to
@@ -248,10 +309,51 @@ impl Tileset<Pixels> {
             .collect();
         RgbaImage::from_raw(width, image_height, raw).expect("Mismatched image size")
     }
+
+    /// Arrange all tiles in a grid with the given number of columns.
+    ///
+    /// Tiles are placed left to right, top to bottom. Unused cells in the
+    /// last row are transparent. `columns` is clamped to `1..=tile_count()`.
+    pub fn image_grid(&self, columns: u32) -> RgbaImage {
+        self.image_grid_spaced(columns, 0, 0)
+    }
+
+    /// Like [image_grid](Self::image_grid), but with `spacing` empty pixels
+    /// between adjacent tiles and `margin` empty pixels around the outside of
+    /// the image.
+    ///
+    /// These use the same meaning as the corresponding tileset properties in
+    /// [Tiled](https://www.mapeditor.org/).
+    pub fn image_grid_spaced(&self, columns: u32, spacing: u32, margin: u32) -> RgbaImage {
+        let (tile_width, tile_height): (u32, u32) = self.tile_size.into();
+        let columns = columns.clamp(1, self.tile_count.max(1));
+        let rows = self.tile_count.div_ceil(columns);
+        let extent =
+            |count: u32, size: u32| count * size + count.saturating_sub(1) * spacing + 2 * margin;
+        let mut image = RgbaImage::new(extent(columns, tile_width), extent(rows, tile_height));
+
+        let pixels = self.pixels.as_ref().expect("No pixel data in tileset");
+        let rgba = pixels.clone_as_image_rgba();
+        let pixels_per_tile = self.tile_size.pixels_per_tile() as usize;
+        for (tile, tile_pixels) in rgba
+            .chunks_exact(pixels_per_tile)
+            .take(self.tile_count as usize)
+            .enumerate()
+        {
+            let tile = tile as u32;
+            let left = margin + (tile % columns) * (tile_width + spacing);
+            let top = margin + (tile / columns) * (tile_height + spacing);
+            for (i, pixel) in tile_pixels.iter().enumerate() {
+                let i = i as u32;
+                image.put_pixel(left + i % tile_width, top + i / tile_width, *pixel);
+            }
+        }
+        image
+    }
 }
 
-/// A map from tileset ids (`u32`) to [Tileset]s.
-#[derive(Debug)]
+/// A map from [TilesetId]s to [Tileset]s.
+#[derive(Debug, Clone)]
 pub struct TilesetsById<P = Pixels>(HashMap<TilesetId, Tileset<P>>);
 
 impl<P> TilesetsById<P> {
@@ -260,7 +362,7 @@ impl<P> TilesetsById<P> {
     }
 
     pub(crate) fn add(&mut self, tileset: Tileset<P>) {
-        self.0.insert(TilesetId::from_raw(tileset.id), tileset);
+        self.0.insert(tileset.id, tileset);
     }
 
     /// Returns the number of entries in the tileset.
@@ -279,32 +381,52 @@ impl<P> TilesetsById<P> {
     }
 
     /// Get a reference to a [Tileset] from an id, if the entry exists.
-    pub fn get(&self, id: u32) -> Option<&Tileset<P>> {
-        self.0.get(&TilesetId::from_raw(id))
+    pub fn get(&self, id: TilesetId) -> Option<&Tileset<P>> {
+        self.0.get(&id)
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tileset<P>> {
+        self.0.values_mut()
+    }
+
+    pub(crate) fn get_mut(&mut self, id: TilesetId) -> Option<&mut Tileset<P>> {
+        self.0.get_mut(&id)
+    }
+
+    pub(crate) fn remove(&mut self, id: TilesetId) -> Option<Tileset<P>> {
+        self.0.remove(&id)
     }
 }
 
 impl TilesetsById<RawPixels> {
+    // `external` contains the already loaded tilesets for tilesets that are
+    // stored in an external file.
     pub(crate) fn validate(
         self,
         pixel_format: &PixelFormat,
         palette: Option<Arc<ColorPalette>>,
+        mut external: TilesetsById<Pixels>,
     ) -> Result<TilesetsById<Pixels>> {
         let mut result = HashMap::with_capacity(self.0.capacity());
         for (id, tileset) in self.0.into_iter() {
-            // Validates that all Tilesets contain their own pixel data.
-            // External file references currently not supported.
-            let _ = tileset.pixels.as_ref().ok_or_else(|| {
-                AsepriteParseError::UnsupportedFeature(
-                    "Expected Tileset data to contain pixels. External file Tilesets not supported"
-                        .into(),
-                )
-            })?;
-
-            let pixels = tileset
-                .pixels
-                .unwrap()
-                .validate(palette.clone(), pixel_format, false)?;
+            // Validates that all Tilesets contain their own pixel data or that
+            // their external file has been resolved.
+            let (pixels, tile_count) = match tileset.pixels {
+                Some(pixels) => (
+                    pixels.validate(palette.clone(), pixel_format, false)?,
+                    tileset.tile_count,
+                ),
+                None => external
+                    .remove(id)
+                    .and_then(|external| Some((external.pixels?, external.tile_count)))
+                    .ok_or_else(|| {
+                        AsepriteParseError::UnsupportedFeature(
+                            "Expected Tileset data to contain pixels. External file Tilesets \
+                             require AsepriteFile::read_with_external_resolver"
+                                .into(),
+                        )
+                    })?,
+            };
 
             result.insert(
                 id,
@@ -312,11 +434,13 @@ impl TilesetsById<RawPixels> {
                     pixels: Some(pixels),
                     id: tileset.id,
                     empty_tile_is_id_zero: tileset.empty_tile_is_id_zero,
-                    tile_count: tileset.tile_count,
+                    tile_count,
                     tile_size: tileset.tile_size,
                     base_index: tileset.base_index,
                     name: tileset.name,
                     external_file: tileset.external_file,
+                    user_data: tileset.user_data,
+                    tile_user_data: tileset.tile_user_data,
                 },
             );
         }
@@ -328,9 +452,9 @@ impl TilesetsById<RawPixels> {
 #[derive(Debug, Clone)]
 pub enum TilesetImageError {
     /// No tileset was found for the given id.
-    MissingTilesetId(u32),
+    MissingTilesetId(TilesetId),
     /// No pixel data contained in the tileset with the given id.
-    NoPixelsInTileset(u32),
+    NoPixelsInTileset(TilesetId),
 }
 
 impl fmt::Display for TilesetImageError {
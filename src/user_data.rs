@@ -1,16 +1,201 @@
-use crate::{reader::AseReader, Result};
+use std::io::Read;
+
+use crate::{reader::AseReader, AsepriteParseError, ExternalFileId, Point, Rect, Result, Size};
 
 /// User-provided metadata which can be attached to various items.
 ///
 /// Aseprite allows attaching user data to several entities, both via the GUI
 /// and via extensions. For an example see the discussion
 /// [How to associate data to each cel](https://community.aseprite.org/t/how-to-associate-data-to-each-cel-frame/6307).
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct UserData {
     /// User-provided string data.
     pub text: Option<String>,
     /// User-provided color.
     pub color: Option<image::Rgba<u8>>,
+    /// Properties set by the user, e.g., via scripts. (Aseprite 1.3+)
+    pub properties: Properties,
+    /// Properties set by extensions. The ID refers to the extension's entry
+    /// in [AsepriteFile::external_files](crate::AsepriteFile::external_files).
+    /// (Aseprite 1.3+)
+    pub extension_properties: Vec<(ExternalFileId, Properties)>,
+}
+
+impl UserData {
+    /// Interpret the text as `key=value` pairs separated by newlines or `;`.
+    ///
+    /// Keys and values are trimmed. Entries without `=` are returned with an
+    /// empty value. Returns an empty list if there is no text.
+    ///
+    /// ```
+    /// # use asefile::UserData;
+    /// let user_data = UserData {
+    ///     text: Some("solid = true; friction=0.5".to_owned()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(user_data.key_values(), [("solid", "true"), ("friction", "0.5")]);
+    /// ```
+    pub fn key_values(&self) -> Vec<(&str, &str)> {
+        let Some(text) = &self.text else {
+            return Vec::new();
+        };
+        text.split(['\n', ';'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (entry, ""),
+            })
+            .collect()
+    }
+
+    /// The value of the first entry with the given key. See
+    /// [key_values](Self::key_values).
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.key_values()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Parse the text as JSON. Returns `None` if there is no text. (Requires
+    /// feature `json`.)
+    #[cfg(feature = "json")]
+    pub fn json(&self) -> Option<serde_json::Result<serde_json::Value>> {
+        self.parse()
+    }
+
+    /// Deserialize the text as JSON into a user-defined type. Returns `None`
+    /// if there is no text. (Requires feature `json`.)
+    ///
+    /// ```
+    /// # use asefile::UserData;
+    /// #[derive(serde::Deserialize)]
+    /// struct Hitbox {
+    ///     damage: u32,
+    /// }
+    ///
+    /// let user_data = UserData {
+    ///     text: Some(r#"{"damage": 3}"#.to_owned()),
+    ///     ..Default::default()
+    /// };
+    /// let hitbox: Hitbox = user_data.parse().unwrap().unwrap();
+    /// assert_eq!(hitbox.damage, 3);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn parse<T: serde::de::DeserializeOwned>(&self) -> Option<serde_json::Result<T>> {
+        self.text.as_deref().map(serde_json::from_str)
+    }
+
+    /// The properties set by the extension with the given ID.
+    pub fn extension(&self, id: ExternalFileId) -> Option<&Properties> {
+        self.extension_properties
+            .iter()
+            .find(|(ext, _)| *ext == id)
+            .map(|(_, properties)| properties)
+    }
+}
+
+/// A map of named [PropertyValue]s, in the order they are stored in the file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Properties {
+    entries: Vec<(String, PropertyValue)>,
+}
+
+impl Properties {
+    /// The value of the property with the given name.
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// All properties as `(name, value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PropertyValue)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Number of properties.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no properties.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The value of a single entry in [Properties].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    /// A 16.16 fixed point number.
+    Fixed(f64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Point(Point),
+    Size(Size),
+    Rect(Rect),
+    Vector(Vec<PropertyValue>),
+    Properties(Properties),
+    Uuid([u8; 16]),
+}
+
+impl PropertyValue {
+    /// The value as a string, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The value as a boolean, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropertyValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Any integer value that fits into an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            PropertyValue::I8(v) => Some(v as i64),
+            PropertyValue::U8(v) => Some(v as i64),
+            PropertyValue::I16(v) => Some(v as i64),
+            PropertyValue::U16(v) => Some(v as i64),
+            PropertyValue::I32(v) => Some(v as i64),
+            PropertyValue::U32(v) => Some(v as i64),
+            PropertyValue::I64(v) => Some(v),
+            PropertyValue::U64(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    /// Any numeric value converted to `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            PropertyValue::Fixed(v) | PropertyValue::F64(v) => Some(v),
+            PropertyValue::F32(v) => Some(v as f64),
+            PropertyValue::U64(v) => Some(v as f64),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
 }
 
 pub(crate) fn parse_userdata_chunk(data: &[u8]) -> Result<UserData> {
@@ -33,6 +218,140 @@ pub(crate) fn parse_userdata_chunk(data: &[u8]) -> Result<UserData> {
     } else {
         None
     };
+    let mut properties = Properties::default();
+    let mut extension_properties = Vec::new();
+    if flags & 4 != 0 {
+        // The size of the properties block includes the size field itself.
+        let size = reader.dword()? as usize;
+        let block = reader.take_bytes(size.saturating_sub(4).min(data.len()))?;
+        let mut reader = AseReader::new(&block);
+        let num_maps = reader.dword()?;
+        for _ in 0..num_maps {
+            let key = reader.dword()?;
+            let mut map = Properties::default();
+            // Values of unknown types have no known size, so nothing after
+            // them can be read. Keep what was parsed so far and skip the rest
+            // of the block.
+            let complete = match parse_properties(&mut reader, &mut map, 0) {
+                Ok(()) => true,
+                Err(AsepriteParseError::UnsupportedFeature(_)) => false,
+                Err(err) => return Err(err),
+            };
+            if key == 0 {
+                properties = map;
+            } else {
+                extension_properties.push((ExternalFileId::new(key), map));
+            }
+            if !complete {
+                break;
+            }
+        }
+    }
+
+    Ok(UserData {
+        text,
+        color,
+        properties,
+        extension_properties,
+    })
+}
+
+// Vectors and maps can be nested. Each level only takes a few bytes of input,
+// so the depth is limited to keep corrupt files from overflowing the stack.
+const MAX_PROPERTY_DEPTH: u32 = 64;
+
+fn parse_properties<R: Read>(
+    reader: &mut AseReader<R>,
+    map: &mut Properties,
+    depth: u32,
+) -> Result<()> {
+    let count = reader.dword()?;
+    for _ in 0..count {
+        let name = reader.string()?;
+        let value_type = reader.word()?;
+        let value = parse_property_value(reader, value_type, depth)?;
+        map.entries.push((name, value));
+    }
+    Ok(())
+}
+
+fn parse_property_value<R: Read>(
+    reader: &mut AseReader<R>,
+    value_type: u16,
+    depth: u32,
+) -> Result<PropertyValue> {
+    if matches!(value_type, 0x0011 | 0x0012) && depth >= MAX_PROPERTY_DEPTH {
+        return Err(AsepriteParseError::InvalidInput(format!(
+            "User data properties are nested deeper than {} levels",
+            MAX_PROPERTY_DEPTH
+        )));
+    }
+    let value = match value_type {
+        0x0001 => PropertyValue::Bool(reader.byte()? != 0),
+        0x0002 => PropertyValue::I8(reader.byte()? as i8),
+        0x0003 => PropertyValue::U8(reader.byte()?),
+        0x0004 => PropertyValue::I16(reader.short()?),
+        0x0005 => PropertyValue::U16(reader.word()?),
+        0x0006 => PropertyValue::I32(reader.long()?),
+        0x0007 => PropertyValue::U32(reader.dword()?),
+        0x0008 => PropertyValue::I64(reader.long64()?),
+        0x0009 => PropertyValue::U64(reader.qword()?),
+        0x000A => PropertyValue::Fixed(reader.long()? as f64 / 65536.0),
+        0x000B => PropertyValue::F32(reader.float()?),
+        0x000C => PropertyValue::F64(reader.double()?),
+        0x000D => PropertyValue::String(reader.string()?),
+        0x000E => PropertyValue::Point(parse_point(reader)?),
+        0x000F => PropertyValue::Size(parse_size(reader)?),
+        0x0010 => {
+            let origin = parse_point(reader)?;
+            let size = parse_size(reader)?;
+            PropertyValue::Rect(Rect::from_origin_size(origin, size))
+        }
+        0x0011 => {
+            let count = reader.dword()?;
+            let element_type = reader.word()?;
+            let mut elements = Vec::new();
+            for _ in 0..count {
+                // Type 0 means every element stores its own type.
+                let value_type = if element_type == 0 {
+                    reader.word()?
+                } else {
+                    element_type
+                };
+                elements.push(parse_property_value(reader, value_type, depth + 1)?);
+            }
+            PropertyValue::Vector(elements)
+        }
+        0x0012 => {
+            let mut map = Properties::default();
+            parse_properties(reader, &mut map, depth + 1)?;
+            PropertyValue::Properties(map)
+        }
+        0x0013 => {
+            let mut uuid = [0; 16];
+            reader.read_exact(&mut uuid)?;
+            PropertyValue::Uuid(uuid)
+        }
+        _ => {
+            return Err(AsepriteParseError::UnsupportedFeature(format!(
+                "Unknown user data property type: {}",
+                value_type
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn parse_point<R: Read>(reader: &mut AseReader<R>) -> Result<Point> {
+    let x = reader.long()?;
+    let y = reader.long()?;
+    Ok(Point::new(x, y))
+}
 
-    Ok(UserData { text, color })
+// Sizes are stored as signed numbers. Negative sizes make no sense, so they
+// are treated as zero.
+fn parse_size<R: Read>(reader: &mut AseReader<R>) -> Result<Size> {
+    let w = reader.long()?;
+    let h = reader.long()?;
+    Ok(Size::new(w.max(0) as u32, h.max(0) as u32))
 }
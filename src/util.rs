@@ -9,11 +9,19 @@
 //! asefile = { version = "0.3", features = ["utils"] }
 //! ```
 
-use image::RgbaImage;
+use byteorder::{LittleEndian, WriteBytesExt};
+use image::{GrayImage, Luma, Rgba, RgbaImage};
 use nohash::IntMap;
-use std::iter::once;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    iter::once,
+};
 
-use crate::ColorPalette;
+use crate::{
+    blend, cel::CelContent, geometry, palette, tile::TileId, AsepriteFile, ColorPalette, Point,
+    Rect, RenderOptions, Slice, Slice9, Tile, Tilemap, Tileset,
+};
 
 /// Add a 1 pixel border around the input image by duplicating the outmost
 /// pixels.
@@ -44,56 +52,1163 @@ pub fn extrude_border(image: RgbaImage) -> RgbaImage {
     RgbaImage::from_raw((w + 2) as u32, (h + 2) as u32, data).unwrap()
 }
 
+/// Convert an image from straight to premultiplied alpha in place.
+///
+/// Each color channel is multiplied by alpha and rounded to the nearest
+/// value, so fully opaque pixels stay unchanged and fully transparent pixels
+/// become `[0, 0, 0, 0]`.
+pub fn premultiply_alpha(image: &mut RgbaImage) {
+    premultiply_alpha_buffer(image);
+}
+
+/// Like [premultiply_alpha], but operates on raw RGBA bytes.
+///
+/// # Panics
+///
+/// Panics if the length of `data` is not a multiple of 4.
+pub fn premultiply_alpha_buffer(data: &mut [u8]) {
+    assert!(
+        data.chunks_exact(4).remainder().is_empty(),
+        "data must consist of RGBA pixels"
+    );
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel[..3] {
+            *channel = div_255(*channel as u32 * alpha);
+        }
+    }
+}
+
+/// Convert an image from premultiplied to straight alpha in place.
+///
+/// This is the inverse of [premultiply_alpha] up to rounding: precision lost
+/// while premultiplying cannot be recovered. Fully transparent pixels become
+/// `[0, 0, 0, 0]`.
+pub fn unpremultiply_alpha(image: &mut RgbaImage) {
+    unpremultiply_alpha_buffer(image);
+}
+
+/// Like [unpremultiply_alpha], but operates on raw RGBA bytes.
+///
+/// # Panics
+///
+/// Panics if the length of `data` is not a multiple of 4.
+pub fn unpremultiply_alpha_buffer(data: &mut [u8]) {
+    assert!(
+        data.chunks_exact(4).remainder().is_empty(),
+        "data must consist of RGBA pixels"
+    );
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel[..3] {
+            *channel = (*channel as u32 * 255 + alpha / 2)
+                .checked_div(alpha)
+                .map_or(0, |value| value.min(255) as u8);
+        }
+    }
+}
+
+// Computes `round(value / 255)` for `value <= 255 * 255` without division.
+fn div_255(value: u32) -> u8 {
+    let value = value + 128;
+    ((value + (value >> 8)) >> 8) as u8
+}
+
+/// Which neighbors of a pixel are considered when drawing an outline. Named
+/// after the matrix options of Aseprite's outline filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlineShape {
+    /// Only the four orthogonal neighbors. Corners stay open, which gives
+    /// rounder outlines.
+    #[default]
+    Circle,
+    /// All eight neighbors, including diagonals. Corners are filled.
+    Square,
+    /// Only the left and right neighbors.
+    Horizontal,
+    /// Only the neighbors above and below.
+    Vertical,
+}
+
+/// Where to draw an outline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlinePlace {
+    /// Color transparent pixels next to the sprite.
+    #[default]
+    Outside,
+    /// Color the sprite's own edge pixels.
+    Inside,
+}
+
+/// Options for [outline].
+#[derive(Debug, Clone)]
+pub struct OutlineOptions {
+    /// Outline color. Default: opaque black.
+    pub color: Rgba<u8>,
+    /// Thickness in pixels. Default: 1.
+    pub thickness: u32,
+    /// Neighborhood used to grow the outline. Default: [OutlineShape::Circle].
+    pub shape: OutlineShape,
+    /// Default: [OutlinePlace::Outside].
+    pub place: OutlinePlace,
+}
+
+impl Default for OutlineOptions {
+    fn default() -> Self {
+        OutlineOptions {
+            color: Rgba([0, 0, 0, 255]),
+            thickness: 1,
+            shape: OutlineShape::default(),
+            place: OutlinePlace::default(),
+        }
+    }
+}
+
+/// Draw an outline around the non-transparent pixels of an image, like
+/// Aseprite's outline filter.
+///
+/// The result has the same size as the input, so an outside outline is cut
+/// off at the image border. Use [extrude_border] or copy the image onto a
+/// larger canvas first to leave room for it. A thickness of `n` applies the
+/// one pixel outline `n` times.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::{outline, OutlineOptions, OutlineShape};
+/// use image::Rgba;
+///
+/// let options = OutlineOptions {
+///     color: Rgba([255, 255, 0, 255]),
+///     shape: OutlineShape::Square,
+///     ..Default::default()
+/// };
+/// let highlighted = outline(&ase.frame(0).image(), &options);
+/// ```
+pub fn outline(image: &RgbaImage, options: &OutlineOptions) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let neighbors: &[(i32, i32)] = match options.shape {
+        OutlineShape::Circle => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+        OutlineShape::Square => &[
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ],
+        OutlineShape::Horizontal => &[(-1, 0), (1, 0)],
+        OutlineShape::Vertical => &[(0, -1), (0, 1)],
+    };
+    // For outside outlines the mask grows with each step, for inside
+    // outlines it shrinks. Pixels outside the image count as transparent.
+    let outside = options.place == OutlinePlace::Outside;
+    let mut mask: Vec<bool> = image.pixels().map(|pixel| pixel[3] != 0).collect();
+    let mut result = image.clone();
+    for _ in 0..options.thickness {
+        let is_set = |x: i32, y: i32| {
+            x >= 0
+                && y >= 0
+                && (x as u32) < width
+                && (y as u32) < height
+                && mask[(y as u32 * width + x as u32) as usize]
+        };
+        let changed: Vec<(u32, u32)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let (x, y) = (x as i32, y as i32);
+                is_set(x, y) != outside
+                    && neighbors
+                        .iter()
+                        .any(|(dx, dy)| is_set(x + dx, y + dy) == outside)
+            })
+            .collect();
+        if changed.is_empty() {
+            break;
+        }
+        for (x, y) in changed {
+            mask[(y * width + x) as usize] = outside;
+            result.put_pixel(x, y, options.color);
+        }
+    }
+    result
+}
+
+/// Where onion skin frames are drawn relative to the current frame. See
+/// [OnionSkinOptions].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnionSkinPosition {
+    /// Draw the other frames on top of the current frame.
+    #[default]
+    InFront,
+    /// Draw the other frames underneath the current frame.
+    Behind,
+}
+
+/// Options for [onion_skin]. The defaults match Aseprite's.
+#[derive(Debug, Clone)]
+pub struct OnionSkinOptions {
+    /// Number of frames before the current frame to show. Default: 1.
+    pub previous: u32,
+    /// Number of frames after the current frame to show. Default: 1.
+    pub next: u32,
+    /// Opacity of the frames right before and after the current frame.
+    /// Default: 68.
+    pub opacity_base: u8,
+    /// How much less opaque each further frame is. Default: 28.
+    pub opacity_step: u8,
+    /// Tint previous frames red and next frames blue. Default: `false`.
+    pub tint: bool,
+    /// Default: [OnionSkinPosition::InFront].
+    pub position: OnionSkinPosition,
+}
+
+impl Default for OnionSkinOptions {
+    fn default() -> Self {
+        OnionSkinOptions {
+            previous: 1,
+            next: 1,
+            opacity_base: 68,
+            opacity_step: 28,
+            tint: false,
+            position: OnionSkinPosition::default(),
+        }
+    }
+}
+
+/// The image of a frame with the frames around it overlaid at reduced
+/// opacity, like Aseprite's onion skin. Useful for animation previews and
+/// debugging tools.
+///
+/// Frames before the first or after the last frame are skipped. Frames
+/// closer to the current frame are drawn over frames further away.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::{onion_skin, OnionSkinOptions};
+///
+/// let options = OnionSkinOptions {
+///     previous: 2,
+///     tint: true,
+///     ..Default::default()
+/// };
+/// let preview = onion_skin(&ase, 2, &options);
+/// assert_eq!(preview.dimensions(), (16, 16));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn onion_skin(file: &AsepriteFile, frame: u32, options: &OnionSkinOptions) -> RgbaImage {
+    let current = file.frame(frame).image();
+    let mut result = match options.position {
+        OnionSkinPosition::InFront => current.clone(),
+        OnionSkinPosition::Behind => RgbaImage::new(current.width(), current.height()),
+    };
+    let previous = (1..=options.previous)
+        .filter_map(|distance| Some((frame.checked_sub(distance)?, distance, Tint::Red)));
+    let next = (1..=options.next)
+        .map(|distance| (frame + distance, distance, Tint::Blue))
+        .filter(|&(other, _, _)| other < file.num_frames());
+    let mut others: Vec<(u32, u32, Tint)> = previous.chain(next).collect();
+    // Draw the furthest frames first. Previous frames go under next frames
+    // at the same distance.
+    others.sort_by_key(|&(_, distance, tint)| (std::cmp::Reverse(distance), tint));
+    for (other, distance, tint) in others {
+        let step = (distance - 1).saturating_mul(options.opacity_step as u32);
+        let opacity = (options.opacity_base as u32).saturating_sub(step) as u8;
+        if opacity == 0 {
+            continue;
+        }
+        let image = file.frame(other).image();
+        for (dst, &src) in result.pixels_mut().zip(image.pixels()) {
+            let src = if options.tint { tint.apply(src) } else { src };
+            *dst = blend::normal(*dst, src, opacity);
+        }
+    }
+    if options.position == OnionSkinPosition::Behind {
+        for (dst, &src) in result.pixels_mut().zip(current.pixels()) {
+            *dst = blend::normal(*dst, src, 255);
+        }
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Tint {
+    Red,
+    Blue,
+}
+
+impl Tint {
+    // Keeps the brightness of the color, like Aseprite's onion skin tint.
+    fn apply(self, color: Rgba<u8>) -> Rgba<u8> {
+        let [r, g, b, alpha] = color.0;
+        let luma = ((r as u32 * 2126 + g as u32 * 7152 + b as u32 * 722) / 10000) as u8;
+        let (low, high) = (luma / 2, ((255 + luma as u32) / 2) as u8);
+        match self {
+            Tint::Red => Rgba([high, low, low, alpha]),
+            Tint::Blue => Rgba([low, low, high, alpha]),
+        }
+    }
+}
+
+/// How [tween] creates intermediate frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TweenMode {
+    /// Fade from the first frame to the second frame.
+    #[default]
+    CrossFade,
+    /// Move cels that have the same pixels in both frames, but a different
+    /// position, from their first position to their second position. All
+    /// other changes fade like in [TweenMode::CrossFade].
+    Motion,
+}
+
+/// Generate `count` images between the frames `from` and `to`, e.g., for
+/// quick previews of an in-between or smear frames.
+///
+/// The images are spaced evenly and do not include the frames themselves.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::{tween, TweenMode};
+///
+/// let between = tween(&ase, 0, 1, 3, TweenMode::Motion);
+/// assert_eq!(between.len(), 3);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `from` or `to` is not less than `num_frames`.
+pub fn tween(
+    file: &AsepriteFile,
+    from: u32,
+    to: u32,
+    count: u32,
+    mode: TweenMode,
+) -> Vec<RgbaImage> {
+    assert!(from < file.num_frames() && to < file.num_frames());
+    let moving: Vec<(u32, Point, Point)> = match mode {
+        TweenMode::CrossFade => Vec::new(),
+        TweenMode::Motion => (0..file.num_layers())
+            .filter_map(|layer| {
+                let start = file.cel(from, layer).top_left();
+                let end = file.cel(to, layer).top_left();
+                (start != end && same_cel_content(file, from, to, layer))
+                    .then_some((layer, start, end))
+            })
+            .collect(),
+    };
+    let options = RenderOptions::default();
+    // Without moving cels, both frames look the same in every step.
+    let still = moving.is_empty().then(|| {
+        (
+            file.frame(from).image_with_options(&options),
+            file.frame(to).image_with_options(&options),
+        )
+    });
+    (1..=count)
+        .map(|step| {
+            let t = step as f64 / (count + 1) as f64;
+            if let Some((first, last)) = &still {
+                return cross_fade(first, last, t);
+            }
+            let position = |layer: u32| {
+                let (_, start, end) = moving.iter().find(|(l, _, _)| *l == layer)?;
+                let lerp = |a: i32, b: i32| (a as f64 + (b - a) as f64 * t).round() as i32;
+                Some(Point::new(lerp(start.x, end.x), lerp(start.y, end.y)))
+            };
+            let first = file.frame_image_with_positions(from as u16, &options, position);
+            let last = file.frame_image_with_positions(to as u16, &options, position);
+            cross_fade(&first, &last, t)
+        })
+        .collect()
+}
+
+// Do the cels of a layer in both frames have the same pixels and opacity?
+fn same_cel_content(file: &AsepriteFile, from: u32, to: u32, layer: u32) -> bool {
+    let (Some(a), Some(b)) = (
+        file.resolved_cel(file.cel(from, layer).cel_id),
+        file.resolved_cel(file.cel(to, layer).cel_id),
+    ) else {
+        return false;
+    };
+    if a.data.opacity != b.data.opacity {
+        return false;
+    }
+    match (&a.content, &b.content) {
+        (CelContent::Raw(a), CelContent::Raw(b)) => {
+            (a.size.width, a.size.height) == (b.size.width, b.size.height)
+                && a.pixels.clone_as_image_rgba() == b.pixels.clone_as_image_rgba()
+        }
+        _ => false,
+    }
+}
+
+// Interpolates between two images of the same size. Colors are weighted by
+// their alpha, so fully transparent pixels do not darken the result.
+fn cross_fade(a: &RgbaImage, b: &RgbaImage, t: f64) -> RgbaImage {
+    let mut result = a.clone();
+    for (dst, src) in result.pixels_mut().zip(b.pixels()) {
+        let alpha_a = dst.0[3] as f64 * (1.0 - t);
+        let alpha_b = src.0[3] as f64 * t;
+        let alpha = alpha_a + alpha_b;
+        if alpha == 0.0 {
+            *dst = Rgba([0, 0, 0, 0]);
+            continue;
+        }
+        for channel in 0..3 {
+            let value = (dst.0[channel] as f64 * alpha_a + src.0[channel] as f64 * alpha_b) / alpha;
+            dst.0[channel] = value.round() as u8;
+        }
+        dst.0[3] = alpha.round() as u8;
+    }
+    result
+}
+
+/// A single color operation. See [adjust_colors].
+///
+/// Operations only change the color channels, except for
+/// [Multiply](ColorAdjustment::Multiply), which also multiplies alpha.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorAdjustment {
+    /// Multiply each channel with the given color, e.g., to colorize a
+    /// grayscale sprite.
+    Multiply(Rgba<u8>),
+    /// Blend towards the given color. Its alpha controls the strength:
+    /// 255 replaces the color completely.
+    Tint(Rgba<u8>),
+    /// Rotate the hue by the given angle in degrees.
+    HueShift(f32),
+    /// Add the given amount to each channel. `1.0` turns everything white,
+    /// `-1.0` turns everything black.
+    Brightness(f32),
+    /// Scale the distance of each channel from mid-gray. `1.0` leaves the
+    /// image unchanged, `0.0` turns it gray.
+    Contrast(f32),
+    /// Replace colors by their luma (ITU-R BT.601).
+    Grayscale,
+}
+
+impl ColorAdjustment {
+    /// Apply the operation to a single color.
+    pub fn apply(&self, color: Rgba<u8>) -> Rgba<u8> {
+        let [r, g, b, a] = color.0;
+        let rgb = [r, g, b].map(|c| c as f32 / 255.0);
+        let rgb = match *self {
+            ColorAdjustment::Multiply(other) => {
+                let [or, og, ob, oa] = other.0;
+                let mul = |c: u8, o: u8| div_255(c as u32 * o as u32);
+                return Rgba([mul(r, or), mul(g, og), mul(b, ob), mul(a, oa)]);
+            }
+            ColorAdjustment::Tint(other) => {
+                let amount = other[3] as f32 / 255.0;
+                let mut result = rgb;
+                for (c, o) in result.iter_mut().zip(other.0) {
+                    *c += (o as f32 / 255.0 - *c) * amount;
+                }
+                result
+            }
+            ColorAdjustment::HueShift(degrees) => {
+                let (hue, saturation, value) = rgb_to_hsv(rgb);
+                hsv_to_rgb((hue + degrees).rem_euclid(360.0), saturation, value)
+            }
+            ColorAdjustment::Brightness(amount) => rgb.map(|c| c + amount),
+            ColorAdjustment::Contrast(factor) => rgb.map(|c| (c - 0.5) * factor + 0.5),
+            ColorAdjustment::Grayscale => {
+                let luma = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+                [luma; 3]
+            }
+        };
+        let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Rgba([r, g, b, a])
+    }
+}
+
+/// Apply a sequence of color operations to every pixel of an image, in
+/// order.
+///
+/// This can be used on a composited frame or on individual cel images
+/// before composing them yourself, e.g., to create team-colored variants of
+/// a sprite.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::{adjust_colors, ColorAdjustment};
+/// use image::Rgba;
+///
+/// let mut image = ase.frame(0).image();
+/// adjust_colors(
+///     &mut image,
+///     &[
+///         ColorAdjustment::Grayscale,
+///         ColorAdjustment::Multiply(Rgba([64, 128, 255, 255])),
+///     ],
+/// );
+/// ```
+pub fn adjust_colors(image: &mut RgbaImage, adjustments: &[ColorAdjustment]) {
+    for pixel in image.pixels_mut() {
+        // Fully transparent pixels have no visible color to adjust.
+        if pixel[3] == 0 {
+            continue;
+        }
+        for adjustment in adjustments {
+            *pixel = adjustment.apply(*pixel);
+        }
+    }
+}
+
+// Hue in degrees, saturation and value in 0..=1.
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation, max)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [f32; 3] {
+    let chroma = value * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r + m, g + m, b + m]
+}
+
+/// A 1-bit mask of the pixels of an image. See [alpha_mask].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlphaMask {
+    width: u32,
+    height: u32,
+    data: Vec<bool>,
+}
+
+impl AlphaMask {
+    /// Width of the mask in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the mask in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns `true` if the pixel at the given position is set. Positions
+    /// outside of the mask are never set, which makes hit-testing with
+    /// arbitrary coordinates easy.
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as u32) < self.width
+            && (y as u32) < self.height
+            && self.data[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    /// Number of pixels that are set.
+    pub fn count(&self) -> usize {
+        self.data.iter().filter(|&&set| set).count()
+    }
+
+    /// The mask values, row by row, starting at the top left.
+    pub fn as_slice(&self) -> &[bool] {
+        &self.data
+    }
+
+    /// Take ownership of the mask values.
+    pub fn into_vec(self) -> Vec<bool> {
+        self.data
+    }
+
+    /// The convex hull of all set pixels, e.g., for use as a physics
+    /// collider.
+    ///
+    /// Points are pixel corners, so a single set pixel at `(0, 0)` has the
+    /// hull `(0, 0), (1, 0), (1, 1), (0, 1)`. Points are ordered clockwise
+    /// (with the y axis pointing down) and collinear points are omitted.
+    /// Returns an empty vector if no pixel is set.
+    pub fn convex_hull(&self) -> Vec<Point> {
+        // Only the outermost corners of each row can be on the hull.
+        let mut points = Vec::new();
+        for y in 0..self.height as i32 {
+            let mut set = (0..self.width as i32).filter(|&x| self.get(x, y));
+            if let Some(left) = set.next() {
+                let right = set.next_back().unwrap_or(left) + 1;
+                points.extend([(left, y), (left, y + 1), (right, y), (right, y + 1)]);
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        if points.is_empty() {
+            return Vec::new();
+        }
+        // Andrew's monotone chain. The last point of each half is the first
+        // point of the other half.
+        let mut hull = half_hull(points.iter());
+        let mut upper = half_hull(points.iter().rev());
+        hull.pop();
+        upper.pop();
+        hull.extend(upper);
+        hull.into_iter().map(Point::from).collect()
+    }
+
+    /// Trace the boundaries of all regions of set pixels and simplify them
+    /// into polygons.
+    ///
+    /// This follows the pixel edges (similar to marching squares) and then
+    /// simplifies each boundary using the Ramer-Douglas-Peucker algorithm:
+    /// vertices are removed as long as the polygon stays within `tolerance`
+    /// pixels of the traced boundary. A tolerance of `0.0` only removes
+    /// collinear vertices.
+    ///
+    /// Points are pixel corners. Outer boundaries are ordered clockwise (with
+    /// the y axis pointing down), boundaries of holes counter-clockwise.
+    /// Pixels that only touch diagonally are treated as separate regions.
+    ///
+    /// ```
+    /// # use asefile::AsepriteFile;
+    /// # use std::path::Path;
+    /// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+    /// # let ase = AsepriteFile::read_file(&path).unwrap();
+    /// use asefile::util::alpha_mask;
+    ///
+    /// let mask = alpha_mask(&ase.frame(0).image(), 1);
+    /// for polygon in mask.outlines(1.0) {
+    ///     // Create a collider from `polygon`.
+    /// }
+    /// ```
+    pub fn outlines(&self, tolerance: f64) -> Vec<Vec<Point>> {
+        type Vertex = (i32, i32);
+        // Directed edges with the set pixels on their right-hand side.
+        let mut edges: Vec<(Vertex, Vertex)> = Vec::new();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if !self.get(x, y) {
+                    continue;
+                }
+                if !self.get(x, y - 1) {
+                    edges.push(((x, y), (x + 1, y)));
+                }
+                if !self.get(x + 1, y) {
+                    edges.push(((x + 1, y), (x + 1, y + 1)));
+                }
+                if !self.get(x, y + 1) {
+                    edges.push(((x + 1, y + 1), (x, y + 1)));
+                }
+                if !self.get(x - 1, y) {
+                    edges.push(((x, y + 1), (x, y)));
+                }
+            }
+        }
+        let mut outgoing: HashMap<Vertex, Vec<usize>> = HashMap::new();
+        for (index, edge) in edges.iter().enumerate() {
+            outgoing.entry(edge.0).or_default().push(index);
+        }
+
+        let mut used = vec![false; edges.len()];
+        let mut polygons = Vec::new();
+        for first in 0..edges.len() {
+            if used[first] {
+                continue;
+            }
+            let mut polygon = Vec::new();
+            let mut current = first;
+            while !used[current] {
+                used[current] = true;
+                let (start, end) = edges[current];
+                polygon.push(start);
+                let direction = (end.0 - start.0, end.1 - start.1);
+                // Where two regions touch diagonally, two edges leave the same
+                // vertex. Turning right keeps following the same region.
+                let turn = |next: usize| {
+                    let (a, b) = edges[next];
+                    let next_direction = (b.0 - a.0, b.1 - a.1);
+                    if next_direction == (-direction.1, direction.0) {
+                        0
+                    } else if next_direction == direction {
+                        1
+                    } else {
+                        2
+                    }
+                };
+                match outgoing[&end]
+                    .iter()
+                    .copied()
+                    .filter(|&next| !used[next] || next == first)
+                    .min_by_key(|&next| turn(next))
+                {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+            let polygon = simplify_polygon(&polygon, tolerance);
+            polygons.push(polygon.into_iter().map(Point::from).collect());
+        }
+        polygons
+    }
+
+    /// Convert the mask into a grayscale image, e.g., for use as a stencil.
+    /// Set pixels are white, all others black.
+    pub fn to_image(&self) -> GrayImage {
+        GrayImage::from_fn(self.width, self.height, |x, y| {
+            Luma([if self.get(x as i32, y as i32) { 255 } else { 0 }])
+        })
+    }
+}
+
+// One half of a convex hull, given points sorted along the x axis.
+fn half_hull<'a, I>(points: I) -> Vec<(i32, i32)>
+where
+    I: Iterator<Item = &'a (i32, i32)>,
+{
+    let cross = |o: (i32, i32), a: (i32, i32), b: (i32, i32)| {
+        (a.0 - o.0) as i64 * (b.1 - o.1) as i64 - (a.1 - o.1) as i64 * (b.0 - o.0) as i64
+    };
+    let mut hull: Vec<(i32, i32)> = Vec::new();
+    for &point in points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0 {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    hull
+}
+
+// Simplifies a closed polygon using the Ramer-Douglas-Peucker algorithm.
+// Collinear vertices are always removed.
+fn simplify_polygon(points: &[(i32, i32)], tolerance: f64) -> Vec<(i32, i32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    // Split the loop at the first point and the point furthest from it, then
+    // simplify both halves as open polylines.
+    let distance2 = |a: (i32, i32), b: (i32, i32)| {
+        let (dx, dy) = ((a.0 - b.0) as i64, (a.1 - b.1) as i64);
+        dx * dx + dy * dy
+    };
+    let far = (1..points.len())
+        .max_by_key(|&i| (distance2(points[0], points[i]), std::cmp::Reverse(i)))
+        .unwrap();
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[far] = true;
+    let closed: Vec<(i32, i32)> = points.iter().copied().chain(once(points[0])).collect();
+    simplify_polyline(&closed, 0, far, tolerance, &mut keep);
+    let mut end_keep = vec![false; closed.len()];
+    simplify_polyline(&closed, far, closed.len() - 1, tolerance, &mut end_keep);
+    for (k, e) in keep.iter_mut().zip(end_keep) {
+        *k |= e;
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&point, keep)| keep.then_some(point))
+        .collect()
+}
+
+fn simplify_polyline(
+    points: &[(i32, i32)],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    keep: &mut [bool],
+) {
+    if last <= first + 1 {
+        return;
+    }
+    let (a, b) = (points[first], points[last]);
+    let (dx, dy) = ((b.0 - a.0) as f64, (b.1 - a.1) as f64);
+    let length = (dx * dx + dy * dy).sqrt();
+    let distance = |p: (i32, i32)| {
+        let (px, py) = ((p.0 - a.0) as f64, (p.1 - a.1) as f64);
+        if length == 0.0 {
+            (px * px + py * py).sqrt()
+        } else {
+            (px * dy - py * dx).abs() / length
+        }
+    };
+    let (index, max) =
+        (first + 1..last)
+            .map(|i| (i, distance(points[i])))
+            .fold(
+                (first, 0.0),
+                |best, item| if item.1 > best.1 { item } else { best },
+            );
+    // Points exactly on the line are dropped even with zero tolerance.
+    if max > tolerance && max > 0.0 {
+        keep[index] = true;
+        simplify_polyline(points, first, index, tolerance, keep);
+        simplify_polyline(points, index, last, tolerance, keep);
+    }
+}
+
+/// Compute a mask of all pixels whose alpha is at least `threshold`.
+///
+/// A threshold of 1 selects every pixel that is not fully transparent. To
+/// get the mask of a frame or cel, pass [Frame::image](crate::Frame::image)
+/// or [Cel::image](crate::Cel::image).
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::alpha_mask;
+///
+/// let mask = alpha_mask(&ase.frame(0).image(), 128);
+/// let hit = mask.get(3, 5);
+/// ```
+pub fn alpha_mask(image: &RgbaImage, threshold: u8) -> AlphaMask {
+    AlphaMask {
+        width: image.width(),
+        height: image.height(),
+        data: image.pixels().map(|pixel| pixel[3] >= threshold).collect(),
+    }
+}
+
+/// Scale an image up by an integer factor, repeating each pixel.
+///
+/// # Panics
+///
+/// Panics if `factor` is zero.
+pub fn scale_nearest(image: &RgbaImage, factor: u32) -> RgbaImage {
+    assert!(factor > 0, "factor must be greater than zero");
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn(width * factor, height * factor, |x, y| {
+        *image.get_pixel(x / factor, y / factor)
+    })
+}
+
+/// Scale an image to twice its size using the
+/// [Scale2x](https://www.scale2x.it/algorithm) algorithm.
+///
+/// Scale2x rounds off diagonal edges instead of just repeating pixels and
+/// never introduces new colors. Pixels outside the image are treated as
+/// copies of the nearest edge pixel.
+pub fn scale2x(image: &RgbaImage) -> RgbaImage {
+    scale_by_neighborhood(image, 2, |[_, b, _, d, e, f, _, h, _], out| {
+        out[0] = if d == b && d != h && b != f { d } else { e };
+        out[1] = if b == f && b != d && f != h { f } else { e };
+        out[2] = if d == h && d != b && h != f { d } else { e };
+        out[3] = if h == f && h != d && f != b { f } else { e };
+    })
+}
+
+/// Scale an image to three times its size using the
+/// [Scale3x](https://www.scale2x.it/algorithm) algorithm.
+///
+/// See [scale2x] for details.
+pub fn scale3x(image: &RgbaImage) -> RgbaImage {
+    scale_by_neighborhood(image, 3, |[a, b, c, d, e, f, g, h, i], out| {
+        out.fill(e);
+        if b == h || d == f {
+            return;
+        }
+        if d == b {
+            out[0] = d;
+        }
+        if (d == b && e != c) || (b == f && e != a) {
+            out[1] = b;
+        }
+        if b == f {
+            out[2] = f;
+        }
+        if (d == b && e != g) || (d == h && e != a) {
+            out[3] = d;
+        }
+        if (b == f && e != i) || (h == f && e != c) {
+            out[5] = f;
+        }
+        if d == h {
+            out[6] = d;
+        }
+        if (d == h && e != i) || (h == f && e != g) {
+            out[7] = h;
+        }
+        if h == f {
+            out[8] = f;
+        }
+    })
+}
+
+/// Scale an image to twice its size using the Eagle algorithm.
+///
+/// Each output pixel takes the color of the three neighbors in its corner if
+/// they all agree, and the color of the source pixel otherwise. Compared to
+/// [scale2x], this produces bolder diagonals.
+pub fn eagle(image: &RgbaImage) -> RgbaImage {
+    scale_by_neighborhood(image, 2, |[s, t, u, v, c, w, x, y, z], out| {
+        out[0] = if v == s && s == t { s } else { c };
+        out[1] = if t == u && u == w { u } else { c };
+        out[2] = if v == x && x == y { x } else { c };
+        out[3] = if w == z && z == y { z } else { c };
+    })
+}
+
+// Calls `scale` for each pixel with its 3x3 neighborhood (row by row) and the
+// `factor * factor` output pixels (row by row) to fill in.
+fn scale_by_neighborhood<F>(image: &RgbaImage, factor: u32, mut scale: F) -> RgbaImage
+where
+    F: FnMut([Rgba<u8>; 9], &mut [Rgba<u8>]),
+{
+    let (width, height) = image.dimensions();
+    let mut result = RgbaImage::new(width * factor, height * factor);
+    let pixel = |x: i64, y: i64| {
+        let x = x.clamp(0, width as i64 - 1) as u32;
+        let y = y.clamp(0, height as i64 - 1) as u32;
+        *image.get_pixel(x, y)
+    };
+    let mut out = vec![Rgba([0, 0, 0, 0]); (factor * factor) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let (cx, cy) = (x as i64, y as i64);
+            let mut neighborhood = [Rgba([0, 0, 0, 0]); 9];
+            for (index, n) in neighborhood.iter_mut().enumerate() {
+                *n = pixel(cx + index as i64 % 3 - 1, cy + index as i64 / 3 - 1);
+            }
+            scale(neighborhood, &mut out);
+            for (index, color) in out.iter().enumerate() {
+                let index = index as u32;
+                result.put_pixel(
+                    x * factor + index % factor,
+                    y * factor + index / factor,
+                    *color,
+                );
+            }
+        }
+    }
+    result
+}
+
+/// Scale an image to the given size using 9-slice scaling.
+///
+/// `image` should contain exactly the pixels inside the slice bounds, as the
+/// [Slice9] center is relative to them. The corners keep their size, the
+/// edges are stretched along one axis and the center along both axes. Pixels
+/// are repeated (nearest neighbor), so pixel art stays crisp.
+///
+/// If the target size is smaller than the combined size of two opposing
+/// corners, the corners are shrunk proportionally and the center is dropped.
+///
+/// If `image` is empty, returns a transparent image of the target size.
+pub fn nine_slice(image: &RgbaImage, slice9: &Slice9, width: u32, height: u32) -> RgbaImage {
+    if image.width() == 0 || image.height() == 0 {
+        return RgbaImage::new(width, height);
+    }
+    let xs = nine_slice_axis(image.width(), slice9.center_x, slice9.center_width, width);
+    let ys = nine_slice_axis(
+        image.height(),
+        slice9.center_y,
+        slice9.center_height,
+        height,
+    );
+    RgbaImage::from_fn(width, height, |x, y| {
+        *image.get_pixel(xs[x as usize], ys[y as usize])
+    })
+}
+
+/// Render the given slice of a frame and scale it to the given size using
+/// [nine_slice].
+///
+/// Returns `None` if the slice has no key at this frame or has no 9-slice
+/// information.
+///
+/// # Panics
+///
+/// Panics if `frame` is not less than `num_frames`.
+pub fn nine_slice_frame(
+    file: &AsepriteFile,
+    frame: u32,
+    slice: &Slice,
+    width: u32,
+    height: u32,
+) -> Option<RgbaImage> {
+    let key = slice.key_for_frame(frame)?;
+    let slice9 = key.slice9.as_ref()?;
+    let source = geometry::crop(&file.frame(frame).image(), key.bounds());
+    Some(nine_slice(&source, slice9, width, height))
+}
+
+// For each target pixel along one axis, computes the source pixel it is
+// copied from.
+fn nine_slice_axis(source: u32, center: i32, center_len: u32, target: u32) -> Vec<u32> {
+    let start = center.clamp(0, source as i32) as u32;
+    let center_len = center_len.min(source - start);
+    let end = source - start - center_len;
+    let (start_t, end_t) = if target < start + end {
+        let start_t = start * target / (start + end);
+        (start_t, target - start_t)
+    } else {
+        (start, end)
+    };
+    let center_t = target - start_t - end_t;
+    (0..target)
+        .map(|t| {
+            let src = if t < start_t {
+                t * start / start_t
+            } else if t < start_t + center_t {
+                if center_len == 0 {
+                    start
+                } else {
+                    start + (t - start_t) * center_len / center_t
+                }
+            } else {
+                start + center_len + (t - start_t - center_t) * end / end_t
+            };
+            src.min(source - 1)
+        })
+        .collect()
+}
+
+/// An image cropped to its non-transparent pixels. See [trim_transparent].
+#[derive(Debug, Clone)]
+pub struct TrimmedImage {
+    /// The cropped pixels.
+    pub image: RgbaImage,
+    /// Position and size of `image` within the original image.
+    pub rect: Rect,
+}
+
+/// The smallest rectangle containing all pixels that are not fully
+/// transparent, or `None` if the image is fully transparent.
+pub fn opaque_bounds(image: &RgbaImage) -> Option<Rect> {
+    geometry::opaque_bounds(image)
+}
+
+/// Crop an image to its non-transparent pixels.
+///
+/// The returned rect gives the position of the trimmed image within the
+/// original, which is needed to draw the sprite at the right place or to
+/// adjust anchor points. Returns `None` if the image is fully transparent.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::trim_transparent;
+///
+/// let trimmed = trim_transparent(&ase.frame(0).image()).unwrap();
+/// let (x, y) = (trimmed.rect.x, trimmed.rect.y);
+/// ```
+pub fn trim_transparent(image: &RgbaImage) -> Option<TrimmedImage> {
+    let rect = geometry::opaque_bounds(image)?;
+    Some(TrimmedImage {
+        image: geometry::crop(image, rect),
+        rect,
+    })
+}
+
 /// A helper for mapping `Rgba` values into indexes in a color palette.
 pub struct PaletteMapper {
     map: IntMap<u32, u8>,
     transparent: u8,
     failure: u8,
+    match_alpha: bool,
 }
 
 /// Configuration of palette mapping.
+#[derive(Debug, Clone, Default)]
 pub struct MappingOptions {
     /// If pixel is not in the palette, use this index.
     pub failure: u8,
-    /// If pixel is transparent (`alpha != 255`), use this index. If `None`
-    /// transparent pixels are treated as failures.
+    /// If pixel is transparent, use this index. If `None` transparent pixels
+    /// are treated as failures.
+    ///
+    /// Without [match_alpha](Self::match_alpha), any `alpha != 255` counts as
+    /// transparent. With it, only `alpha == 0` does.
     pub transparent: Option<u8>,
+    /// Match semi-transparent pixels against palette entries with the same
+    /// alpha value. Useful for palettes with translucent entries. Default:
+    /// `false`.
+    pub match_alpha: bool,
 }
 
 impl PaletteMapper {
     /// Create a new mapper from a color palette.
+    ///
+    /// If a color appears several times in the palette, it is mapped to the
+    /// lowest of its indices.
     pub fn new(palette: &ColorPalette, options: MappingOptions) -> PaletteMapper {
         let mut map = IntMap::default();
-        for (idx, entry) in palette.entries.iter() {
-            let m =
-                entry.red() as u32 + ((entry.green() as u32) << 8) + ((entry.blue() as u32) << 16);
-            let col = if *idx < 256 {
-                *idx as u8
+        for entry in palette {
+            let [r, g, b, a] = entry.raw_rgba8();
+            let m = color_key(r, g, b, if options.match_alpha { a } else { 255 });
+            let col = if entry.id() < 256 {
+                entry.id() as u8
             } else {
                 options.failure
             };
-            let _ = map.insert(m, col);
+            // If a color appears several times, use the lowest index.
+            map.entry(m).or_insert(col);
         }
         PaletteMapper {
             map,
             transparent: options.transparent.unwrap_or(options.failure),
             failure: options.failure,
+            match_alpha: options.match_alpha,
         }
     }
 
     /// Look up a color in the palette.
     ///
-    /// An `alpha` other than `255` is considered transparent. If the color
-    /// is not in the palette returns the failure color.
+    /// By default, an `alpha` other than `255` is considered transparent. See
+    /// [MappingOptions::match_alpha]. If the color is not in the palette
+    /// returns the failure color.
     pub fn lookup(&self, r: u8, g: u8, b: u8, alpha: u8) -> u8 {
-        if alpha != 255 {
-            return self.transparent;
-        }
-        let m = r as u32 + ((g as u32) << 8) + ((b as u32) << 16);
-        *self.map.get(&m).unwrap_or(&self.failure)
+        let alpha = if self.match_alpha {
+            if alpha == 0 {
+                return self.transparent;
+            }
+            alpha
+        } else {
+            if alpha != 255 {
+                return self.transparent;
+            }
+            255
+        };
+        *self
+            .map
+            .get(&color_key(r, g, b, alpha))
+            .unwrap_or(&self.failure)
     }
 }
 
+fn color_key(r: u8, g: u8, b: u8, a: u8) -> u32 {
+    u32::from_le_bytes([r, g, b, a])
+}
+
 /// Turn an `RgbaImage` into an indexed image.
 ///
 /// Returns image dimensions and raw index data.
@@ -113,7 +1228,7 @@ impl PaletteMapper {
 ///     ase.palette().unwrap(),
 ///     MappingOptions {
 ///         transparent: ase.transparent_color_index(),
-///         failure: 0,
+///         ..Default::default()
 ///     }
 /// );
 /// let ((w, h), data) = to_indexed_image(img, &mapper);
@@ -126,3 +1241,318 @@ pub fn to_indexed_image(image: RgbaImage, mapper: &PaletteMapper) -> ((u32, u32)
         .collect();
     (image.dimensions(), data)
 }
+
+/// The result of [quantize].
+#[derive(Debug)]
+pub struct Quantized {
+    /// The generated palette. If any input pixel is fully transparent, index
+    /// 0 is a fully transparent color.
+    pub palette: ColorPalette,
+    /// The input images as indexed images, in the same format as returned
+    /// by [to_indexed_image].
+    pub images: Vec<((u32, u32), Vec<u8>)>,
+}
+
+/// Build a palette of at most `max_colors` colors for the given images using
+/// the median cut algorithm, and convert the images to indexed images.
+///
+/// Fully transparent pixels all map to a single transparent entry at index
+/// 0. All other pixels are mapped to the closest palette color, including
+/// alpha.
+///
+/// ```
+/// # use asefile::AsepriteFile;
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/layers_and_tags.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// let frames: Vec<_> = (0..ase.num_frames()).map(|f| ase.frame(f).image()).collect();
+/// let quantized = asefile::util::quantize(&frames, 4);
+/// assert!(quantized.palette.num_colors() <= 4);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `max_colors` is not in the range `1..=256`.
+pub fn quantize(images: &[RgbaImage], max_colors: u32) -> Quantized {
+    assert!(
+        (1..=256).contains(&max_colors),
+        "max_colors must be between 1 and 256"
+    );
+    let mut histogram: HashMap<[u8; 4], u64> = HashMap::new();
+    let mut has_transparent = false;
+    for pixel in images.iter().flat_map(|image| image.pixels()) {
+        if pixel[3] == 0 {
+            has_transparent = true;
+        } else {
+            *histogram.entry(pixel.0).or_insert(0) += 1;
+        }
+    }
+
+    let mut colors: Vec<[u8; 4]> = Vec::new();
+    if has_transparent {
+        colors.push([0, 0, 0, 0]);
+    }
+    let mut histogram: Vec<([u8; 4], u64)> = histogram.into_iter().collect();
+    // Sort so the result does not depend on hash map order.
+    histogram.sort_unstable();
+    let target = (max_colors as usize - colors.len()).max(1);
+    colors.extend(palette::median_cut(histogram, target));
+    colors.truncate(max_colors as usize);
+    let palette = ColorPalette::from_colors(colors.iter().copied());
+
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+    let images = images
+        .iter()
+        .map(|image| {
+            let data = image
+                .pixels()
+                .map(|pixel| {
+                    if pixel[3] == 0 && has_transparent {
+                        return 0;
+                    }
+                    *cache
+                        .entry(pixel.0)
+                        .or_insert_with(|| closest_color(&colors, pixel.0))
+                })
+                .collect();
+            (image.dimensions(), data)
+        })
+        .collect();
+    Quantized { palette, images }
+}
+
+fn closest_color(colors: &[[u8; 4]], color: [u8; 4]) -> u8 {
+    let distance = |other: &[u8; 4]| -> u32 {
+        (0..4)
+            .map(|channel| {
+                let d = color[channel] as i32 - other[channel] as i32;
+                (d * d) as u32
+            })
+            .sum()
+    };
+    colors
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+/// A tileset with duplicate tiles removed. See [dedup_tileset].
+#[derive(Debug, Clone)]
+pub struct DedupedTileset {
+    /// The unique tiles as one long vertical image, in the same layout as
+    /// [Tileset::image].
+    pub image: RgbaImage,
+    /// Maps each tile ID of the original tileset to its ID in `image`.
+    pub remap: Vec<u32>,
+}
+
+impl DedupedTileset {
+    /// Number of unique tiles.
+    pub fn tile_count(&self) -> u32 {
+        self.remap.iter().max().map_or(0, |&id| id + 1)
+    }
+
+    /// Tiles of the given tilemap, rewritten to refer to the deduplicated
+    /// tileset. Tiles are listed row by row, starting at the top left. Flip
+    /// and rotation flags are kept.
+    ///
+    /// The tilemap must use the tileset that was passed to [dedup_tileset].
+    pub fn remap_tilemap(&self, tilemap: &Tilemap) -> Vec<Tile> {
+        let mut tiles = tilemap.to_tile_vec();
+        for tile in &mut tiles {
+            if let Some(&id) = self.remap.get(tile.id() as usize) {
+                tile.id = TileId(id);
+            }
+        }
+        tiles
+    }
+}
+
+/// Remove tiles that are pixel-identical to an earlier tile.
+///
+/// The first occurrence of each tile is kept, so tile IDs never increase and
+/// the empty tile (ID 0) stays at ID 0.
+///
+/// ```
+/// # use asefile::{AsepriteFile, TilesetId};
+/// # use std::path::Path;
+/// # let path = Path::new("./tests/data/tilemap.aseprite");
+/// # let ase = AsepriteFile::read_file(&path).unwrap();
+/// use asefile::util::dedup_tileset;
+///
+/// let tileset = ase.tilesets().get(TilesetId::new(0)).unwrap();
+/// let deduped = dedup_tileset(tileset);
+/// assert!(deduped.tile_count() <= tileset.tile_count());
+/// let tilemap = ase.tilemap(0, 0).unwrap();
+/// let tiles = deduped.remap_tilemap(&tilemap);
+/// ```
+pub fn dedup_tileset(tileset: &Tileset) -> DedupedTileset {
+    let (tile_width, tile_height) = tileset.tile_size().into();
+    let image = tileset.image();
+    let bytes_per_tile = (4 * tile_width * tile_height) as usize;
+
+    let mut unique: HashMap<&[u8], u32> = HashMap::new();
+    let mut data: Vec<u8> = Vec::new();
+    let mut remap = Vec::with_capacity(tileset.tile_count() as usize);
+    for tile in image.as_raw().chunks_exact(bytes_per_tile) {
+        let next_id = unique.len() as u32;
+        let id = *unique.entry(tile).or_insert_with(|| {
+            data.extend_from_slice(tile);
+            next_id
+        });
+        remap.push(id);
+    }
+    let image = RgbaImage::from_raw(tile_width, tile_height * unique.len() as u32, data)
+        .expect("Mismatched image size");
+    DedupedTileset { image, remap }
+}
+
+/// Write the given frames as a Windows icon (`.ico`) file.
+///
+/// Each frame becomes one image in the icon. Frames must be at most 256x256
+/// pixels.
+///
+/// Fails with [io::ErrorKind::InvalidInput] if a frame index is out of range.
+pub fn write_ico<W: Write>(file: &AsepriteFile, frames: &[u32], output: W) -> io::Result<()> {
+    let entries = frames
+        .iter()
+        .map(|&frame| Ok((icon_frame_image(file, frame)?, (1, 32))))
+        .collect::<io::Result<Vec<_>>>()?;
+    write_icon_entries(output, IconType::Icon, &entries)
+}
+
+/// Write the given frames as a Windows cursor (`.cur`) file.
+///
+/// The cursor's hotspot is taken from the pivot of the slice named
+/// `hotspot_slice` (relative to the canvas, i.e., slice origin plus pivot). If
+/// the slice has no pivot, its origin is used. If the slice does not exist yet
+/// at a frame, the hotspot is the top-left corner. Cursor files cannot store a
+/// hotspot outside the image, so it is clamped to the nearest pixel inside the
+/// frame. Frames must be at most 256x256 pixels.
+///
+/// Fails with [io::ErrorKind::InvalidInput] if no slice with the given name
+/// exists or if a frame index is out of range.
+pub fn write_cur<W: Write>(
+    file: &AsepriteFile,
+    frames: &[u32],
+    hotspot_slice: &str,
+    output: W,
+) -> io::Result<()> {
+    let slice = file.slice_by_name(hotspot_slice).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("No slice named {:?}", hotspot_slice),
+        )
+    })?;
+    let entries = frames
+        .iter()
+        .map(|&frame| {
+            let image = icon_frame_image(file, frame)?;
+            let (x, y) = slice
+                .key_for_frame(frame)
+                .map(|k| {
+                    let pivot = k.pivot.unwrap_or_default();
+                    (k.origin.x + pivot.x, k.origin.y + pivot.y)
+                })
+                .unwrap_or((0, 0));
+            let x = x.clamp(0, image.width() as i32 - 1) as u16;
+            let y = y.clamp(0, image.height() as i32 - 1) as u16;
+            Ok((image, (x, y)))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    write_icon_entries(output, IconType::Cursor, &entries)
+}
+
+fn icon_frame_image(file: &AsepriteFile, frame: u32) -> io::Result<RgbaImage> {
+    if frame >= file.num_frames() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Frame {} is out of range, the file has {} frames",
+                frame,
+                file.num_frames()
+            ),
+        ));
+    }
+    Ok(file.frame(frame).image())
+}
+
+#[derive(Clone, Copy)]
+enum IconType {
+    Icon = 1,
+    Cursor = 2,
+}
+
+// Icon and cursor files share the same layout. The only difference is that
+// cursors store the hotspot where icons store planes and bits per pixel.
+// Images are stored as 32-bit BMP data, which all Windows versions support.
+fn write_icon_entries<W: Write>(
+    mut output: W,
+    icon_type: IconType,
+    entries: &[(RgbaImage, (u16, u16))],
+) -> io::Result<()> {
+    const HEADER_SIZE: u32 = 6;
+    const ENTRY_SIZE: u32 = 16;
+    const BMP_HEADER_SIZE: u32 = 40;
+
+    let mut data = Vec::new();
+    let mut directory = Vec::new();
+    let mut offset = HEADER_SIZE + ENTRY_SIZE * entries.len() as u32;
+    for (image, (field1, field2)) in entries {
+        let (w, h) = image.dimensions();
+        if w == 0 || h == 0 || w > 256 || h > 256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Icon images must be 1x1 to 256x256, got {}x{}", w, h),
+            ));
+        }
+        let mask_row_bytes = w.div_ceil(32) * 4;
+        let image_size = w * h * 4 + mask_row_bytes * h;
+        let entry_size = BMP_HEADER_SIZE + image_size;
+
+        // 256 is stored as 0.
+        directory.write_u8(w as u8)?;
+        directory.write_u8(h as u8)?;
+        directory.write_u8(0)?; // color count
+        directory.write_u8(0)?; // reserved
+        directory.write_u16::<LittleEndian>(*field1)?;
+        directory.write_u16::<LittleEndian>(*field2)?;
+        directory.write_u32::<LittleEndian>(entry_size)?;
+        directory.write_u32::<LittleEndian>(offset)?;
+        offset += entry_size;
+
+        data.write_u32::<LittleEndian>(BMP_HEADER_SIZE)?;
+        data.write_i32::<LittleEndian>(w as i32)?;
+        // Height includes the AND mask.
+        data.write_i32::<LittleEndian>(2 * h as i32)?;
+        data.write_u16::<LittleEndian>(1)?; // planes
+        data.write_u16::<LittleEndian>(32)?; // bits per pixel
+        data.write_u32::<LittleEndian>(0)?; // no compression
+        data.write_u32::<LittleEndian>(image_size)?;
+        data.write_all(&[0; 16])?; // resolution and palette info
+
+        // Rows are stored bottom to top.
+        for y in (0..h).rev() {
+            for x in 0..w {
+                let [r, g, b, a] = image.get_pixel(x, y).0;
+                data.write_all(&[b, g, r, a])?;
+            }
+        }
+        for y in (0..h).rev() {
+            let mut row = vec![0u8; mask_row_bytes as usize];
+            for x in 0..w {
+                if image.get_pixel(x, y).0[3] == 0 {
+                    row[(x / 8) as usize] |= 0x80 >> (x % 8);
+                }
+            }
+            data.write_all(&row)?;
+        }
+    }
+
+    output.write_u16::<LittleEndian>(0)?; // reserved
+    output.write_u16::<LittleEndian>(icon_type as u16)?;
+    output.write_u16::<LittleEndian>(entries.len() as u16)?;
+    output.write_all(&directory)?;
+    output.write_all(&data)
+}
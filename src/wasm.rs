@@ -0,0 +1,169 @@
+//! JavaScript bindings using wasm-bindgen. (Requires feature `wasm`.)
+//!
+//! Build with [wasm-pack](https://rustwasm.github.io/wasm-pack/) or
+//! `wasm-bindgen` for the `wasm32-unknown-unknown` target:
+//!
+//! ```text
+//! wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! ```js
+//! import init, { AsepriteFile } from "./pkg/asefile.js";
+//!
+//! await init();
+//! const bytes = new Uint8Array(await (await fetch("player.aseprite")).arrayBuffer());
+//! const ase = new AsepriteFile(bytes);
+//! for (const tag of ase.tags()) {
+//!     console.log(tag.name, tag.fromFrame, tag.toFrame);
+//! }
+//! context.putImageData(ase.frameImageData(0), 0, 0);
+//! ```
+
+use wasm_bindgen::{prelude::*, Clamped};
+use web_sys::ImageData;
+
+use crate::{AnimationDirection, AsepriteFile};
+
+/// An Aseprite file, exported to JavaScript as `AsepriteFile`.
+#[wasm_bindgen(js_name = AsepriteFile)]
+pub struct WasmAsepriteFile {
+    file: AsepriteFile,
+}
+
+impl WasmAsepriteFile {
+    fn check_frame(&self, frame: u32) -> Result<(), JsError> {
+        if frame < self.file.num_frames() {
+            Ok(())
+        } else {
+            Err(JsError::new("Frame index out of range"))
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = AsepriteFile)]
+impl WasmAsepriteFile {
+    /// Parse a file from its contents, e.g., a `Uint8Array`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8]) -> Result<WasmAsepriteFile, JsError> {
+        let file = AsepriteFile::read(data)?;
+        Ok(WasmAsepriteFile { file })
+    }
+
+    /// Canvas width in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.file.width() as u32
+    }
+
+    /// Canvas height in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.file.height() as u32
+    }
+
+    /// Number of frames.
+    #[wasm_bindgen(getter, js_name = numFrames)]
+    pub fn num_frames(&self) -> u32 {
+        self.file.num_frames()
+    }
+
+    /// Number of layers.
+    #[wasm_bindgen(getter, js_name = numLayers)]
+    pub fn num_layers(&self) -> u32 {
+        self.file.num_layers()
+    }
+
+    /// Duration of the given frame in milliseconds.
+    #[wasm_bindgen(js_name = frameDuration)]
+    pub fn frame_duration(&self, frame: u32) -> Result<u32, JsError> {
+        self.check_frame(frame)?;
+        Ok(self.file.frame(frame).duration_ms())
+    }
+
+    /// The composited image of a frame as RGBA bytes (a `Uint8Array`), row by
+    /// row.
+    #[wasm_bindgen(js_name = frameRgba)]
+    pub fn frame_rgba(&self, frame: u32) -> Result<Vec<u8>, JsError> {
+        self.check_frame(frame)?;
+        Ok(self.file.frame(frame).image().into_raw())
+    }
+
+    /// The composited image of a frame as an `ImageData`, ready to be drawn
+    /// onto a canvas with `putImageData`.
+    #[wasm_bindgen(js_name = frameImageData)]
+    pub fn frame_image_data(&self, frame: u32) -> Result<ImageData, JsValue> {
+        let pixels = self.frame_rgba(frame)?;
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), self.width(), self.height())
+    }
+
+    /// Names of all layers, from bottom to top.
+    #[wasm_bindgen(js_name = layerNames)]
+    pub fn layer_names(&self) -> Vec<String> {
+        self.file
+            .layers()
+            .map(|layer| layer.name().to_owned())
+            .collect()
+    }
+
+    /// All tags, in the order they appear in the file.
+    pub fn tags(&self) -> Vec<WasmTag> {
+        self.file
+            .tags()
+            .map(|tag| WasmTag {
+                name: tag.name().to_owned(),
+                from_frame: tag.from_frame(),
+                to_frame: tag.to_frame(),
+                direction: tag.animation_direction(),
+                repeat: tag.repeat().map(|repeat| repeat.get()),
+            })
+            .collect()
+    }
+}
+
+/// A tag, exported to JavaScript as `Tag`.
+#[wasm_bindgen(js_name = Tag)]
+pub struct WasmTag {
+    name: String,
+    from_frame: u32,
+    to_frame: u32,
+    direction: AnimationDirection,
+    repeat: Option<u32>,
+}
+
+#[wasm_bindgen(js_class = Tag)]
+impl WasmTag {
+    /// Tag name.
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// First frame included in the tag.
+    #[wasm_bindgen(getter, js_name = fromFrame)]
+    pub fn from_frame(&self) -> u32 {
+        self.from_frame
+    }
+
+    /// Last frame included in the tag.
+    #[wasm_bindgen(getter, js_name = toFrame)]
+    pub fn to_frame(&self) -> u32 {
+        self.to_frame
+    }
+
+    /// `"forward"`, `"reverse"` or `"pingpong"`.
+    #[wasm_bindgen(getter)]
+    pub fn direction(&self) -> String {
+        match self.direction {
+            AnimationDirection::Forward => "forward",
+            AnimationDirection::Reverse => "reverse",
+            AnimationDirection::PingPong => "pingpong",
+        }
+        .to_owned()
+    }
+
+    /// Number of repetitions, or `undefined` to repeat forever.
+    #[wasm_bindgen(getter)]
+    pub fn repeat(&self) -> Option<u32> {
+        self.repeat
+    }
+}